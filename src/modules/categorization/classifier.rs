@@ -0,0 +1,136 @@
+use chrono::{Datelike, NaiveDate};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+/// Suggestions less likely than this are left out, since they only add noise to the list.
+const MIN_CONFIDENCE: f64 = 0.05;
+
+/// What an income or expense is known by when its category is learned or guessed.
+#[derive(Debug, PartialEq)]
+pub struct CategoryFeatures {
+    pub entity_id: i64, // 0 if unknown
+    pub description: String,
+    pub value: f64, // 0 if not entered yet
+    pub date: NaiveDate,
+}
+
+impl CategoryFeatures {
+    /// Returns the features as words, so that they can be counted: the entity, the words of
+    /// the description, the order of magnitude of the value and the day of the week.
+    pub(crate) fn tokens(&self) -> Vec<String> {
+        let mut tokens: Vec<String> = Vec::new();
+        // the unknown entity tells nothing
+        if self.entity_id > 0 {
+            tokens.push(format!("entity:{}", self.entity_id));
+        }
+        let words: HashSet<String> = self
+            .description
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| (word.chars().count() > 1) & !word.chars().all(|c| c.is_numeric()))
+            .map(|word| word.to_lowercase())
+            .collect();
+        tokens.extend(words.into_iter().map(|word| format!("word:{word}")));
+        // amounts within a power of two of each other look alike
+        if self.value.abs() >= 0.01 {
+            tokens.push(format!("amount:{}", self.value.abs().log2().floor() as i64));
+        }
+        tokens.push(format!("weekday:{}", self.date.weekday()));
+
+        tokens
+    }
+}
+
+/// Category that an income or expense likely belongs to, with how likely it is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CategorySuggestion {
+    pub category: String,
+    pub confidence: f64, // from 0 to 1
+}
+
+impl Display for CategorySuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:.0}%)", self.category, self.confidence * 100.0)
+    }
+}
+
+/// Naive Bayes classifier that learns the categories of incomes or expenses from the ones
+/// already in the ledger, counting how often each feature comes with each category.
+#[derive(Debug, Default)]
+pub struct CategoryClassifier {
+    n_examples: usize,
+    category_counts: HashMap<String, usize>,
+    token_counts: HashMap<String, HashMap<String, usize>>, // by category, then by token
+    token_totals: HashMap<String, usize>,                  // by category
+    vocabulary: HashSet<String>,
+}
+
+impl CategoryClassifier {
+    pub(crate) fn learn(&mut self, features: &CategoryFeatures, category: &str) {
+        self.n_examples += 1;
+        *self
+            .category_counts
+            .entry(category.to_string())
+            .or_default() += 1;
+        let tokens: Vec<String> = features.tokens();
+        *self.token_totals.entry(category.to_string()).or_default() += tokens.len();
+        let token_counts: &mut HashMap<String, usize> =
+            self.token_counts.entry(category.to_string()).or_default();
+        for token in tokens {
+            *token_counts.entry(token.clone()).or_default() += 1;
+            self.vocabulary.insert(token);
+        }
+    }
+
+    /// Returns the most likely categories, at most `n` of them, from the most likely down.
+    pub(crate) fn suggest(&self, features: &CategoryFeatures, n: usize) -> Vec<CategorySuggestion> {
+        if self.n_examples == 0 {
+            return Vec::new();
+        }
+        // tokens never seen tell nothing about any category
+        let tokens: Vec<String> = features
+            .tokens()
+            .into_iter()
+            .filter(|token| self.vocabulary.contains(token))
+            .collect();
+        let n_vocabulary: f64 = self.vocabulary.len() as f64;
+
+        let mut log_likelihoods: Vec<(String, f64)> = Vec::new();
+        for (category, count) in self.category_counts.iter() {
+            let token_counts: &HashMap<String, usize> = &self.token_counts[category];
+            let token_total: f64 = self.token_totals[category] as f64;
+            let mut log_likelihood: f64 = (*count as f64 / self.n_examples as f64).ln();
+            for token in tokens.iter() {
+                // with add-one smoothing, so that an unseen pair does not rule a category out
+                let token_count: f64 = *token_counts.get(token).unwrap_or(&0) as f64;
+                log_likelihood += ((token_count + 1.0) / (token_total + n_vocabulary)).ln();
+            }
+            log_likelihoods.push((category.clone(), log_likelihood));
+        }
+
+        // the exponentials are shifted by the largest to keep them from underflowing
+        let max_log_likelihood: f64 = log_likelihoods
+            .iter()
+            .map(|(_, log_likelihood)| *log_likelihood)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let total: f64 = log_likelihoods
+            .iter()
+            .map(|(_, log_likelihood)| (log_likelihood - max_log_likelihood).exp())
+            .sum();
+        let mut suggestions: Vec<CategorySuggestion> = log_likelihoods
+            .into_iter()
+            .map(|(category, log_likelihood)| CategorySuggestion {
+                category,
+                confidence: (log_likelihood - max_log_likelihood).exp() / total,
+            })
+            .filter(|suggestion| suggestion.confidence >= MIN_CONFIDENCE)
+            .collect();
+        suggestions.sort_by(|a, b| {
+            b.confidence
+                .total_cmp(&a.confidence)
+                .then_with(|| a.category.cmp(&b.category))
+        });
+        suggestions.truncate(n);
+
+        suggestions
+    }
+}
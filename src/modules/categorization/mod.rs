@@ -0,0 +1,15 @@
+pub mod classifier;
+pub mod rules;
+
+use crate::modules::financial::{Currency, TransactionType};
+
+/// What is known about an income or expense when it is entered or imported, from which its
+/// entity, category and tags are guessed.
+#[derive(Debug, PartialEq)]
+pub struct CategorizationSubject {
+    pub transaction_type: TransactionType, // Income or Expense
+    pub text: String, // description, and payee or name of the entity, as one text
+    pub value: f64,
+    pub currency: Currency,
+    pub account_id: i64, // account the money comes from or goes to, -1 if unknown
+}
@@ -0,0 +1,113 @@
+use crate::modules::categorization::CategorizationSubject;
+use crate::modules::financial::{Currency, TransactionType};
+use regex::{Regex, RegexBuilder};
+
+/// Rule that proposes the entity, category, subcategory and tags of the incomes or expenses
+/// that it matches. Conditions left empty match anything, and rules are tried from the
+/// lowest priority up until one matches.
+#[derive(Debug, PartialEq)]
+pub struct CategorizationRule {
+    pub name: String,
+    pub priority: i64,
+    pub transaction_type: TransactionType, // Income or Expense
+    pub description_pattern: String,       // regular expression, regardless of case
+    pub min_value: Option<f64>,
+    pub max_value: Option<f64>,
+    pub account_id: i64, // -1 if any account
+    pub currency: Option<Currency>,
+    pub entity_id: i64, // proposed entity, -1 to propose none
+    pub category: String,
+    pub subcategory: String,
+    pub tags: Vec<String>,
+}
+
+impl Default for CategorizationRule {
+    fn default() -> Self {
+        CategorizationRule {
+            name: String::default(),
+            priority: 0,
+            transaction_type: TransactionType::Expense,
+            description_pattern: String::default(),
+            min_value: None,
+            max_value: None,
+            account_id: -1,
+            currency: None,
+            entity_id: -1,
+            category: String::default(),
+            subcategory: String::default(),
+            tags: Vec::new(),
+        }
+    }
+}
+
+impl CategorizationRule {
+    /// Compiles the pattern on the description, which does not tell capitals apart.
+    pub(crate) fn regex(&self) -> Result<Regex, regex::Error> {
+        RegexBuilder::new(&self.description_pattern)
+            .case_insensitive(true)
+            .build()
+    }
+
+    /// Checks whether the subject meets every condition of the rule, with the pattern
+    /// already compiled.
+    pub(crate) fn matches(&self, regex: &Regex, subject: &CategorizationSubject) -> bool {
+        (self.transaction_type == subject.transaction_type)
+            & regex.is_match(&subject.text)
+            & self
+                .min_value
+                .is_none_or(|min_value| subject.value >= min_value)
+            & self
+                .max_value
+                .is_none_or(|max_value| subject.value <= max_value)
+            & ((self.account_id < 0) | (self.account_id == subject.account_id))
+            & self
+                .currency
+                .as_ref()
+                .is_none_or(|currency| *currency == subject.currency)
+    }
+}
+
+/// What a rule proposes for an income or expense, with the rule that fired.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleProposal {
+    pub rule_id: i64,
+    pub entity_id: i64, // -1 if the rule proposes none
+    pub category: String,
+    pub subcategory: String,
+    pub tags: Vec<String>,
+    pub explanation: String, // which rule fired, and why
+}
+
+/// Rules of the ledger with their patterns compiled and their explanations written, so that
+/// they can be tried on many incomes or expenses. Kept in the order they are tried.
+#[derive(Debug, Default)]
+pub struct CompiledRules {
+    rules: Vec<(i64, CategorizationRule, Regex, String)>, // ID, rule, pattern, explanation
+}
+
+impl CompiledRules {
+    pub(crate) fn push(
+        &mut self,
+        rule_id: i64,
+        rule: CategorizationRule,
+        regex: Regex,
+        explanation: String,
+    ) {
+        self.rules.push((rule_id, rule, regex, explanation));
+    }
+
+    /// Returns what the first rule that matches the subject proposes, if any rule does.
+    pub(crate) fn propose(&self, subject: &CategorizationSubject) -> Option<RuleProposal> {
+        self.rules
+            .iter()
+            .find(|(_, rule, regex, _)| rule.matches(regex, subject))
+            .map(|(rule_id, rule, _, explanation)| RuleProposal {
+                rule_id: *rule_id,
+                entity_id: rule.entity_id,
+                category: rule.category.clone(),
+                subcategory: rule.subcategory.clone(),
+                tags: rule.tags.clone(),
+                explanation: explanation.clone(),
+            })
+    }
+}
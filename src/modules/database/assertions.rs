@@ -0,0 +1,148 @@
+use crate::modules::database::{
+    capitalize_every_word, data_frame_to_csv_string, status_filter, DataBase,
+};
+use crate::modules::financial::{Account, BalanceAssertion, Currency};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::vec::IntoIter;
+
+impl DataBase {
+    pub fn insert_balance_assertion(
+        &mut self,
+        balance_assertion: &BalanceAssertion,
+    ) -> Result<i64, PolarsError> {
+        self.balance_assertion_table
+            .insert_balance_assertion(balance_assertion)
+    }
+
+    pub(crate) fn iter_balance_assertion_ids(&self) -> Result<IntoIter<i64>, PolarsError> {
+        self.balance_assertion_table.iter()
+    }
+
+    pub(crate) fn balance_assertion(
+        &self,
+        balance_assertion_id: i64,
+    ) -> Result<BalanceAssertion, Box<dyn std::error::Error>> {
+        self.balance_assertion_table
+            .balance_assertion(balance_assertion_id)
+    }
+
+    pub(crate) fn delete_balance_assertion(
+        &mut self,
+        balance_assertion_id: i64,
+    ) -> Result<(), PolarsError> {
+        self.balance_assertion_table
+            .delete_balance_assertion(balance_assertion_id)
+    }
+
+    /// Returns the balance of the account in the given currency at the end of the date: the
+    /// initial balance (if the account is in that currency) plus all the fund movements up
+    /// to the date, included, that are neither planned nor void.
+    pub(crate) fn account_balance(
+        &self,
+        account_id: i64,
+        currency: &Currency,
+        date: NaiveDate,
+    ) -> Result<f64, Box<dyn std::error::Error>> {
+        let account: Account = self.account(account_id)?;
+        let initial_balance: f64 = if account.currency() == currency {
+            account.initial_balance()
+        } else {
+            0.0
+        };
+
+        let movements: f64 = self
+            .funds_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("account_id")
+                    .eq(lit(account_id))
+                    .and(col("currency").eq(lit(currency.to_string())))
+                    .and(col("date").lt_eq(lit(date)))
+                    .and(status_filter(false)),
+            )
+            .collect()?
+            .column("value")?
+            .f64()?
+            .sum()
+            .unwrap_or(0.0);
+
+        Ok(((initial_balance + movements) * 100.0).round() / 100.0)
+    }
+
+    /// Checks every balance assertion against the balance derived from the fund movements.
+    pub(crate) fn balance_assertions(&self) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let mut account_names: Vec<String> = Vec::new();
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        let mut currencies: Vec<String> = Vec::new();
+        let mut asserted_values: Vec<f64> = Vec::new();
+        let mut actual_values: Vec<f64> = Vec::new();
+        let mut balance_assertion_ids: Vec<i64> = Vec::new();
+
+        for balance_assertion_id in self.iter_balance_assertion_ids()? {
+            let balance_assertion: BalanceAssertion =
+                self.balance_assertion(balance_assertion_id)?;
+            let account: Account = self.account(balance_assertion.account_id())?;
+
+            account_names.push(account.name());
+            dates.push(*balance_assertion.date());
+            currencies.push(balance_assertion.currency().to_string());
+            asserted_values.push(balance_assertion.value());
+            actual_values.push(self.account_balance(
+                balance_assertion.account_id(),
+                balance_assertion.currency(),
+                *balance_assertion.date(),
+            )?);
+            balance_assertion_ids.push(balance_assertion_id);
+        }
+
+        Ok(df!(
+            "account_name" => account_names,
+            "date" => dates,
+            "currency" => currencies,
+            "asserted" => asserted_values,
+            "actual" => actual_values,
+            "balance_assertion_id" => balance_assertion_ids,
+        )?
+        .lazy()
+        .with_column(
+            (col("actual") - col("asserted"))
+                .round(2)
+                .alias("difference"),
+        )
+        .with_column(
+            when(col("difference").abs().lt(lit(0.005)))
+                .then(lit("Holds"))
+                .otherwise(lit("Fails"))
+                .alias("status"),
+        )
+        .select([
+            col("account_name"),
+            col("date"),
+            col("currency"),
+            col("asserted"),
+            col("actual"),
+            col("difference"),
+            col("status"),
+            col("balance_assertion_id"),
+        ])
+        .collect()?)
+    }
+
+    /// Returns a csv in String format with the result of every balance assertion.
+    pub(crate) fn balance_assertions_report(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut report: DataFrame = self
+            .balance_assertions()?
+            .lazy()
+            .select([all().name().map(|name| {
+                Ok(PlSmallStr::from_string(capitalize_every_word(
+                    name.replace("_", " "),
+                )))
+            })])
+            .collect()?;
+
+        data_frame_to_csv_string(&mut report)
+    }
+}
@@ -0,0 +1,165 @@
+use crate::modules::currency_exchange::CurrencyExchange;
+use crate::modules::database::{capitalize_every_word, data_frame_to_csv_string, DataBase};
+use crate::modules::financial::{Budget, BudgetPeriod, Currency};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::vec::IntoIter;
+
+impl DataBase {
+    pub fn insert_budget(&mut self, budget: &Budget) -> Result<i64, PolarsError> {
+        self.budget_table.insert_budget(budget)
+    }
+
+    pub(crate) fn iter_budget_ids(&self) -> Result<IntoIter<i64>, PolarsError> {
+        self.budget_table.iter()
+    }
+
+    pub(crate) fn budget(&self, budget_id: i64) -> Result<Budget, Box<dyn std::error::Error>> {
+        self.budget_table.budget(budget_id)
+    }
+
+    pub(crate) fn delete_budget(&mut self, budget_id: i64) -> Result<(), PolarsError> {
+        self.budget_table.delete_budget(budget_id)
+    }
+
+    /// Compares every budget with the expenses of its period (the month or the year that
+    /// contains the given date) up to the given date. Besides the amount spent and the
+    /// remaining amount, the spending at the end of the period is projected linearly.
+    /// All amounts are expressed in the currency_to, exchanged at the given date.
+    pub(crate) fn budget_vs_actual(
+        &self,
+        currency_exchange: &CurrencyExchange,
+        date: NaiveDate,
+        currency_to: &Currency,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        // the actual expenses only depend on the period, so they are computed once per period
+        let mut actuals: HashMap<String, DataFrame> = HashMap::new();
+
+        let mut budget_ids: Vec<i64> = Vec::new();
+        let mut categories: Vec<String> = Vec::new();
+        let mut subcategories: Vec<String> = Vec::new();
+        let mut periods: Vec<String> = Vec::new();
+        let mut budgeted_values: Vec<f64> = Vec::new();
+        let mut spent_values: Vec<f64> = Vec::new();
+        let mut projected_values: Vec<f64> = Vec::new();
+        let mut statuses: Vec<String> = Vec::new();
+
+        for budget_id in self.iter_budget_ids()? {
+            let budget: Budget = self.budget(budget_id)?;
+            let period: &BudgetPeriod = budget.period();
+            let (first_day, last_day) = period.bounds(date);
+
+            if let Entry::Vacant(entry) = actuals.entry(period.to_string()) {
+                let actual: DataFrame = self
+                    .expenses_by_subcategory(
+                        currency_exchange,
+                        first_day,
+                        date,
+                        currency_to,
+                        false,
+                    )?
+                    .lazy()
+                    .with_column(col("subcategory").fill_null(lit("")))
+                    .collect()?;
+                entry.insert(actual);
+            }
+            let actual: &DataFrame = actuals.get(&period.to_string()).ok_or("No actuals!")?;
+
+            // a budget on a subcategory covers the ones nested below it too
+            let subcategory: String = budget.subcategory();
+            let nested_prefix: String = format!("{subcategory}:");
+            let in_budget = |spent_subcategory: &str| {
+                subcategory.is_empty()
+                    | (spent_subcategory == subcategory)
+                    | spent_subcategory.starts_with(&nested_prefix)
+            };
+            let category_actual: DataFrame = actual
+                .clone()
+                .lazy()
+                .filter(col("category").eq(lit(budget.category())))
+                .collect()?;
+            let spent: f64 = category_actual
+                .column("subcategory")?
+                .str()?
+                .into_iter()
+                .zip(
+                    category_actual
+                        .column(currency_to.to_string().as_str())?
+                        .f64()?,
+                )
+                .filter(|(spent_subcategory, _)| in_budget(spent_subcategory.unwrap_or("")))
+                .fold(0.0, |spent, (_, value)| spent + value.unwrap_or(0.0));
+
+            let budgeted: f64 = budget.value()
+                * currency_exchange.exchange_currency(budget.currency(), currency_to, date)?;
+            let elapsed_days: i64 = date.signed_duration_since(first_day).num_days() + 1;
+            let period_days: i64 = last_day.signed_duration_since(first_day).num_days() + 1;
+            let projected: f64 = spent * period_days as f64 / elapsed_days as f64;
+
+            let status: &str = if spent > budgeted {
+                "Overrun"
+            } else if projected > budgeted {
+                "Projected overrun"
+            } else {
+                "On track"
+            };
+
+            budget_ids.push(budget_id);
+            categories.push(budget.category());
+            subcategories.push(budget.subcategory());
+            periods.push(period.to_string());
+            budgeted_values.push(budgeted);
+            spent_values.push(spent);
+            projected_values.push(projected);
+            statuses.push(status.to_string());
+        }
+
+        Ok(df!(
+            "category" => categories,
+            "subcategory" => subcategories,
+            "period" => periods,
+            "budget" => budgeted_values,
+            "spent" => spent_values,
+            "projected" => projected_values,
+            "status" => statuses,
+            "budget_id" => budget_ids,
+        )?
+        .lazy()
+        .with_column((col("budget") - col("spent")).alias("remaining"))
+        .select([
+            col("category"),
+            col("subcategory"),
+            col("period"),
+            col("budget").round(2),
+            col("spent").round(2),
+            col("remaining").round(2),
+            col("projected").round(2),
+            col("status"),
+            col("budget_id"),
+        ])
+        .collect()?)
+    }
+
+    /// Returns a csv in String format with the budget-vs-actual report.
+    pub(crate) fn budget_report(
+        &self,
+        date: NaiveDate,
+        currency_to: &Currency,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
+
+        let mut report: DataFrame = self
+            .budget_vs_actual(&currency_exchange, date, currency_to)?
+            .lazy()
+            .select([all().name().map(|name| {
+                Ok(PlSmallStr::from_string(capitalize_every_word(
+                    name.replace("_", " "),
+                )))
+            })])
+            .collect()?;
+
+        data_frame_to_csv_string(&mut report)
+    }
+}
@@ -0,0 +1,349 @@
+use crate::modules::database::DataBase;
+use crate::modules::financial::{category_path, Category, TransactionType};
+use polars::prelude::*;
+use std::collections::HashMap;
+
+/// Checks that the name can be a level of the hierarchy of categories. Colons separate the
+/// levels in the subcategory of the records.
+fn is_valid_category_name(name: &str) -> bool {
+    !name.trim().is_empty() & !name.contains(":")
+}
+
+impl DataBase {
+    pub(crate) fn category(
+        &self,
+        category_id: i64,
+    ) -> Result<Category, Box<dyn std::error::Error>> {
+        self.category_table.category(category_id)
+    }
+
+    /// Returns every category of the type with its path from the top, parents before their
+    /// children and siblings sorted by name.
+    pub(crate) fn category_paths(
+        &self,
+        transaction_type: &TransactionType,
+    ) -> Result<Vec<(i64, Vec<String>)>, PolarsError> {
+        let categories: DataFrame = self
+            .category_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("transaction_type").eq(lit(transaction_type.to_string())))
+            .sort(["name"], Default::default())
+            .collect()?;
+
+        let mut children: HashMap<i64, Vec<(i64, String)>> = HashMap::new();
+        for ((category_id, name), parent_id) in categories
+            .column("category_id")?
+            .i64()?
+            .into_no_null_iter()
+            .zip(categories.column("name")?.str()?.into_no_null_iter())
+            .zip(categories.column("parent_id")?.i64()?.into_no_null_iter())
+        {
+            children
+                .entry(parent_id)
+                .or_default()
+                .push((category_id, name.to_string()));
+        }
+
+        let mut paths: Vec<(i64, Vec<String>)> = Vec::new();
+        // last in, first out, so that the children are pushed in reverse order
+        let mut pending: Vec<(i64, Vec<String>)> = Vec::new();
+        for (category_id, name) in children.get(&-1).into_iter().flatten().rev() {
+            pending.push((*category_id, vec![name.clone()]));
+        }
+        while let Some((category_id, path)) = pending.pop() {
+            for (child_id, name) in children.get(&category_id).into_iter().flatten().rev() {
+                let mut child_path: Vec<String> = path.clone();
+                child_path.push(name.clone());
+                pending.push((*child_id, child_path));
+            }
+            paths.push((category_id, path));
+        }
+
+        Ok(paths)
+    }
+
+    /// Returns the path of the category from the top.
+    pub(crate) fn category_path(
+        &self,
+        category_id: i64,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let category: Category = self.category(category_id)?;
+        Ok(self
+            .category_paths(category.transaction_type())?
+            .into_iter()
+            .find(|(id, _path)| *id == category_id)
+            .map(|(_id, path)| path)
+            .ok_or("The category is not linked to the top level!")?)
+    }
+
+    /// Returns the ID of the category of the type at the end of the path, if it exists.
+    pub(crate) fn find_category(
+        &self,
+        transaction_type: &TransactionType,
+        path: &[String],
+    ) -> Result<Option<i64>, PolarsError> {
+        let mut category_id: i64 = -1;
+        for name in path {
+            match self
+                .category_table
+                .child(transaction_type, category_id, name)?
+            {
+                Some(child_id) => category_id = child_id,
+                None => return Ok(None),
+            }
+        }
+
+        Ok(if path.is_empty() {
+            None
+        } else {
+            Some(category_id)
+        })
+    }
+
+    /// Checks whether the category and subcategory of a transaction are in the registry.
+    pub(crate) fn is_known_category(
+        &self,
+        transaction_type: &TransactionType,
+        category: &str,
+        subcategory: &str,
+    ) -> Result<bool, PolarsError> {
+        Ok(self
+            .find_category(transaction_type, &category_path(category, subcategory))?
+            .is_some())
+    }
+
+    /// Adds the category and subcategory of a transaction to the registry, with all the
+    /// levels of the path that are missing. Returns the ID of the deepest one.
+    pub(crate) fn register_category(
+        &mut self,
+        transaction_type: &TransactionType,
+        category: &str,
+        subcategory: &str,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        if (transaction_type != &TransactionType::Income)
+            & (transaction_type != &TransactionType::Expense)
+        {
+            return Err("Only incomes and expenses have categories!".into());
+        }
+        let path: Vec<String> = category_path(category, subcategory);
+        if !path.iter().all(|name| is_valid_category_name(name)) {
+            return Err("Invalid category name!".into());
+        }
+
+        Ok(self.insert_category_path(transaction_type, &path)?)
+    }
+
+    /// Adds the levels of the path that are missing in the registry, and returns the ID of
+    /// the deepest one.
+    fn insert_category_path(
+        &mut self,
+        transaction_type: &TransactionType,
+        path: &[String],
+    ) -> Result<i64, PolarsError> {
+        let mut category_id: i64 = -1;
+        for name in path.iter() {
+            category_id = match self
+                .category_table
+                .child(transaction_type, category_id, name)?
+            {
+                Some(child_id) => child_id,
+                None => self.category_table.insert_category(&Category::new(
+                    transaction_type.clone(),
+                    name.clone(),
+                    category_id,
+                ))?,
+            };
+        }
+
+        Ok(category_id)
+    }
+
+    /// Fills an empty registry with the categories in use, so that databases from before
+    /// the registry existed keep their categories.
+    pub(crate) fn seed_categories(&mut self) -> Result<(), PolarsError> {
+        if self.category_table.data_frame.height() > 0 {
+            return Ok(());
+        }
+
+        let mut in_use: Vec<(TransactionType, String, String)> = Vec::new();
+        for (transaction_type, data_frame) in [
+            (TransactionType::Income, &self.incomes_table.data_frame),
+            (TransactionType::Expense, &self.expenses_table.data_frame),
+            (TransactionType::Expense, &self.budget_table.data_frame),
+        ] {
+            for (category, subcategory) in data_frame
+                .column("category")?
+                .str()?
+                .into_iter()
+                .zip(data_frame.column("subcategory")?.str()?)
+            {
+                // empty strings are read as nulls
+                in_use.push((
+                    transaction_type.clone(),
+                    category.unwrap_or("").to_string(),
+                    subcategory.unwrap_or("").to_string(),
+                ));
+            }
+        }
+        let templates: &DataFrame = &self.template_transaction_table.data_frame;
+        for ((transaction_type, category), subcategory) in templates
+            .column("transaction_type")?
+            .str()?
+            .into_iter()
+            .zip(templates.column("category")?.str()?)
+            .zip(templates.column("subcategory")?.str()?)
+        {
+            for possible_type in [TransactionType::Income, TransactionType::Expense] {
+                if transaction_type == Some(possible_type.to_string().as_str()) {
+                    in_use.push((
+                        possible_type,
+                        category.unwrap_or("").to_string(),
+                        subcategory.unwrap_or("").to_string(),
+                    ));
+                }
+            }
+        }
+
+        for (transaction_type, category, subcategory) in in_use {
+            let path: Vec<String> = category_path(&category, &subcategory);
+            // records without a valid category are left as they are
+            if path.iter().all(|name| is_valid_category_name(name)) {
+                self.insert_category_path(&transaction_type, &path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Files all the records under the old path, or below it, under the new path.
+    fn rewrite_category(
+        &mut self,
+        transaction_type: &TransactionType,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), PolarsError> {
+        match transaction_type {
+            TransactionType::Income => {
+                self.incomes_table.rewrite_category(old_path, new_path)?;
+            }
+            TransactionType::Expense => {
+                self.expenses_table.rewrite_category(old_path, new_path)?;
+                self.budget_table.rewrite_category(old_path, new_path)?;
+                self.assignment_table.rewrite_category(old_path, new_path)?;
+            }
+            _ => {}
+        }
+        self.template_transaction_table
+            .rewrite_category(transaction_type, old_path, new_path)?;
+        self.staging_table
+            .rewrite_category(transaction_type, old_path, new_path)?;
+        self.categorization_rule_table
+            .rewrite_category(transaction_type, old_path, new_path)
+    }
+
+    /// Renames the category in the registry and in every record filed under it.
+    pub(crate) fn rename_category(
+        &mut self,
+        category_id: i64,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let name: &str = name.trim();
+        if !is_valid_category_name(name) {
+            return Err("Invalid category name!".into());
+        }
+        let category: Category = self.category(category_id)?;
+        if self
+            .category_table
+            .child(category.transaction_type(), category.parent_id(), name)?
+            .is_some()
+        {
+            return Err(format!("{name} already exists, merge them instead!").into());
+        }
+
+        let old_path: Vec<String> = self.category_path(category_id)?;
+        let mut new_path: Vec<String> = old_path.clone();
+        new_path.pop();
+        new_path.push(name.to_string());
+
+        self.rewrite_category(category.transaction_type(), &old_path, &new_path)?;
+        self.category_table.rename(category_id, name)?;
+
+        Ok(())
+    }
+
+    /// Moves the category, and everything under it, under another parent, or to the top
+    /// level if the parent is -1. The records filed under it are moved along.
+    pub(crate) fn move_category(
+        &mut self,
+        category_id: i64,
+        parent_id: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let category: Category = self.category(category_id)?;
+        let old_path: Vec<String> = self.category_path(category_id)?;
+        let mut new_path: Vec<String> = if parent_id >= 0 {
+            if self.category(parent_id)?.transaction_type() != category.transaction_type() {
+                return Err("Incomes and expenses have separate categories!".into());
+            }
+            self.category_path(parent_id)?
+        } else {
+            Vec::new()
+        };
+        if new_path.starts_with(&old_path) {
+            return Err("Cannot move a category under itself!".into());
+        }
+        if self
+            .category_table
+            .child(category.transaction_type(), parent_id, &category.name())?
+            .is_some()
+        {
+            return Err(format!("{} already exists there, merge them instead!", category).into());
+        }
+        new_path.push(category.name());
+
+        self.rewrite_category(category.transaction_type(), &old_path, &new_path)?;
+        self.category_table.set_parent(category_id, parent_id)?;
+
+        Ok(())
+    }
+
+    /// Merges a category into another one of the same type: the records filed under it are
+    /// filed under the other one, and so are its subcategories, merging those with the same
+    /// name. The merged category is deleted.
+    pub(crate) fn merge_categories(
+        &mut self,
+        from_id: i64,
+        into_id: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let from_category: Category = self.category(from_id)?;
+        let into_category: Category = self.category(into_id)?;
+        if from_category.transaction_type() != into_category.transaction_type() {
+            return Err("Incomes and expenses have separate categories!".into());
+        }
+        let from_path: Vec<String> = self.category_path(from_id)?;
+        let into_path: Vec<String> = self.category_path(into_id)?;
+        if into_path.starts_with(&from_path) {
+            return Err("Cannot merge a category into itself or one under it!".into());
+        }
+
+        self.rewrite_category(from_category.transaction_type(), &from_path, &into_path)?;
+        for child_id in self
+            .category_table
+            .children(from_category.transaction_type(), from_id)?
+        {
+            let child_name: String = self.category(child_id)?.name();
+            match self.category_table.child(
+                from_category.transaction_type(),
+                into_id,
+                &child_name,
+            )? {
+                Some(twin_id) => self.merge_categories(child_id, twin_id)?,
+                None => self.category_table.set_parent(child_id, into_id)?,
+            }
+        }
+        self.category_table.delete_category(from_id)?;
+
+        Ok(())
+    }
+}
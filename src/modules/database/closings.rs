@@ -0,0 +1,109 @@
+use crate::modules::database::{status_filter, DataBase};
+use crate::modules::financial::Account;
+use chrono::NaiveDate;
+use polars::prelude::*;
+
+impl DataBase {
+    /// Returns the balance of every currency the account has moved funds in, counting the
+    /// planned movements too, in a column named value.
+    fn account_balances(&self, account_id: i64) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let account: Account = self.account(account_id)?;
+        let initial_balance: DataFrame = df!(
+            "currency" => [account.currency().to_string()],
+            "value" => [account.initial_balance()],
+        )?;
+
+        Ok(self
+            .funds_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("account_id").eq(lit(account_id)))
+            .filter(status_filter(true))
+            .select([col("currency"), col("value")])
+            .collect()?
+            .vstack(&initial_balance)?
+            .lazy()
+            .group_by([col("currency")])
+            .agg([col("value").sum()])
+            .sort(["currency"], Default::default())
+            .collect()?)
+    }
+
+    /// Closes the account on the closing date. It has to be empty in all its currencies, and
+    /// without movements after that date. The account is kept, so that its history still
+    /// shows in every report, but it is no longer offered when inputting.
+    pub(crate) fn close_account(
+        &mut self,
+        account_id: i64,
+        closing_date: NaiveDate,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.account_table.closing_date(account_id)?.is_some() {
+            return Err("Account already closed!".into());
+        }
+
+        let later_movements: usize = self
+            .funds_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("account_id").eq(lit(account_id)))
+            .filter(status_filter(true))
+            .filter(col("date").gt(lit(closing_date)))
+            .collect()?
+            .height();
+        if later_movements > 0 {
+            return Err(format!(
+                "The account has {later_movements} movements after {closing_date}!"
+            )
+            .into());
+        }
+
+        let balances: DataFrame = self.account_balances(account_id)?;
+        for (currency, value) in balances
+            .column("currency")?
+            .str()?
+            .into_no_null_iter()
+            .zip(balances.column("value")?.f64()?.into_no_null_iter())
+        {
+            // one or more cents left
+            if value.abs() >= 0.01 {
+                return Err(format!("The account still holds {value:.2} {currency}!").into());
+            }
+        }
+
+        self.account_table
+            .set_closing_date(account_id, Some(closing_date))?;
+
+        Ok(())
+    }
+
+    /// Reopens a closed account, offering it again when inputting.
+    pub(crate) fn reopen_account(&mut self, account_id: i64) -> Result<(), PolarsError> {
+        self.account_table.set_closing_date(account_id, None)
+    }
+
+    pub(crate) fn account_closing_date(
+        &self,
+        account_id: i64,
+    ) -> Result<Option<NaiveDate>, PolarsError> {
+        self.account_table.closing_date(account_id)
+    }
+
+    /// Archives the entity, or brings it back if archived is false. Archived entities are
+    /// no longer offered when inputting, but their transactions stay in every report.
+    pub(crate) fn set_entity_archived(
+        &mut self,
+        entity_id: i64,
+        archived: bool,
+    ) -> Result<(), PolarsError> {
+        self.entity_table.set_archived(entity_id, archived)
+    }
+
+    pub(crate) fn is_entity_archived(
+        &self,
+        entity_id: i64,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        self.entity_table.is_archived(entity_id)
+    }
+}
@@ -0,0 +1,360 @@
+use crate::modules::database::{capitalize_every_word, data_frame_to_csv_string, DataBase};
+use crate::modules::financial::{Party, Transaction, TransactionStatus, TransactionType};
+use crate::modules::tables::{FundsTable, Table};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Days apart that two transactions can be and still be taken for the same one, since a
+/// receipt and the booking of the bank rarely have the same date.
+pub(crate) const DUPLICATE_DAYS: i64 = 3;
+
+/// Share of the words of the shorter description that the other one has to contain.
+const DESCRIPTION_SIMILARITY: f64 = 0.5;
+
+/// Returns the words of a description, in lowercase and without punctuation.
+fn words(description: &str) -> HashSet<String> {
+    description
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Checks whether two descriptions tell about the same thing: most of the words of the
+/// shorter one are in the longer one, like "Aldi" and "ALDI SUED 1234". A missing
+/// description tells nothing against it.
+pub(crate) fn is_similar_description(description: &str, other_description: &str) -> bool {
+    let words_a: HashSet<String> = words(description);
+    let words_b: HashSet<String> = words(other_description);
+    if words_a.is_empty() | words_b.is_empty() {
+        return true;
+    }
+    let shared: usize = words_a.intersection(&words_b).count();
+    shared as f64 / words_a.len().min(words_b.len()) as f64 >= DESCRIPTION_SIMILARITY
+}
+
+/// Transaction of the ledger, with what tells it apart from the others.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateCandidate {
+    pub transaction_type: String,
+    pub party_id: i64, // -1 if the transaction is not in the ledger yet
+    pub date: NaiveDate,
+    pub value: f64,
+    pub currency: String,
+    pub counterpart_id: i64, // entity of incomes and expenses, account of fund movements
+    pub description: String, // empty for fund movements
+}
+
+impl DuplicateCandidate {
+    pub(crate) fn from_transaction(transaction: &Transaction, party_id: i64) -> Self {
+        let (counterpart_id, description) = match transaction {
+            Transaction::Income {
+                entity_id,
+                description,
+                ..
+            }
+            | Transaction::Expense {
+                entity_id,
+                description,
+                ..
+            } => (*entity_id, description.clone()),
+            Transaction::Credit { account_id, .. } | Transaction::Debit { account_id, .. } => {
+                (*account_id, String::default())
+            }
+        };
+        DuplicateCandidate {
+            transaction_type: transaction.transaction_type(),
+            party_id,
+            date: *transaction.date(),
+            value: transaction.value(),
+            currency: transaction.currency().to_string(),
+            counterpart_id,
+            description,
+        }
+    }
+
+    /// Checks whether both transactions look like the same one entered twice: same type,
+    /// entity or account, amount and currency, close dates and similar descriptions.
+    pub(crate) fn is_duplicate_of(&self, other: &DuplicateCandidate) -> bool {
+        (self.transaction_type == other.transaction_type)
+            & (self.counterpart_id == other.counterpart_id)
+            & (self.currency == other.currency)
+            & ((self.value - other.value).abs() < 0.005)
+            & ((self.date - other.date).num_days().abs() <= DUPLICATE_DAYS)
+            & is_similar_description(&self.description, &other.description)
+    }
+}
+
+/// Returns the legs that tell a party apart: its incomes and expenses, or its fund
+/// movements when it has nothing else, like a transfer. Fund movements have no
+/// description, so the payments of the same amount from an account would all look alike.
+fn distinctive_legs(legs: Vec<DuplicateCandidate>) -> Vec<DuplicateCandidate> {
+    let is_fund_movement = |leg: &DuplicateCandidate| {
+        (leg.transaction_type == TransactionType::Credit.to_string())
+            | (leg.transaction_type == TransactionType::Debit.to_string())
+    };
+    if legs.iter().all(is_fund_movement) {
+        legs
+    } else {
+        legs.into_iter()
+            .filter(|leg| !is_fund_movement(leg))
+            .collect()
+    }
+}
+
+/// Checks whether two parties look like the same one entered twice: every leg of each has
+/// a look-alike in the other.
+fn are_duplicate_parties(legs: &[DuplicateCandidate], other_legs: &[DuplicateCandidate]) -> bool {
+    !legs.is_empty()
+        & !other_legs.is_empty()
+        & legs
+            .iter()
+            .all(|leg| other_legs.iter().any(|other| leg.is_duplicate_of(other)))
+        & other_legs
+            .iter()
+            .all(|other| legs.iter().any(|leg| other.is_duplicate_of(leg)))
+}
+
+impl Display for DuplicateCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {:.2} {} on {}",
+            self.transaction_type, self.value, self.currency, self.date
+        )?;
+        if !self.description.is_empty() {
+            write!(f, " ({})", self.description)?;
+        }
+        write!(f, " in party {}", self.party_id)
+    }
+}
+
+impl DataBase {
+    /// Returns the transactions of the type in the ledger that pass the filter, sorted by
+    /// date. Void transactions are left out, since they never count.
+    fn ledger_candidates(
+        &self,
+        transaction_type: &TransactionType,
+        filter: Expr,
+    ) -> Result<Vec<DuplicateCandidate>, Box<dyn std::error::Error>> {
+        let (data_frame, counterpart) = match transaction_type {
+            TransactionType::Income => (self.incomes_table.data_frame.clone().lazy(), "entity_id"),
+            // refunds are stored as negative expenses, and are told apart by their fund
+            // movements instead, so that they are not taken for the expense they give back
+            TransactionType::Expense => (
+                self.expenses_table
+                    .data_frame
+                    .clone()
+                    .lazy()
+                    .filter(col("refund_of").lt(lit(0i64))),
+                "entity_id",
+            ),
+            TransactionType::Credit | TransactionType::Debit => (
+                self.funds_table
+                    .data_frame
+                    .clone()
+                    .lazy()
+                    .filter(
+                        col(format!("{}_type", FundsTable::name()).as_str())
+                            .eq(lit(transaction_type.to_string())),
+                    )
+                    // debits are stored with a negative value
+                    .with_column(col("value").abs())
+                    .with_column(lit("").alias("description")),
+                "account_id",
+            ),
+            TransactionType::Refund => return Ok(Vec::new()),
+        };
+        let data_frame: DataFrame = data_frame
+            .filter(
+                col("status")
+                    .neq(lit(TransactionStatus::Void.to_string()))
+                    .and(filter),
+            )
+            .sort(["date"], Default::default())
+            .collect()?;
+
+        let party_ids = data_frame.column("party_id")?.i64()?;
+        let mut dates = data_frame.column("date")?.date()?.as_date_iter();
+        let values = data_frame.column("value")?.f64()?;
+        let currencies = data_frame.column("currency")?.str()?;
+        let counterpart_ids = data_frame.column(counterpart)?.i64()?;
+        let descriptions = data_frame.column("description")?.str()?;
+
+        let mut candidates: Vec<DuplicateCandidate> = Vec::new();
+        for i in 0..data_frame.height() {
+            candidates.push(DuplicateCandidate {
+                transaction_type: transaction_type.to_string(),
+                party_id: party_ids.get(i).ok_or("No party_id!")?,
+                date: dates.next().flatten().ok_or("No date!")?,
+                value: values.get(i).ok_or("No value!")?,
+                currency: currencies.get(i).ok_or("No currency!")?.to_string(),
+                counterpart_id: counterpart_ids.get(i).ok_or("No counterpart!")?,
+                // empty descriptions are read as nulls
+                description: descriptions.get(i).unwrap_or("").to_string(),
+            });
+        }
+
+        Ok(candidates)
+    }
+
+    /// Returns the legs of a party of the ledger that tell it apart from the others.
+    fn party_legs(
+        &self,
+        party_id: i64,
+    ) -> Result<Vec<DuplicateCandidate>, Box<dyn std::error::Error>> {
+        let mut legs: Vec<DuplicateCandidate> = Vec::new();
+        for transaction_type in [
+            TransactionType::Income,
+            TransactionType::Expense,
+            TransactionType::Credit,
+            TransactionType::Debit,
+        ] {
+            legs.extend(
+                self.ledger_candidates(&transaction_type, col("party_id").eq(lit(party_id)))?,
+            );
+        }
+
+        Ok(distinctive_legs(legs))
+    }
+
+    /// Returns the transactions of the ledger that the party could be duplicating, at most
+    /// one per party of the ledger. Whole parties are compared, so that two payments of the
+    /// same amount from the same account to different entities are not taken for the same.
+    pub(crate) fn find_duplicates(
+        &self,
+        party: &Party,
+    ) -> Result<Vec<DuplicateCandidate>, Box<dyn std::error::Error>> {
+        let legs: Vec<DuplicateCandidate> = distinctive_legs(
+            party
+                .transactions
+                .iter()
+                // refunds are compared by their fund movements, like in the ledger
+                .filter(|transaction| {
+                    !matches!(transaction, Transaction::Expense { refund_of, .. } if *refund_of >= 0)
+                })
+                .map(|transaction| DuplicateCandidate::from_transaction(transaction, -1))
+                .collect(),
+        );
+        let Some(candidate) = legs.first() else {
+            return Ok(Vec::new());
+        };
+
+        // narrows the ledger down with one leg before comparing whole parties
+        let first_date: NaiveDate = candidate.date - chrono::Days::new(DUPLICATE_DAYS as u64);
+        let last_date: NaiveDate = candidate.date + chrono::Days::new(DUPLICATE_DAYS as u64);
+        let filter: Expr = col("currency")
+            .eq(lit(candidate.currency.clone()))
+            .and((col("value") - lit(candidate.value)).abs().lt(lit(0.005)))
+            .and(col("date").gt_eq(lit(first_date).cast(DataType::Date)))
+            .and(col("date").lt_eq(lit(last_date).cast(DataType::Date)));
+
+        let mut duplicates: Vec<DuplicateCandidate> = Vec::new();
+        let mut party_ids: HashSet<i64> = HashSet::new();
+        for other in self.ledger_candidates(
+            &TransactionType::from_str(&candidate.transaction_type)?,
+            filter,
+        )? {
+            // each party of the ledger is compared once
+            if !candidate.is_duplicate_of(&other) || !party_ids.insert(other.party_id) {
+                continue;
+            }
+            if are_duplicate_parties(&legs, &self.party_legs(other.party_id)?) {
+                duplicates.push(other);
+            }
+        }
+
+        Ok(duplicates)
+    }
+
+    /// Looks through the ledger for parties that look like entered twice. Returns one row per
+    /// pair of parties, with the first transaction that made them look alike.
+    pub(crate) fn duplicates(&self) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let mut party_ids: Vec<i64> = Vec::new();
+        let mut duplicate_party_ids: Vec<i64> = Vec::new();
+        let mut transaction_types: Vec<String> = Vec::new();
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        let mut duplicate_dates: Vec<NaiveDate> = Vec::new();
+        let mut values: Vec<f64> = Vec::new();
+        let mut currencies: Vec<String> = Vec::new();
+        let mut descriptions: Vec<String> = Vec::new();
+
+        let mut pairs: HashSet<(i64, i64)> = HashSet::new();
+        let mut legs: HashMap<i64, Vec<DuplicateCandidate>> = HashMap::new();
+        for transaction_type in [
+            TransactionType::Income,
+            TransactionType::Expense,
+            TransactionType::Credit,
+            TransactionType::Debit,
+        ] {
+            let candidates: Vec<DuplicateCandidate> =
+                self.ledger_candidates(&transaction_type, lit(true))?;
+            for (i, candidate) in candidates.iter().enumerate() {
+                // sorted by date, so the ones further on are too late once one is
+                for other in candidates[i + 1..]
+                    .iter()
+                    .take_while(|other| (other.date - candidate.date).num_days() <= DUPLICATE_DAYS)
+                {
+                    if (candidate.party_id == other.party_id) | !candidate.is_duplicate_of(other) {
+                        continue;
+                    }
+                    let pair: (i64, i64) = (
+                        candidate.party_id.min(other.party_id),
+                        candidate.party_id.max(other.party_id),
+                    );
+                    // whole parties are compared once per pair
+                    if !pairs.insert(pair) {
+                        continue;
+                    }
+                    for party_id in [candidate.party_id, other.party_id] {
+                        if let Entry::Vacant(entry) = legs.entry(party_id) {
+                            entry.insert(self.party_legs(party_id)?);
+                        }
+                    }
+                    if !are_duplicate_parties(&legs[&candidate.party_id], &legs[&other.party_id]) {
+                        continue;
+                    }
+                    party_ids.push(candidate.party_id);
+                    duplicate_party_ids.push(other.party_id);
+                    transaction_types.push(transaction_type.to_string());
+                    dates.push(candidate.date);
+                    duplicate_dates.push(other.date);
+                    values.push(candidate.value);
+                    currencies.push(candidate.currency.clone());
+                    descriptions.push(candidate.description.clone());
+                }
+            }
+        }
+
+        Ok(df!(
+            "party_id" => party_ids,
+            "duplicate_party_id" => duplicate_party_ids,
+            "transaction_type" => transaction_types,
+            "date" => dates,
+            "duplicate_date" => duplicate_dates,
+            "value" => values,
+            "currency" => currencies,
+            "description" => descriptions,
+        )?
+        .sort(["date", "party_id"], Default::default())?)
+    }
+
+    /// Returns a csv in String format with the parties that look like entered twice.
+    pub(crate) fn duplicates_report(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut report: DataFrame = self
+            .duplicates()?
+            .lazy()
+            .select([all().name().map(|name| {
+                Ok(PlSmallStr::from_string(capitalize_every_word(
+                    name.replace("_", " "),
+                )))
+            })])
+            .collect()?;
+
+        data_frame_to_csv_string(&mut report)
+    }
+}
@@ -0,0 +1,130 @@
+use crate::modules::database::DataBase;
+use crate::modules::financial::Entity;
+use crate::modules::tables::Table;
+use polars::prelude::*;
+
+/// Returns the name in lowercase and with single spaces, so that "ALDI  Süd" and "Aldi Süd"
+/// are taken as the same.
+fn normalize_entity_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+impl DataBase {
+    /// Replaces the name, country, type and subtype of the entity. Its transactions are
+    /// kept, since they refer to it by ID.
+    pub(crate) fn update_entity(
+        &mut self,
+        entity_id: i64,
+        entity: &Entity,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // fails if there is no such entity
+        self.entity(entity_id)?;
+        self.entity_table.update_entity(entity_id, entity)?;
+
+        Ok(())
+    }
+
+    /// Merges a duplicate entity into another one: its incomes, expenses, templates, IOUs,
+    /// aliases, staged drafts and categorization rules are moved to the other entity, and
+    /// its name is kept as an alias of it before deleting it.
+    pub(crate) fn merge_entities(
+        &mut self,
+        from_id: i64,
+        into_id: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if from_id == into_id {
+            return Err("Cannot merge an entity into itself!".into());
+        }
+        let from_entity: Entity = self.entity(from_id)?;
+        let into_entity: Entity = self.entity(into_id)?;
+
+        self.incomes_table
+            .replace_id("entity_id", from_id, into_id)?;
+        self.expenses_table
+            .replace_id("entity_id", from_id, into_id)?;
+        self.template_transaction_table
+            .replace_id("entity_id", from_id, into_id)?;
+        self.iou_table.replace_id("entity_id", from_id, into_id)?;
+        self.entity_alias_table
+            .replace_id("entity_id", from_id, into_id)?;
+        self.staging_table
+            .replace_id("entity_id", from_id, into_id)?;
+        self.categorization_rule_table
+            .replace_id("entity_id", from_id, into_id)?;
+
+        if normalize_entity_name(&from_entity.name()) != normalize_entity_name(&into_entity.name())
+        {
+            self.entity_alias_table
+                .insert_alias(into_id, from_entity.name().as_str())?;
+        }
+        self.entity_table.delete_entity(from_id)?;
+
+        Ok(())
+    }
+
+    /// Records another name under which the entity is known, like the one a bank shows in
+    /// its statements. An alias cannot point to two entities.
+    pub(crate) fn insert_entity_alias(
+        &mut self,
+        entity_id: i64,
+        alias: &str,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        let alias: &str = alias.trim();
+        if alias.is_empty() {
+            return Err("Empty alias!".into());
+        }
+        // fails if there is no such entity
+        self.entity(entity_id)?;
+        if let Some(matched_id) = self.match_entity(alias)? {
+            return Err(format!(
+                "{} already matches {}!",
+                alias,
+                self.entity(matched_id)?.name()
+            )
+            .into());
+        }
+
+        Ok(self.entity_alias_table.insert_alias(entity_id, alias)?)
+    }
+
+    pub(crate) fn entity_aliases(&self, entity_id: i64) -> Result<Vec<String>, PolarsError> {
+        self.entity_alias_table.entity_aliases(entity_id)
+    }
+
+    pub(crate) fn delete_entity_alias(
+        &mut self,
+        entity_id: i64,
+        alias: &str,
+    ) -> Result<(), PolarsError> {
+        self.entity_alias_table.delete_alias(entity_id, alias)
+    }
+
+    /// Returns the entity known under the name, be it its own or one of its aliases. Case
+    /// and repeated spaces are ignored.
+    pub(crate) fn match_entity(&self, name: &str) -> Result<Option<i64>, PolarsError> {
+        let name: String = normalize_entity_name(name);
+        let entities: &DataFrame = &self.entity_table.data_frame;
+
+        for (entity_name, entity_id) in entities
+            .column("name")?
+            .str()?
+            .into_no_null_iter()
+            .zip(entities.column("entity_id")?.i64()?.into_no_null_iter())
+        {
+            if normalize_entity_name(entity_name) == name {
+                return Ok(Some(entity_id));
+            }
+        }
+
+        for (alias, entity_id) in self.entity_alias_table.aliases()? {
+            if normalize_entity_name(&alias) == name {
+                return Ok(Some(entity_id));
+            }
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,344 @@
+use crate::modules::currency_exchange::CurrencyExchange;
+use crate::modules::database::{
+    capitalize_every_word, data_frame_to_csv_string, status_filter, DataBase,
+};
+use crate::modules::financial::{Assignment, BudgetPeriod, Currency};
+use chrono::NaiveDate;
+use polars::prelude::*;
+
+/// Everything the envelope window needs, computed at once so that the currency exchange is
+/// only loaded when the user asks for it.
+pub struct EnvelopeOverview {
+    pub envelopes_csv: String,
+    pub to_be_assigned: f64,
+    /// ID, date and amount still to assign of the income parties with unassigned money
+    pub assignable_parties: Vec<(i64, NaiveDate, f64)>,
+}
+
+impl DataBase {
+    pub fn insert_assignment(&mut self, assignment: &Assignment) -> Result<i64, PolarsError> {
+        self.assignment_table.insert_assignment(assignment)
+    }
+
+    pub(crate) fn delete_assignment(&mut self, assignment_id: i64) -> Result<(), PolarsError> {
+        self.assignment_table.delete_assignment(assignment_id)
+    }
+
+    /// Returns the IDs and the assignments of an income party.
+    pub(crate) fn party_assignments(
+        &self,
+        party_id: i64,
+    ) -> Result<Vec<(i64, Assignment)>, Box<dyn std::error::Error>> {
+        let mut assignments: Vec<(i64, Assignment)> = Vec::new();
+        for assignment_id in self.assignment_table.iter_party(party_id)? {
+            assignments.push((
+                assignment_id,
+                self.assignment_table.assignment(assignment_id)?,
+            ));
+        }
+
+        Ok(assignments)
+    }
+
+    /// Returns the first day of envelope budgeting: the earliest date of the assignments and
+    /// of the income parties they come from. Nothing before that day is taken into account.
+    fn envelope_start(&self) -> Result<Option<NaiveDate>, PolarsError> {
+        let first_income: Option<NaiveDate> = self
+            .incomes_table
+            .data_frame
+            .clone()
+            .lazy()
+            .join(
+                self.assignment_table
+                    .data_frame
+                    .clone()
+                    .lazy()
+                    .select([col("party_id")])
+                    .unique(None, UniqueKeepStrategy::First),
+                [col("party_id")],
+                [col("party_id")],
+                JoinArgs::new(JoinType::Inner),
+            )
+            .select([col("date").min()])
+            .collect()?
+            .column("date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .flatten();
+
+        let first_assignment: Option<NaiveDate> = self
+            .assignment_table
+            .data_frame
+            .column("date")?
+            .date()?
+            .as_date_iter()
+            .flatten()
+            .min();
+
+        Ok(match (first_income, first_assignment) {
+            (Some(income_date), Some(assignment_date)) => Some(income_date.min(assignment_date)),
+            (income_date, assignment_date) => income_date.or(assignment_date),
+        })
+    }
+
+    /// Selects the date, currency and value of the records of the data frame between
+    /// date_from and date_to, both included, with the values in currency_to, exchanged at
+    /// the date of each record.
+    fn exchanged_values(
+        currency_exchange: &CurrencyExchange,
+        data_frame: &DataFrame,
+        keys: &[&str],
+        date_from: NaiveDate,
+        date_to: NaiveDate,
+        currency_to: &Currency,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let mut columns: Vec<Expr> = keys.iter().map(|key| col(*key)).collect();
+        columns.extend([col("date"), col("currency"), col("value")]);
+
+        let data_frame: DataFrame = data_frame
+            .clone()
+            .lazy()
+            .filter(col("date").is_between(lit(date_from), lit(date_to), ClosedInterval::Both))
+            .select(columns)
+            .collect()?;
+
+        currency_exchange.exchange_currencies(currency_to, data_frame)
+    }
+
+    /// Leaves out the planned and void transactions, which never count for the envelopes.
+    fn counted_transactions(data_frame: &DataFrame) -> Result<DataFrame, PolarsError> {
+        data_frame
+            .clone()
+            .lazy()
+            .filter(status_filter(false))
+            .collect()
+    }
+
+    /// Computes the state of the envelope of every expense category in the month of the given
+    /// date: the amount rolled over from the previous months (assigned minus spent), the
+    /// amount assigned and spent during the month, and the amount available at its end.
+    /// Overspent envelopes roll over as negative amounts. All amounts are expressed in the
+    /// currency_to, exchanged at the date of each assignment or expense.
+    pub(crate) fn envelopes(
+        &self,
+        currency_exchange: &CurrencyExchange,
+        month: NaiveDate,
+        currency_to: &Currency,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let (first_day, last_day) = BudgetPeriod::Monthly.bounds(month);
+        let start: NaiveDate = self.envelope_start()?.unwrap_or(first_day);
+
+        let split_by_month = |name: &str| -> [Expr; 2] {
+            [
+                when(col("date").lt(lit(first_day)))
+                    .then(col("value"))
+                    .otherwise(lit(0.0))
+                    .alias(format!("{}_before", name).as_str()),
+                when(col("date").gt_eq(lit(first_day)))
+                    .then(col("value"))
+                    .otherwise(lit(0.0))
+                    .alias(name),
+            ]
+        };
+
+        let assignments: DataFrame = Self::exchanged_values(
+            currency_exchange,
+            &self.assignment_table.data_frame,
+            &["category"],
+            start,
+            last_day,
+            currency_to,
+        )?
+        .lazy()
+        .with_columns(split_by_month("assigned"))
+        .group_by([col("category")])
+        .agg([col("assigned_before").sum(), col("assigned").sum()])
+        .collect()?;
+
+        let expenses: DataFrame = Self::exchanged_values(
+            currency_exchange,
+            &Self::counted_transactions(&self.expenses_table.data_frame)?,
+            &["category"],
+            start,
+            last_day,
+            currency_to,
+        )?
+        .lazy()
+        .with_columns(split_by_month("spent"))
+        .group_by([col("category")])
+        .agg([col("spent_before").sum(), col("spent").sum()])
+        .collect()?;
+
+        Ok(assignments
+            .lazy()
+            .join(
+                expenses.lazy(),
+                [col("category")],
+                [col("category")],
+                JoinArgs::new(JoinType::Full).with_coalesce(JoinCoalesce::CoalesceColumns),
+            )
+            .with_columns([
+                col("assigned_before").fill_null(0.0),
+                col("assigned").fill_null(0.0),
+                col("spent_before").fill_null(0.0),
+                col("spent").fill_null(0.0),
+            ])
+            .with_column((col("assigned_before") - col("spent_before")).alias("rolled_over"))
+            .with_column((col("rolled_over") + col("assigned") - col("spent")).alias("available"))
+            .sort(["category"], Default::default())
+            .select([
+                col("category"),
+                col("rolled_over").round(2),
+                col("assigned").round(2),
+                col("spent").round(2),
+                col("available").round(2),
+            ])
+            .collect()?)
+    }
+
+    /// Returns the income earned since the start of envelope budgeting up to the end of the
+    /// month of the given date that has not been assigned to any envelope yet.
+    pub(crate) fn to_be_assigned(
+        &self,
+        currency_exchange: &CurrencyExchange,
+        month: NaiveDate,
+        currency_to: &Currency,
+    ) -> Result<f64, Box<dyn std::error::Error>> {
+        let (first_day, last_day) = BudgetPeriod::Monthly.bounds(month);
+        let start: NaiveDate = self.envelope_start()?.unwrap_or(first_day);
+
+        let income: f64 = Self::exchanged_values(
+            currency_exchange,
+            &Self::counted_transactions(&self.incomes_table.data_frame)?,
+            &[],
+            start,
+            last_day,
+            currency_to,
+        )?
+        .column("value")?
+        .f64()?
+        .sum()
+        .unwrap_or(0.0);
+
+        let assigned: f64 = Self::exchanged_values(
+            currency_exchange,
+            &self.assignment_table.data_frame,
+            &[],
+            start,
+            last_day,
+            currency_to,
+        )?
+        .column("value")?
+        .f64()?
+        .sum()
+        .unwrap_or(0.0);
+
+        Ok(((income - assigned) * 100.0).round() / 100.0)
+    }
+
+    /// Returns the income of every income party and the part of it that has been assigned,
+    /// sorted from the most recent party. Incomes are exchanged at the date of the party and
+    /// assignments at their own date.
+    pub(crate) fn income_parties(
+        &self,
+        currency_exchange: &CurrencyExchange,
+        currency_to: &Currency,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let incomes: DataFrame = Self::exchanged_values(
+            currency_exchange,
+            &Self::counted_transactions(&self.incomes_table.data_frame)?,
+            &["party_id"],
+            NaiveDate::MIN,
+            NaiveDate::MAX,
+            currency_to,
+        )?
+        .lazy()
+        .group_by([col("party_id")])
+        .agg([col("date").min(), col("value").sum().alias("income")])
+        .collect()?;
+
+        let assignments: DataFrame = Self::exchanged_values(
+            currency_exchange,
+            &self.assignment_table.data_frame,
+            &["party_id"],
+            NaiveDate::MIN,
+            NaiveDate::MAX,
+            currency_to,
+        )?
+        .lazy()
+        .group_by([col("party_id")])
+        .agg([col("value").sum().alias("assigned")])
+        .collect()?;
+
+        Ok(incomes
+            .lazy()
+            .join(
+                assignments.lazy(),
+                [col("party_id")],
+                [col("party_id")],
+                JoinArgs::new(JoinType::Left),
+            )
+            .with_column(col("assigned").fill_null(0.0))
+            .with_column((col("income") - col("assigned")).alias("unassigned"))
+            .sort(
+                ["date", "party_id"],
+                SortMultipleOptions::default().with_order_descending_multi([true, true]),
+            )
+            .select([
+                col("party_id"),
+                col("date"),
+                col("income").round(2),
+                col("assigned").round(2),
+                col("unassigned").round(2),
+            ])
+            .collect()?)
+    }
+
+    /// Computes the envelopes of the month of the given date, the money left to assign and
+    /// the income parties that still have money to assign.
+    pub(crate) fn envelope_overview(
+        &self,
+        month: NaiveDate,
+        currency_to: &Currency,
+    ) -> Result<EnvelopeOverview, Box<dyn std::error::Error>> {
+        let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
+
+        let mut envelopes: DataFrame = self
+            .envelopes(&currency_exchange, month, currency_to)?
+            .lazy()
+            .select([all().name().map(|name| {
+                Ok(PlSmallStr::from_string(capitalize_every_word(
+                    name.replace("_", " "),
+                )))
+            })])
+            .collect()?;
+
+        let income_parties: DataFrame = self
+            .income_parties(&currency_exchange, currency_to)?
+            .lazy()
+            .filter(col("unassigned").gt(lit(0.0)))
+            .collect()?;
+
+        let mut assignable_parties: Vec<(i64, NaiveDate, f64)> = Vec::new();
+        for ((party_id, date), unassigned) in income_parties
+            .column("party_id")?
+            .i64()?
+            .into_iter()
+            .zip(income_parties.column("date")?.date()?.as_date_iter())
+            .zip(income_parties.column("unassigned")?.f64()?)
+        {
+            assignable_parties.push((
+                party_id.ok_or("No party_id!")?,
+                date.ok_or("No date!")?,
+                unassigned.ok_or("No value!")?,
+            ));
+        }
+
+        Ok(EnvelopeOverview {
+            envelopes_csv: data_frame_to_csv_string(&mut envelopes)?,
+            to_be_assigned: self.to_be_assigned(&currency_exchange, month, currency_to)?,
+            assignable_parties,
+        })
+    }
+}
@@ -0,0 +1,249 @@
+use crate::modules::database::summaries::ReportDate;
+use crate::modules::database::templates::DueParty;
+use crate::modules::database::{
+    capitalize_every_word, data_frame_to_csv_string, status_filter, DataBase,
+};
+use crate::modules::financial::{Account, Transaction};
+use chrono::{Days, Months, NaiveDate};
+use polars::prelude::pivot::pivot_stable;
+use polars::prelude::*;
+
+impl DataBase {
+    /// Returns the changes of the balance of every account, in each of its currencies, that
+    /// are known or expected until the given date: the current balances at the start date,
+    /// the fund movements dated after it, the planned fund movements and the fund movements
+    /// of the parties that the recurring templates will generate. Movements count on their
+    /// value date, when they settle. Planned movements and due parties that are already
+    /// overdue are expected to happen on the start date.
+    fn forecast_movements(
+        &self,
+        date_from: NaiveDate,
+        date_to: NaiveDate,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let initial_balances: DataFrame = self
+            .account_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("initial_balance").neq(lit(0.0)))
+            .select([
+                col("account_id"),
+                col("currency"),
+                lit(date_from).cast(DataType::Date).alias("date"),
+                col("initial_balance").alias("value"),
+            ])
+            .collect()?;
+
+        let fund_movements: DataFrame = self
+            .with_report_date(
+                self.funds_table
+                    .data_frame
+                    .clone()
+                    .lazy()
+                    .filter(status_filter(true))
+                    .collect()?,
+                &ReportDate::Value,
+            )?
+            .select(["account_id", "currency", "date", "value"])?;
+
+        let mut account_ids: Vec<i64> = Vec::new();
+        let mut currencies: Vec<String> = Vec::new();
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        let mut values: Vec<f64> = Vec::new();
+        let due_parties: Vec<DueParty> = self.due_parties(date_to)?;
+        for due_party in due_parties.iter() {
+            for transaction in due_party.party.transactions.iter() {
+                let (account_id, value) = match transaction {
+                    Transaction::Credit {
+                        account_id, value, ..
+                    } => (*account_id, *value),
+                    Transaction::Debit {
+                        account_id, value, ..
+                    } => (*account_id, -value),
+                    _ => continue,
+                };
+                account_ids.push(account_id);
+                currencies.push(transaction.currency().to_string());
+                dates.push(transaction.value_date().unwrap_or(*transaction.date()));
+                values.push(value);
+            }
+        }
+        let recurring_movements: DataFrame = df!(
+            "account_id" => account_ids,
+            "currency" => currencies,
+            "date" => dates,
+            "value" => values,
+        )?;
+
+        Ok(initial_balances
+            .vstack(&fund_movements)?
+            .vstack(&recurring_movements)?
+            .lazy()
+            .with_column(
+                when(col("date").lt(lit(date_from)))
+                    .then(lit(date_from).cast(DataType::Date))
+                    .otherwise(col("date"))
+                    .alias("date"),
+            )
+            .filter(col("date").lt_eq(lit(date_to)))
+            .group_by([col("account_id"), col("currency"), col("date")])
+            .agg([col("value").sum()])
+            .collect()?)
+    }
+
+    /// Projects the balance of every account, in each of its currencies, at the end of every
+    /// day from the given date until the given number of months later.
+    pub(crate) fn cash_flow_forecast(
+        &self,
+        date_from: NaiveDate,
+        months: u32,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let date_to: NaiveDate = date_from
+            .checked_add_months(Months::new(months))
+            .ok_or("Forecast out of range!")?;
+        let movements: DataFrame = self.forecast_movements(date_from, date_to)?;
+
+        let keys: DataFrame = movements
+            .clone()
+            .lazy()
+            .select([col("account_id"), col("currency")])
+            .unique(None, UniqueKeepStrategy::First)
+            .sort(["account_id", "currency"], Default::default())
+            .collect()?;
+        let key_account_ids = keys.column("account_id")?.i64()?;
+        let key_currencies = keys.column("currency")?.str()?;
+
+        // one row per account, currency and day, even if nothing happens on that day
+        let mut account_ids: Vec<i64> = Vec::new();
+        let mut account_names: Vec<String> = Vec::new();
+        let mut currencies: Vec<String> = Vec::new();
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        for i in 0..keys.height() {
+            let account_id: i64 = key_account_ids.get(i).ok_or("No account ID!")?;
+            let account: Account = self.account(account_id)?;
+            let currency: &str = key_currencies.get(i).ok_or("No currency!")?;
+            let mut date: NaiveDate = date_from;
+            while date <= date_to {
+                account_ids.push(account_id);
+                account_names.push(account.name());
+                currencies.push(currency.to_string());
+                dates.push(date);
+                date = date
+                    .checked_add_days(Days::new(1))
+                    .ok_or("Date out of range!")?;
+            }
+        }
+        let days: DataFrame = df!(
+            "account_id" => account_ids,
+            "account_name" => account_names,
+            "currency" => currencies,
+            "date" => dates,
+        )?;
+
+        Ok(days
+            .lazy()
+            .join(
+                movements.lazy(),
+                [col("account_id"), col("currency"), col("date")],
+                [col("account_id"), col("currency"), col("date")],
+                JoinArgs::new(JoinType::Left),
+            )
+            .with_column(col("value").fill_null(0.0))
+            .sort(["account_id", "currency", "date"], Default::default())
+            .with_column(
+                col("value")
+                    .cum_sum(false)
+                    .over([col("account_id"), col("currency")])
+                    .round(2)
+                    .alias("balance"),
+            )
+            .select([
+                col("date"),
+                col("account_id"),
+                col("account_name"),
+                col("currency"),
+                col("balance"),
+            ])
+            .collect()?)
+    }
+
+    /// Returns one warning per account and currency whose projected balance goes below zero,
+    /// or below the given threshold, with the first day it does and its lowest balance.
+    pub(crate) fn forecast_warnings(
+        forecast: &DataFrame,
+        threshold: f64,
+    ) -> Result<DataFrame, PolarsError> {
+        let limit: f64 = threshold.max(0.0);
+
+        forecast
+            .clone()
+            .lazy()
+            .filter(col("balance").lt(lit(limit)))
+            .sort(["date"], Default::default())
+            .group_by_stable([col("account_name"), col("currency")])
+            .agg([
+                col("date").first().alias("first_date"),
+                col("balance").min().alias("lowest_balance"),
+                col("date")
+                    .sort_by([col("balance")], Default::default())
+                    .first()
+                    .alias("lowest_date"),
+            ])
+            .with_column(
+                when(col("lowest_balance").lt(lit(0.0)))
+                    .then(lit("Below zero"))
+                    .otherwise(lit("Below threshold"))
+                    .alias("warning"),
+            )
+            .sort(["first_date", "account_name"], Default::default())
+            .collect()
+    }
+
+    /// Returns two csv in String format: the projected balance of every account day by day,
+    /// with one column per account and currency, and the warnings of the forecast.
+    pub(crate) fn cash_flow_forecast_report(
+        &self,
+        date_from: NaiveDate,
+        months: u32,
+        threshold: f64,
+    ) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let forecast: DataFrame = self.cash_flow_forecast(date_from, months)?;
+
+        let mut warnings: DataFrame = Self::forecast_warnings(&forecast, threshold)?
+            .lazy()
+            .select([all().name().map(|name| {
+                Ok(PlSmallStr::from_string(capitalize_every_word(
+                    name.replace("_", " "),
+                )))
+            })])
+            .collect()?;
+
+        let accounts: Vec<String> = forecast
+            .column("account_name")?
+            .str()?
+            .into_iter()
+            .zip(forecast.column("currency")?.str()?)
+            .map(|(name, currency)| format!("{} {}", name.unwrap_or(""), currency.unwrap_or("")))
+            .collect();
+
+        let mut projection: DataFrame = pivot_stable(
+            &forecast
+                .lazy()
+                .select([col("date"), col("balance")])
+                .with_column(Series::new("account".into(), accounts).lit())
+                .collect()?,
+            ["account"],
+            Some(["date"]),
+            Some(["balance"]),
+            false,
+            None,
+            None,
+        )?;
+        projection.rename("date", PlSmallStr::from_str("Date"))?;
+
+        Ok((
+            data_frame_to_csv_string(&mut projection)?,
+            data_frame_to_csv_string(&mut warnings)?,
+        ))
+    }
+}
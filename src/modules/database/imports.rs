@@ -0,0 +1,268 @@
+use crate::modules::categorization::CategorizationSubject;
+use crate::modules::database::staging::DraftParty;
+use crate::modules::database::DataBase;
+use crate::modules::financial::Account;
+use crate::modules::importers::bank_csv::CsvMapping;
+use crate::modules::importers::camt::{is_valid_iban, normalize_iban, CamtStatement};
+use crate::modules::importers::mt940::Mt940Statement;
+use crate::modules::importers::ImportedMovement;
+use polars::prelude::*;
+use std::collections::HashSet;
+use std::vec::IntoIter;
+
+impl DataBase {
+    /// Saves the layout of the CSV exports of a bank. A mapping saved under the same name is
+    /// replaced, so that a layout can be corrected.
+    pub(crate) fn insert_csv_mapping(
+        &mut self,
+        mapping: &CsvMapping,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        if mapping.name.trim().is_empty() {
+            return Err("The mapping needs the name of the bank!".into());
+        }
+        if mapping.date_column.is_empty() | mapping.amount_column.is_empty() {
+            return Err("The mapping needs a date and an amount column!".into());
+        }
+        if !mapping.delimiter.is_ascii() {
+            return Err("The delimiter has to be a single ASCII character!".into());
+        }
+
+        if let Some(mapping_id) = self.csv_mapping_table.find_mapping(&mapping.name)? {
+            self.csv_mapping_table.delete_mapping(mapping_id)?;
+        }
+        Ok(self.csv_mapping_table.insert_mapping(mapping)?)
+    }
+
+    pub(crate) fn csv_mapping(
+        &self,
+        mapping_id: i64,
+    ) -> Result<CsvMapping, Box<dyn std::error::Error>> {
+        self.csv_mapping_table.mapping(mapping_id)
+    }
+
+    pub(crate) fn iter_csv_mapping_ids(&self) -> Result<IntoIter<i64>, PolarsError> {
+        self.csv_mapping_table.iter()
+    }
+
+    pub(crate) fn delete_csv_mapping(&mut self, mapping_id: i64) -> Result<(), PolarsError> {
+        self.csv_mapping_table.delete_mapping(mapping_id)
+    }
+
+    /// Stages imported movements of an account as draft parties, to be reviewed before they
+    /// are committed: money that came in is an income credited to the account, money that
+    /// went out an expense debited from it, with the given categories. The entity is matched
+    /// on the payee, by name or alias, and is left unknown otherwise. A categorization rule
+    /// that matches the movement overrides both, and adds its tags. Movements whose
+    /// reference was already imported into the account, or is already in the inbox, are
+    /// skipped, so that overlapping statements can be imported. Returns the IDs of the
+    /// staged drafts.
+    pub(crate) fn stage_movements(
+        &mut self,
+        account_id: i64,
+        movements: &[ImportedMovement],
+        income_category: &str,
+        expense_category: &str,
+    ) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+        let account: Account = self.account(account_id)?;
+
+        let mut drafts: Vec<DraftParty> = Vec::new();
+        let mut references: HashSet<&str> = HashSet::new();
+        for movement in movements {
+            // the same reference twice in a file is the same movement as well
+            let is_imported: bool = !movement.reference.is_empty()
+                & (!references.insert(&movement.reference)
+                    | self
+                        .import_reference_table
+                        .is_imported(account_id, &movement.reference)?
+                    | (self
+                        .staging_table
+                        .count_reference(account_id, &movement.reference)?
+                        > 0));
+            if is_imported {
+                continue;
+            }
+            if !account.has_currency(&movement.currency) {
+                return Err(format!(
+                    "The account {} has no {} funds!",
+                    account.name(),
+                    movement.currency
+                )
+                .into());
+            }
+            // zero-value lines, like fee notices, do not move money
+            if movement.value.abs() < 0.01 {
+                continue;
+            }
+
+            let mut draft = DraftParty {
+                account_id,
+                movement: movement.clone(),
+                entity_id: self.match_entity(&movement.payee)?.unwrap_or(0),
+                category: if movement.value > 0.0 {
+                    income_category.to_string()
+                } else {
+                    expense_category.to_string()
+                },
+                subcategory: String::default(),
+                tags: Vec::new(),
+                rule_id: -1,
+                accepted: true,
+            };
+            let subject = CategorizationSubject {
+                transaction_type: draft.transaction_type(),
+                text: format!("{} {}", movement.description, movement.payee),
+                value: movement.value.abs(),
+                currency: movement.currency.clone(),
+                account_id,
+            };
+            if let Some(proposal) = self.propose_categorization(&subject)? {
+                if proposal.entity_id >= 0 {
+                    draft.entity_id = proposal.entity_id;
+                }
+                if !proposal.category.is_empty() {
+                    draft.category = proposal.category;
+                    draft.subcategory = proposal.subcategory;
+                }
+                draft.tags = proposal.tags;
+                draft.rule_id = proposal.rule_id;
+            }
+            drafts.push(draft);
+        }
+
+        let mut staging_ids: Vec<i64> = Vec::new();
+        for draft in drafts.iter() {
+            staging_ids.push(self.stage_draft(draft)?);
+        }
+
+        Ok(staging_ids)
+    }
+
+    /// Records the IBAN of the account, so that its bank statements are matched to it. An
+    /// IBAN cannot belong to two accounts.
+    pub(crate) fn set_account_iban(
+        &mut self,
+        account_id: i64,
+        iban: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let iban: String = normalize_iban(iban);
+        if !is_valid_iban(&iban) {
+            return Err(format!("{iban} is not a valid IBAN!").into());
+        }
+        // fails if there is no such account
+        self.account(account_id)?;
+        if let Some(other_id) = self.account_table.find_iban(&iban)? {
+            if other_id != account_id {
+                return Err(format!(
+                    "{iban} already belongs to {}!",
+                    self.account(other_id)?.name()
+                )
+                .into());
+            }
+        }
+        self.account_table.set_iban(account_id, &iban)?;
+
+        Ok(())
+    }
+
+    /// Returns the account with the IBAN, if any
+    pub(crate) fn find_account_by_iban(&self, iban: &str) -> Result<Option<i64>, PolarsError> {
+        let iban: String = normalize_iban(iban);
+        // accounts without IBAN have an empty one
+        if iban.is_empty() {
+            return Ok(None);
+        }
+        self.account_table.find_iban(&iban)
+    }
+
+    /// Stages the parties of the statements of a camt file, each one on the account with
+    /// its IBAN. Nothing is staged if any statement cannot be, like one with an IBAN not
+    /// linked to an account, of a closed account or in a currency that its account has no
+    /// funds in.
+    pub(crate) fn stage_camt_statements(
+        &mut self,
+        statements: &[CamtStatement],
+        income_category: &str,
+        expense_category: &str,
+    ) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+        let mut account_ids: Vec<i64> = Vec::new();
+        for statement in statements {
+            match self.find_account_by_iban(&statement.iban)? {
+                Some(account_id) => {
+                    if let Some(closing_date) = self.account_closing_date(account_id)? {
+                        return Err(format!(
+                            "{} was closed on {closing_date}!",
+                            self.account(account_id)?.name()
+                        )
+                        .into());
+                    }
+                    account_ids.push(account_id);
+                }
+                None => {
+                    return Err(format!(
+                        "No account has the IBAN {}, link it to one first!",
+                        statement.iban
+                    )
+                    .into())
+                }
+            };
+        }
+
+        // staging only writes drafts to the inbox, which is restored if a statement fails
+        let inbox: DataFrame = self.staging_table.data_frame.clone();
+        let mut staging_ids: Vec<i64> = Vec::new();
+        for (statement, account_id) in statements.iter().zip(account_ids) {
+            match self.stage_movements(
+                account_id,
+                &statement.movements,
+                income_category,
+                expense_category,
+            ) {
+                Ok(ids) => staging_ids.extend(ids),
+                Err(e) => {
+                    self.staging_table.data_frame = inbox;
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(staging_ids)
+    }
+
+    /// Stages the movements of the statements of an MT940 file on the account. All the
+    /// statements have to be of one account, and not of one whose IBAN is linked to another
+    /// account, so that a file exported for several accounts is not staged onto one.
+    pub(crate) fn stage_mt940_statements(
+        &mut self,
+        account_id: i64,
+        statements: &[Mt940Statement],
+        income_category: &str,
+        expense_category: &str,
+    ) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+        let accounts: HashSet<String> = statements
+            .iter()
+            .map(|statement| normalize_iban(&statement.account))
+            .collect();
+        if accounts.len() > 1 {
+            return Err("The file has statements of several accounts!".into());
+        }
+        // banks name the account by its IBAN, or by their code and the account number
+        for account in accounts.iter().filter(|account| is_valid_iban(account)) {
+            if let Some(other_id) = self.find_account_by_iban(account)? {
+                if other_id != account_id {
+                    return Err(format!(
+                        "The statements are of {}, not of {}!",
+                        self.account(other_id)?.name(),
+                        self.account(account_id)?.name()
+                    )
+                    .into());
+                }
+            }
+        }
+
+        let movements: Vec<ImportedMovement> = statements
+            .iter()
+            .flat_map(|statement| statement.movements.clone())
+            .collect();
+        self.stage_movements(account_id, &movements, income_category, expense_category)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::modules::database::{capitalize_every_word, data_frame_to_csv_string, DataBase};
+use polars::prelude::*;
+
+impl DataBase {
+    /// Runs all the consistency checks of the database and returns one row per issue found,
+    /// with the name of the check and a description of the issue.
+    pub(crate) fn integrity_check(&self) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let mut checks: Vec<String> = Vec::new();
+        let mut issues: Vec<String> = Vec::new();
+
+        let failed_assertions: DataFrame = self
+            .balance_assertions()?
+            .lazy()
+            .filter(col("status").eq(lit("Fails")))
+            .collect()?;
+        let account_names = failed_assertions.column("account_name")?.str()?;
+        let mut dates = failed_assertions.column("date")?.date()?.as_date_iter();
+        let currencies = failed_assertions.column("currency")?.str()?;
+        let asserted_values = failed_assertions.column("asserted")?.f64()?;
+        let actual_values = failed_assertions.column("actual")?.f64()?;
+        for i in 0..failed_assertions.height() {
+            checks.push(String::from("Balance assertion"));
+            issues.push(format!(
+                "{} held {:.2} {} on {} instead of {:.2}",
+                account_names.get(i).ok_or("No account name!")?,
+                actual_values.get(i).ok_or("No actual balance!")?,
+                currencies.get(i).ok_or("No currency!")?,
+                dates.next().flatten().ok_or("No date!")?,
+                asserted_values.get(i).ok_or("No asserted balance!")?,
+            ));
+        }
+
+        Ok(df!(
+            "check" => checks,
+            "issue" => issues,
+        )?)
+    }
+
+    /// Returns a csv in String format with the issues found by the integrity check.
+    pub(crate) fn integrity_report(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut report: DataFrame = self
+            .integrity_check()?
+            .lazy()
+            .select([all().name().map(|name| {
+                Ok(PlSmallStr::from_string(capitalize_every_word(
+                    name.replace("_", " "),
+                )))
+            })])
+            .collect()?;
+
+        data_frame_to_csv_string(&mut report)
+    }
+}
@@ -0,0 +1,150 @@
+use crate::modules::currency_exchange::CurrencyExchange;
+use crate::modules::database::{data_frame_to_csv_string, status_filter, DataBase};
+use crate::modules::financial::{Currency, Iou, Party, Transaction};
+use chrono::{Local, NaiveDate};
+use polars::prelude::*;
+use std::str::FromStr;
+
+impl DataBase {
+    /// Returns what every person owes us, or we owe them, in each currency, in a column
+    /// named value. Positive values are owed to us. Settled debts are left out.
+    pub(crate) fn iou_balances(&self, include_planned: bool) -> Result<DataFrame, PolarsError> {
+        self.iou_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(status_filter(include_planned))
+            .group_by([col("entity_id"), col("currency")])
+            .agg([col("value").sum()])
+            // one or more cents off
+            .filter(col("value").abs().gt_eq(lit(0.01)))
+            .sort(["entity_id", "currency"], Default::default())
+            .collect()
+    }
+
+    /// Returns what the person owes us in the currency, negative if we owe them.
+    pub(crate) fn iou_balance(
+        &self,
+        entity_id: i64,
+        currency: &Currency,
+    ) -> Result<f64, PolarsError> {
+        Ok(self
+            .iou_balances(false)?
+            .lazy()
+            .filter(
+                col("entity_id")
+                    .eq(lit(entity_id))
+                    .and(col("currency").eq(lit(currency.to_string()))),
+            )
+            .collect()?
+            .column("value")?
+            .f64()?
+            .sum()
+            .unwrap_or(0.0))
+    }
+
+    /// Builds the party that clears the balance with a person in a currency: the money
+    /// handed over through the account, and the debt of opposite sign.
+    pub(crate) fn settlement_party(
+        &self,
+        entity_id: i64,
+        currency: &Currency,
+        account_id: i64,
+        date: NaiveDate,
+    ) -> Result<Party, Box<dyn std::error::Error>> {
+        let balance: f64 = self.iou_balance(entity_id, currency)?;
+        if balance == 0.0 {
+            return Err("Nothing to settle!".into());
+        }
+
+        let fund_movement: Transaction = if balance > 0.0 {
+            // the person pays us back
+            Transaction::Credit {
+                value: balance,
+                currency: currency.clone(),
+                date,
+                account_id,
+                value_date: None,
+            }
+        } else {
+            Transaction::Debit {
+                value: -balance,
+                currency: currency.clone(),
+                date,
+                account_id,
+                value_date: None,
+            }
+        };
+
+        let mut party: Party = Party::new(vec![fund_movement]);
+        party.add_iou(Iou::new(
+            entity_id,
+            -balance,
+            currency.clone(),
+            date,
+            String::from("Settlement"),
+        ));
+
+        Ok(party)
+    }
+
+    /// Generates the table of who owes whom: the open balance with every person in each
+    /// currency, also expressed in the currency_to at today's rate, and the net total.
+    pub(crate) fn iou_report(
+        &self,
+        currency_to: &Currency,
+        include_planned: bool,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
+        let today: NaiveDate = Local::now().date_naive();
+        let balances: DataFrame = self.iou_balances(include_planned)?;
+
+        let mut people: Vec<String> = Vec::new();
+        let mut directions: Vec<String> = Vec::new();
+        let mut currencies: Vec<String> = Vec::new();
+        let mut values: Vec<f64> = Vec::new();
+        let mut exchanged_values: Vec<f64> = Vec::new();
+        for ((entity_id, currency), value) in balances
+            .column("entity_id")?
+            .i64()?
+            .into_no_null_iter()
+            .zip(balances.column("currency")?.str()?.into_no_null_iter())
+            .zip(balances.column("value")?.f64()?.into_no_null_iter())
+        {
+            let currency_from: Currency = Currency::from_str(currency)?;
+            let exchange_rate: f64 =
+                currency_exchange.exchange_currency(&currency_from, currency_to, today)?;
+
+            people.push(self.entity(entity_id)?.name());
+            directions.push(if value > 0.0 {
+                String::from("Owes us")
+            } else {
+                String::from("We owe")
+            });
+            currencies.push(currency.to_string());
+            values.push((100.0 * value.abs()).round() / 100.0);
+            exchanged_values.push((100.0 * value * exchange_rate).round() / 100.0);
+        }
+        let total: f64 = exchanged_values.iter().sum();
+
+        people.push(String::from("Total"));
+        directions.push(if total >= 0.0 {
+            String::from("Owed to us")
+        } else {
+            String::from("We owe")
+        });
+        currencies.push(currency_to.to_string());
+        values.push((100.0 * total.abs()).round() / 100.0);
+        exchanged_values.push((100.0 * total).round() / 100.0);
+
+        let mut report: DataFrame = df!(
+            "Person" => people,
+            "Direction" => directions,
+            "Currency" => currencies,
+            "Balance" => values,
+            currency_to.to_string().as_str() => exchanged_values,
+        )?;
+
+        data_frame_to_csv_string(&mut report)
+    }
+}
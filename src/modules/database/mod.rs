@@ -1,6 +1,25 @@
+pub mod assertions;
+pub mod budgets;
+pub mod categories;
+pub mod closings;
+pub mod duplicates;
+pub mod entities;
+pub mod envelopes;
+pub mod forecasts;
+pub mod imports;
+pub mod integrity;
+pub mod ious;
 mod palettes;
 pub mod plotter;
+pub mod projects;
+pub mod reconciliations;
+pub mod refunds;
+pub mod rules;
+pub mod staging;
+pub mod suggestions;
 pub mod summaries;
+pub mod tags;
+pub mod templates;
 pub mod views;
 
 use crate::modules::financial::*;
@@ -31,6 +50,17 @@ fn data_frame_to_csv_string(
         .to_string())
 }
 
+/// Filter on the status of the transactions for the reports: void transactions never count,
+/// and planned ones only if asked for.
+fn status_filter(include_planned: bool) -> Expr {
+    let filter: Expr = col("status").neq(lit(TransactionStatus::Void.to_string()));
+    if include_planned {
+        filter
+    } else {
+        filter.and(col("status").neq(lit(TransactionStatus::Planned.to_string())))
+    }
+}
+
 fn capitalize_every_word(sentence: String) -> String {
     // Copied and addapted to my needs from thirtyseconds
     // https://docs.rs/thirtyseconds/latest/thirtyseconds/strings/fn.capitalize_every_word.html
@@ -49,6 +79,21 @@ pub struct DataBase {
     party_table: PartyTable,
     entity_table: EntityTable,
     account_table: AccountTable,
+    template_table: TemplateTable,
+    template_transaction_table: TemplateTransactionTable,
+    budget_table: BudgetTable,
+    assignment_table: AssignmentTable,
+    reconciliation_table: ReconciliationTable,
+    balance_assertion_table: BalanceAssertionTable,
+    tag_table: TagTable,
+    project_table: ProjectTable,
+    iou_table: IouTable,
+    entity_alias_table: EntityAliasTable,
+    category_table: CategoryTable,
+    csv_mapping_table: CsvMappingTable,
+    import_reference_table: ImportReferenceTable,
+    staging_table: StagingTable,
+    categorization_rule_table: CategorizationRuleTable,
 }
 
 impl DataBase {
@@ -59,6 +104,21 @@ impl DataBase {
         let party_table = *PartyTable::new()?;
         let entity_table = *EntityTable::new()?;
         let account_table = *AccountTable::new()?;
+        let template_table = *TemplateTable::new()?;
+        let template_transaction_table = *TemplateTransactionTable::new()?;
+        let budget_table = *BudgetTable::new()?;
+        let assignment_table = *AssignmentTable::new()?;
+        let reconciliation_table = *ReconciliationTable::new()?;
+        let balance_assertion_table = *BalanceAssertionTable::new()?;
+        let tag_table = *TagTable::new()?;
+        let project_table = *ProjectTable::new()?;
+        let iou_table = *IouTable::new()?;
+        let entity_alias_table = *EntityAliasTable::new()?;
+        let category_table = *CategoryTable::new()?;
+        let csv_mapping_table = *CsvMappingTable::new()?;
+        let import_reference_table = *ImportReferenceTable::new()?;
+        let staging_table = *StagingTable::new()?;
+        let categorization_rule_table = *CategorizationRuleTable::new()?;
 
         Ok(DataBase {
             incomes_table,
@@ -67,6 +127,21 @@ impl DataBase {
             party_table,
             entity_table,
             account_table,
+            template_table,
+            template_transaction_table,
+            budget_table,
+            assignment_table,
+            reconciliation_table,
+            balance_assertion_table,
+            tag_table,
+            project_table,
+            iou_table,
+            entity_alias_table,
+            category_table,
+            csv_mapping_table,
+            import_reference_table,
+            staging_table,
+            categorization_rule_table,
         })
     }
 
@@ -77,15 +152,48 @@ impl DataBase {
         let party_table = *PartyTable::init()?;
         let entity_table = *EntityTable::init()?;
         let account_table = *AccountTable::init()?;
-
-        Ok(DataBase {
+        let template_table = *TemplateTable::init()?;
+        let template_transaction_table = *TemplateTransactionTable::init()?;
+        let budget_table = *BudgetTable::init()?;
+        let assignment_table = *AssignmentTable::init()?;
+        let reconciliation_table = *ReconciliationTable::init()?;
+        let balance_assertion_table = *BalanceAssertionTable::init()?;
+        let tag_table = *TagTable::init()?;
+        let project_table = *ProjectTable::init()?;
+        let iou_table = *IouTable::init()?;
+        let entity_alias_table = *EntityAliasTable::init()?;
+        let category_table = *CategoryTable::init()?;
+        let csv_mapping_table = *CsvMappingTable::init()?;
+        let import_reference_table = *ImportReferenceTable::init()?;
+        let staging_table = *StagingTable::init()?;
+        let categorization_rule_table = *CategorizationRuleTable::init()?;
+
+        let mut database: DataBase = DataBase {
             incomes_table,
             expenses_table,
             funds_table,
             party_table,
             entity_table,
             account_table,
-        })
+            template_table,
+            template_transaction_table,
+            budget_table,
+            assignment_table,
+            reconciliation_table,
+            balance_assertion_table,
+            tag_table,
+            project_table,
+            iou_table,
+            entity_alias_table,
+            category_table,
+            csv_mapping_table,
+            import_reference_table,
+            staging_table,
+            categorization_rule_table,
+        };
+        database.seed_categories()?;
+
+        Ok(database)
     }
 
     pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -95,14 +203,34 @@ impl DataBase {
         self.party_table.save()?;
         self.entity_table.save()?;
         self.account_table.save()?;
+        self.template_table.save()?;
+        self.template_transaction_table.save()?;
+        self.budget_table.save()?;
+        self.assignment_table.save()?;
+        self.reconciliation_table.save()?;
+        self.balance_assertion_table.save()?;
+        self.tag_table.save()?;
+        self.project_table.save()?;
+        self.iou_table.save()?;
+        self.entity_alias_table.save()?;
+        self.category_table.save()?;
+        self.csv_mapping_table.save()?;
+        self.import_reference_table.save()?;
+        self.staging_table.save()?;
+        self.categorization_rule_table.save()?;
 
         Ok(())
     }
 
     pub fn insert_party(&mut self, party: &mut Party) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_refunds(party)?;
         let party_id: i64 = self.party_table.next_id()?;
+        let status: TransactionStatus = party.status.clone();
         for transaction in party.iter() {
-            self.insert_transaction(&transaction, party_id)?;
+            self.insert_transaction(transaction, party_id, &status)?;
+        }
+        for iou in party.ious.iter() {
+            self.iou_table.insert_iou(iou, party_id, &status)?;
         }
 
         self.party_table.insert_party(party)?;
@@ -114,17 +242,26 @@ impl DataBase {
         &mut self,
         transaction: &Transaction,
         party_id: i64,
+        status: &TransactionStatus,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match transaction {
-            Transaction::Expense { .. } => self
-                .expenses_table
-                .insert_transaction(transaction, party_id)?,
-            Transaction::Income { .. } => {
+            Transaction::Expense { tags, .. } => {
+                let expense_id: i64 = self.expenses_table.next_id()?;
+                self.expenses_table
+                    .insert_transaction(transaction, party_id, status)?;
+                self.tag_table
+                    .insert_tags(&transaction.transaction_type(), expense_id, tags)?;
+            }
+            Transaction::Income { tags, .. } => {
+                let income_id: i64 = self.incomes_table.next_id()?;
                 self.incomes_table
-                    .insert_transaction(transaction, party_id)?;
+                    .insert_transaction(transaction, party_id, status)?;
+                self.tag_table
+                    .insert_tags(&transaction.transaction_type(), income_id, tags)?;
             }
             Transaction::Credit { .. } | Transaction::Debit { .. } => {
-                self.funds_table.insert_transaction(transaction, party_id)?;
+                self.funds_table
+                    .insert_transaction(transaction, party_id, status)?;
             }
         }
 
@@ -160,6 +297,11 @@ impl DataBase {
         self.entity_table.iter()
     }
 
+    /// Iterator over the IDs of the entities to offer when inputting
+    pub(crate) fn iter_active_entity_ids(&self) -> Result<IntoIter<i64>, PolarsError> {
+        self.entity_table.iter_active()
+    }
+
     pub(crate) fn entity(&self, entity_id: i64) -> Result<Entity, Box<dyn std::error::Error>> {
         self.entity_table.entity(entity_id)
     }
@@ -168,6 +310,11 @@ impl DataBase {
         self.account_table.iter()
     }
 
+    /// Iterator over the IDs of the accounts to offer when inputting
+    pub(crate) fn iter_open_account_ids(&self) -> Result<IntoIter<i64>, PolarsError> {
+        self.account_table.iter_open()
+    }
+
     pub(crate) fn entity_countries(&self) -> Result<Vec<String>, PolarsError> {
         self.entity_table.countries()
     }
@@ -185,8 +332,12 @@ impl DataBase {
         transaction_type: &TransactionType,
     ) -> Result<Vec<String>, PolarsError> {
         match transaction_type {
-            TransactionType::Income => self.incomes_table.categories(),
-            TransactionType::Expense => self.expenses_table.categories(),
+            TransactionType::Income | TransactionType::Expense => Ok(self
+                .category_paths(transaction_type)?
+                .into_iter()
+                .filter(|(_category_id, path)| path.len() == 1)
+                .map(|(_category_id, path)| path[0].clone())
+                .collect()),
             _ => Ok(Vec::new()), // rethink whether it's the correct thing to do
         }
     }
@@ -196,9 +347,14 @@ impl DataBase {
         transaction_type: &TransactionType,
         category: String,
     ) -> Result<Vec<String>, PolarsError> {
+        // levels below the subcategory are joined by colons
         match transaction_type {
-            TransactionType::Income => self.incomes_table.subcategories(category),
-            TransactionType::Expense => self.expenses_table.subcategories(category),
+            TransactionType::Income | TransactionType::Expense => Ok(self
+                .category_paths(transaction_type)?
+                .into_iter()
+                .filter(|(_category_id, path)| (path.len() > 1) & (path[0] == category))
+                .map(|(_category_id, path)| path[1..].join(":"))
+                .collect()),
             _ => Ok(Vec::new()),
         }
     }
@@ -207,27 +363,101 @@ impl DataBase {
         self.entity_table.subtypes()
     }
 
-    /// Deletes from the database all records from the party.
+    /// Deletes from the database all records from the party. Parties with reconciled fund
+    /// movements are locked and cannot be deleted, and so are parties with expenses given
+    /// back by refunds, which would be left pointing at an expense that is gone.
     pub(crate) fn delete_party(&mut self, party_id: i64) -> Result<(), PolarsError> {
+        if self.funds_table.is_reconciled_party(party_id)? {
+            return Err(PolarsError::InvalidOperation(
+                "The party has reconciled fund movements!".into(),
+            ));
+        }
+        if self.expenses_table.is_refunded_party(party_id)? {
+            return Err(PolarsError::InvalidOperation(
+                "The party has expenses with refunds!".into(),
+            ));
+        }
+        for income_id in self.incomes_table.iter_party(party_id)? {
+            self.tag_table.delete_transaction("Income", income_id)?;
+        }
+        for expense_id in self.expenses_table.iter_party(party_id)? {
+            self.tag_table.delete_transaction("Expense", expense_id)?;
+        }
         self.incomes_table.delete_party(party_id)?;
         self.expenses_table.delete_party(party_id)?;
         self.funds_table.delete_party(party_id)?;
+        self.iou_table.delete_party(party_id)?;
         self.party_table.delete_party(party_id)?;
+        self.assignment_table.delete_party(party_id)?;
+        self.import_reference_table.delete_party(party_id)?;
 
         Ok(())
     }
 
+    /// Changes the status of all the transactions of the party. Parties with reconciled fund
+    /// movements are locked.
+    pub(crate) fn set_party_status(
+        &mut self,
+        party_id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        if self.funds_table.is_reconciled_party(party_id)? {
+            return Err(PolarsError::InvalidOperation(
+                "The party has reconciled fund movements!".into(),
+            ));
+        }
+        self.incomes_table.set_party_status(party_id, status)?;
+        self.expenses_table.set_party_status(party_id, status)?;
+        self.funds_table.set_party_status(party_id, status)?;
+        self.iou_table.set_party_status(party_id, status)?;
+
+        Ok(())
+    }
+
+    /// Changes the status of a single transaction, leaving the rest of its party as it is.
+    /// Reconciled fund movements are locked.
+    pub(crate) fn set_transaction_status(
+        &mut self,
+        transaction_type: &TransactionType,
+        transaction_id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        match transaction_type {
+            TransactionType::Income => self.incomes_table.set_status(transaction_id, status),
+            TransactionType::Expense | TransactionType::Refund => {
+                self.expenses_table.set_status(transaction_id, status)
+            }
+            TransactionType::Credit | TransactionType::Debit => {
+                self.funds_table.set_status(transaction_id, status)
+            }
+        }
+    }
+
     pub(crate) fn party(&self, party_id: i64) -> Result<Party, Box<dyn std::error::Error>> {
         let mut party: Party = Party::new(Vec::new());
         for income_id in self.incomes_table.iter_party(party_id)? {
-            party.add_transaction(self.incomes_table.transaction(income_id)?);
+            let mut income: Transaction = self.incomes_table.transaction(income_id)?;
+            income.set_tags(self.tag_table.transaction_tags("Income", income_id)?);
+            party.add_transaction(income);
         }
         for expense_id in self.expenses_table.iter_party(party_id)? {
-            party.add_transaction(self.expenses_table.transaction(expense_id)?);
+            let mut expense: Transaction = self.expenses_table.transaction(expense_id)?;
+            expense.set_tags(self.tag_table.transaction_tags("Expense", expense_id)?);
+            party.add_transaction(expense);
         }
         for fund_id in self.funds_table.iter_party(party_id)? {
             party.add_transaction(self.funds_table.transaction(fund_id)?);
         }
+        if let Some(status) = self
+            .incomes_table
+            .party_status(party_id)?
+            .or(self.expenses_table.party_status(party_id)?)
+            .or(self.funds_table.party_status(party_id)?)
+        {
+            party.status = status;
+        }
+        party.project_id = self.party_table.project_id(party_id)?;
+        party.ious = self.iou_table.party_ious(party_id)?;
 
         Ok(party)
     }
@@ -1,6 +1,7 @@
 use crate::modules::currency_exchange::CurrencyExchange;
 use crate::modules::database::palettes::fetch_palette;
-use crate::modules::database::DataBase;
+use crate::modules::database::summaries::{Basis, ReportDate};
+use crate::modules::database::{status_filter, DataBase};
 use crate::modules::financial::Currency;
 use chrono::{Months, NaiveDate};
 use plotters::prelude::*;
@@ -111,6 +112,8 @@ impl DataBase {
     pub(crate) fn funds_evolution(
         &self,
         currency_to: &Currency,
+        include_planned: bool,
+        report_date: &ReportDate,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
 
@@ -130,9 +133,15 @@ impl DataBase {
 
         // Fetch the table with fund movements.
         let mut funds_table: DataFrame = self
-            .funds_table
-            .data_frame
-            .clone()
+            .with_report_date(
+                self.funds_table
+                    .data_frame
+                    .clone()
+                    .lazy()
+                    .filter(status_filter(include_planned))
+                    .collect()?,
+                report_date,
+            )?
             .select(["value", "currency", "date"])?;
 
         // First step is getting all fund changes in history, and to those, adding the initial
@@ -255,29 +264,42 @@ impl DataBase {
         &self,
         currency_to: &Currency,
         barplot_type: &BarplotType,
+        include_planned: bool,
+        report_date: &ReportDate,
+        basis: &Basis,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
 
-        let mut data_frame: DataFrame = self.expenses_table.data_frame.clone();
-
-        // First: convert the ammounts to the desired output currency,
-        // and group by month.
-        data_frame = currency_exchange
-            .exchange_currencies(currency_to, data_frame)?
-            .lazy()
-            .sort(["date"], Default::default())
-            .group_by_dynamic(
-                col("date"),
-                [col("category")],
-                DynamicGroupOptions {
-                    every: Duration::parse("1mo"),
-                    period: Duration::parse("1mo"),
-                    offset: Duration::parse("0"),
-                    ..Default::default()
-                },
-            )
-            .agg([col("value").sum()])
-            .collect()?;
+        let mut data_frame: DataFrame = self.with_report_date(
+            self.expenses_table
+                .data_frame
+                .clone()
+                .lazy()
+                .filter(status_filter(include_planned))
+                .collect()?,
+            report_date,
+        )?;
+
+        // First: convert the ammounts to the desired output currency, at the rate of the
+        // day they were paid, spread them if needed, and group by month.
+        data_frame = Self::with_basis(
+            currency_exchange.exchange_currencies(currency_to, data_frame)?,
+            basis,
+        )?
+        .lazy()
+        .sort(["date"], Default::default())
+        .group_by_dynamic(
+            col("date"),
+            [col("category")],
+            DynamicGroupOptions {
+                every: Duration::parse("1mo"),
+                period: Duration::parse("1mo"),
+                offset: Duration::parse("0"),
+                ..Default::default()
+            },
+        )
+        .agg([col("value").sum()])
+        .collect()?;
 
         // Then: if the column plot is relative (columns add to 100),
         // normalize all months to add to 100.
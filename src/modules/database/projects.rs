@@ -0,0 +1,155 @@
+use crate::modules::currency_exchange::CurrencyExchange;
+use crate::modules::database::{
+    capitalize_every_word, data_frame_to_csv_string, status_filter, DataBase,
+};
+use crate::modules::financial::{Currency, Project};
+use chrono::{Local, NaiveDate};
+use polars::prelude::*;
+use std::vec::IntoIter;
+
+impl DataBase {
+    pub fn insert_project(&mut self, project: &Project) -> Result<i64, PolarsError> {
+        self.project_table.insert_project(project)
+    }
+
+    pub(crate) fn iter_project_ids(&self) -> Result<IntoIter<i64>, PolarsError> {
+        self.project_table.iter()
+    }
+
+    pub(crate) fn project(&self, project_id: i64) -> Result<Project, Box<dyn std::error::Error>> {
+        self.project_table.project(project_id)
+    }
+
+    /// Deletes the project. Its parties are kept, but they are no longer part of any project.
+    pub(crate) fn delete_project(&mut self, project_id: i64) -> Result<(), PolarsError> {
+        self.party_table.detach_project(project_id)?;
+        self.project_table.delete_project(project_id)
+    }
+
+    /// Sums the expenses and the incomes of the parties of a project per category. The sums
+    /// are expressed in the currency_to, every transaction exchanged at its own date, in a
+    /// column named value.
+    pub(crate) fn project_by_category(
+        &self,
+        currency_exchange: &CurrencyExchange,
+        project_id: i64,
+        currency_to: &Currency,
+        include_planned: bool,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let parties: LazyFrame = self
+            .party_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("project_id").eq(lit(project_id)))
+            .select([col("party_id")]);
+
+        let mut transactions: Vec<DataFrame> = Vec::new();
+        for (table, transaction_type) in [
+            (&self.expenses_table.data_frame, "Expense"),
+            (&self.incomes_table.data_frame, "Income"),
+        ] {
+            transactions.push(
+                table
+                    .clone()
+                    .lazy()
+                    .filter(status_filter(include_planned))
+                    .join(
+                        parties.clone(),
+                        [col("party_id")],
+                        [col("party_id")],
+                        JoinArgs::new(JoinType::Inner),
+                    )
+                    .with_column(lit(transaction_type).alias("type"))
+                    .select([
+                        col("type"),
+                        col("category"),
+                        col("date"),
+                        col("currency"),
+                        col("value"),
+                    ])
+                    .collect()?,
+            );
+        }
+
+        Ok(currency_exchange
+            .exchange_currencies(currency_to, transactions[0].vstack(&transactions[1])?)?
+            .lazy()
+            .group_by([col("type"), col("category")])
+            .agg([col("value").sum()])
+            .sort(["type", "category"], Default::default())
+            .collect()?)
+    }
+
+    /// Generates the report of a project: its expenses and incomes per category, expressed
+    /// in the currency_to, and what the project cost in total, which is its expenses minus
+    /// its incomes (like refunds, or the share paid back by a friend). If the project has a
+    /// budget, it is compared with the cost, exchanged at today's rate.
+    pub(crate) fn project_report(
+        &self,
+        project_id: i64,
+        currency_to: &Currency,
+        include_planned: bool,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
+        let project: Project = self.project(project_id)?;
+
+        let by_category: DataFrame =
+            self.project_by_category(&currency_exchange, project_id, currency_to, include_planned)?;
+        let sum_of = |transaction_type: &str| -> Result<f64, PolarsError> {
+            Ok(by_category
+                .clone()
+                .lazy()
+                .filter(col("type").eq(lit(transaction_type)))
+                .collect()?
+                .column("value")?
+                .f64()?
+                .sum()
+                .unwrap_or(0.0))
+        };
+        let total_expenses: f64 = sum_of("Expense")?;
+        let total_cost: f64 = total_expenses - sum_of("Income")?;
+
+        let mut report: DataFrame = by_category
+            .lazy()
+            .with_columns([
+                when(col("type").eq(lit("Expense")))
+                    .then((col("value") * lit(100) / lit(total_expenses)).round(2))
+                    .otherwise(lit(NULL).cast(DataType::Float64))
+                    .alias("%_total_expenses"),
+                col("value").round(2).alias(currency_to.to_string()),
+            ])
+            .select([
+                col("type"),
+                col("category"),
+                col(currency_to.to_string()),
+                col("%_total_expenses"),
+            ])
+            .select([all().name().map(|name| {
+                Ok(PlSmallStr::from_string(capitalize_every_word(
+                    name.replace("_", " "),
+                )))
+            })])
+            .collect()?;
+
+        let mut last_rows: Vec<(&str, f64)> = vec![("Total cost", total_cost)];
+        if project.budget() > 0.0 {
+            let today: NaiveDate = Local::now().date_naive();
+            let budget: f64 = project.budget()
+                * currency_exchange.exchange_currency(project.currency(), currency_to, today)?;
+            last_rows.push(("Budget", budget));
+            last_rows.push(("Remaining", budget - total_cost));
+        }
+        for (name, value) in last_rows {
+            let last_row: DataFrame = df!(
+                "Type" => ["Total"],
+                "Category" => [name],
+                currency_to.to_string().as_str() => [(100.0 * value).round() / 100.0],
+                "% Total Expenses" => [None::<f64>],
+            )?;
+            report = report.vstack(&last_row)?;
+        }
+
+        data_frame_to_csv_string(&mut report)
+    }
+}
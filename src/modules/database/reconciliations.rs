@@ -0,0 +1,149 @@
+use crate::modules::database::{status_filter, DataBase};
+use crate::modules::financial::{Account, Currency, TransactionStatus};
+use chrono::NaiveDate;
+use polars::prelude::*;
+
+impl DataBase {
+    /// Returns the fund movements of the account in the currency sorted by date, with the
+    /// running balance of that pocket after each of them. Void movements do not change the
+    /// balance, while planned ones do, so that the balance after them is the expected one.
+    pub(crate) fn account_movements(
+        &self,
+        account_id: i64,
+        currency: &Currency,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let initial_balance: f64 = self.pocket_initial_balance(account_id, currency)?;
+
+        Ok(self
+            .funds_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("account_id").eq(lit(account_id)))
+            .filter(col("currency").eq(lit(currency.to_string())))
+            .sort(["date", "fund_movement_id"], Default::default())
+            .with_column(
+                (lit(initial_balance)
+                    + when(status_filter(true))
+                        .then(col("value"))
+                        .otherwise(lit(0.0))
+                        .cum_sum(false))
+                .alias("balance"),
+            )
+            .select([
+                col("fund_movement_id"),
+                col("date"),
+                col("fund_movement_type"),
+                col("value"),
+                col("currency"),
+                col("balance").round(2),
+                col("status"),
+                col("reconciliation_id"),
+                col("party_id"),
+            ])
+            .collect()?)
+    }
+
+    /// Changes the status of a single fund movement, for instance when it is ticked off as
+    /// cleared by the bank.
+    pub(crate) fn set_fund_movement_status(
+        &mut self,
+        fund_movement_id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        self.funds_table.set_status(fund_movement_id, status)
+    }
+
+    /// Returns the balance of the account in the currency counting only the cleared
+    /// movements up to the statement date, included.
+    pub(crate) fn cleared_balance(
+        &self,
+        account_id: i64,
+        currency: &Currency,
+        statement_date: NaiveDate,
+    ) -> Result<f64, Box<dyn std::error::Error>> {
+        let initial_balance: f64 = self.pocket_initial_balance(account_id, currency)?;
+
+        let cleared: f64 = self
+            .funds_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("account_id")
+                    .eq(lit(account_id))
+                    .and(col("currency").eq(lit(currency.to_string())))
+                    .and(col("status").eq(lit(TransactionStatus::Cleared.to_string())))
+                    .and(col("date").lt_eq(lit(statement_date))),
+            )
+            .collect()?
+            .column("value")?
+            .f64()?
+            .sum()
+            .unwrap_or(0.0);
+
+        Ok(((initial_balance + cleared) * 100.0).round() / 100.0)
+    }
+
+    /// Returns the statement date and balance of the last reconciliation of the account in
+    /// the currency.
+    pub(crate) fn last_reconciliation(
+        &self,
+        account_id: i64,
+        currency: &Currency,
+    ) -> Result<Option<(NaiveDate, f64)>, PolarsError> {
+        self.reconciliation_table
+            .last_reconciliation(account_id, currency)
+    }
+
+    /// Reconciles the pocket of the account in the currency with the bank statement: if the
+    /// cleared balance matches the statement balance, the cleared movements up to the
+    /// statement date get locked. Returns the ID of the reconciliation.
+    pub(crate) fn reconcile(
+        &mut self,
+        account_id: i64,
+        currency: &Currency,
+        statement_date: NaiveDate,
+        statement_balance: f64,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        let difference: f64 =
+            statement_balance - self.cleared_balance(account_id, currency, statement_date)?;
+        if difference.abs() >= 0.005 {
+            return Err(format!(
+                "The cleared balance differs from the statement balance by {:.2}!",
+                difference
+            )
+            .into());
+        }
+
+        let reconciliation_id: i64 = self.reconciliation_table.insert_reconciliation(
+            account_id,
+            currency,
+            statement_date,
+            statement_balance,
+        )?;
+        self.funds_table
+            .reconcile(account_id, currency, statement_date, reconciliation_id)?;
+
+        Ok(reconciliation_id)
+    }
+
+    /// Returns the initial balance of the account in the currency, which is only the one of
+    /// the account in its main currency, since the pockets start empty.
+    fn pocket_initial_balance(
+        &self,
+        account_id: i64,
+        currency: &Currency,
+    ) -> Result<f64, Box<dyn std::error::Error>> {
+        let account: Account = self.account(account_id)?;
+        if !account.has_currency(currency) {
+            return Err(format!("{} has no {} pocket!", account.name(), currency).into());
+        }
+
+        Ok(if account.currency() == currency {
+            account.initial_balance()
+        } else {
+            0.0
+        })
+    }
+}
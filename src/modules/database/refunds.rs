@@ -0,0 +1,222 @@
+use crate::modules::database::{
+    capitalize_every_word, data_frame_to_csv_string, status_filter, DataBase,
+};
+use crate::modules::financial::{Party, Transaction};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+impl DataBase {
+    /// Returns the expenses that are not refunds themselves, with the amount given back so
+    /// far in a column named refunded and what is left in a column named outstanding. Void
+    /// refunds do not count.
+    fn expenses_with_refunds(&self) -> LazyFrame {
+        let refunds: LazyFrame = self
+            .expenses_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(status_filter(true))
+            .filter(col("refund_of").gt_eq(lit(0i64)))
+            .group_by([col("refund_of")])
+            .agg([(lit(0.0) - col("value")).sum().alias("refunded")]);
+
+        self.expenses_table
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(status_filter(true))
+            .filter(col("refund_of").lt(lit(0i64)))
+            .join(
+                refunds,
+                [col("expense_id")],
+                [col("refund_of")],
+                JoinArgs::new(JoinType::Left),
+            )
+            .with_column(col("refunded").fill_null(lit(0.0)))
+            .with_column((col("value") - col("refunded")).alias("outstanding"))
+    }
+
+    /// Returns what is left to give back of the expense.
+    pub(crate) fn refundable_value(&self, expense_id: i64) -> Result<f64, PolarsError> {
+        Ok(self
+            .expenses_with_refunds()
+            .filter(col("expense_id").eq(lit(expense_id)))
+            .collect()?
+            .column("outstanding")?
+            .f64()?
+            .sum()
+            .unwrap_or(0.0))
+    }
+
+    /// Returns the last n expenses that are not fully given back yet, by expense ID, with a
+    /// label to choose them from.
+    pub(crate) fn refundable_expenses(&self, n: usize) -> Result<Vec<(i64, String)>, PolarsError> {
+        let expenses: DataFrame = self
+            .expenses_with_refunds()
+            .filter(col("outstanding").gt_eq(lit(0.01)))
+            .sort(
+                ["date", "expense_id"],
+                SortMultipleOptions::default().with_order_descending_multi([true, true]),
+            )
+            .collect()?
+            .head(Some(n));
+
+        let mut labels: Vec<(i64, String)> = Vec::new();
+        for i in 0..expenses.height() {
+            let expense_id: i64 = expenses.column("expense_id")?.i64()?.get(i).unwrap_or(-1);
+            let date: String = expenses
+                .column("date")?
+                .date()?
+                .as_date_iter()
+                .nth(i)
+                .flatten()
+                .map(|date| date.to_string())
+                .unwrap_or_default();
+            // empty strings are read as nulls
+            let category: &str = expenses.column("category")?.str()?.get(i).unwrap_or("");
+            let description: &str = expenses.column("description")?.str()?.get(i).unwrap_or("");
+            let value: f64 = expenses.column("value")?.f64()?.get(i).unwrap_or(0.0);
+            let currency: &str = expenses.column("currency")?.str()?.get(i).unwrap_or("");
+            labels.push((
+                expense_id,
+                format!("{date} {category} {value:.2} {currency} {description}"),
+            ));
+        }
+
+        Ok(labels)
+    }
+
+    /// Fails unless the expense can be given back the value: it must not be a refund
+    /// itself, and no more than what is left of it can be given back.
+    fn check_refundable(
+        &self,
+        expense_id: i64,
+        value: f64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Transaction::Expense { refund_of, .. } =
+            self.expenses_table.transaction(expense_id)?
+        {
+            if refund_of >= 0 {
+                return Err("A refund cannot be refunded!".into());
+            }
+        }
+        if value <= 0.0 {
+            return Err("A refund must give back a positive value!".into());
+        }
+        let refundable_value: f64 = self.refundable_value(expense_id)?;
+        if value > refundable_value + 0.005 {
+            return Err(
+                format!("Only {refundable_value:.2} is left to give back of the expense!").into(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Fails if the refunds of the party give back more than what is left of their
+    /// expenses, counting together the ones that give back the same expense.
+    pub(super) fn check_refunds(&self, party: &Party) -> Result<(), Box<dyn std::error::Error>> {
+        let mut refunded: HashMap<i64, f64> = HashMap::new();
+        for transaction in party.transactions.iter() {
+            if let Transaction::Expense {
+                value, refund_of, ..
+            } = transaction
+            {
+                if *refund_of >= 0 {
+                    *refunded.entry(*refund_of).or_default() -= value;
+                }
+            }
+        }
+        for (expense_id, value) in refunded {
+            self.check_refundable(expense_id, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds the refund of part or all of an expense: a negative expense of the same
+    /// category, subcategory and entity, so that it nets against them in every report.
+    pub(crate) fn refund(
+        &self,
+        expense_id: i64,
+        value: f64,
+        date: NaiveDate,
+        description: String,
+    ) -> Result<Transaction, Box<dyn std::error::Error>> {
+        self.check_refundable(expense_id, value)?;
+        match self.expenses_table.transaction(expense_id)? {
+            Transaction::Expense {
+                currency,
+                category,
+                subcategory,
+                entity_id,
+                ..
+            } => Ok(Transaction::Expense {
+                value: -value,
+                currency,
+                date,
+                category,
+                subcategory,
+                description,
+                entity_id,
+                amortization_months: 1,
+                tags: Vec::new(),
+                refund_of: expense_id,
+                reimbursable: false,
+            }),
+            _ => Err("Not an expense!".into()),
+        }
+    }
+
+    /// Returns the reimbursable expenses that are still awaiting payback, oldest first.
+    pub(crate) fn outstanding_reimbursements(&self) -> Result<DataFrame, PolarsError> {
+        let entities_table: LazyFrame = self
+            .entity_table
+            .data_frame
+            .clone()
+            .lazy()
+            .select([col("entity_id"), col("name").alias("entity_name")]);
+
+        self.expenses_with_refunds()
+            .filter(col("reimbursable"))
+            .filter(col("outstanding").gt_eq(lit(0.01)))
+            .join(
+                entities_table,
+                [col("entity_id")],
+                [col("entity_id")],
+                JoinArgs::new(JoinType::Left),
+            )
+            .sort(["date", "expense_id"], Default::default())
+            .select([
+                col("date"),
+                col("entity_name"),
+                col("category"),
+                col("subcategory"),
+                col("description"),
+                col("currency"),
+                col("value"),
+                col("refunded").round(2),
+                col("outstanding").round(2),
+                col("expense_id"),
+            ])
+            .collect()
+    }
+
+    /// Returns a csv in String format with the reimbursable expenses still awaiting payback.
+    pub(crate) fn outstanding_reimbursements_csv(
+        &self,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let mut outstanding: DataFrame = self
+            .outstanding_reimbursements()?
+            .lazy()
+            .select([all().name().map(|name| {
+                Ok(PlSmallStr::from_string(capitalize_every_word(
+                    name.replace("_", " "),
+                )))
+            })])
+            .collect()?;
+
+        data_frame_to_csv_string(&mut outstanding)
+    }
+}
@@ -0,0 +1,238 @@
+use crate::modules::categorization::rules::{CategorizationRule, CompiledRules, RuleProposal};
+use crate::modules::categorization::CategorizationSubject;
+use crate::modules::database::DataBase;
+use crate::modules::financial::{Currency, TransactionStatus, TransactionType};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::vec::IntoIter;
+
+impl DataBase {
+    /// Saves the rule, replacing the one with the same name if any. Returns the ID of the
+    /// rule.
+    pub(crate) fn insert_categorization_rule(
+        &mut self,
+        rule: &CategorizationRule,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        if rule.name.trim().is_empty() {
+            return Err("The rule needs a name!".into());
+        }
+        if (rule.transaction_type != TransactionType::Income)
+            & (rule.transaction_type != TransactionType::Expense)
+        {
+            return Err("Rules only categorize incomes and expenses!".into());
+        }
+        if let Err(error) = rule.regex() {
+            return Err(format!("The description pattern is not valid: {error}").into());
+        }
+        if let (Some(min_value), Some(max_value)) = (rule.min_value, rule.max_value) {
+            if min_value > max_value {
+                return Err("The minimum value is above the maximum value!".into());
+            }
+        }
+        if rule.category.is_empty() & !rule.subcategory.is_empty() {
+            return Err("A subcategory needs a category!".into());
+        }
+        // a rule can propose only the entity or the tags
+        if !rule.category.is_empty()
+            & !self.is_known_category(&rule.transaction_type, &rule.category, &rule.subcategory)?
+        {
+            return Err(format!(
+                "Unknown {} category {}!",
+                rule.transaction_type, rule.category
+            )
+            .into());
+        }
+        // fail if there is no such entity or account
+        if rule.entity_id >= 0 {
+            self.entity(rule.entity_id)?;
+        }
+        if rule.account_id >= 0 {
+            self.account(rule.account_id)?;
+        }
+
+        if let Some(rule_id) = self.categorization_rule_table.find_rule(&rule.name)? {
+            self.categorization_rule_table.delete_rule(rule_id)?;
+        }
+        Ok(self.categorization_rule_table.insert_rule(rule)?)
+    }
+
+    pub(crate) fn categorization_rule(
+        &self,
+        rule_id: i64,
+    ) -> Result<CategorizationRule, Box<dyn std::error::Error>> {
+        self.categorization_rule_table.rule(rule_id)
+    }
+
+    /// IDs of the rules, in the order they are tried
+    pub(crate) fn iter_categorization_rule_ids(&self) -> Result<IntoIter<i64>, PolarsError> {
+        self.categorization_rule_table.iter()
+    }
+
+    pub(crate) fn delete_categorization_rule(&mut self, rule_id: i64) -> Result<(), PolarsError> {
+        self.categorization_rule_table.delete_rule(rule_id)
+    }
+
+    /// Tells in words the conditions of the rule, to explain why it fired.
+    pub(crate) fn explain_rule(
+        &self,
+        rule: &CategorizationRule,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let mut conditions: Vec<String> = vec![format!(
+            "{} with a description matching \"{}\"",
+            rule.transaction_type.to_string().to_lowercase(),
+            rule.description_pattern
+        )];
+        match (rule.min_value, rule.max_value) {
+            (Some(min_value), Some(max_value)) => {
+                conditions.push(format!("from {min_value:.2} to {max_value:.2}"))
+            }
+            (Some(min_value), None) => conditions.push(format!("of at least {min_value:.2}")),
+            (None, Some(max_value)) => conditions.push(format!("of at most {max_value:.2}")),
+            (None, None) => {}
+        }
+        if let Some(currency) = &rule.currency {
+            conditions.push(format!("in {currency}"));
+        }
+        if rule.account_id >= 0 {
+            conditions.push(format!("on {}", self.account(rule.account_id)?.name()));
+        }
+
+        Ok(format!(
+            "Rule \"{}\" (priority {}): {}",
+            rule.name,
+            rule.priority,
+            conditions.join(", ")
+        ))
+    }
+
+    /// Returns the rules compiled, so that they are read from the table only once when
+    /// tried on many incomes or expenses.
+    pub(crate) fn compiled_categorization_rules(
+        &self,
+    ) -> Result<CompiledRules, Box<dyn std::error::Error>> {
+        let mut compiled_rules: CompiledRules = CompiledRules::default();
+        for rule_id in self.categorization_rule_table.iter()? {
+            let rule: CategorizationRule = self.categorization_rule_table.rule(rule_id)?;
+            let regex = rule.regex()?;
+            let explanation: String = self.explain_rule(&rule)?;
+            compiled_rules.push(rule_id, rule, regex, explanation);
+        }
+
+        Ok(compiled_rules)
+    }
+
+    /// Returns what the first rule that matches the subject proposes, if any rule does.
+    pub(crate) fn propose_categorization(
+        &self,
+        subject: &CategorizationSubject,
+    ) -> Result<Option<RuleProposal>, Box<dyn std::error::Error>> {
+        Ok(self.compiled_categorization_rules()?.propose(subject))
+    }
+
+    /// Tries the rule on the incomes or expenses already in the ledger, as if it were the
+    /// only one. Returns one row per transaction it matches, telling whether the rule
+    /// agrees with how it was categorized.
+    pub(crate) fn test_categorization_rule(
+        &self,
+        rule: &CategorizationRule,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let regex = rule.regex()?;
+        let data_frame: DataFrame = match rule.transaction_type {
+            TransactionType::Income => self.incomes_table.data_frame.clone(),
+            TransactionType::Expense => self.expenses_table.data_frame.clone(),
+            _ => return Err("Rules only categorize incomes and expenses!".into()),
+        }
+        .lazy()
+        .filter(col("status").neq(lit(TransactionStatus::Void.to_string())))
+        .sort(["date"], Default::default())
+        .collect()?;
+
+        // the account of an income or expense is the one of the first fund movement of its
+        // party
+        let mut accounts: HashMap<i64, i64> = HashMap::new();
+        let funds: &DataFrame = &self.funds_table.data_frame;
+        for (party_id, account_id) in funds
+            .column("party_id")?
+            .i64()?
+            .into_no_null_iter()
+            .zip(funds.column("account_id")?.i64()?.into_no_null_iter())
+        {
+            accounts.entry(party_id).or_insert(account_id);
+        }
+        let mut entity_names: HashMap<i64, String> = HashMap::new();
+
+        let party_ids_column = data_frame.column("party_id")?.i64()?;
+        let mut dates_column = data_frame.column("date")?.date()?.as_date_iter();
+        let values_column = data_frame.column("value")?.f64()?;
+        let currencies_column = data_frame.column("currency")?.str()?;
+        let descriptions_column = data_frame.column("description")?.str()?;
+        let entity_ids_column = data_frame.column("entity_id")?.i64()?;
+        let categories_column = data_frame.column("category")?.str()?;
+        let subcategories_column = data_frame.column("subcategory")?.str()?;
+
+        let mut party_ids: Vec<i64> = Vec::new();
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        let mut values: Vec<f64> = Vec::new();
+        let mut currencies: Vec<String> = Vec::new();
+        let mut descriptions: Vec<String> = Vec::new();
+        let mut entities: Vec<String> = Vec::new();
+        let mut categories: Vec<String> = Vec::new();
+        let mut subcategories: Vec<String> = Vec::new();
+        let mut agreements: Vec<bool> = Vec::new();
+        for i in 0..data_frame.height() {
+            let party_id: i64 = party_ids_column.get(i).ok_or("No party_id!")?;
+            let date: NaiveDate = dates_column.next().flatten().ok_or("No date!")?;
+            let entity_id: i64 = entity_ids_column.get(i).ok_or("No entity_id!")?;
+            let entity_name: String = match entity_names.get(&entity_id) {
+                Some(entity_name) => entity_name.clone(),
+                None => {
+                    let entity_name: String = self.entity(entity_id)?.name();
+                    entity_names.insert(entity_id, entity_name.clone());
+                    entity_name
+                }
+            };
+            // empty strings are read as nulls
+            let description: &str = descriptions_column.get(i).unwrap_or("");
+            let category: &str = categories_column.get(i).unwrap_or("");
+            let subcategory: &str = subcategories_column.get(i).unwrap_or("");
+            let subject = CategorizationSubject {
+                transaction_type: rule.transaction_type.clone(),
+                text: format!("{} {}", description, entity_name),
+                value: values_column.get(i).ok_or("No value!")?,
+                currency: Currency::from_str(currencies_column.get(i).ok_or("No currency!")?)?,
+                account_id: *accounts.get(&party_id).unwrap_or(&-1),
+            };
+            if !rule.matches(&regex, &subject) {
+                continue;
+            }
+
+            party_ids.push(party_id);
+            dates.push(date);
+            values.push(subject.value);
+            currencies.push(subject.currency.to_string());
+            descriptions.push(description.to_string());
+            entities.push(entity_name);
+            categories.push(category.to_string());
+            subcategories.push(subcategory.to_string());
+            agreements.push(
+                ((rule.entity_id < 0) | (rule.entity_id == entity_id))
+                    & (rule.category.is_empty()
+                        | ((rule.category == category) & (rule.subcategory == subcategory))),
+            );
+        }
+
+        Ok(df!(
+            "party_id" => party_ids,
+            "date" => dates,
+            "value" => values,
+            "currency" => currencies,
+            "description" => descriptions,
+            "entity" => entities,
+            "category" => categories,
+            "subcategory" => subcategories,
+            "agrees" => agreements,
+        )?)
+    }
+}
@@ -0,0 +1,334 @@
+use crate::modules::database::DataBase;
+use crate::modules::financial::{Party, Transaction, TransactionType};
+use crate::modules::importers::ImportedMovement;
+use crate::modules::tables::Table;
+use polars::prelude::*;
+use std::fmt::Display;
+use std::vec::IntoIter;
+
+/// A party drafted from a movement of a bank statement. It waits in the staging inbox until
+/// it is reviewed and committed into the database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DraftParty {
+    pub account_id: i64,
+    pub movement: ImportedMovement,
+    pub entity_id: i64, // 0 if the payee did not match any entity
+    pub category: String,
+    pub subcategory: String,
+    pub tags: Vec<String>,
+    pub rule_id: i64,   // categorization rule that proposed the category, -1 if none
+    pub accepted: bool, // whether it goes into the database at the next commit
+}
+
+impl DraftParty {
+    /// Money that came in is an income, money that went out an expense.
+    pub(crate) fn transaction_type(&self) -> TransactionType {
+        if self.movement.value > 0.0 {
+            TransactionType::Income
+        } else {
+            TransactionType::Expense
+        }
+    }
+
+    /// Returns the party of the draft: an income credited to the account, or an expense
+    /// debited from it.
+    pub(crate) fn party(&self) -> Party {
+        let movement: &ImportedMovement = &self.movement;
+        let description: String = if movement.description.is_empty() {
+            movement.payee.clone()
+        } else {
+            movement.description.clone()
+        };
+        let value: f64 = movement.value.abs();
+
+        let transactions: Vec<Transaction> = match self.transaction_type() {
+            TransactionType::Income => vec![
+                Transaction::Income {
+                    value,
+                    currency: movement.currency.clone(),
+                    date: movement.date,
+                    category: self.category.clone(),
+                    subcategory: self.subcategory.clone(),
+                    description,
+                    entity_id: self.entity_id,
+                    tags: self.tags.clone(),
+                },
+                Transaction::Credit {
+                    value,
+                    currency: movement.currency.clone(),
+                    date: movement.date,
+                    account_id: self.account_id,
+                    value_date: movement.value_date,
+                },
+            ],
+            _ => vec![
+                Transaction::Expense {
+                    value,
+                    currency: movement.currency.clone(),
+                    date: movement.date,
+                    category: self.category.clone(),
+                    subcategory: self.subcategory.clone(),
+                    description,
+                    entity_id: self.entity_id,
+                    amortization_months: 1,
+                    tags: self.tags.clone(),
+                    refund_of: -1,
+                    reimbursable: false,
+                },
+                Transaction::Debit {
+                    value,
+                    currency: movement.currency.clone(),
+                    date: movement.date,
+                    account_id: self.account_id,
+                    value_date: movement.value_date,
+                },
+            ],
+        };
+
+        Party::new(transactions)
+    }
+}
+
+/// Problems of a draft party found when reviewing it.
+#[derive(Debug, PartialEq, Clone)]
+pub enum StagingFlag {
+    Unbalanced,        // its transactions do not cancel each other out
+    UnknownCategory,   // not in the category registry
+    Duplicate,         // its movement was already imported, or is staged twice
+    PossibleDuplicate, // looks like a party of the ledger, maybe entered by hand
+}
+
+impl StagingFlag {
+    /// Whether the flag keeps the draft from being committed. A possible duplicate is only
+    /// a warning, since the same amount can be paid twice to the same entity.
+    pub(crate) fn is_blocking(&self) -> bool {
+        *self != StagingFlag::PossibleDuplicate
+    }
+}
+
+impl Display for StagingFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            StagingFlag::Unbalanced => "Unbalanced".to_string(),
+            StagingFlag::UnknownCategory => "Unknown category".to_string(),
+            StagingFlag::Duplicate => "Duplicate".to_string(),
+            StagingFlag::PossibleDuplicate => "Possible duplicate".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Copy of the tables that a commit writes to, so that a failed commit leaves no trace.
+struct StagingSnapshot {
+    incomes: DataFrame,
+    expenses: DataFrame,
+    funds: DataFrame,
+    party: DataFrame,
+    tag: DataFrame,
+    iou: DataFrame,
+    import_reference: DataFrame,
+    staging: DataFrame,
+}
+
+impl DataBase {
+    /// Adds a draft party to the staging inbox
+    pub(crate) fn stage_draft(&mut self, draft: &DraftParty) -> Result<i64, PolarsError> {
+        self.staging_table.insert_draft(draft)
+    }
+
+    pub(crate) fn staged_draft(
+        &self,
+        staging_id: i64,
+    ) -> Result<DraftParty, Box<dyn std::error::Error>> {
+        self.staging_table.draft(staging_id)
+    }
+
+    pub(crate) fn iter_staged_ids(&self) -> Result<IntoIter<i64>, PolarsError> {
+        self.staging_table.iter()
+    }
+
+    /// Returns the problems of the draft, if any.
+    pub(crate) fn staging_flags(
+        &self,
+        staging_id: i64,
+    ) -> Result<Vec<StagingFlag>, Box<dyn std::error::Error>> {
+        let draft: DraftParty = self.staging_table.draft(staging_id)?;
+        let mut flags: Vec<StagingFlag> = Vec::new();
+
+        if !draft.party().is_valid() {
+            flags.push(StagingFlag::Unbalanced);
+        }
+        if !self.is_known_category(
+            &draft.transaction_type(),
+            &draft.category,
+            &draft.subcategory,
+        )? {
+            flags.push(StagingFlag::UnknownCategory);
+        }
+        let reference: &str = &draft.movement.reference;
+        let is_duplicate: bool = !reference.is_empty()
+            & (self
+                .import_reference_table
+                .is_imported(draft.account_id, reference)?
+                | (self
+                    .staging_table
+                    .count_reference(draft.account_id, reference)?
+                    > 1));
+        if is_duplicate {
+            flags.push(StagingFlag::Duplicate);
+        } else if !self.find_duplicates(&draft.party())?.is_empty() {
+            flags.push(StagingFlag::PossibleDuplicate);
+        }
+
+        Ok(flags)
+    }
+
+    /// Assigns the entity to all the drafts at once
+    pub(crate) fn assign_staged_entity(
+        &mut self,
+        staging_ids: &[i64],
+        entity_id: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // fails if there is no such entity
+        self.entity(entity_id)?;
+        Ok(self.staging_table.set_entity(staging_ids, entity_id)?)
+    }
+
+    /// Assigns the category to the drafts of the transaction type, since incomes and
+    /// expenses have categories of their own. Returns the number of drafts changed.
+    pub(crate) fn assign_staged_category(
+        &mut self,
+        staging_ids: &[i64],
+        transaction_type: &TransactionType,
+        category: &str,
+        subcategory: &str,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        if !self.is_known_category(transaction_type, category, subcategory)? {
+            return Err(format!(
+                "Unknown {transaction_type} category {}!",
+                [category, subcategory]
+                    .iter()
+                    .filter(|name| !name.is_empty())
+                    .copied()
+                    .collect::<Vec<&str>>()
+                    .join(" / ")
+            )
+            .into());
+        }
+
+        let mut ids: Vec<i64> = Vec::new();
+        for staging_id in staging_ids {
+            if &self.staging_table.draft(*staging_id)?.transaction_type() == transaction_type {
+                ids.push(*staging_id);
+            }
+        }
+        self.staging_table
+            .set_category(&ids, category, subcategory)?;
+
+        Ok(ids.len())
+    }
+
+    pub(crate) fn set_staged_accepted(
+        &mut self,
+        staging_ids: &[i64],
+        accepted: bool,
+    ) -> Result<(), PolarsError> {
+        self.staging_table.set_accepted(staging_ids, accepted)
+    }
+
+    /// Removes the drafts from the inbox without committing them
+    pub(crate) fn discard_staged(&mut self, staging_ids: &[i64]) -> Result<(), PolarsError> {
+        self.staging_table.delete_drafts(staging_ids)
+    }
+
+    fn staging_snapshot(&self) -> StagingSnapshot {
+        StagingSnapshot {
+            incomes: self.incomes_table.data_frame.clone(),
+            expenses: self.expenses_table.data_frame.clone(),
+            funds: self.funds_table.data_frame.clone(),
+            party: self.party_table.data_frame.clone(),
+            tag: self.tag_table.data_frame.clone(),
+            iou: self.iou_table.data_frame.clone(),
+            import_reference: self.import_reference_table.data_frame.clone(),
+            staging: self.staging_table.data_frame.clone(),
+        }
+    }
+
+    fn restore_staging_snapshot(&mut self, snapshot: StagingSnapshot) {
+        self.incomes_table.data_frame = snapshot.incomes;
+        self.expenses_table.data_frame = snapshot.expenses;
+        self.funds_table.data_frame = snapshot.funds;
+        self.party_table.data_frame = snapshot.party;
+        self.tag_table.data_frame = snapshot.tag;
+        self.iou_table.data_frame = snapshot.iou;
+        self.import_reference_table.data_frame = snapshot.import_reference;
+        self.staging_table.data_frame = snapshot.staging;
+    }
+
+    /// Inserts the parties of the accepted drafts, records their references so that their
+    /// movements are not imported again, and takes them out of the inbox. Either all of them
+    /// are committed or none is: a draft with a blocking flag stops the commit before
+    /// anything is written, and a failure while writing undoes what was written. Returns the
+    /// number of parties committed.
+    pub(crate) fn commit_staged(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut drafts: Vec<(i64, DraftParty)> = Vec::new();
+        for staging_id in self.staging_table.iter()? {
+            let draft: DraftParty = self.staging_table.draft(staging_id)?;
+            if !draft.accepted {
+                continue;
+            }
+            let flags: Vec<StagingFlag> = self
+                .staging_flags(staging_id)?
+                .into_iter()
+                .filter(|flag| flag.is_blocking())
+                .collect();
+            if !flags.is_empty() {
+                return Err(format!(
+                    "The draft of {} on {} is flagged as {}, fix or discard it first!",
+                    draft.movement.value.abs(),
+                    draft.movement.date,
+                    flags
+                        .iter()
+                        .map(|flag| flag.to_string().to_lowercase())
+                        .collect::<Vec<String>>()
+                        .join(" and ")
+                )
+                .into());
+            }
+            drafts.push((staging_id, draft));
+        }
+        if drafts.is_empty() {
+            return Err("No draft party is accepted!".into());
+        }
+
+        let snapshot: StagingSnapshot = self.staging_snapshot();
+        match self.commit_drafts(&drafts) {
+            Ok(()) => Ok(drafts.len()),
+            Err(error) => {
+                self.restore_staging_snapshot(snapshot);
+                Err(error)
+            }
+        }
+    }
+
+    fn commit_drafts(
+        &mut self,
+        drafts: &[(i64, DraftParty)],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for (_, draft) in drafts {
+            let party_id: i64 = self.party_table.next_id()?;
+            self.insert_party(&mut draft.party())?;
+            if !draft.movement.reference.is_empty() {
+                self.import_reference_table.insert_reference(
+                    draft.account_id,
+                    &draft.movement.reference,
+                    party_id,
+                )?;
+            }
+        }
+        let staging_ids: Vec<i64> = drafts.iter().map(|(staging_id, _)| *staging_id).collect();
+        self.staging_table.delete_drafts(&staging_ids)?;
+
+        Ok(())
+    }
+}
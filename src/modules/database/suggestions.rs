@@ -0,0 +1,59 @@
+use crate::modules::categorization::classifier::{CategoryClassifier, CategoryFeatures};
+use crate::modules::database::DataBase;
+use crate::modules::financial::{TransactionStatus, TransactionType};
+use chrono::NaiveDate;
+use polars::prelude::*;
+
+impl DataBase {
+    /// Returns a classifier that learned the categories of the incomes or expenses of the
+    /// ledger. Void transactions are left out, and so are refunds, which are stored as
+    /// negative expenses.
+    pub(crate) fn category_classifier(
+        &self,
+        transaction_type: &TransactionType,
+    ) -> Result<CategoryClassifier, Box<dyn std::error::Error>> {
+        let data_frame: DataFrame = match transaction_type {
+            TransactionType::Income => self.incomes_table.data_frame.clone(),
+            TransactionType::Expense => self.expenses_table.data_frame.clone(),
+            _ => return Err("Only incomes and expenses have categories!".into()),
+        }
+        .lazy()
+        .filter(
+            col("status")
+                .neq(lit(TransactionStatus::Void.to_string()))
+                .and(col("value").gt(lit(0.0))),
+        )
+        .collect()?;
+
+        let entity_ids = data_frame.column("entity_id")?.i64()?;
+        let descriptions = data_frame.column("description")?.str()?;
+        let values = data_frame.column("value")?.f64()?;
+        // collected, so that the rows skipped below do not put the dates out of step
+        let dates: Vec<Option<NaiveDate>> =
+            data_frame.column("date")?.date()?.as_date_iter().collect();
+        let categories = data_frame.column("category")?.str()?;
+
+        let mut classifier: CategoryClassifier = CategoryClassifier::default();
+        for (i, date) in dates.into_iter().enumerate() {
+            // there is nothing to learn from a transaction without a category, which is
+            // empty, or null once read back
+            let category: &str = categories.get(i).unwrap_or("");
+            let Some(entity_id) = entity_ids.get(i) else {
+                continue;
+            };
+            if category.is_empty() {
+                continue;
+            }
+            let features = CategoryFeatures {
+                entity_id,
+                // empty descriptions are read as nulls
+                description: descriptions.get(i).unwrap_or("").to_string(),
+                value: values.get(i).ok_or("No value!")?,
+                date: date.ok_or("No date!")?,
+            };
+            classifier.learn(&features, category);
+        }
+
+        Ok(classifier)
+    }
+}
@@ -1,7 +1,9 @@
 use crate::modules::currency_exchange::CurrencyExchange;
-use crate::modules::database::{capitalize_every_word, data_frame_to_csv_string, DataBase};
+use crate::modules::database::{
+    capitalize_every_word, data_frame_to_csv_string, status_filter, DataBase,
+};
 use crate::modules::financial::Currency;
-use chrono::{Local, NaiveDate};
+use chrono::{Local, Months, NaiveDate};
 use polars::prelude::pivot::pivot_stable;
 use polars::prelude::*;
 use std::fmt::Display;
@@ -46,14 +48,147 @@ impl Default for TimeUnit {
     }
 }
 
+/// Date that places the transactions in time in the reports: the date they were booked on,
+/// or the date their fund movements settled on.
+#[derive(Debug, Default, Hash, PartialEq, Eq, EnumIter, Clone, EnumString)]
+pub(crate) enum ReportDate {
+    #[default]
+    Booking,
+    Value,
+}
+
+// Conversion to string
+impl Display for ReportDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            ReportDate::Booking => "Booking date".to_string(),
+            ReportDate::Value => "Value date".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Periods the expenses count in: the one they were paid in, or the months they cover when
+/// they are amortized.
+#[derive(Debug, Default, Hash, PartialEq, Eq, EnumIter, Clone, EnumString)]
+pub(crate) enum Basis {
+    #[default]
+    Cash,
+    Accrual,
+}
+
+// Conversion to string
+impl Display for Basis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Basis::Cash => "Cash".to_string(),
+            Basis::Accrual => "Accrual".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
 impl DataBase {
+    /// Replaces the date of the transactions by the one the report is based on. With the
+    /// value date, fund movements take their own value date, and incomes and expenses the
+    /// latest value date of the fund movements of their party. Transactions without a value
+    /// date keep their date.
+    pub(crate) fn with_report_date(
+        &self,
+        data_frame: DataFrame,
+        report_date: &ReportDate,
+    ) -> Result<DataFrame, PolarsError> {
+        if report_date == &ReportDate::Booking {
+            return Ok(data_frame);
+        }
+
+        let mut data_frame: LazyFrame = data_frame.lazy();
+        if !data_frame.collect_schema()?.contains("value_date") {
+            let party_value_dates: LazyFrame = self
+                .funds_table
+                .data_frame
+                .clone()
+                .lazy()
+                .group_by([col("party_id")])
+                .agg([col("value_date").max()]);
+            data_frame = data_frame.join(
+                party_value_dates,
+                [col("party_id")],
+                [col("party_id")],
+                JoinArgs::new(JoinType::Left),
+            );
+        }
+
+        data_frame
+            .with_column(
+                when(col("value_date").is_not_null())
+                    .then(col("value_date"))
+                    .otherwise(col("date"))
+                    .alias("date"),
+            )
+            .select([all().exclude(["value_date"])])
+            .collect()
+    }
+
+    /// Spreads the expenses over the months they cover when the report is accrual based: an
+    /// expense amortized over n months counts for an n-th of its value on the same day of
+    /// each of the n months starting with its date. Any other column is kept as it is.
+    pub(crate) fn with_basis(
+        data_frame: DataFrame,
+        basis: &Basis,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        if basis == &Basis::Cash {
+            return Ok(data_frame);
+        }
+
+        let max_months: i64 = data_frame
+            .column("amortization_months")?
+            .i64()?
+            .max()
+            .unwrap_or(1);
+
+        let mut spread: DataFrame = data_frame
+            .clone()
+            .lazy()
+            .filter(col("amortization_months").lt_eq(lit(1i64)))
+            .collect()?;
+        for month in 0..max_months {
+            let mut installments: DataFrame = data_frame
+                .clone()
+                .lazy()
+                .filter(
+                    col("amortization_months")
+                        .gt(lit(month))
+                        .and(col("amortization_months").gt(lit(1i64))),
+                )
+                .with_column(
+                    (col("value") / col("amortization_months").cast(DataType::Float64))
+                        .alias("value"),
+                )
+                .collect()?;
+            let dates: Vec<Option<NaiveDate>> = installments
+                .column("date")?
+                .date()?
+                .as_date_iter()
+                .map(|date| {
+                    date.and_then(|date| date.checked_add_months(Months::new(month as u32)))
+                })
+                .collect();
+            installments.with_column(Series::new("date".into(), dates))?;
+            spread.vstack_mut(&installments)?;
+        }
+
+        Ok(spread)
+    }
+
     /// Calculates the sum of all the incomes earned between date_from to date_to, both included,
     /// in the currency currency_to.
-    fn total_income(
+    pub(crate) fn total_income(
         &self,
         date_from: NaiveDate,
         date_to: NaiveDate,
         currency_to: &Currency,
+        include_planned: bool,
     ) -> Result<f64, Box<dyn std::error::Error>> {
         let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
 
@@ -63,6 +198,7 @@ impl DataBase {
             .clone()
             .lazy()
             .filter(col("date").is_between(lit(date_from), lit(date_to), ClosedInterval::Both))
+            .filter(status_filter(include_planned))
             .collect()?;
 
         let mut exchange_rates = Vec::new();
@@ -87,19 +223,93 @@ impl DataBase {
             .ok_or("Sum of empty values?")?)
     }
 
+    /// Returns one row per account and currency it holds: the main currency with the initial
+    /// balance, and every other pocket starting at zero.
+    pub(crate) fn account_pockets(&self) -> Result<DataFrame, PolarsError> {
+        let columns = [
+            col("account_id"),
+            col("name"),
+            col("country"),
+            col("currency"),
+            col("account_type"),
+            col("initial_balance"),
+        ];
+        let main_currencies: DataFrame = self
+            .account_table
+            .data_frame
+            .clone()
+            .lazy()
+            .select(columns)
+            .collect()?;
+        let accounts: DataFrame = self.account_table.data_frame.clone();
+        let mut account_ids: Vec<i64> = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        let mut countries: Vec<String> = Vec::new();
+        let mut currencies: Vec<String> = Vec::new();
+        let mut account_types: Vec<String> = Vec::new();
+        for i in 0..accounts.height() {
+            // empty strings are read as nulls
+            let pockets_string: &str = accounts.column("pockets")?.str()?.get(i).unwrap_or("");
+            for pocket in pockets_string
+                .split(";")
+                .filter(|pocket| !pocket.is_empty())
+            {
+                account_ids.push(accounts.column("account_id")?.i64()?.get(i).unwrap_or(-1));
+                names.push(
+                    accounts
+                        .column("name")?
+                        .str()?
+                        .get(i)
+                        .unwrap_or("")
+                        .to_string(),
+                );
+                countries.push(
+                    accounts
+                        .column("country")?
+                        .str()?
+                        .get(i)
+                        .unwrap_or("")
+                        .to_string(),
+                );
+                currencies.push(pocket.to_string());
+                account_types.push(
+                    accounts
+                        .column("account_type")?
+                        .str()?
+                        .get(i)
+                        .unwrap_or("")
+                        .to_string(),
+                );
+            }
+        }
+        let initial_balances: Vec<f64> = vec![0.0; account_ids.len()];
+        let pockets: DataFrame = df!(
+            "account_id" => account_ids,
+            "name" => names,
+            "country" => countries,
+            "currency" => currencies,
+            "account_type" => account_types,
+            "initial_balance" => initial_balances,
+        )?;
+
+        main_currencies.vstack(&pockets)
+    }
+
     pub(crate) fn current_fund_stand(
         &self,
         currency_to: Option<&Currency>,
+        include_planned: bool,
     ) -> Result<String, Box<dyn std::error::Error>> {
         let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
 
-        let initial_balances: DataFrame = self.account_table.data_frame.clone();
+        let initial_balances: DataFrame = self.account_pockets()?;
 
         let funds_table: DataFrame = self
             .funds_table
             .data_frame
             .clone()
             .lazy()
+            .filter(status_filter(include_planned))
             .group_by(["account_id", "currency"])
             .agg([col("value").sum()])
             .collect()?;
@@ -184,23 +394,24 @@ impl DataBase {
         data_frame_to_csv_string(&mut summary)
     }
 
-    /// Generates a summary table of all expenses between date_from to date_to, expressed in the currency_to
-    pub(crate) fn expenses_summary(
+    /// Sums all expenses between date_from to date_to, both included, per category and
+    /// subcategory. The sums are expressed in the currency_to, exchanged at date_to, in a
+    /// column named after the currency.
+    pub(crate) fn expenses_by_subcategory(
         &self,
+        currency_exchange: &CurrencyExchange,
         date_from: NaiveDate,
         date_to: NaiveDate,
         currency_to: &Currency,
-    ) -> Result<String, Box<dyn std::error::Error>> {
-        let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
-        let total_income: f64 = self.total_income(date_from, date_to, currency_to)?;
-        let num_days: i64 = date_to.signed_duration_since(date_from).num_days();
-
+        include_planned: bool,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
         let expenses_table: DataFrame = self
             .expenses_table
             .data_frame
             .clone()
             .lazy()
             .filter(col("date").is_between(lit(date_from), lit(date_to), ClosedInterval::Both))
+            .filter(status_filter(include_planned))
             .collect()?;
 
         let mut exchange_rates = Vec::new();
@@ -215,12 +426,37 @@ impl DataBase {
 
         let exchange_rates: Series = Series::new("exchange_rate".into(), exchange_rates);
 
-        let mut summary: DataFrame = expenses_table
+        Ok(expenses_table
             .lazy()
             .with_column(exchange_rates.lit())
             .with_column((col("exchange_rate") * col("value")).alias(currency_to.to_string()))
             .group_by([col("category"), col("subcategory")])
             .agg([col(currency_to.to_string()).sum()])
+            .collect()?)
+    }
+
+    /// Generates a summary table of all expenses between date_from to date_to, expressed in the currency_to
+    pub(crate) fn expenses_summary(
+        &self,
+        date_from: NaiveDate,
+        date_to: NaiveDate,
+        currency_to: &Currency,
+        include_planned: bool,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
+        let total_income: f64 =
+            self.total_income(date_from, date_to, currency_to, include_planned)?;
+        let num_days: i64 = date_to.signed_duration_since(date_from).num_days();
+
+        let mut summary: DataFrame = self
+            .expenses_by_subcategory(
+                &currency_exchange,
+                date_from,
+                date_to,
+                currency_to,
+                include_planned,
+            )?
+            .lazy()
             .with_columns([
                 col(currency_to.to_string()).round(2),
                 (col(currency_to.to_string()) / lit(num_days))
@@ -268,11 +504,22 @@ impl DataBase {
         &self,
         currency_to: &Currency,
         time_unit: &TimeUnit,
+        include_planned: bool,
+        report_date: &ReportDate,
+        basis: &Basis,
     ) -> Result<String, Box<dyn std::error::Error>> {
         let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
         let duration: &str = time_unit.duration();
 
-        let expenses_table: DataFrame = self.expenses_table.data_frame.clone();
+        let expenses_table: DataFrame = self.with_report_date(
+            self.expenses_table
+                .data_frame
+                .clone()
+                .lazy()
+                .filter(status_filter(include_planned))
+                .collect()?,
+            report_date,
+        )?;
 
         let mut exchange_rates = Vec::new();
         let currency_iterator = expenses_table.column("currency")?.str()?.into_iter();
@@ -289,10 +536,19 @@ impl DataBase {
 
         let exchange_rates: Series = Series::new("exchange_rate".into(), exchange_rates);
 
+        // the amounts are converted before they are spread, as in the plots
+        let expenses_table: DataFrame = Self::with_basis(
+            expenses_table
+                .lazy()
+                .with_column(exchange_rates.lit())
+                .with_column((col("exchange_rate") * col("value")).alias("value"))
+                .collect()?,
+            basis,
+        )?;
+
         let summary: DataFrame = expenses_table
             .lazy()
-            .with_column(exchange_rates.lit())
-            .with_column((col("exchange_rate") * col("value")).alias(currency_to.to_string()))
+            .with_column(col("value").alias(currency_to.to_string()))
             .sort(["date"], Default::default())
             .group_by_dynamic(
                 col("date"),
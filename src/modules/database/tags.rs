@@ -0,0 +1,171 @@
+use crate::modules::currency_exchange::CurrencyExchange;
+use crate::modules::database::{
+    capitalize_every_word, data_frame_to_csv_string, status_filter, DataBase,
+};
+use crate::modules::financial::Currency;
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+impl DataBase {
+    /// Returns all the tags in use, sorted
+    pub(crate) fn tags(&self) -> Result<Vec<String>, PolarsError> {
+        self.tag_table.tags()
+    }
+
+    /// Returns the tags of every tagged income and expense, by transaction type and ID.
+    pub(crate) fn tags_by_transaction(
+        &self,
+    ) -> Result<HashMap<(String, i64), Vec<String>>, PolarsError> {
+        let data_frame: &DataFrame = &self.tag_table.data_frame;
+        let mut tags: HashMap<(String, i64), Vec<String>> = HashMap::new();
+        for ((transaction_type, transaction_id), tag) in data_frame
+            .column("transaction_type")?
+            .str()?
+            .into_no_null_iter()
+            .zip(
+                data_frame
+                    .column("transaction_id")?
+                    .i64()?
+                    .into_no_null_iter(),
+            )
+            .zip(data_frame.column("tag")?.str()?.into_no_null_iter())
+        {
+            tags.entry((transaction_type.to_string(), transaction_id))
+                .or_default()
+                .push(tag.to_string());
+        }
+
+        Ok(tags)
+    }
+
+    /// Generates a summary table of the incomes and expenses of every tag between date_from
+    /// and date_to, both included, expressed in the currency_to, exchanged at date_to. Since a
+    /// transaction can have many tags, the rows do not add up to the totals, so there is no
+    /// total row.
+    pub(crate) fn tag_summary(
+        &self,
+        date_from: NaiveDate,
+        date_to: NaiveDate,
+        currency_to: &Currency,
+        include_planned: bool,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let currency_exchange: CurrencyExchange = CurrencyExchange::init()?;
+        let total_income: f64 =
+            self.total_income(date_from, date_to, currency_to, include_planned)?;
+        let total_expenses: f64 = self
+            .expenses_by_subcategory(
+                &currency_exchange,
+                date_from,
+                date_to,
+                currency_to,
+                include_planned,
+            )?
+            .column(currency_to.to_string().as_str())?
+            .f64()?
+            .sum()
+            .unwrap_or(0.0);
+        let num_days: i64 = date_to.signed_duration_since(date_from).num_days();
+
+        let mut transactions: Vec<DataFrame> = Vec::new();
+        for (table, transaction_type, id_column) in [
+            (&self.incomes_table.data_frame, "Income", "income_id"),
+            (&self.expenses_table.data_frame, "Expense", "expense_id"),
+        ] {
+            transactions.push(
+                table
+                    .clone()
+                    .lazy()
+                    .filter(col("date").is_between(
+                        lit(date_from),
+                        lit(date_to),
+                        ClosedInterval::Both,
+                    ))
+                    .filter(status_filter(include_planned))
+                    .with_column(lit(transaction_type).alias("transaction_type"))
+                    .select([
+                        col("transaction_type"),
+                        col(id_column).alias("transaction_id"),
+                        col("value"),
+                        col("currency"),
+                    ])
+                    .collect()?,
+            );
+        }
+        let tagged_transactions: DataFrame = transactions[0]
+            .vstack(&transactions[1])?
+            .lazy()
+            .join(
+                self.tag_table.data_frame.clone().lazy(),
+                [col("transaction_type"), col("transaction_id")],
+                [col("transaction_type"), col("transaction_id")],
+                JoinArgs::new(JoinType::Inner),
+            )
+            .collect()?;
+
+        let mut exchange_rates = Vec::new();
+        let currency_iterator = tagged_transactions.column("currency")?.str()?.into_iter();
+        for currency in currency_iterator {
+            let currency_from =
+                Currency::from_str(currency.ok_or("Null value in currency column!")?)?;
+            let exchange_rate: f64 =
+                currency_exchange.exchange_currency(&currency_from, currency_to, date_to)?;
+            exchange_rates.push(exchange_rate);
+        }
+
+        let exchange_rates: Series = Series::new("exchange_rate".into(), exchange_rates);
+
+        let mut summary: DataFrame = tagged_transactions
+            .lazy()
+            .with_column(exchange_rates.lit())
+            .with_column((col("exchange_rate") * col("value")).alias("value"))
+            .group_by([col("tag")])
+            .agg([
+                col("transaction_id").count().alias("transactions"),
+                when(col("transaction_type").eq(lit("Income")))
+                    .then(col("value"))
+                    .otherwise(lit(0.0))
+                    .sum()
+                    .alias("income"),
+                when(col("transaction_type").eq(lit("Expense")))
+                    .then(col("value"))
+                    .otherwise(lit(0.0))
+                    .sum()
+                    .alias("expenses"),
+            ])
+            .with_columns([
+                (col("income") - col("expenses")).round(2).alias("balance"),
+                (col("expenses") / lit(num_days))
+                    .round(2)
+                    .alias("expenses_/_day"),
+                (col("expenses") * lit(100) / lit(total_expenses))
+                    .round(2)
+                    .alias("%_total_expenses"),
+                (col("expenses") * lit(100) / lit(total_income))
+                    .round(2)
+                    .alias("%_total_income"),
+                col("income").round(2),
+                col("expenses").round(2),
+            ])
+            .select([
+                col("tag"),
+                col("transactions"),
+                col("income"),
+                col("expenses"),
+                col("balance"),
+                col("expenses_/_day"),
+                col("%_total_expenses"),
+                col("%_total_income"),
+            ])
+            .sort(["tag"], Default::default())
+            .select([all().name().map(|name| {
+                Ok(PlSmallStr::from_string(capitalize_every_word(
+                    name.replace("_", " "),
+                )))
+            })])
+            .collect()?;
+
+        data_frame_to_csv_string(&mut summary)
+    }
+}
@@ -0,0 +1,118 @@
+use crate::modules::database::DataBase;
+use crate::modules::financial::{Party, Template};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::vec::IntoIter;
+
+/// A party generated from a recurring template, waiting to be confirmed, skipped or edited
+/// before it gets into the database.
+pub struct DueParty {
+    pub template_id: i64,
+    pub template_name: String,
+    pub date: NaiveDate,
+    pub party: Party,
+}
+
+impl DataBase {
+    /// Saves the template and its transactions. Returns the ID of the template.
+    pub fn insert_template(
+        &mut self,
+        template: &Template,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        let template_id: i64 = self.template_table.insert_template(template)?;
+        for transaction in template.transactions.iter() {
+            self.template_transaction_table
+                .insert_transaction(transaction, template_id)?;
+        }
+
+        Ok(template_id)
+    }
+
+    pub(crate) fn iter_template_ids(&self) -> Result<IntoIter<i64>, PolarsError> {
+        self.template_table.iter()
+    }
+
+    /// Returns the template, with its transactions dated at the start date.
+    pub(crate) fn template(
+        &self,
+        template_id: i64,
+    ) -> Result<Template, Box<dyn std::error::Error>> {
+        let mut template: Template = self.template_table.template(template_id)?;
+        template.transactions = self
+            .template_transaction_table
+            .transactions(template_id, template.start_date())?;
+
+        Ok(template)
+    }
+
+    /// Deletes the template and its transactions. Parties that were already generated from
+    /// it are not affected.
+    pub(crate) fn delete_template(&mut self, template_id: i64) -> Result<(), PolarsError> {
+        self.template_table.delete_template(template_id)?;
+        self.template_transaction_table
+            .delete_template(template_id)?;
+
+        Ok(())
+    }
+
+    /// Generates one party for every due date of every recurring template that has not been
+    /// handled yet, up to the given date. The parties are sorted by date.
+    pub(crate) fn due_parties(
+        &self,
+        until: NaiveDate,
+    ) -> Result<Vec<DueParty>, Box<dyn std::error::Error>> {
+        let mut due_parties: Vec<DueParty> = Vec::new();
+        for template_id in self.iter_template_ids()? {
+            let template: Template = self.template(template_id)?;
+            let last_date: NaiveDate = self.template_table.last_date(template_id)?;
+            let handled_dates: Vec<NaiveDate> = self.template_table.handled_dates(template_id)?;
+
+            for date in template
+                .due_dates(last_date, until)
+                .into_iter()
+                .filter(|date| !handled_dates.contains(date))
+            {
+                due_parties.push(DueParty {
+                    template_id,
+                    template_name: template.name(),
+                    date,
+                    party: template.party(date),
+                });
+            }
+        }
+        due_parties.sort_by_key(|due_party| due_party.date);
+
+        Ok(due_parties)
+    }
+
+    /// Marks the occurrence of the template at the given date as handled, so that it is not
+    /// generated again. Earlier occurrences that are still pending stay due.
+    pub(crate) fn handle_due_party(
+        &mut self,
+        template_id: i64,
+        date: NaiveDate,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let template: Template = self.template(template_id)?;
+        let mut last_date: NaiveDate = self.template_table.last_date(template_id)?;
+        let mut handled_dates: Vec<NaiveDate> = self.template_table.handled_dates(template_id)?;
+        if (date <= last_date) | handled_dates.contains(&date) {
+            return Ok(());
+        }
+        handled_dates.push(date);
+
+        // the last handled date only moves forward over occurrences that are all handled
+        let latest_date: NaiveDate = *handled_dates.iter().max().unwrap_or(&date);
+        for due_date in template.due_dates(last_date, latest_date) {
+            if !handled_dates.contains(&due_date) {
+                break;
+            }
+            last_date = due_date;
+        }
+        handled_dates.retain(|handled_date| *handled_date > last_date);
+        handled_dates.sort();
+
+        Ok(self
+            .template_table
+            .set_handled(template_id, last_date, &handled_dates)?)
+    }
+}
@@ -1,15 +1,22 @@
-use crate::modules::database::{capitalize_every_word, data_frame_to_csv_string, DataBase};
+use crate::modules::database::{
+    capitalize_every_word, data_frame_to_csv_string, status_filter, DataBase,
+};
 use polars::prelude::*;
 
 impl DataBase {
-    /// Returns a csv in String format with the last n transactions.
-    pub(crate) fn last_transactions(&self, n: usize) -> Result<String, Box<dyn std::error::Error>> {
+    /// Returns a csv in String format with the last n transactions, with their tags. If a
+    /// tag is given, only the transactions with that tag are returned.
+    pub(crate) fn last_transactions(
+        &self,
+        n: usize,
+        tag: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
         let incomes_table: DataFrame = self
             .incomes_table
             .data_frame
             .clone()
             .lazy()
-            .select([all().exclude(["income_id"])])
+            .rename(["income_id"], ["transaction_id"], true)
             .with_column(lit("Income").alias("type"))
             .collect()?;
         let expenses_table: DataFrame = self
@@ -17,7 +24,8 @@ impl DataBase {
             .data_frame
             .clone()
             .lazy()
-            .select([all().exclude(["expense_id"])])
+            .select([all().exclude(["amortization_months", "refund_of", "reimbursable"])])
+            .rename(["expense_id"], ["transaction_id"], true)
             .with_column(lit("Expense").alias("type"))
             .collect()?;
         let entities_table: DataFrame = self
@@ -28,9 +36,38 @@ impl DataBase {
             .select([col("entity_id"), col("name")])
             .rename(["name"], ["entity_name"], true)
             .collect()?;
-        let transactions_table: DataFrame = incomes_table
-            .vstack(&expenses_table)?
-            .inner_join(&entities_table, ["entity_id"], ["entity_id"])?
+        let mut transactions_table: DataFrame = incomes_table.vstack(&expenses_table)?.inner_join(
+            &entities_table,
+            ["entity_id"],
+            ["entity_id"],
+        )?;
+
+        // tags are joined with semicolons, since commas separate the columns
+        let tags_by_transaction = self.tags_by_transaction()?;
+        let mut tags: Vec<String> = Vec::new();
+        let mut mask: Vec<bool> = Vec::new();
+        for (transaction_type, transaction_id) in transactions_table
+            .column("type")?
+            .str()?
+            .into_no_null_iter()
+            .zip(
+                transactions_table
+                    .column("transaction_id")?
+                    .i64()?
+                    .into_no_null_iter(),
+            )
+        {
+            let transaction_tags: Vec<String> = tags_by_transaction
+                .get(&(transaction_type.to_string(), transaction_id))
+                .cloned()
+                .unwrap_or_default();
+            mask.push(tag.is_empty() | transaction_tags.iter().any(|t| t == tag));
+            tags.push(transaction_tags.join("; "));
+        }
+        transactions_table.with_column(Series::new("tags".into(), tags))?;
+
+        let transactions_table: DataFrame = transactions_table
+            .filter(&BooleanChunked::from_slice("mask".into(), &mask))?
             .select([
                 "type",
                 "date",
@@ -40,6 +77,9 @@ impl DataBase {
                 "category",
                 "subcategory",
                 "description",
+                "tags",
+                "status",
+                "transaction_id",
                 "party_id",
             ])?
             .lazy()
@@ -57,18 +97,63 @@ impl DataBase {
         data_frame_to_csv_string(&mut transactions_table.head(Some(n)))
     }
 
-    /// Returns a csv in String format with the last n fund movements.
+    /// Returns a csv in String format with the last n fund movements, with the running
+    /// balance of their account and their state: reconciled, or else their status. Void
+    /// movements do not change the balance.
     pub(crate) fn last_fund_movements(
         &self,
         n: usize,
         account_id: i64,
     ) -> Result<String, Box<dyn std::error::Error>> {
+        let accounts_table: DataFrame = self
+            .account_table
+            .data_frame
+            .clone()
+            .lazy()
+            .select([
+                col("account_id"),
+                col("name"),
+                col("currency"),
+                col("initial_balance"),
+            ])
+            .rename(
+                ["name", "currency"],
+                ["account_name", "account_currency"],
+                true,
+            )
+            .collect()?;
+
+        // the running balance needs all the movements of the account, so it is computed
+        // before filtering. Each pocket has its own, and only the main one starts from the
+        // initial balance
         let mut funds_table: DataFrame = self
             .funds_table
             .data_frame
             .clone()
             .lazy()
-            .select([all().exclude(["fund_movement_id"])])
+            .join(
+                accounts_table.lazy(),
+                [col("account_id")],
+                [col("account_id")],
+                JoinArgs::new(JoinType::Inner),
+            )
+            .sort(["date", "fund_movement_id"], Default::default())
+            .with_columns([
+                (when(col("currency").eq(col("account_currency")))
+                    .then(col("initial_balance"))
+                    .otherwise(lit(0.0))
+                    + when(status_filter(true))
+                        .then(col("value"))
+                        .otherwise(lit(0.0))
+                        .cum_sum(false)
+                        .over([col("account_id"), col("currency")]))
+                .round(2)
+                .alias("balance"),
+                when(col("reconciliation_id").gt_eq(lit(0i64)))
+                    .then(lit("Reconciled"))
+                    .otherwise(col("status"))
+                    .alias("state"),
+            ])
             .collect()?;
 
         if account_id >= 0 {
@@ -78,23 +163,16 @@ impl DataBase {
                 .collect()?;
         }
 
-        let accounts_table: DataFrame = self
-            .account_table
-            .data_frame
-            .clone()
-            .lazy()
-            .select([col("account_id"), col("name")])
-            .rename(["name"], ["account_name"], true)
-            .collect()?;
-
         let mut last_fund_movements = funds_table
-            .inner_join(&accounts_table, ["account_id"], ["account_id"])?
             .select([
                 "fund_movement_type",
                 "date",
+                "value_date",
                 "value",
                 "currency",
                 "account_name",
+                "balance",
+                "state",
                 "party_id",
             ])?
             .lazy()
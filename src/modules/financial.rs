@@ -1,4 +1,5 @@
 use chrono::prelude::*;
+use chrono::{Days, Months};
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::slice::Iter;
@@ -11,14 +12,20 @@ use strum_macros::{EnumIter, EnumString};
 /// touch to the code.
 pub struct Party {
     pub transactions: Vec<Transaction>,
+    pub status: TransactionStatus, // of all the transactions when the party is inserted
     pub creation_date: NaiveDate,
+    pub project_id: i64, // -1 if the party is not part of a project
+    pub ious: Vec<Iou>,  // debts with people that the party opens or settles
 }
 
 impl Party {
     pub fn new(transactions: Vec<Transaction>) -> Party {
         Party {
             transactions,
+            status: TransactionStatus::default(),
             creation_date: Local::now().date_naive(),
+            project_id: -1,
+            ious: Vec::new(),
         }
     }
 
@@ -29,6 +36,8 @@ impl Party {
     /// withdrawal from a bank account. The relationship does not need to be 1:1, for
     /// instance, a 350 SEK bill for clothing and a 230 SEK bill for presents can be balanced
     /// with a 500 SEK withdrawal from a bank account and an 80 SEK withdrawal from pocket money.
+    /// Debts with people balance like expenses: a 100€ dinner paid from the bank where a
+    /// friend owes us half is a 50€ expense, a 50€ debt of the friend and a 100€ withdrawal.
     pub(crate) fn is_valid(&self) -> bool {
         let mut aggregates: HashMap<&Currency, f64> = HashMap::new();
 
@@ -41,6 +50,14 @@ impl Party {
                 .and_modify(|aggregate: &mut f64| *aggregate += value)
                 .or_insert(value);
         }
+        for iou in &self.ious {
+            let value: f64 = -iou.value();
+
+            aggregates
+                .entry(iou.currency())
+                .and_modify(|aggregate: &mut f64| *aggregate += value)
+                .or_insert(value);
+        }
 
         for (_, val) in aggregates.iter() {
             if (*val).abs() >= 0.01 {
@@ -68,6 +85,57 @@ impl Party {
     pub(crate) fn remove(&mut self, i: usize) -> Transaction {
         self.transactions.remove(i)
     }
+
+    /// Adds a new debt with a person to the party.
+    pub(crate) fn add_iou(&mut self, iou: Iou) {
+        self.ious.push(iou);
+    }
+}
+
+/// Debt with a person, recorded along with a party. A positive value means that the person
+/// owes us, like their share of a dinner we paid, and a negative one that we owe them. A
+/// settlement is a debt of opposite sign, balanced by the money handed over.
+#[derive(Clone)]
+pub struct Iou {
+    entity_id: i64,
+    value: f64,
+    currency: Currency,
+    date: NaiveDate,
+    description: String,
+}
+
+impl Iou {
+    pub(crate) fn entity_id(&self) -> i64 {
+        self.entity_id
+    }
+    pub(crate) fn value(&self) -> f64 {
+        self.value
+    }
+    pub(crate) fn currency(&self) -> &Currency {
+        &self.currency
+    }
+    pub(crate) fn date(&self) -> NaiveDate {
+        self.date
+    }
+    pub(crate) fn description(&self) -> String {
+        self.description.to_string()
+    }
+
+    pub fn new(
+        entity_id: i64,
+        value: f64,
+        currency: Currency,
+        date: NaiveDate,
+        description: String,
+    ) -> Self {
+        Self {
+            entity_id,
+            value,
+            currency,
+            date,
+            description,
+        }
+    }
 }
 
 impl Default for Party {
@@ -76,10 +144,374 @@ impl Default for Party {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, EnumIter)]
+/// A template is a party that is saved to be entered again, like the rent or the salary.
+/// It can be loaded by hand, or it can have a recurrence rule that determines on which dates
+/// a new party is due.
+pub struct Template {
+    name: String,
+    recurrence: Recurrence,
+    recurrence_step: u32, // day of the month (monthly), number of weeks (weekly)
+    start_date: NaiveDate,
+    pub transactions: Vec<Transaction>,
+}
+
+impl Template {
+    pub(crate) fn name(&self) -> String {
+        self.name.to_string()
+    }
+    pub(crate) fn recurrence(&self) -> &Recurrence {
+        &self.recurrence
+    }
+    pub(crate) fn recurrence_step(&self) -> u32 {
+        self.recurrence_step
+    }
+    pub(crate) fn start_date(&self) -> NaiveDate {
+        self.start_date
+    }
+
+    pub fn new(
+        name: String,
+        recurrence: Recurrence,
+        recurrence_step: u32,
+        start_date: NaiveDate,
+        transactions: Vec<Transaction>,
+    ) -> Self {
+        Self {
+            name,
+            recurrence,
+            recurrence_step,
+            start_date,
+            transactions,
+        }
+    }
+
+    /// Returns a new party with the transactions of the template, all of them dated at the
+    /// given date.
+    pub(crate) fn party(&self, date: NaiveDate) -> Party {
+        Party::new(
+            self.transactions
+                .iter()
+                .map(|transaction| transaction.with_date(date))
+                .collect(),
+        )
+    }
+
+    /// Returns the dates on which the template is due, starting after the date `after`
+    /// (excluded) and until the date `until` (included). Templates without recurrence are
+    /// never due.
+    pub(crate) fn due_dates(&self, after: NaiveDate, until: NaiveDate) -> Vec<NaiveDate> {
+        let mut due_dates: Vec<NaiveDate> = Vec::new();
+
+        let mut k: u32 = 0;
+        loop {
+            let date: NaiveDate = match self.recurrence {
+                Recurrence::Manual => break,
+                Recurrence::Monthly => {
+                    let first_of_month: NaiveDate = match self
+                        .start_date
+                        .with_day(1)
+                        .and_then(|date| date.checked_add_months(Months::new(k)))
+                    {
+                        Some(date) => date,
+                        None => break,
+                    };
+                    let day: u32 = self
+                        .recurrence_step
+                        .clamp(1, days_in_month(&first_of_month));
+                    first_of_month.with_day(day).unwrap() // safe due to the clamp
+                }
+                Recurrence::Weekly => {
+                    let weeks: u64 = (k as u64) * (self.recurrence_step.max(1) as u64);
+                    match self.start_date.checked_add_days(Days::new(7 * weeks)) {
+                        Some(date) => date,
+                        None => break,
+                    }
+                }
+                Recurrence::Yearly => match self.start_date.checked_add_months(Months::new(12 * k))
+                {
+                    Some(date) => date,
+                    None => break,
+                },
+            };
+
+            if date > until {
+                break;
+            }
+            if (date > after) & (date >= self.start_date) {
+                due_dates.push(date);
+            }
+
+            k += 1;
+        }
+
+        due_dates
+    }
+}
+
+/// Maximum amount of money planned to be spent on an expense category, or on a single
+/// subcategory of it, during a month or a year.
+pub struct Budget {
+    category: String,
+    subcategory: String, // empty if the budget covers the whole category
+    period: BudgetPeriod,
+    value: f64,
+    currency: Currency,
+}
+
+impl Budget {
+    pub(crate) fn category(&self) -> String {
+        self.category.to_string()
+    }
+    pub(crate) fn subcategory(&self) -> String {
+        self.subcategory.to_string()
+    }
+    pub(crate) fn period(&self) -> &BudgetPeriod {
+        &self.period
+    }
+    pub(crate) fn value(&self) -> f64 {
+        self.value
+    }
+    pub(crate) fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    pub fn new(
+        category: String,
+        subcategory: String,
+        period: BudgetPeriod,
+        value: f64,
+        currency: Currency,
+    ) -> Self {
+        Self {
+            category,
+            subcategory,
+            period,
+            value,
+            currency,
+        }
+    }
+}
+
+impl Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = if self.subcategory.is_empty() {
+            format!(
+                "{} ({}, {} {})",
+                self.category, self.period, self.currency, self.value
+            )
+        } else {
+            format!(
+                "{}, {} ({}, {} {})",
+                self.category, self.subcategory, self.period, self.currency, self.value
+            )
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// A trip, event or project that parties can be attached to, to know what it cost all
+/// together whatever the currencies it was paid in.
+pub struct Project {
+    name: String,
+    description: String,
+    budget: f64, // 0 if the project has no budget
+    currency: Currency,
+}
+
+impl Project {
+    pub(crate) fn name(&self) -> String {
+        self.name.to_string()
+    }
+    pub(crate) fn description(&self) -> String {
+        self.description.to_string()
+    }
+    pub(crate) fn budget(&self) -> f64 {
+        self.budget
+    }
+    pub(crate) fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    pub fn new(name: String, description: String, budget: f64, currency: Currency) -> Self {
+        Self {
+            name,
+            description,
+            budget,
+            currency,
+        }
+    }
+}
+
+impl Display for Project {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = if self.budget > 0.0 {
+            format!("{} ({} {})", self.name, self.currency, self.budget)
+        } else {
+            self.name.to_string()
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Node of the hierarchy of income or expense categories. The top level is the category of
+/// the transactions, and the levels below make up their subcategory, joined by colons, like
+/// "Groceries:Organic" under "Food".
+pub struct Category {
+    transaction_type: TransactionType, // Income or Expense
+    name: String,
+    parent_id: i64, // -1 for the top level
+}
+
+impl Category {
+    pub(crate) fn transaction_type(&self) -> &TransactionType {
+        &self.transaction_type
+    }
+    pub(crate) fn name(&self) -> String {
+        self.name.to_string()
+    }
+    pub(crate) fn parent_id(&self) -> i64 {
+        self.parent_id
+    }
+
+    pub fn new(transaction_type: TransactionType, name: String, parent_id: i64) -> Self {
+        Self {
+            transaction_type,
+            name,
+            parent_id,
+        }
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Returns the levels of the hierarchy of categories that the category and subcategory of a
+/// record stand for, from the top.
+pub(crate) fn category_path(category: &str, subcategory: &str) -> Vec<String> {
+    let mut path: Vec<String> = vec![category.trim().to_string()];
+    path.extend(
+        subcategory
+            .split(":")
+            .map(|level| level.trim().to_string())
+            .filter(|level| !level.is_empty()),
+    );
+    path
+}
+
+/// Returns the category and subcategory to record for a path of the hierarchy of categories.
+pub(crate) fn split_category_path(path: &[String]) -> (String, String) {
+    match path.split_first() {
+        Some((category, subcategories)) => (category.to_string(), subcategories.join(":")),
+        None => (String::new(), String::new()),
+    }
+}
+
+/// Part of the amount of an income party that is put aside in the envelope of an expense
+/// category for the month of the date.
+pub struct Assignment {
+    party_id: i64,
+    category: String,
+    value: f64,
+    currency: Currency,
+    date: NaiveDate,
+}
+
+impl Assignment {
+    pub(crate) fn party_id(&self) -> i64 {
+        self.party_id
+    }
+    pub(crate) fn category(&self) -> String {
+        self.category.to_string()
+    }
+    pub(crate) fn value(&self) -> f64 {
+        self.value
+    }
+    pub(crate) fn currency(&self) -> &Currency {
+        &self.currency
+    }
+    pub(crate) fn date(&self) -> &NaiveDate {
+        &self.date
+    }
+
+    pub fn new(
+        party_id: i64,
+        category: String,
+        value: f64,
+        currency: Currency,
+        date: NaiveDate,
+    ) -> Self {
+        Self {
+            party_id,
+            category,
+            value,
+            currency,
+            date,
+        }
+    }
+}
+
+impl Display for Assignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} to {} ({})",
+            self.currency, self.value, self.category, self.date
+        )
+    }
+}
+
+/// Statement that an account held a given amount at the end of a date, checked against the
+/// balance derived from the fund movements.
+pub struct BalanceAssertion {
+    account_id: i64,
+    date: NaiveDate,
+    value: f64,
+    currency: Currency,
+}
+
+impl BalanceAssertion {
+    pub(crate) fn account_id(&self) -> i64 {
+        self.account_id
+    }
+    pub(crate) fn date(&self) -> &NaiveDate {
+        &self.date
+    }
+    pub(crate) fn value(&self) -> f64 {
+        self.value
+    }
+    pub(crate) fn currency(&self) -> &Currency {
+        &self.currency
+    }
+
+    pub fn new(account_id: i64, date: NaiveDate, value: f64, currency: Currency) -> Self {
+        Self {
+            account_id,
+            date,
+            value,
+            currency,
+        }
+    }
+}
+
+/// Returns the number of days of the month of the given date.
+fn days_in_month(date: &NaiveDate) -> u32 {
+    let first_of_month: NaiveDate = date.with_day(1).unwrap(); // day 1 always exists
+    match first_of_month.checked_add_months(Months::new(1)) {
+        Some(first_of_next_month) => first_of_next_month
+            .signed_duration_since(first_of_month)
+            .num_days() as u32,
+        None => 31,
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, EnumIter, EnumString)]
 pub enum TransactionType {
     Income,
     Expense,
+    Refund, // entered as the positive amount given back, saved as a negative expense
     Credit,
     Debit,
 }
@@ -89,6 +521,7 @@ impl TransactionType {
         match self {
             TransactionType::Income => TransactionType::Income,
             TransactionType::Expense => TransactionType::Expense,
+            TransactionType::Refund => TransactionType::Refund,
             TransactionType::Credit => TransactionType::Credit,
             TransactionType::Debit => TransactionType::Debit,
         }
@@ -96,7 +529,7 @@ impl TransactionType {
 
     pub(crate) fn is_fund_change(&self) -> bool {
         match self {
-            TransactionType::Income | TransactionType::Expense => false,
+            TransactionType::Income | TransactionType::Expense | TransactionType::Refund => false,
             TransactionType::Credit | TransactionType::Debit => true,
         }
     }
@@ -108,6 +541,7 @@ impl Display for TransactionType {
         let str = match self {
             TransactionType::Income => "Income".to_string(),
             TransactionType::Expense => "Expense".to_string(),
+            TransactionType::Refund => "Refund".to_string(),
             TransactionType::Credit => "Credit".to_string(),
             TransactionType::Debit => "Debit".to_string(),
         };
@@ -133,6 +567,7 @@ pub enum Transaction {
         subcategory: String, // regular salary, 13-month salary
         description: String,
         entity_id: i64,
+        tags: Vec<String>, // cross-cutting labels: vacation-2025, reimbursable, gift
     },
     Expense {
         value: f64,
@@ -142,18 +577,24 @@ pub enum Transaction {
         subcategory: String, // train, bus, hairdresser
         description: String,
         entity_id: i64,
+        amortization_months: u32, // months over which the expense is spread, 1 if it is not
+        tags: Vec<String>,
+        refund_of: i64, // expense_id given back by this negative expense, -1 if none
+        reimbursable: bool, // awaiting payback, like a business trip paid out of pocket
     },
     Credit {
         value: f64,
         currency: Currency,
         date: NaiveDate,
         account_id: i64,
+        value_date: Option<NaiveDate>, // settlement date, if it differs from the booking
     },
     Debit {
         value: f64,
         currency: Currency,
         date: NaiveDate,
         account_id: i64,
+        value_date: Option<NaiveDate>,
     },
 }
 
@@ -198,6 +639,50 @@ impl Transaction {
         }
     }
 
+    /// Value date getter. Only fund movements can settle on a different date than the one
+    /// they are booked on.
+    pub(crate) fn value_date(&self) -> Option<NaiveDate> {
+        match self {
+            Transaction::Income { .. } | Transaction::Expense { .. } => None,
+            Transaction::Credit { value_date, .. } | Transaction::Debit { value_date, .. } => {
+                *value_date
+            }
+        }
+    }
+
+    /// Tags setter. Fund movements are not tagged, so they are left unchanged.
+    pub(crate) fn set_tags(&mut self, new_tags: Vec<String>) {
+        match self {
+            Transaction::Income { tags, .. } | Transaction::Expense { tags, .. } => {
+                *tags = new_tags
+            }
+            Transaction::Credit { .. } | Transaction::Debit { .. } => {}
+        }
+    }
+
+    /// Returns a copy of the transaction, but at a different date. The value date, if any,
+    /// keeps the same delay with respect to the date.
+    pub(crate) fn with_date(&self, date: NaiveDate) -> Transaction {
+        let mut transaction: Transaction = self.clone();
+        match &mut transaction {
+            Transaction::Income { date: d, .. } | Transaction::Expense { date: d, .. } => *d = date,
+            Transaction::Credit {
+                date: d,
+                value_date: v,
+                ..
+            }
+            | Transaction::Debit {
+                date: d,
+                value_date: v,
+                ..
+            } => {
+                *v = v.map(|value_date| date + (value_date - *d));
+                *d = date;
+            }
+        }
+        transaction
+    }
+
     /// Type getter.
     pub(crate) fn transaction_type(&self) -> String {
         match self {
@@ -325,13 +810,16 @@ impl Display for Entity {
     }
 }
 
-/// Account where funds are stored.
+/// Account where funds are stored. Besides its main currency, an account like a Wise or
+/// Revolut wallet can hold pockets in other currencies, every fund movement being in one of
+/// them.
 pub struct Account {
     name: String,
     country: String,
-    currency: Currency,
+    currency: Currency, // main currency, the one of the initial balance
     account_type: AccountType,
     initial_balance: f64,
+    pockets: Vec<Currency>, // other currencies held, empty for single currency accounts
 }
 
 impl Account {
@@ -350,6 +838,14 @@ impl Account {
     pub(crate) fn initial_balance(&self) -> f64 {
         self.initial_balance
     }
+    pub(crate) fn pockets(&self) -> &[Currency] {
+        &self.pockets
+    }
+
+    /// Checks whether the account can hold funds in the currency.
+    pub(crate) fn has_currency(&self, currency: &Currency) -> bool {
+        (&self.currency == currency) | self.pockets.contains(currency)
+    }
 
     pub fn new(
         name: String,
@@ -357,6 +853,7 @@ impl Account {
         currency: Currency,
         account_type: AccountType,
         initial_balance: f64,
+        pockets: Vec<Currency>,
     ) -> Self {
         Self {
             name,
@@ -364,18 +861,16 @@ impl Account {
             currency,
             account_type,
             initial_balance,
+            pockets,
         }
     }
 }
 
 impl Display for Account {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let str = format!(
-            "{} ({}, {})",
-            self.name,
-            self.country,
-            self.currency.to_string()
-        );
+        let mut currencies: Vec<String> = vec![self.currency.to_string()];
+        currencies.extend(self.pockets.iter().map(|pocket| pocket.to_string()));
+        let str = format!("{} ({}, {})", self.name, self.country, currencies.join("/"));
         write!(f, "{}", str)
     }
 }
@@ -451,3 +946,88 @@ impl Default for AccountType {
         AccountType::Deposit
     }
 }
+
+#[derive(Debug, Default, Hash, PartialEq, Eq, EnumIter, Clone, EnumString)]
+pub enum Recurrence {
+    Manual, // never due, only loaded by hand
+    #[default]
+    Monthly, // every month on a given day
+    Weekly, // every given number of weeks
+    Yearly, // every year on the day of the start date
+}
+
+/// Conversion to string
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Recurrence::Manual => "Manual".to_string(),
+            Recurrence::Monthly => "Monthly".to_string(),
+            Recurrence::Weekly => "Weekly".to_string(),
+            Recurrence::Yearly => "Yearly".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Debug, Default, Hash, PartialEq, Eq, EnumIter, Clone, EnumString)]
+pub enum BudgetPeriod {
+    #[default]
+    Monthly,
+    Yearly,
+}
+
+impl BudgetPeriod {
+    /// Returns the first and the last day of the period that contains the given date.
+    pub(crate) fn bounds(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let first_day: NaiveDate = match self {
+            BudgetPeriod::Monthly => date.with_day(1).unwrap(), // day 1 always exists
+            BudgetPeriod::Yearly => date.with_ordinal(1).unwrap(), // day 1 always exists
+        };
+        let months: u32 = match self {
+            BudgetPeriod::Monthly => 1,
+            BudgetPeriod::Yearly => 12,
+        };
+        let last_day: NaiveDate = first_day
+            .checked_add_months(Months::new(months))
+            .and_then(|date| date.pred_opt())
+            .unwrap_or(NaiveDate::MAX);
+
+        (first_day, last_day)
+    }
+}
+
+/// Conversion to string
+impl Display for BudgetPeriod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            BudgetPeriod::Monthly => "Monthly".to_string(),
+            BudgetPeriod::Yearly => "Yearly".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Life cycle of a transaction. Planned transactions are known future payments, pending ones
+/// have not been posted by the bank yet, and void ones are kept for the record but do not
+/// count anywhere.
+#[derive(Debug, Default, Hash, PartialEq, Eq, EnumIter, Clone, EnumString)]
+pub enum TransactionStatus {
+    Planned,
+    #[default]
+    Pending,
+    Cleared,
+    Void,
+}
+
+/// Conversion to string
+impl Display for TransactionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            TransactionStatus::Planned => "Planned".to_string(),
+            TransactionStatus::Pending => "Pending".to_string(),
+            TransactionStatus::Cleared => "Cleared".to_string(),
+            TransactionStatus::Void => "Void".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
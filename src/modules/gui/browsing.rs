@@ -1,7 +1,11 @@
+use crate::modules::financial::{TransactionStatus, TransactionType};
 use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
 use eframe::egui::ComboBox;
 use egui::{Align, Color32, Layout};
 use egui_extras::*;
+use polars::prelude::PolarsError;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
 
 impl AppState {
     fn is_valid_last_transactions_n(&self) -> bool {
@@ -20,6 +24,19 @@ impl AppState {
         }
     }
 
+    pub fn load_outstanding_reimbursements(&mut self) {
+        match self.database.outstanding_reimbursements_csv() {
+            Ok(s) => {
+                self.outstanding_reimbursements_csv = s;
+                self.outstanding_reimbursements_csv_correct = true;
+            }
+            Err(e) => {
+                self.outstanding_reimbursements_csv_correct = false;
+                self.throw_error(e);
+            }
+        }
+    }
+
     pub fn handle_show_browse_last_transactions_window(&mut self, ctx: &egui::Context) -> () {
         ctx.show_viewport_immediate(
             egui::ViewportId::from_hash_of("browse_last_transactions_window"),
@@ -64,13 +81,45 @@ impl AppState {
                                             );
                                         }
                                         ui.end_row();
+
+                                        ui.label("Tag:")
+                                            .on_hover_text("Tag to filter for.");
+                                        ComboBox::from_id_salt("Tag")
+                                            .selected_text(if self.browse_tag.is_empty() {
+                                                String::from("All tags")
+                                            } else {
+                                                self.browse_tag.clone()
+                                            })
+                                            .show_ui(ui, |ui| {
+                                                ui.selectable_value(
+                                                    &mut self.browse_tag,
+                                                    String::new(),
+                                                    String::from("All tags")
+                                                );
+                                                match self.database.tags() {
+                                                    Ok(tags) => {
+                                                        for tag in tags {
+                                                            ui.selectable_value(
+                                                                &mut self.browse_tag,
+                                                                tag.clone(),
+                                                                tag,
+                                                            );
+                                                        }
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_polars_error(e);
+                                                    }
+                                                }
+                                            });
+                                        ui.label("");
+                                        ui.end_row();
                                     });
                                 ui.separator();
                                 ui.vertical_centered_justified(|ui| {
                                     if self.is_valid_last_transactions_n() {
                                         if ui.button("Generate!").clicked() {
                                             self.last_transactions_n = self.last_transactions_n_temptative.parse::<usize>().expect("Failed to parse the number of last transactions.");
-                                            match self.database.last_transactions(self.last_transactions_n) {
+                                            match self.database.last_transactions(self.last_transactions_n, &self.browse_tag) {
                                                 Ok(s) => {
                                                     self.last_transactions_csv = s; 
                                                     self.last_transactions_csv_correct = true;},
@@ -102,7 +151,54 @@ impl AppState {
                                             let mut i: usize = 0;
                                             for element in row_line.split(",") {
                                                 row_ui.col(|ui| {
-                                                    if i == column_count - 1 {
+                                                    if i == column_count - 3 {
+                                                        // index of the status column
+                                                        ui.menu_button(element, |ui| {
+                                                            let elements: Vec<&str> = row_line.split(",").collect();
+                                                            let party_id: i64 = elements[column_count - 1].parse().unwrap();
+                                                            let transaction_id: i64 = elements[column_count - 2].parse().unwrap();
+                                                            let transaction_type: TransactionType = TransactionType::from_str(elements[0]).unwrap();
+                                                            let mut result: Option<Result<(), PolarsError>> = None;
+                                                            ui.label("Transaction:");
+                                                            for possible_status in TransactionStatus::iter() {
+                                                                if ui.button(possible_status.to_string()).on_hover_text("Changes the status of this transaction only").clicked() {
+                                                                    result = Some(self.database.set_transaction_status(&transaction_type, transaction_id, &possible_status));
+                                                                }
+                                                            }
+                                                            ui.separator();
+                                                            ui.label("Party:");
+                                                            for possible_status in TransactionStatus::iter() {
+                                                                if ui.button(possible_status.to_string()).on_hover_text("Changes the status of all the transactions of the party").clicked() {
+                                                                    result = Some(self.database.set_party_status(party_id, &possible_status));
+                                                                }
+                                                            }
+                                                            if let Some(result) = result {
+                                                                match result {
+                                                                    Ok(_) => {
+                                                                        match self.database.save() {
+                                                                            Ok(_) => {
+                                                                                match self.database.last_transactions(self.last_transactions_n, &self.browse_tag) {
+                                                                                    Ok(s) => {
+                                                                                        self.last_transactions_csv = s;
+                                                                                    }
+                                                                                    Err(e) => {
+                                                                                        self.throw_error(e);
+                                                                                    }
+                                                                                }
+                                                                            },
+                                                                            Err(e) => {
+                                                                                self.throw_error(e);
+                                                                            }
+                                                                        }
+                                                                    },
+                                                                    Err(e) => {
+                                                                        self.throw_polars_error(e);
+                                                                    }
+                                                                }
+                                                                ui.close();
+                                                            }
+                                                        });
+                                                    } else if i == column_count - 1 {
                                                         // index of the last column
                                                         if ui.button("Edit/Remove").on_hover_text("Removes the party from the database, and launches the input menu with an equal party already loaded").clicked() {
                                                             let party_id: i64 =
@@ -313,4 +409,85 @@ impl AppState {
             },
             );
     }
+
+    pub fn handle_show_outstanding_reimbursements_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("outstanding_reimbursements_window"),
+            egui::ViewportBuilder::default()
+            .with_title("Outstanding reimbursements window")
+            .with_inner_size([WINDOW_WIDTH * 1.2, WINDOW_HEIGHT]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let outstanding_reimbursements_csv = self.outstanding_reimbursements_csv.clone();
+                    let header_line: String =
+                        outstanding_reimbursements_csv.split("\n").collect::<Vec<&str>>()[0].to_string();
+                    let row_lines: Vec<&str> =
+                        outstanding_reimbursements_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+                    let column_count: usize = header_line.split(",").count();
+
+                    StripBuilder::new(ui)
+                        .size(Size::exact(30.0))
+                        .size(Size::remainder().at_least(120.0))
+                        .vertical(|mut strip| {
+                            strip.cell(|ui| {
+                                ui.vertical_centered_justified(|ui| {
+                                    if ui.button("Refresh").on_hover_text("Reimbursable expenses that have not been fully given back yet.").clicked() {
+                                        self.load_outstanding_reimbursements();
+                                    }
+                                });
+                                ui.separator();
+                            });
+                            if self.outstanding_reimbursements_csv_correct {
+                            strip.cell(|ui| {
+                                TableBuilder::new(ui)
+                                    .columns(Column::auto().resizable(true), column_count)
+                                    .striped(true)
+                                    .cell_layout(Layout::right_to_left(Align::Center))
+                                    .header(20.0, |mut header| {
+                                        for column_name in header_line.split(",") {
+                                            header.col(|ui| {
+                                                ui.strong(column_name).on_hover_text(column_name);
+                                            });
+                                        }
+                                    })
+                                .body(|mut body| {
+                                    for row_line in row_lines {
+                                        body.row(30.0, |mut row_ui| {
+                                            let elements: Vec<&str> = row_line.split(",").collect();
+                                            for (i, element) in elements.iter().enumerate() {
+                                                row_ui.col(|ui| {
+                                                    if i == column_count - 1 {
+                                                        // index of the expense_id column
+                                                        if ui.button("Enter refund").on_hover_text("Launches the input menu with a refund of the outstanding amount").clicked() {
+                                                            self.transaction_type = TransactionType::Refund;
+                                                            self.transaction_refund_of = element.parse().unwrap_or(-1);
+                                                            // the outstanding amount is the column before
+                                                            self.transaction_value_tentative = elements[i - 1].to_string();
+                                                            self.show_input_party_window = true;
+                                                            self.show_input_transaction_window = true;
+                                                        }
+                                                    } else {
+                                                        ui.label(*element);
+                                                    }
+                                                });
+                                            }
+                                        });
+                                    }
+                                });
+                                ui.separator();
+                            });
+                            }
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_outstanding_reimbursements_window = false;
+                }
+            },
+            );
+    }
 }
@@ -0,0 +1,594 @@
+use crate::modules::database::envelopes::EnvelopeOverview;
+use crate::modules::financial::*;
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use chrono::NaiveDate;
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox};
+use egui::{Align, Layout};
+use egui_autocomplete::AutoCompleteTextEdit;
+use egui_extras::*;
+use strum::IntoEnumIterator;
+
+impl AppState {
+    fn is_valid_budget_value(&self) -> bool {
+        let parsing_result = self.budget_value_tentative.parse::<f64>();
+        match parsing_result {
+            Ok(value) => value > 0.0,
+            Err(_e) => false,
+        }
+    }
+
+    fn are_valid_budget_fields(&self) -> bool {
+        (!self.budget_category.is_empty()) & self.is_valid_budget_value()
+    }
+
+    fn clear_budget_fields(&mut self) {
+        self.budget_category = String::default();
+        self.budget_subcategory = String::default();
+        self.budget_period = BudgetPeriod::default();
+        self.budget_value_tentative = String::default();
+        self.budget_currency = Currency::default();
+    }
+
+    fn generate_budget_report(&mut self) {
+        match self
+            .database
+            .budget_report(self.budget_report_date, &self.budget_report_currency)
+        {
+            Ok(s) => {
+                self.budget_report_csv = s;
+                self.budget_report_csv_correct = true;
+            }
+            Err(e) => {
+                self.budget_report_csv_correct = false;
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_budget_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("budget_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Budgets window")
+                .with_inner_size([WINDOW_WIDTH * 1.2, WINDOW_HEIGHT * 1.5]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let budget_report_csv = self.budget_report_csv.clone();
+                    let header_line: String = budget_report_csv.split("\n").collect::<Vec<&str>>()[0].to_string();
+                    let row_lines: Vec<&str> = budget_report_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+                    let column_count: usize = header_line.split(",").count();
+
+                    StripBuilder::new(ui)
+                        .size(Size::exact(260.0))
+                        .size(Size::remainder().at_least(120.0))
+                        .vertical(|mut strip| {
+                            strip.cell(|ui| {
+                                egui::Grid::new("budget_input")
+                                    .num_columns(3)
+                                    .spacing([45.0, 4.0])
+                                    .show(ui, |ui| {
+                                        ui.label("Category:").on_hover_text("Expense category of the budget.");
+                                        match self.database.transaction_categories(&TransactionType::Expense) {
+                                            Ok(categories) => {
+                                                ui.add(
+                                                    AutoCompleteTextEdit::new(&mut self.budget_category, categories)
+                                                        .max_suggestions(10)
+                                                        .highlight_matches(true),
+                                                );
+                                            }
+                                            Err(e) => {
+                                                self.throw_polars_error(e);
+                                            }
+                                        }
+                                        if !self.budget_category.is_empty() {
+                                            ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid category!");
+                                        } else {
+                                            ui.colored_label(Color32::from_rgb(255, 0, 0), "Please enter a category!");
+                                        }
+                                        ui.end_row();
+
+                                        ui.label("Subcategory:").on_hover_text("Expense subcategory of the budget. Leave empty for a budget over the whole category.");
+                                        match self.database.transaction_subcategories(&TransactionType::Expense, self.budget_category.clone()) {
+                                            Ok(subcategories) => {
+                                                ui.add(
+                                                    AutoCompleteTextEdit::new(&mut self.budget_subcategory, subcategories)
+                                                        .max_suggestions(10)
+                                                        .highlight_matches(true),
+                                                );
+                                            }
+                                            Err(e) => {
+                                                self.throw_polars_error(e);
+                                            }
+                                        }
+                                        ui.end_row();
+
+                                        ui.label("Period:").on_hover_text("Period covered by the budget.");
+                                        ComboBox::from_id_salt("Budget period")
+                                            .selected_text(format!("{}", self.budget_period))
+                                            .show_ui(ui, |ui| {
+                                                for possible_budget_period in BudgetPeriod::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.budget_period,
+                                                        possible_budget_period.clone(),
+                                                        format!("{possible_budget_period}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Amount:").on_hover_text("Maximum amount to spend during the period.");
+                                        ui.text_edit_singleline(&mut self.budget_value_tentative);
+                                        if self.is_valid_budget_value() {
+                                            ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid amount!");
+                                        } else {
+                                            ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid amount!");
+                                        }
+                                        ui.end_row();
+
+                                        ui.label("Currency:").on_hover_text("Currency of the amount.");
+                                        ComboBox::from_id_salt("Budget currency")
+                                            .selected_text(format!("{}", self.budget_currency))
+                                            .show_ui(ui, |ui| {
+                                                for possible_budget_currency in Currency::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.budget_currency,
+                                                        possible_budget_currency.clone(),
+                                                        format!("{possible_budget_currency}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("");
+                                        if self.are_valid_budget_fields()
+                                            && ui.button("Add budget").on_hover_text("Save budget into the database.").clicked() {
+                                                let budget: Budget = Budget::new(
+                                                    self.budget_category.clone(),
+                                                    self.budget_subcategory.clone(),
+                                                    self.budget_period.clone(),
+                                                    self.budget_value_tentative.parse::<f64>().expect("Error parsing budget amount"),
+                                                    self.budget_currency.clone(),
+                                                );
+                                                match self.database.insert_budget(&budget) {
+                                                    Ok(_) => {
+                                                        match self.database.save() {
+                                                            Ok(_) => {
+                                                                self.clear_budget_fields();
+                                                                if self.budget_report_csv_correct {
+                                                                    self.generate_budget_report();
+                                                                }
+                                                            }
+                                                            Err(e) => {
+                                                                self.throw_error(e);
+                                                            }
+                                                        }
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_polars_error(e);
+                                                    }
+                                                }
+                                            }
+                                        ui.end_row();
+                                    });
+                                ui.separator();
+
+                                egui::Grid::new("budget_report")
+                                    .num_columns(3)
+                                    .spacing([45.0, 4.0])
+                                    .show(ui, |ui| {
+                                        ui.label("Date:").on_hover_text("Compare the budgets with the expenses of the month or year of this date, up to this date, included.");
+                                        ui.add(DatePickerButton::new(&mut self.budget_report_date).id_salt("budget_report_date"));
+                                        ui.end_row();
+
+                                        ui.label("Currency:").on_hover_text("Currency in which to express the ammounts.");
+                                        ComboBox::from_id_salt("Budget report currency")
+                                            .selected_text(format!("{}", self.budget_report_currency))
+                                            .show_ui(ui, |ui| {
+                                                for possible_budget_report_currency in Currency::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.budget_report_currency,
+                                                        possible_budget_report_currency.clone(),
+                                                        format!("{possible_budget_report_currency}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("");
+                                        if ui.button("Generate!").clicked() {
+                                            self.generate_budget_report();
+                                        }
+                                    });
+                                ui.separator();
+                            });
+                            if self.budget_report_csv_correct {
+                            strip.cell(|ui| {
+                                let mut remove_budget_id: Option<i64> = None;
+                                TableBuilder::new(ui)
+                                    .columns(Column::auto().resizable(true), column_count)
+                                    .striped(true)
+                                    .cell_layout(Layout::right_to_left(Align::Center))
+                                    .header(20.0, |mut header| {
+                                        for column_name in header_line.split(",") {
+                                            header.col(|ui| {
+                                                ui.strong(column_name).on_hover_text(column_name);
+                                            });
+                                        }
+                                    })
+                                    .body(|mut body| {
+                                        for row_line in row_lines {
+                                            // highlight the budgets that are (or will be) overrun
+                                            let colour: Option<Color32> = if row_line.contains(",Overrun,") {
+                                                Some(Color32::from_rgb(255, 0, 0))
+                                            } else if row_line.contains(",Projected overrun,") {
+                                                Some(Color32::from_rgb(255, 165, 0))
+                                            } else {
+                                                None
+                                            };
+                                            body.row(30.0, |mut row_ui| {
+                                                for (i, element) in row_line.split(",").enumerate() {
+                                                    row_ui.col(|ui| {
+                                                        if i == column_count - 1 {
+                                                            // index of the last column
+                                                            if ui.button("Remove").on_hover_text("Removes the budget from the database.").clicked() {
+                                                                remove_budget_id = element.parse().ok();
+                                                            }
+                                                        } else if let Some(colour) = colour {
+                                                            ui.colored_label(colour, element);
+                                                        } else {
+                                                            ui.label(element);
+                                                        }
+                                                    });
+                                                }
+                                            });
+                                        }
+                                    });
+                                if let Some(budget_id) = remove_budget_id {
+                                    match self.database.delete_budget(budget_id) {
+                                        Ok(_) => {
+                                            match self.database.save() {
+                                                Ok(_) => {
+                                                    self.generate_budget_report();
+                                                }
+                                                Err(e) => {
+                                                    self.throw_error(e);
+                                                }
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                }
+                                ui.separator();
+                            });
+                            }
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_budget_window = false;
+                }
+            },
+        );
+    }
+
+    fn is_valid_assignment_value(&self) -> bool {
+        let parsing_result = self.assignment_value_tentative.parse::<f64>();
+        match parsing_result {
+            Ok(value) => value > 0.0,
+            Err(_e) => false,
+        }
+    }
+
+    fn are_valid_assignment_fields(&self) -> bool {
+        (self.assignment_party_id >= 0)
+            & (!self.assignment_category.is_empty())
+            & self.is_valid_assignment_value()
+    }
+
+    fn generate_envelope_overview(&mut self) {
+        match self
+            .database
+            .envelope_overview(self.envelope_month, &self.envelope_currency)
+        {
+            Ok(envelope_overview) => {
+                self.envelope_overview = Some(envelope_overview);
+            }
+            Err(e) => {
+                self.envelope_overview = None;
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_envelope_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("envelope_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Envelopes window")
+                .with_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT * 1.5]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    egui::Grid::new("envelope_report")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Month:").on_hover_text(
+                                "Any date of the month to show. Money is assigned to this month.",
+                            );
+                            ui.add(
+                                DatePickerButton::new(&mut self.envelope_month)
+                                    .id_salt("envelope_month"),
+                            );
+                            ui.end_row();
+
+                            ui.label("Currency:").on_hover_text(
+                                "Currency in which to express and assign the ammounts.",
+                            );
+                            ComboBox::from_id_salt("Envelope currency")
+                                .selected_text(format!("{}", self.envelope_currency))
+                                .show_ui(ui, |ui| {
+                                    for possible_envelope_currency in Currency::iter() {
+                                        ui.selectable_value(
+                                            &mut self.envelope_currency,
+                                            possible_envelope_currency.clone(),
+                                            format!("{possible_envelope_currency}"),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("");
+                            if ui.button("Generate!").clicked() {
+                                self.generate_envelope_overview();
+                            }
+                        });
+                    ui.separator();
+
+                    let Some(envelope_overview) = &self.envelope_overview else {
+                        return;
+                    };
+                    let EnvelopeOverview {
+                        envelopes_csv,
+                        to_be_assigned,
+                        assignable_parties,
+                    } = envelope_overview;
+                    let envelopes_csv: String = envelopes_csv.clone();
+                    let to_be_assigned: f64 = *to_be_assigned;
+                    let assignable_parties: Vec<(i64, NaiveDate, f64)> = assignable_parties.clone();
+
+                    if to_be_assigned < 0.0 {
+                        ui.colored_label(
+                            Color32::from_rgb(255, 0, 0),
+                            format!(
+                                "Assigned more than earned: {} {}",
+                                to_be_assigned, self.envelope_currency
+                            ),
+                        );
+                    } else {
+                        ui.strong(format!(
+                            "To be assigned: {} {}",
+                            to_be_assigned, self.envelope_currency
+                        ));
+                    }
+                    ui.separator();
+
+                    let mut changed: bool = false;
+                    egui::Grid::new("assignment_input")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            let party_label =
+                                |party_id: i64, date: NaiveDate, unassigned: f64| -> String {
+                                    format!("{} (party {}): {} left", date, party_id, unassigned)
+                                };
+
+                            ui.label("Income:")
+                                .on_hover_text("Income party to take the money from.");
+                            let selected_text: String = assignable_parties
+                                .iter()
+                                .find(|(party_id, _, _)| *party_id == self.assignment_party_id)
+                                .map(|(party_id, date, unassigned)| {
+                                    party_label(*party_id, *date, *unassigned)
+                                })
+                                .unwrap_or(String::from("Select income"));
+                            ComboBox::from_id_salt("Assignment party")
+                                .selected_text(selected_text)
+                                .show_ui(ui, |ui| {
+                                    for (party_id, date, unassigned) in assignable_parties.iter() {
+                                        ui.selectable_value(
+                                            &mut self.assignment_party_id,
+                                            *party_id,
+                                            party_label(*party_id, *date, *unassigned),
+                                        );
+                                    }
+                                });
+                            if self.assignment_party_id >= 0 {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid income!");
+                            } else {
+                                ui.colored_label(
+                                    Color32::from_rgb(255, 0, 0),
+                                    "Please select an income!",
+                                );
+                            }
+                            ui.end_row();
+
+                            ui.label("Envelope:")
+                                .on_hover_text("Expense category that receives the money.");
+                            match self
+                                .database
+                                .transaction_categories(&TransactionType::Expense)
+                            {
+                                Ok(categories) => {
+                                    ui.add(
+                                        AutoCompleteTextEdit::new(
+                                            &mut self.assignment_category,
+                                            categories,
+                                        )
+                                        .max_suggestions(10)
+                                        .highlight_matches(true),
+                                    );
+                                }
+                                Err(e) => {
+                                    self.throw_polars_error(e);
+                                }
+                            }
+                            if !self.assignment_category.is_empty() {
+                                ui.colored_label(
+                                    Color32::from_rgb(110, 255, 110),
+                                    "Valid envelope!",
+                                );
+                            } else {
+                                ui.colored_label(
+                                    Color32::from_rgb(255, 0, 0),
+                                    "Please enter a category!",
+                                );
+                            }
+                            ui.end_row();
+
+                            ui.label("Amount:")
+                                .on_hover_text("Amount to assign, in the currency above.");
+                            ui.text_edit_singleline(&mut self.assignment_value_tentative);
+                            if self.is_valid_assignment_value() {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid amount!");
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid amount!");
+                            }
+                            ui.end_row();
+
+                            ui.label("");
+                            if self.are_valid_assignment_fields()
+                                && ui
+                                    .button("Assign")
+                                    .on_hover_text(
+                                        "Puts the amount into the envelope for the selected month.",
+                                    )
+                                    .clicked()
+                            {
+                                let assignment: Assignment = Assignment::new(
+                                    self.assignment_party_id,
+                                    self.assignment_category.clone(),
+                                    self.assignment_value_tentative
+                                        .parse::<f64>()
+                                        .expect("Error parsing assignment amount"),
+                                    self.envelope_currency.clone(),
+                                    self.envelope_month,
+                                );
+                                match self.database.insert_assignment(&assignment) {
+                                    Ok(_) => {
+                                        self.assignment_category = String::default();
+                                        self.assignment_value_tentative = String::default();
+                                        changed = true;
+                                    }
+                                    Err(e) => {
+                                        self.throw_polars_error(e);
+                                    }
+                                }
+                            }
+                            ui.end_row();
+
+                            if self.assignment_party_id >= 0 {
+                                match self.database.party_assignments(self.assignment_party_id) {
+                                    Ok(assignments) => {
+                                        for (assignment_id, assignment) in assignments {
+                                            ui.label("");
+                                            ui.label(assignment.to_string());
+                                            if ui
+                                                .button("Remove")
+                                                .on_hover_text(
+                                                    "Takes the money back out of the envelope.",
+                                                )
+                                                .clicked()
+                                            {
+                                                match self.database.delete_assignment(assignment_id)
+                                                {
+                                                    Ok(_) => {
+                                                        changed = true;
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_polars_error(e);
+                                                    }
+                                                }
+                                            }
+                                            ui.end_row();
+                                        }
+                                    }
+                                    Err(e) => {
+                                        self.throw_error(e);
+                                    }
+                                }
+                            }
+                        });
+                    ui.separator();
+
+                    let header_line: String =
+                        envelopes_csv.split("\n").collect::<Vec<&str>>()[0].to_string();
+                    let row_lines: Vec<&str> =
+                        envelopes_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+                    let column_count: usize = header_line.split(",").count();
+
+                    TableBuilder::new(ui)
+                        .columns(Column::auto().resizable(true), column_count)
+                        .striped(true)
+                        .cell_layout(Layout::right_to_left(Align::Center))
+                        .header(20.0, |mut header| {
+                            for column_name in header_line.split(",") {
+                                header.col(|ui| {
+                                    ui.strong(column_name).on_hover_text(column_name);
+                                });
+                            }
+                        })
+                        .body(|mut body| {
+                            for row_line in row_lines {
+                                // overspent envelopes have a negative available amount
+                                let overspent: bool = row_line
+                                    .split(",")
+                                    .last()
+                                    .and_then(|available| available.parse::<f64>().ok())
+                                    .is_some_and(|available| available < 0.0);
+                                body.row(30.0, |mut row_ui| {
+                                    for element in row_line.split(",") {
+                                        row_ui.col(|ui| {
+                                            if overspent {
+                                                ui.colored_label(
+                                                    Color32::from_rgb(255, 0, 0),
+                                                    element,
+                                                );
+                                            } else {
+                                                ui.label(element);
+                                            }
+                                        });
+                                    }
+                                });
+                            }
+                        });
+
+                    if changed {
+                        match self.database.save() {
+                            Ok(_) => {
+                                self.generate_envelope_overview();
+                            }
+                            Err(e) => {
+                                self.throw_error(e);
+                            }
+                        }
+                    }
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_envelope_window = false;
+                }
+            },
+        );
+    }
+}
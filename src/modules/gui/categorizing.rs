@@ -0,0 +1,805 @@
+use crate::modules::categorization::rules::CategorizationRule;
+use crate::modules::financial::*;
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox, ScrollArea};
+use egui::{Align, Layout};
+use egui_autocomplete::AutoCompleteTextEdit;
+use egui_extras::*;
+use polars::prelude::{AnyValue, DataFrame};
+use regex::RegexBuilder;
+use strum::IntoEnumIterator;
+
+impl AppState {
+    fn rename_category(&mut self) {
+        match self
+            .database
+            .rename_category(self.category_manager_id, &self.category_manager_name)
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {
+                self.category_manager_name = String::default();
+                // the rules propose the renamed category
+                self.transaction_rules = None;
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn merge_category(&mut self) {
+        match self
+            .database
+            .merge_categories(self.category_manager_id, self.category_manager_target_id)
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {
+                // the merged category is gone, so the one it was merged into is shown
+                self.category_manager_id = self.category_manager_target_id;
+                self.category_manager_target_id = -1;
+                self.transaction_rules = None;
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn move_category(&mut self) {
+        match self
+            .database
+            .move_category(self.category_manager_id, self.category_manager_parent_id)
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {
+                self.transaction_rules = None;
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn add_category(&mut self) {
+        // the levels are typed separated by colons, as they are shown
+        let path: Vec<String> = self
+            .category_manager_new_path
+            .split(":")
+            .map(|level| level.to_string())
+            .collect();
+        let (category, subcategory) = split_category_path(&path);
+        match self
+            .database
+            .register_category(&self.category_manager_type, &category, &subcategory)
+            .and_then(|category_id| {
+                self.database.save()?;
+                Ok(category_id)
+            }) {
+            Ok(category_id) => {
+                self.category_manager_id = category_id;
+                self.category_manager_new_path = String::default();
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    /// Fills the rule fields with the selected saved rule, once it is chosen
+    fn load_rule(&mut self) {
+        if self.rule_loaded_id == self.rule_id {
+            return;
+        }
+        self.rule_loaded_id = self.rule_id;
+        self.rule_test_rows = Vec::new();
+        self.rule_tested = false;
+        if self.rule_id < 0 {
+            return;
+        }
+
+        match self.database.categorization_rule(self.rule_id) {
+            Ok(rule) => {
+                self.rule_name = rule.name;
+                self.rule_priority_tentative = rule.priority.to_string();
+                self.rule_type = rule.transaction_type;
+                self.rule_pattern = rule.description_pattern;
+                self.rule_min_value_tentative = rule
+                    .min_value
+                    .map(|value| value.to_string())
+                    .unwrap_or_default();
+                self.rule_max_value_tentative = rule
+                    .max_value
+                    .map(|value| value.to_string())
+                    .unwrap_or_default();
+                self.rule_account_id = rule.account_id;
+                self.rule_currency = rule.currency;
+                self.rule_entity_id = rule.entity_id;
+                self.rule_category = rule.category;
+                self.rule_subcategory = rule.subcategory;
+                self.rule_tags_tentative = rule.tags.join(", ");
+            }
+            Err(e) => {
+                self.rule_id = -1;
+                self.rule_loaded_id = -1;
+                self.throw_error(e);
+            }
+        }
+    }
+
+    /// Builds the rule out of the fields, some of them typed as text
+    fn rule_from_fields(&self) -> Result<CategorizationRule, Box<dyn std::error::Error>> {
+        // an empty bound means no bound
+        let bound = |tentative: &str| -> Result<Option<f64>, String> {
+            if tentative.trim().is_empty() {
+                return Ok(None);
+            }
+            match tentative.trim().parse::<f64>() {
+                Ok(value) => Ok(Some(value)),
+                Err(_e) => Err(format!("{tentative} is not a value!")),
+            }
+        };
+
+        Ok(CategorizationRule {
+            name: self.rule_name.trim().to_string(),
+            priority: self
+                .rule_priority_tentative
+                .trim()
+                .parse::<i64>()
+                .map_err(|_e| "The priority has to be a whole number!")?,
+            transaction_type: self.rule_type.clone(),
+            description_pattern: self.rule_pattern.clone(),
+            min_value: bound(&self.rule_min_value_tentative)?,
+            max_value: bound(&self.rule_max_value_tentative)?,
+            account_id: self.rule_account_id,
+            currency: self.rule_currency.clone(),
+            entity_id: self.rule_entity_id,
+            category: self.rule_category.clone(),
+            subcategory: self.rule_subcategory.clone(),
+            tags: self
+                .rule_tags_tentative
+                .split(",")
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        })
+    }
+
+    fn save_rule(&mut self) {
+        match self
+            .rule_from_fields()
+            .and_then(|rule| self.database.insert_categorization_rule(&rule))
+            .and_then(|rule_id| {
+                self.database.save()?;
+                Ok(rule_id)
+            }) {
+            Ok(rule_id) => {
+                self.rule_id = rule_id;
+                self.rule_loaded_id = rule_id;
+                self.transaction_rules = None;
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn delete_rule(&mut self, rule_id: i64) {
+        match self.database.delete_categorization_rule(rule_id) {
+            Ok(_) => match self.database.save() {
+                Ok(_) => {
+                    if self.rule_id == rule_id {
+                        self.rule_id = -1;
+                        self.rule_loaded_id = -1;
+                    }
+                    self.transaction_rules = None;
+                }
+                Err(e) => {
+                    self.throw_error(e);
+                }
+            },
+            Err(e) => {
+                self.throw_polars_error(e);
+            }
+        }
+    }
+
+    /// Tries the rule in the fields on the incomes or expenses of the ledger
+    fn test_rule(&mut self) {
+        let tested: Result<DataFrame, Box<dyn std::error::Error>> = self
+            .rule_from_fields()
+            .and_then(|rule| self.database.test_categorization_rule(&rule));
+        let rows: Result<Vec<Vec<String>>, Box<dyn std::error::Error>> =
+            tested.and_then(|data_frame| {
+                let mut rows: Vec<Vec<String>> = Vec::new();
+                for i in 0..data_frame.height() {
+                    let mut row: Vec<String> = Vec::new();
+                    for column in data_frame.get_columns() {
+                        row.push(match column.get(i)? {
+                            AnyValue::String(text) => text.to_string(),
+                            AnyValue::Boolean(true) => String::from("Yes"),
+                            AnyValue::Boolean(false) => String::from("No"),
+                            AnyValue::Float64(value) => format!("{value:.2}"),
+                            AnyValue::Null => String::default(),
+                            value => value.to_string(),
+                        });
+                    }
+                    rows.push(row);
+                }
+                Ok(rows)
+            });
+        match rows {
+            Ok(rows) => {
+                self.rule_test_rows = rows;
+                self.rule_tested = true;
+            }
+            Err(e) => {
+                self.rule_test_rows = Vec::new();
+                self.rule_tested = false;
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_category_manager_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("category_manager_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Category manager window")
+                .with_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT * 1.2]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let paths: Vec<(i64, Vec<String>)> = match self.database.category_paths(&self.category_manager_type) {
+                        Ok(paths) => paths,
+                        Err(e) => {
+                            self.throw_polars_error(e);
+                            Vec::new()
+                        }
+                    };
+                    let path_string = |category_id: i64| -> String {
+                        paths
+                            .iter()
+                            .find(|(id, _path)| *id == category_id)
+                            .map(|(_id, path)| path.join(":"))
+                            .unwrap_or(String::from("Select category"))
+                    };
+                    let selected_path: Vec<String> = paths
+                        .iter()
+                        .find(|(id, _path)| *id == self.category_manager_id)
+                        .map(|(_id, path)| path.clone())
+                        .unwrap_or_default();
+
+                    egui::Grid::new("category_manager_selection")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Type:").on_hover_text("Incomes and expenses have separate categories.");
+                            ComboBox::from_id_salt("Category manager type")
+                                .selected_text(format!("{}", self.category_manager_type))
+                                .show_ui(ui, |ui| {
+                                    for possible_type in [TransactionType::Income, TransactionType::Expense] {
+                                        if ui.selectable_value(
+                                            &mut self.category_manager_type,
+                                            possible_type.clone(),
+                                            format!("{possible_type}"),
+                                        ).changed() {
+                                            self.category_manager_id = -1;
+                                            self.category_manager_target_id = -1;
+                                            self.category_manager_parent_id = -1;
+                                        }
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Category:").on_hover_text("Category to rename, merge or move. Levels below the subcategory are separated by colons.");
+                            ComboBox::from_id_salt("Managed category")
+                                .selected_text(path_string(self.category_manager_id))
+                                .show_ui(ui, |ui| {
+                                    for (category_id, path) in paths.iter() {
+                                        ui.selectable_value(
+                                            &mut self.category_manager_id,
+                                            *category_id,
+                                            path.join(":"),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    if !selected_path.is_empty() {
+                        egui::Grid::new("category_manager_editing")
+                            .num_columns(3)
+                            .spacing([45.0, 4.0])
+                            .show(ui, |ui| {
+                                ui.label("New name:").on_hover_text("Renames the category in every transaction, template and budget filed under it.");
+                                ui.text_edit_singleline(&mut self.category_manager_name);
+                                if !self.category_manager_name.trim().is_empty()
+                                    && ui.button("Rename").clicked() {
+                                        self.rename_category();
+                                    }
+                                ui.end_row();
+
+                                ui.label("Merge into:").on_hover_text("Files everything under the category, subcategories included, under another one, and deletes it.");
+                                ComboBox::from_id_salt("Merge into category")
+                                    .selected_text(path_string(self.category_manager_target_id))
+                                    .show_ui(ui, |ui| {
+                                        for (category_id, path) in paths.iter() {
+                                            // not into itself or one under it
+                                            if !path.starts_with(&selected_path) {
+                                                ui.selectable_value(
+                                                    &mut self.category_manager_target_id,
+                                                    *category_id,
+                                                    path.join(":"),
+                                                );
+                                            }
+                                        }
+                                    });
+                                if self.category_manager_target_id >= 0
+                                    && ui.button("Merge").clicked() {
+                                        self.merge_category();
+                                    }
+                                ui.end_row();
+
+                                ui.label("Move under:").on_hover_text("Moves the category, with its subcategories, under another one or to the top level.");
+                                let parent_string: String = if self.category_manager_parent_id >= 0 {
+                                    path_string(self.category_manager_parent_id)
+                                } else {
+                                    String::from("Top level")
+                                };
+                                ComboBox::from_id_salt("Move under category")
+                                    .selected_text(parent_string)
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(
+                                            &mut self.category_manager_parent_id,
+                                            -1,
+                                            "Top level",
+                                        );
+                                        for (category_id, path) in paths.iter() {
+                                            if !path.starts_with(&selected_path) {
+                                                ui.selectable_value(
+                                                    &mut self.category_manager_parent_id,
+                                                    *category_id,
+                                                    path.join(":"),
+                                                );
+                                            }
+                                        }
+                                    });
+                                let current_parent_id: i64 = match self.database.category(self.category_manager_id) {
+                                    Ok(category) => category.parent_id(),
+                                    Err(_e) => -1,
+                                };
+                                if self.category_manager_parent_id != current_parent_id
+                                    && ui.button("Move").clicked() {
+                                        self.move_category();
+                                    }
+                                ui.end_row();
+                            });
+                        ui.separator();
+                    }
+
+                    egui::Grid::new("category_manager_adding")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("New category:").on_hover_text("Category to add, with its levels separated by colons, like Food:Groceries:Organic.");
+                            ui.text_edit_singleline(&mut self.category_manager_new_path);
+                            if !self.category_manager_new_path.trim().is_empty() {
+                                if ui.button("Add").clicked() {
+                                    self.add_category();
+                                }
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Please enter a category!");
+                            }
+                            ui.end_row();
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_category_manager_window = false;
+                }
+            },
+        );
+    }
+
+    pub fn handle_show_rule_window(&mut self, ctx: &egui::Context) {
+        self.load_rule();
+
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("rule_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Categorization rules window")
+                .with_inner_size([WINDOW_WIDTH * 1.5, WINDOW_HEIGHT * 2.0]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("rule_editing")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Rule:").on_hover_text("Saved rule to edit. Saving under a new name adds a rule.");
+                            let rule_string: String = if self.rule_id >= 0 {
+                                self.rule_name.clone()
+                            } else {
+                                String::from("New rule")
+                            };
+                            ComboBox::from_id_salt("Categorization rule")
+                                .selected_text(rule_string)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.rule_id, -1, "New rule");
+                                    match self.database.iter_categorization_rule_ids() {
+                                        Ok(iterator) => {
+                                            for rule_id in iterator {
+                                                match self.database.categorization_rule(rule_id) {
+                                                    Ok(rule) => {
+                                                        ui.selectable_value(&mut self.rule_id, rule_id, rule.name);
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_error(e);
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Name:").on_hover_text("Name of the rule, shown when it fires.");
+                            ui.text_edit_singleline(&mut self.rule_name);
+                            if self.rule_name.trim().is_empty() {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Please enter a name!");
+                            } else {
+                                ui.label("");
+                            }
+                            ui.end_row();
+
+                            ui.label("Priority:").on_hover_text("Rules are tried from the lowest priority up, and the first that matches fires.");
+                            ui.text_edit_singleline(&mut self.rule_priority_tentative);
+                            if self.rule_priority_tentative.trim().parse::<i64>().is_ok() {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid priority!");
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid priority!");
+                            }
+                            ui.end_row();
+
+                            ui.label("Type:").on_hover_text("Incomes and expenses have separate categories.");
+                            ComboBox::from_id_salt("Rule type")
+                                .selected_text(format!("{}", self.rule_type))
+                                .show_ui(ui, |ui| {
+                                    for possible_type in [TransactionType::Income, TransactionType::Expense] {
+                                        ui.selectable_value(
+                                            &mut self.rule_type,
+                                            possible_type.clone(),
+                                            format!("{possible_type}"),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Description pattern:").on_hover_text("Regular expression, regardless of case, searched in the description and the payee or entity name, like aldi|lidl or ^SEPA.*rent.");
+                            ui.text_edit_singleline(&mut self.rule_pattern);
+                            match RegexBuilder::new(&self.rule_pattern).case_insensitive(true).build() {
+                                Ok(_) => {
+                                    ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid pattern!");
+                                }
+                                Err(_e) => {
+                                    ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid pattern!");
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label("Minimum value:").on_hover_text("Optional. Values are compared without their sign.");
+                            ui.text_edit_singleline(&mut self.rule_min_value_tentative);
+                            ui.label("");
+                            ui.end_row();
+
+                            ui.label("Maximum value:").on_hover_text("Optional.");
+                            ui.text_edit_singleline(&mut self.rule_max_value_tentative);
+                            ui.label("");
+                            ui.end_row();
+
+                            ui.label("Account:").on_hover_text("Account the money comes from or goes to.");
+                            let account_string: String = if self.rule_account_id >= 0 {
+                                match self.database.account(self.rule_account_id) {
+                                    Ok(account) => account.to_string(),
+                                    Err(e) => {
+                                        self.rule_account_id = -1;
+                                        self.throw_error(e);
+                                        String::from("Any account")
+                                    }
+                                }
+                            } else {
+                                String::from("Any account")
+                            };
+                            ComboBox::from_id_salt("Rule account")
+                                .selected_text(account_string)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.rule_account_id, -1, "Any account");
+                                    match self.database.iter_open_account_ids() {
+                                        Ok(iterator) => {
+                                            for account_id in iterator {
+                                                match self.database.account(account_id) {
+                                                    Ok(account) => {
+                                                        ui.selectable_value(&mut self.rule_account_id, account_id, account.to_string());
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_error(e);
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Currency:");
+                            let currency_string: String = match &self.rule_currency {
+                                Some(currency) => currency.to_string(),
+                                None => String::from("Any currency"),
+                            };
+                            ComboBox::from_id_salt("Rule currency")
+                                .selected_text(currency_string)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.rule_currency, None, "Any currency");
+                                    for possible_currency in Currency::iter() {
+                                        ui.selectable_value(
+                                            &mut self.rule_currency,
+                                            Some(possible_currency.clone()),
+                                            format!("{possible_currency}"),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Proposed entity:").on_hover_text("Optional. Otherwise the entity is left as it is.");
+                            let entity_string: String = if self.rule_entity_id >= 0 {
+                                match self.database.entity(self.rule_entity_id) {
+                                    Ok(entity) => entity.to_string(),
+                                    Err(e) => {
+                                        self.rule_entity_id = -1;
+                                        self.throw_error(e);
+                                        String::from("No entity")
+                                    }
+                                }
+                            } else {
+                                String::from("No entity")
+                            };
+                            ComboBox::from_id_salt("Rule entity")
+                                .selected_text(entity_string)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut self.rule_entity_id, -1, "No entity");
+                                    match self.database.iter_active_entity_ids() {
+                                        Ok(iterator) => {
+                                            for entity_id in iterator {
+                                                match self.database.entity(entity_id) {
+                                                    Ok(entity) => {
+                                                        ui.selectable_value(&mut self.rule_entity_id, entity_id, entity.to_string());
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_error(e);
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Proposed category:").on_hover_text("Optional. Otherwise the category is left as it is.");
+                            match self.database.transaction_categories(&self.rule_type) {
+                                Ok(categories) => {
+                                    ui.add(
+                                        AutoCompleteTextEdit::new(&mut self.rule_category, categories)
+                                            .max_suggestions(10)
+                                            .highlight_matches(true),
+                                    );
+                                }
+                                Err(e) => {
+                                    self.throw_polars_error(e);
+                                }
+                            }
+                            ui.label("");
+                            ui.end_row();
+
+                            ui.label("Proposed subcategory:").on_hover_text("Optional.");
+                            match self.database.transaction_subcategories(&self.rule_type, self.rule_category.clone()) {
+                                Ok(subcategories) => {
+                                    ui.add(
+                                        AutoCompleteTextEdit::new(&mut self.rule_subcategory, subcategories)
+                                            .max_suggestions(10)
+                                            .highlight_matches(true),
+                                    );
+                                }
+                                Err(e) => {
+                                    self.throw_polars_error(e);
+                                }
+                            }
+                            if self.rule_category.is_empty() {
+                                ui.label("");
+                            } else {
+                                match self.database.is_known_category(&self.rule_type, &self.rule_category, &self.rule_subcategory) {
+                                    Ok(true) => {
+                                        ui.colored_label(Color32::from_rgb(110, 255, 110), "Known category!");
+                                    }
+                                    Ok(false) => {
+                                        ui.colored_label(Color32::from_rgb(255, 0, 0), "Unknown category!");
+                                    }
+                                    Err(e) => {
+                                        self.throw_polars_error(e);
+                                    }
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label("Proposed tags:").on_hover_text("Optional, separated by commas.");
+                            ui.text_edit_singleline(&mut self.rule_tags_tentative);
+                            ui.label("");
+                            ui.end_row();
+                        });
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Test against history").on_hover_text("Lists the incomes or expenses of the ledger that the rule matches, and whether it agrees with how they were categorized.").clicked() {
+                            self.test_rule();
+                        }
+                        if ui.button("Save rule").clicked() {
+                            self.save_rule();
+                        }
+                        if self.rule_id >= 0
+                            && ui.button("Delete rule").clicked() {
+                                self.delete_rule(self.rule_id);
+                            }
+                    });
+
+                    if self.rule_tested {
+                        ui.separator();
+                        let n_agreeing: usize = self
+                            .rule_test_rows
+                            .iter()
+                            .filter(|row| row.last().is_some_and(|agrees| agrees == "Yes"))
+                            .count();
+                        ui.label(format!(
+                            "Matches {} transactions, and agrees with {} of them.",
+                            self.rule_test_rows.len(),
+                            n_agreeing
+                        ));
+                        ScrollArea::horizontal().id_salt("rule_test").show(ui, |ui| {
+                            TableBuilder::new(ui)
+                                .id_salt("rule_test_table")
+                                .columns(Column::auto().resizable(true), 9)
+                                .striped(true)
+                                .max_scroll_height(WINDOW_HEIGHT * 0.6)
+                                .cell_layout(Layout::left_to_right(Align::Center))
+                                .header(20.0, |mut header| {
+                                    for column_name in ["Party", "Date", "Value", "Currency", "Description", "Entity", "Category", "Subcategory", "Agrees"] {
+                                        header.col(|ui| {
+                                            ui.strong(column_name);
+                                        });
+                                    }
+                                })
+                                .body(|mut body| {
+                                    for row in self.rule_test_rows.iter() {
+                                        body.row(30.0, |mut row_ui| {
+                                            for field in row {
+                                                row_ui.col(|ui| {
+                                                    match field.as_str() {
+                                                        "Yes" => {
+                                                            ui.colored_label(Color32::from_rgb(110, 255, 110), field);
+                                                        }
+                                                        "No" => {
+                                                            ui.colored_label(Color32::from_rgb(255, 0, 0), field);
+                                                        }
+                                                        _ => {
+                                                            ui.label(field);
+                                                        }
+                                                    }
+                                                });
+                                            }
+                                        });
+                                    }
+                                });
+                        });
+                    }
+
+                    ui.separator();
+                    let mut edited: Option<i64> = None;
+                    let mut deleted: Option<i64> = None;
+                    TableBuilder::new(ui)
+                        .id_salt("rule_list_table")
+                        .columns(Column::auto().resizable(true), 6)
+                        .striped(true)
+                        .cell_layout(Layout::left_to_right(Align::Center))
+                        .header(20.0, |mut header| {
+                            for column_name in ["Priority", "Name", "Type", "Pattern", "Proposes", ""] {
+                                header.col(|ui| {
+                                    ui.strong(column_name);
+                                });
+                            }
+                        })
+                        .body(|mut body| {
+                            let rule_ids: Vec<i64> = match self.database.iter_categorization_rule_ids() {
+                                Ok(iterator) => iterator.collect(),
+                                Err(e) => {
+                                    self.throw_polars_error(e);
+                                    Vec::new()
+                                }
+                            };
+                            for rule_id in rule_ids {
+                                let rule: CategorizationRule = match self.database.categorization_rule(rule_id) {
+                                    Ok(rule) => rule,
+                                    Err(e) => {
+                                        self.throw_error(e);
+                                        continue;
+                                    }
+                                };
+                                let explanation: String = self.database.explain_rule(&rule).unwrap_or_default();
+                                body.row(30.0, |mut row_ui| {
+                                    row_ui.col(|ui| {
+                                        ui.label(rule.priority.to_string());
+                                    });
+                                    row_ui.col(|ui| {
+                                        ui.label(rule.name.clone()).on_hover_text(explanation);
+                                    });
+                                    row_ui.col(|ui| {
+                                        ui.label(rule.transaction_type.to_string());
+                                    });
+                                    row_ui.col(|ui| {
+                                        ui.label(rule.description_pattern.clone());
+                                    });
+                                    row_ui.col(|ui| {
+                                        let mut proposes: Vec<String> = Vec::new();
+                                        if let Ok(entity) = self.database.entity(rule.entity_id) {
+                                            proposes.push(entity.name());
+                                        }
+                                        if !rule.category.is_empty() {
+                                            proposes.push(category_path(&rule.category, &rule.subcategory).join(":"));
+                                        }
+                                        proposes.extend(rule.tags.iter().map(|tag| format!("#{tag}")));
+                                        ui.label(proposes.join(", "));
+                                    });
+                                    row_ui.col(|ui| {
+                                        ui.horizontal(|ui| {
+                                            if ui.button("Edit").clicked() {
+                                                edited = Some(rule_id);
+                                            }
+                                            if ui.button("Delete").clicked() {
+                                                deleted = Some(rule_id);
+                                            }
+                                        });
+                                    });
+                                });
+                            }
+                        });
+                    if let Some(rule_id) = edited {
+                        self.rule_id = rule_id;
+                    }
+                    if let Some(rule_id) = deleted {
+                        self.delete_rule(rule_id);
+                    }
+                    });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_rule_window = false;
+                }
+            },
+        );
+    }
+}
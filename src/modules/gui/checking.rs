@@ -0,0 +1,163 @@
+use crate::modules::database::duplicates::DUPLICATE_DAYS;
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use eframe::egui;
+use eframe::egui::Color32;
+use egui::{Align, Layout};
+use egui_extras::*;
+
+impl AppState {
+    /// Runs the integrity check of the database. Returns whether any issue was found.
+    pub fn load_integrity_report(&mut self) -> bool {
+        match self.database.integrity_report() {
+            Ok(s) => {
+                self.integrity_report_csv = s;
+                self.integrity_report_csv.contains("\n")
+            }
+            Err(e) => {
+                self.integrity_report_csv = String::default();
+                self.throw_error(e);
+                false
+            }
+        }
+    }
+
+    pub fn handle_show_integrity_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("integrity_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Integrity check window")
+                .with_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.vertical_centered_justified(|ui| {
+                        if ui.button("Check again").clicked() {
+                            self.load_integrity_report();
+                        }
+                    });
+                    ui.separator();
+
+                    let integrity_report_csv: String = self.integrity_report_csv.clone();
+                    let row_lines: Vec<&str> =
+                        integrity_report_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+
+                    if row_lines.is_empty() {
+                        ui.colored_label(Color32::from_rgb(110, 255, 110), "No issues found!");
+                        return;
+                    }
+
+                    TableBuilder::new(ui)
+                        .column(Column::auto().resizable(true))
+                        .column(Column::remainder())
+                        .striped(true)
+                        .cell_layout(Layout::left_to_right(Align::Center))
+                        .header(20.0, |mut header| {
+                            for column_name in ["Check", "Issue"] {
+                                header.col(|ui| {
+                                    ui.strong(column_name);
+                                });
+                            }
+                        })
+                        .body(|mut body| {
+                            for row_line in row_lines {
+                                // the issue is free text and may contain commas
+                                let (check, issue) =
+                                    row_line.split_once(",").unwrap_or((row_line, ""));
+                                body.row(30.0, |mut row_ui| {
+                                    row_ui.col(|ui| {
+                                        ui.colored_label(Color32::from_rgb(255, 0, 0), check);
+                                    });
+                                    row_ui.col(|ui| {
+                                        ui.label(issue.trim_matches('"'));
+                                    });
+                                });
+                            }
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_integrity_window = false;
+                }
+            },
+        );
+    }
+
+    /// Looks for parties that were entered twice.
+    pub fn load_duplicates_report(&mut self) {
+        match self.database.duplicates_report() {
+            Ok(s) => {
+                self.duplicates_report_csv = s;
+            }
+            Err(e) => {
+                self.duplicates_report_csv = String::default();
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_duplicates_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("duplicates_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Duplicates window")
+                .with_inner_size([WINDOW_WIDTH * 1.5, WINDOW_HEIGHT]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.vertical_centered_justified(|ui| {
+                        if ui.button("Look again").clicked() {
+                            self.load_duplicates_report();
+                        }
+                    });
+                    ui.label(format!("Same type, entity or account, amount and currency, at most {DUPLICATE_DAYS} days apart and with similar descriptions."));
+                    ui.separator();
+
+                    let duplicates_report_csv: String = self.duplicates_report_csv.clone();
+                    let row_lines: Vec<&str> =
+                        duplicates_report_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+
+                    if row_lines.is_empty() {
+                        ui.colored_label(Color32::from_rgb(110, 255, 110), "No duplicates found!");
+                        return;
+                    }
+
+                    TableBuilder::new(ui)
+                        .columns(Column::auto().resizable(true), 7)
+                        .column(Column::remainder())
+                        .striped(true)
+                        .cell_layout(Layout::left_to_right(Align::Center))
+                        .header(20.0, |mut header| {
+                            for column_name in ["Party", "Duplicate party", "Type", "Date", "Duplicate date", "Value", "Currency", "Description"] {
+                                header.col(|ui| {
+                                    ui.strong(column_name);
+                                });
+                            }
+                        })
+                        .body(|mut body| {
+                            for row_line in row_lines {
+                                // the description is free text and may contain commas
+                                let fields: Vec<&str> = row_line.splitn(8, ",").collect();
+                                body.row(30.0, |mut row_ui| {
+                                    for field in fields {
+                                        row_ui.col(|ui| {
+                                            ui.label(field.trim_matches('"'));
+                                        });
+                                    }
+                                });
+                            }
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_duplicates_window = false;
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use chrono::NaiveDate;
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox};
+use egui_extras::*;
+
+impl AppState {
+    fn close_account(&mut self) {
+        match self
+            .database
+            .close_account(self.closing_account_id, self.closing_date)
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {}
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn reopen_account(&mut self) {
+        match self.database.reopen_account(self.closing_account_id) {
+            Ok(_) => match self.database.save() {
+                Ok(_) => {}
+                Err(e) => {
+                    self.throw_error(e);
+                }
+            },
+            Err(e) => {
+                self.throw_polars_error(e);
+            }
+        }
+    }
+
+    fn set_entity_archived(&mut self, archived: bool) {
+        match self
+            .database
+            .set_entity_archived(self.archiving_entity_id, archived)
+        {
+            Ok(_) => match self.database.save() {
+                Ok(_) => {}
+                Err(e) => {
+                    self.throw_error(e);
+                }
+            },
+            Err(e) => {
+                self.throw_polars_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_closing_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("closing_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Closing window")
+                .with_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let account_string: String = if self.closing_account_id >= 0 {
+                        self.database
+                            .account(self.closing_account_id)
+                            .map(|account| account.to_string())
+                            .unwrap_or_default()
+                    } else {
+                        String::from("Select account")
+                    };
+                    let closing_date: Option<NaiveDate> = if self.closing_account_id >= 0 {
+                        match self.database.account_closing_date(self.closing_account_id) {
+                            Ok(closing_date) => closing_date,
+                            Err(e) => {
+                                self.closing_account_id = -1;
+                                self.throw_polars_error(e);
+                                None
+                            }
+                        }
+                    } else {
+                        None
+                    };
+
+                    egui::Grid::new("account_closing")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Account:").on_hover_text("Account to close or reopen. Closed accounts are kept in every report, but no longer offered when inputting.");
+                            ComboBox::from_id_salt("Closing account")
+                                .selected_text(account_string)
+                                .show_ui(ui, |ui| {
+                                    match self.database.iter_account_ids() {
+                                        Ok(iterator) => {
+                                            for account_id in iterator {
+                                                ui.selectable_value(
+                                                    &mut self.closing_account_id,
+                                                    account_id,
+                                                    self.database
+                                                        .account(account_id)
+                                                        .unwrap() // safe because we iterate over the ids!
+                                                        .to_string(),
+                                                );
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            match closing_date {
+                                Some(date) => {
+                                    ui.colored_label(Color32::from_rgb(255, 0, 0), format!("Closed on {}", date));
+                                }
+                                None => {
+                                    ui.label("Open");
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label("Closing date:").on_hover_text("Date in which the account was closed. It has to be empty by then, in all its currencies.");
+                            ui.add(DatePickerButton::new(&mut self.closing_date).id_salt("closing_date"));
+                            ui.end_row();
+
+                            ui.label("");
+                            if self.closing_account_id >= 0 {
+                                if closing_date.is_some() {
+                                    if ui.button("Reopen").on_hover_text("Offers the account again when inputting.").clicked() {
+                                        self.reopen_account();
+                                    }
+                                } else if ui.button("Close").on_hover_text("Closes the account, if it is empty.").clicked() {
+                                    self.close_account();
+                                }
+                            }
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    let entity_string: String = if self.archiving_entity_id >= 0 {
+                        self.database
+                            .entity(self.archiving_entity_id)
+                            .map(|entity| entity.to_string())
+                            .unwrap_or_default()
+                    } else {
+                        String::from("Select entity")
+                    };
+                    let is_archived: bool = if self.archiving_entity_id >= 0 {
+                        match self.database.is_entity_archived(self.archiving_entity_id) {
+                            Ok(is_archived) => is_archived,
+                            Err(e) => {
+                                self.archiving_entity_id = -1;
+                                self.throw_error(e);
+                                false
+                            }
+                        }
+                    } else {
+                        false
+                    };
+
+                    egui::Grid::new("entity_archiving")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Entity:").on_hover_text("Entity to archive or restore. Archived entities are kept in every report, but no longer offered when inputting.");
+                            ComboBox::from_id_salt("Archiving entity")
+                                .selected_text(entity_string)
+                                .show_ui(ui, |ui| {
+                                    match self.database.iter_entity_ids() {
+                                        Ok(iterator) => {
+                                            for entity_id in iterator {
+                                                ui.selectable_value(
+                                                    &mut self.archiving_entity_id,
+                                                    entity_id,
+                                                    self.database
+                                                        .entity(entity_id)
+                                                        .unwrap() // safe because we iterate over the ids!
+                                                        .to_string(),
+                                                );
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            if is_archived {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Archived");
+                            } else {
+                                ui.label("Active");
+                            }
+                            ui.end_row();
+
+                            ui.label("");
+                            if self.archiving_entity_id >= 0 {
+                                if is_archived {
+                                    if ui.button("Restore").on_hover_text("Offers the entity again when inputting.").clicked() {
+                                        self.set_entity_archived(false);
+                                    }
+                                } else if ui.button("Archive").on_hover_text("Stops offering the entity when inputting.").clicked() {
+                                    self.set_entity_archived(true);
+                                }
+                            }
+                            ui.end_row();
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_closing_window = false;
+                }
+            },
+        );
+    }
+}
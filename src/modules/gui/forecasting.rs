@@ -0,0 +1,145 @@
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use chrono::Local;
+use eframe::egui;
+use eframe::egui::Color32;
+use egui::{Align, Layout};
+use egui_extras::*;
+
+impl AppState {
+    fn is_valid_forecast_months(&self) -> bool {
+        let parsing_result = self.forecast_months_tentative.parse::<u32>();
+        match parsing_result {
+            Ok(months) => (months > 0) & (months <= 60),
+            Err(_e) => false,
+        }
+    }
+
+    fn is_valid_forecast_threshold(&self) -> bool {
+        let parsing_result = self.forecast_threshold_tentative.parse::<f64>();
+        match parsing_result {
+            Ok(_value) => true,
+            Err(_e) => false,
+        }
+    }
+
+    pub fn handle_show_forecast_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("forecast_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Cash-flow forecast window")
+                .with_inner_size([WINDOW_WIDTH * 1.2, WINDOW_HEIGHT * 1.5]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    egui::Grid::new("forecast")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Months:").on_hover_text("Number of months to project, starting today.");
+                            ui.text_edit_singleline(&mut self.forecast_months_tentative);
+                            if self.is_valid_forecast_months() {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid number of months!");
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid number of months!");
+                            }
+                            ui.end_row();
+
+                            ui.label("Threshold:").on_hover_text("Warn when the balance of an account is projected to go below this amount, in the currency of the balance. Going below zero always warns.");
+                            ui.text_edit_singleline(&mut self.forecast_threshold_tentative);
+                            if self.is_valid_forecast_threshold() {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid threshold!");
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid threshold!");
+                            }
+                            ui.end_row();
+
+                            ui.label("");
+                            if self.is_valid_forecast_months() & self.is_valid_forecast_threshold()
+                                && ui.button("Generate!").on_hover_text("Projects the current balances with the planned fund movements and the recurring templates.").clicked() {
+                                    match self.database.cash_flow_forecast_report(
+                                        Local::now().date_naive(),
+                                        self.forecast_months_tentative.parse::<u32>().expect("Error parsing forecast months"),
+                                        self.forecast_threshold_tentative.parse::<f64>().expect("Error parsing forecast threshold"),
+                                    ) {
+                                        Ok((projection, warnings)) => {
+                                            self.forecast_projection_csv = projection;
+                                            self.forecast_warnings_csv = warnings;
+                                            self.forecast_csv_correct = true;
+                                        }
+                                        Err(e) => {
+                                            self.forecast_csv_correct = false;
+                                            self.throw_error(e);
+                                        }
+                                    }
+                                }
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    if !self.forecast_csv_correct {
+                        return;
+                    }
+
+                    let forecast_warnings_csv: String = self.forecast_warnings_csv.clone();
+                    let warning_lines: Vec<&str> = forecast_warnings_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+                    if warning_lines.is_empty() {
+                        ui.colored_label(Color32::from_rgb(110, 255, 110), "No account is projected to go below the threshold!");
+                    }
+                    for warning_line in warning_lines {
+                        let fields: Vec<&str> = warning_line.split(",").collect();
+                        if let [account_name, currency, first_date, lowest_balance, lowest_date, warning] = fields[..] {
+                            ui.colored_label(
+                                Color32::from_rgb(255, 0, 0),
+                                format!(
+                                    "{}: {} goes below on {}, down to {} {} on {}",
+                                    warning, account_name, first_date, lowest_balance, currency, lowest_date
+                                ),
+                            );
+                        }
+                    }
+                    ui.separator();
+
+                    let forecast_projection_csv: String = self.forecast_projection_csv.clone();
+                    let header_line: String = forecast_projection_csv.split("\n").collect::<Vec<&str>>()[0].to_string();
+                    let row_lines: Vec<&str> = forecast_projection_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+                    let column_count: usize = header_line.split(",").count();
+
+                    TableBuilder::new(ui)
+                        .columns(Column::auto().resizable(true), column_count)
+                        .striped(true)
+                        .cell_layout(Layout::right_to_left(Align::Center))
+                        .header(20.0, |mut header| {
+                            for column_name in header_line.split(",") {
+                                header.col(|ui| {
+                                    ui.strong(column_name).on_hover_text(column_name);
+                                });
+                            }
+                        })
+                        .body(|mut body| {
+                            for row_line in row_lines {
+                                body.row(30.0, |mut row_ui| {
+                                    for element in row_line.split(",") {
+                                        row_ui.col(|ui| {
+                                            // negative balances are shown in red
+                                            if element.starts_with("-") {
+                                                ui.colored_label(Color32::from_rgb(255, 0, 0), element);
+                                            } else {
+                                                ui.label(element);
+                                            }
+                                        });
+                                    }
+                                });
+                            }
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_forecast_window = false;
+                }
+            },
+        );
+    }
+}
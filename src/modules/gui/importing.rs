@@ -0,0 +1,427 @@
+use crate::modules::financial::*;
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::modules::importers::bank_csv::{AmountSign, CsvMapping};
+use crate::modules::importers::camt::{parse_camt, CamtStatement};
+use crate::modules::importers::mt940::{parse_mt940, Mt940Statement};
+use crate::modules::importers::ofx::parse_ofx;
+use crate::modules::importers::{ImportFormat, ImportedMovement};
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox};
+use egui_autocomplete::AutoCompleteTextEdit;
+use strum::IntoEnumIterator;
+
+impl AppState {
+    /// Fills the mapping fields with the selected saved mapping, once it is chosen
+    fn load_import_mapping(&mut self) {
+        if self.import_mapping_loaded_id == self.import_mapping_id {
+            return;
+        }
+        self.import_mapping_loaded_id = self.import_mapping_id;
+
+        match self.database.csv_mapping(self.import_mapping_id) {
+            Ok(mapping) => {
+                self.import_delimiter_tentative = mapping.delimiter.to_string();
+                self.import_skip_rows_tentative = mapping.skip_rows.to_string();
+                self.import_mapping = mapping;
+            }
+            Err(e) => {
+                self.import_mapping_id = -1;
+                self.import_mapping_loaded_id = -1;
+                self.throw_error(e);
+            }
+        }
+    }
+
+    /// Completes the mapping with the fields typed as text
+    fn import_mapping_from_fields(&self) -> Result<CsvMapping, Box<dyn std::error::Error>> {
+        let mut mapping: CsvMapping = self.import_mapping.clone();
+        // tabs cannot be typed in a single line
+        mapping.delimiter = match self.import_delimiter_tentative.as_str() {
+            "\\t" => '\t',
+            delimiter if delimiter.chars().count() == 1 => delimiter.chars().next().unwrap_or(','),
+            _ => return Err("The delimiter has to be a single character!".into()),
+        };
+        mapping.skip_rows = self
+            .import_skip_rows_tentative
+            .trim()
+            .parse::<usize>()
+            .map_err(|_e| "The rows to skip have to be a whole number!")?;
+
+        Ok(mapping)
+    }
+
+    fn save_import_mapping(&mut self) {
+        match self
+            .import_mapping_from_fields()
+            .and_then(|mapping| self.database.insert_csv_mapping(&mapping))
+            .and_then(|mapping_id| {
+                self.database.save()?;
+                Ok(mapping_id)
+            }) {
+            Ok(mapping_id) => {
+                self.import_mapping_id = mapping_id;
+                self.import_mapping_loaded_id = mapping_id;
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn delete_import_mapping(&mut self) {
+        match self.database.delete_csv_mapping(self.import_mapping_id) {
+            Ok(_) => match self.database.save() {
+                Ok(_) => {
+                    self.import_mapping_id = -1;
+                    self.import_mapping_loaded_id = -1;
+                }
+                Err(e) => {
+                    self.throw_error(e);
+                }
+            },
+            Err(e) => {
+                self.throw_polars_error(e);
+            }
+        }
+    }
+
+    fn link_import_iban(&mut self) {
+        match self
+            .database
+            .set_account_iban(self.import_account_id, &self.import_iban)
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {
+                self.import_iban = String::default();
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    /// Reads the file and stages its movements, returning the IDs of the drafts
+    fn stage_import(&mut self) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+        let content: Vec<u8> = std::fs::read(self.import_file_path.trim())?;
+        match self.import_format {
+            ImportFormat::BankCsv => {
+                let mapping: CsvMapping = self.import_mapping_from_fields()?;
+                let account: Account = self.database.account(self.import_account_id)?;
+                let movements: Vec<ImportedMovement> =
+                    mapping.parse(&content, account.currency())?;
+                self.database.stage_movements(
+                    self.import_account_id,
+                    &movements,
+                    &self.import_income_category,
+                    &self.import_expense_category,
+                )
+            }
+            ImportFormat::Ofx => {
+                let account: Account = self.database.account(self.import_account_id)?;
+                let movements: Vec<ImportedMovement> =
+                    parse_ofx(&String::from_utf8_lossy(&content), account.currency())?;
+                self.database.stage_movements(
+                    self.import_account_id,
+                    &movements,
+                    &self.import_income_category,
+                    &self.import_expense_category,
+                )
+            }
+            ImportFormat::Mt940 => {
+                // the balances of every statement are checked when parsing
+                let statements: Vec<Mt940Statement> =
+                    parse_mt940(&String::from_utf8_lossy(&content))?;
+                self.database.stage_mt940_statements(
+                    self.import_account_id,
+                    &statements,
+                    &self.import_income_category,
+                    &self.import_expense_category,
+                )
+            }
+            ImportFormat::Camt => {
+                let statements: Vec<CamtStatement> =
+                    parse_camt(&String::from_utf8_lossy(&content))?;
+                self.database.stage_camt_statements(
+                    &statements,
+                    &self.import_income_category,
+                    &self.import_expense_category,
+                )
+            }
+        }
+    }
+
+    fn stage_import_file(&mut self) {
+        match self.stage_import().and_then(|_| self.database.save()) {
+            Ok(_) => {
+                self.import_file_path = String::default();
+                self.show_staging_window = true;
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_import_window(&mut self, ctx: &egui::Context) {
+        if self.import_mapping_id >= 0 {
+            self.load_import_mapping();
+        }
+
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("import_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Import window")
+                .with_inner_size([WINDOW_WIDTH * 1.2, WINDOW_HEIGHT * 2.0]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    egui::Grid::new("import_format")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Format:").on_hover_text("Kind of file exported by the bank.");
+                            ComboBox::from_id_salt("Import format")
+                                .selected_text(format!("{}", self.import_format))
+                                .show_ui(ui, |ui| {
+                                    for possible_format in ImportFormat::iter() {
+                                        ui.selectable_value(
+                                            &mut self.import_format,
+                                            possible_format.clone(),
+                                            format!("{possible_format}"),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    if self.import_format == ImportFormat::BankCsv {
+                        let mapping_string: String = if self.import_mapping_id >= 0 {
+                            self.database
+                                .csv_mapping(self.import_mapping_id)
+                                .map(|mapping| mapping.name)
+                                .unwrap_or_default()
+                        } else {
+                            String::from("New mapping")
+                        };
+
+                        egui::Grid::new("import_mapping")
+                            .num_columns(3)
+                            .spacing([45.0, 4.0])
+                            .show(ui, |ui| {
+                                ui.label("Saved mapping:").on_hover_text("Layout of the CSV exports of a bank, saved to import its statements again.");
+                                ComboBox::from_id_salt("Import mapping")
+                                    .selected_text(mapping_string)
+                                    .show_ui(ui, |ui| {
+                                        match self.database.iter_csv_mapping_ids() {
+                                            Ok(iterator) => {
+                                                for mapping_id in iterator {
+                                                    ui.selectable_value(
+                                                        &mut self.import_mapping_id,
+                                                        mapping_id,
+                                                        self.database
+                                                            .csv_mapping(mapping_id)
+                                                            .map(|mapping| mapping.name)
+                                                            .unwrap_or_default(),
+                                                    );
+                                                }
+                                            }
+                                            Err(e) => {
+                                                self.throw_polars_error(e);
+                                            }
+                                        }
+                                    });
+                                if self.import_mapping_id >= 0
+                                    && ui.button("Delete mapping").clicked() {
+                                        self.delete_import_mapping();
+                                    }
+                                ui.end_row();
+
+                                ui.label("Bank:").on_hover_text("Name under which the mapping is saved.");
+                                ui.text_edit_singleline(&mut self.import_mapping.name);
+                                ui.end_row();
+
+                                ui.label("Delimiter:").on_hover_text("Character between the columns, \\t for tabs.");
+                                ui.text_edit_singleline(&mut self.import_delimiter_tentative);
+                                ui.end_row();
+
+                                ui.label("Rows to skip:").on_hover_text("Lines before the header, like the details of the account.");
+                                ui.text_edit_singleline(&mut self.import_skip_rows_tentative);
+                                ui.end_row();
+
+                                ui.label("Date column:").on_hover_text("Header of the column with the booking date.");
+                                ui.text_edit_singleline(&mut self.import_mapping.date_column);
+                                ui.end_row();
+
+                                ui.label("Date format:").on_hover_text("Like %d.%m.%Y for 31.12.2024 or %m/%d/%Y for 12/31/2024.");
+                                ui.text_edit_singleline(&mut self.import_mapping.date_format);
+                                ui.end_row();
+
+                                ui.label("Amount sign:").on_hover_text("Signed: negative amounts leave the account. Inverted: positive ones do, like in credit card exports. SplitColumns: money out and in have their own columns.");
+                                ComboBox::from_id_salt("Import amount sign")
+                                    .selected_text(format!("{}", self.import_mapping.amount_sign))
+                                    .show_ui(ui, |ui| {
+                                        for possible_sign in AmountSign::iter() {
+                                            ui.selectable_value(
+                                                &mut self.import_mapping.amount_sign,
+                                                possible_sign.clone(),
+                                                format!("{possible_sign}"),
+                                            );
+                                        }
+                                    });
+                                ui.end_row();
+
+                                if self.import_mapping.amount_sign == AmountSign::SplitColumns {
+                                    ui.label("Debit column:").on_hover_text("Header of the column with the money out.");
+                                    ui.text_edit_singleline(&mut self.import_mapping.amount_column);
+                                    ui.end_row();
+
+                                    ui.label("Credit column:").on_hover_text("Header of the column with the money in.");
+                                    ui.text_edit_singleline(&mut self.import_mapping.credit_column);
+                                    ui.end_row();
+                                } else {
+                                    ui.label("Amount column:").on_hover_text("Header of the column with the amount.");
+                                    ui.text_edit_singleline(&mut self.import_mapping.amount_column);
+                                    ui.end_row();
+                                }
+
+                                ui.label("Decimal separator:").on_hover_text("Thousands separators are ignored.");
+                                ComboBox::from_id_salt("Import decimal separator")
+                                    .selected_text(self.import_mapping.decimal_separator.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for possible_separator in ['.', ','] {
+                                            ui.selectable_value(
+                                                &mut self.import_mapping.decimal_separator,
+                                                possible_separator,
+                                                possible_separator.to_string(),
+                                            );
+                                        }
+                                    });
+                                ui.end_row();
+
+                                ui.label("Currency column:").on_hover_text("Leave empty if all the amounts are in the currency of the account.");
+                                ui.text_edit_singleline(&mut self.import_mapping.currency_column);
+                                ui.end_row();
+
+                                ui.label("Payee column:").on_hover_text("Matched against the names and aliases of the entities. Optional.");
+                                ui.text_edit_singleline(&mut self.import_mapping.payee_column);
+                                ui.end_row();
+
+                                ui.label("Description column:").on_hover_text("The payee is used if it is left empty.");
+                                ui.text_edit_singleline(&mut self.import_mapping.description_column);
+                                ui.end_row();
+
+                                ui.label("");
+                                if !self.import_mapping.name.trim().is_empty()
+                                    && ui.button("Save mapping").on_hover_text("Replaces the mapping saved under the same name.").clicked() {
+                                        self.save_import_mapping();
+                                    }
+                                ui.end_row();
+                            });
+                        ui.separator();
+                    }
+
+                    let account_string: String = if self.import_account_id >= 0 {
+                        self.database
+                            .account(self.import_account_id)
+                            .map(|account| account.to_string())
+                            .unwrap_or_default()
+                    } else {
+                        String::from("Select account")
+                    };
+
+                    egui::Grid::new("import_file")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("File:").on_hover_text("Path of the file exported by the bank.");
+                            ui.text_edit_singleline(&mut self.import_file_path);
+                            ui.end_row();
+
+                            let account_hover_text: &str = match self.import_format {
+                                ImportFormat::Camt => "Account to link an IBAN to. The statements are matched to the accounts by their IBAN.",
+                                _ => "Account of the statement.",
+                            };
+                            ui.label("Account:").on_hover_text(account_hover_text);
+                            ComboBox::from_id_salt("Import account")
+                                .selected_text(account_string)
+                                .show_ui(ui, |ui| {
+                                    match self.database.iter_open_account_ids() {
+                                        Ok(iterator) => {
+                                            for account_id in iterator {
+                                                ui.selectable_value(
+                                                    &mut self.import_account_id,
+                                                    account_id,
+                                                    self.database
+                                                        .account(account_id)
+                                                        .unwrap() // safe because we iterate over the ids!
+                                                        .to_string(),
+                                                );
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            ui.end_row();
+
+                            if self.import_format == ImportFormat::Camt {
+                                ui.label("IBAN:").on_hover_text("IBAN of the selected account, as the bank gives it in its statements.");
+                                ui.text_edit_singleline(&mut self.import_iban);
+                                if (!self.import_iban.trim().is_empty()) & (self.import_account_id >= 0)
+                                    && ui.button("Link").clicked() {
+                                        self.link_import_iban();
+                                    }
+                                ui.end_row();
+                            }
+
+                            for (transaction_type, category) in [
+                                (TransactionType::Income, &mut self.import_income_category),
+                                (TransactionType::Expense, &mut self.import_expense_category),
+                            ] {
+                                ui.label(format!("{transaction_type} category:")).on_hover_text("Category of the imported movements, to be refined in the staging inbox.");
+                                match self.database.transaction_categories(&transaction_type) {
+                                    Ok(categories) => {
+                                        ui.add(
+                                            AutoCompleteTextEdit::new(category, categories)
+                                                .max_suggestions(10)
+                                                .highlight_matches(true),
+                                        );
+                                    }
+                                    Err(e) => {
+                                        self.error_message = e.to_string();
+                                        self.show_error_window = true;
+                                    }
+                                }
+                                match self.database.is_known_category(&transaction_type, category, "") {
+                                    Ok(true) => {
+                                        ui.colored_label(Color32::from_rgb(110, 255, 110), "Known category!");
+                                    }
+                                    _ => {
+                                        ui.colored_label(Color32::from_rgb(255, 0, 0), "Unknown category!");
+                                    }
+                                }
+                                ui.end_row();
+                            }
+
+                            ui.label("");
+                            let has_account: bool = (self.import_account_id >= 0) | (self.import_format == ImportFormat::Camt);
+                            if (!self.import_file_path.trim().is_empty()) & has_account
+                                && ui.button("Stage").on_hover_text("Reads the file and puts its movements in the staging inbox, to review them before they are committed.").clicked() {
+                                    self.stage_import_file();
+                                }
+                            ui.end_row();
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_import_window = false;
+                }
+            },
+        );
+    }
+}
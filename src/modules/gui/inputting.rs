@@ -1,5 +1,11 @@
+use crate::modules::categorization::classifier::{
+    CategoryClassifier, CategoryFeatures, CategorySuggestion,
+};
+use crate::modules::categorization::rules::{CompiledRules, RuleProposal};
+use crate::modules::categorization::CategorizationSubject;
 use crate::modules::financial::*;
 use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use chrono::Local;
 use eframe::egui;
 use eframe::egui::{Color32, ComboBox};
 use egui::{containers, Align, Layout, PopupCloseBehavior};
@@ -9,7 +15,11 @@ use strum::IntoEnumIterator;
 
 impl AppState {
     fn clear_fields(&mut self) -> () {
+        // the due parties are only generated once, on startup
+        let due_parties = std::mem::take(&mut self.due_parties);
         *self = AppState::default();
+        self.due_parties = due_parties;
+        self.due_parties_checked = true;
     }
 
     fn clear_transaction_fields(&mut self) -> () {
@@ -22,6 +32,19 @@ impl AppState {
         self.transaction_account_string = String::default();
         self.transaction_type = TransactionType::default();
         self.transaction_filter = String::default();
+        self.transaction_has_value_date = bool::default();
+        self.transaction_value_date = Local::now().date_naive();
+        self.transaction_amortization_months_tentative = String::from("1");
+        self.transaction_tag = String::default();
+        self.transaction_tags = Vec::new();
+        self.transaction_share_entity_id = -1;
+        self.transaction_share_tentative = String::default();
+        self.transaction_shares = Vec::new();
+        self.transaction_reimbursable = bool::default();
+        self.transaction_refund_of = -1;
+        // learns again from the ledger, with the transactions added since
+        self.transaction_classifier = None;
+        self.transaction_rules = None;
     }
 
     fn clear_entity_fields(&mut self) -> () {
@@ -38,6 +61,28 @@ impl AppState {
         self.account_type = AccountType::default();
         self.account_initial_balance = f64::default();
         self.account_initial_balance_tentative = String::default();
+        self.account_pockets = Vec::new();
+    }
+
+    fn clear_template_fields(&mut self) {
+        self.template_name = String::default();
+        self.template_recurrence = Recurrence::default();
+        self.template_recurrence_step_tentative = String::default();
+        self.template_start_date = Local::now().date_naive();
+    }
+
+    fn is_valid_template_recurrence_step(&self) -> bool {
+        let parsing_result = self.template_recurrence_step_tentative.parse::<u32>();
+        match (parsing_result, &self.template_recurrence) {
+            (Ok(step), Recurrence::Monthly) => (1..=31).contains(&step),
+            (Ok(step), Recurrence::Weekly) => step >= 1,
+            (_, Recurrence::Manual | Recurrence::Yearly) => true,
+            (Err(_e), _) => false,
+        }
+    }
+
+    fn are_valid_template_fields(&self) -> bool {
+        (!self.template_name.is_empty()) & self.is_valid_template_recurrence_step()
     }
 
     fn are_valid_entity_fields(&self) -> bool {
@@ -68,7 +113,7 @@ impl AppState {
 
     fn is_valid_transaction_currency(&mut self) -> bool {
         match self.database.account(self.transaction_account_id) {
-            Ok(account) => &self.transaction_currency == account.currency(),
+            Ok(account) => account.has_currency(&self.transaction_currency),
             Err(e) => {
                 self.throw_error(e);
                 false
@@ -76,10 +121,196 @@ impl AppState {
         }
     }
 
+    fn is_valid_transaction_amortization_months(&self) -> bool {
+        let parsing_result = self
+            .transaction_amortization_months_tentative
+            .parse::<u32>();
+        match parsing_result {
+            Ok(months) => months > 0,
+            Err(_e) => false,
+        }
+    }
+
+    fn is_valid_transaction_tag(&self) -> bool {
+        // commas and semicolons separate the tags when they are listed
+        let tag: &str = self.transaction_tag.trim();
+        (!tag.is_empty())
+            & !tag.contains(",")
+            & !tag.contains(";")
+            & !self.transaction_tags.contains(&tag.to_string())
+    }
+
+    fn is_valid_transaction_share(&self) -> bool {
+        let parsing_result = self.transaction_share_tentative.parse::<f64>();
+        match parsing_result {
+            Ok(value) => {
+                (value > 0.0)
+                    & (self.transaction_share_entity_id >= 0)
+                    & !self
+                        .transaction_shares
+                        .iter()
+                        .any(|(entity_id, _)| *entity_id == self.transaction_share_entity_id)
+            }
+            Err(_e) => false,
+        }
+    }
+
+    fn are_valid_transaction_shares(&self) -> bool {
+        // our own share of the expense cannot be empty
+        let shares: f64 = self.transaction_shares.iter().map(|(_, value)| value).sum();
+        match self.transaction_value_tentative.parse::<f64>() {
+            Ok(value) => shares < value,
+            Err(_e) => false,
+        }
+    }
+
+    fn is_valid_transaction_refund(&mut self) -> bool {
+        if self.transaction_refund_of < 0 {
+            return false;
+        }
+        // no more than what is left to give back of the expense
+        match (
+            self.transaction_value_tentative.parse::<f64>(),
+            self.database.refundable_value(self.transaction_refund_of),
+        ) {
+            (Ok(value), Ok(refundable_value)) => {
+                (value > 0.0) & (value <= refundable_value + 0.005)
+            }
+            (_, Err(e)) => {
+                self.throw_polars_error(e);
+                false
+            }
+            (Err(_e), _) => false,
+        }
+    }
+
+    /// Checks that the category of the income or expense is in the registry, so that typos
+    /// do not create new categories unnoticed.
+    fn is_known_transaction_category(&mut self) -> bool {
+        if self.transaction_category.is_empty() {
+            return false;
+        }
+        match self.database.is_known_category(
+            &self.transaction_type,
+            &self.transaction_category,
+            &self.transaction_subcategory,
+        ) {
+            Ok(is_known) => is_known,
+            Err(e) => {
+                self.throw_polars_error(e);
+                false
+            }
+        }
+    }
+
+    /// Returns the rule proposal for the income or expense being entered, if any rule
+    /// matches it. The account is the one of the fund movements already in the party.
+    fn transaction_rule_proposal(&mut self) -> Option<RuleProposal> {
+        let entity_name: String = match self.database.entity(self.transaction_entity_id) {
+            Ok(entity) => entity.name(),
+            Err(_e) => String::default(),
+        };
+        let account_id: i64 =
+            self.party
+                .transactions
+                .iter()
+                .find_map(|transaction| match transaction {
+                    Transaction::Credit { account_id, .. }
+                    | Transaction::Debit { account_id, .. } => Some(*account_id),
+                    _ => None,
+                })
+                .unwrap_or(-1);
+        let subject = CategorizationSubject {
+            transaction_type: self.transaction_type.clone(),
+            text: format!("{} {}", self.transaction_description, entity_name),
+            value: self
+                .transaction_value_tentative
+                .parse::<f64>()
+                .unwrap_or(0.0),
+            currency: self.transaction_currency.clone(),
+            account_id,
+        };
+        if self.transaction_rules.is_none() {
+            let rules: CompiledRules = match self.database.compiled_categorization_rules() {
+                Ok(rules) => rules,
+                Err(e) => {
+                    self.throw_error(e);
+                    // no rules propose nothing, and keep the error from being thrown again on
+                    // every frame
+                    CompiledRules::default()
+                }
+            };
+            self.transaction_rules = Some(rules);
+        }
+
+        self.transaction_rules
+            .as_ref()
+            .and_then(|rules| rules.propose(&subject))
+    }
+
+    /// Fills the transaction fields with what the rule proposes, keeping the tags already
+    /// added.
+    fn apply_transaction_rule_proposal(&mut self, proposal: RuleProposal) {
+        if proposal.entity_id >= 0 {
+            self.transaction_entity_id = proposal.entity_id;
+        }
+        if !proposal.category.is_empty() {
+            self.transaction_category = proposal.category;
+            self.transaction_subcategory = proposal.subcategory;
+        }
+        for tag in proposal.tags {
+            if !self.transaction_tags.contains(&tag) {
+                self.transaction_tags.push(tag);
+            }
+        }
+    }
+
+    /// Returns the categories that the income or expense being entered likely belongs to,
+    /// learned from the ones in the ledger.
+    fn transaction_category_suggestions(&mut self) -> Vec<CategorySuggestion> {
+        let is_learned: bool = self
+            .transaction_classifier
+            .as_ref()
+            .is_some_and(|(transaction_type, _)| *transaction_type == self.transaction_type);
+        if !is_learned {
+            let classifier: CategoryClassifier =
+                match self.database.category_classifier(&self.transaction_type) {
+                    Ok(classifier) => classifier,
+                    Err(e) => {
+                        self.throw_error(e);
+                        // an empty classifier suggests nothing, and keeps the error from
+                        // being thrown again on every frame
+                        CategoryClassifier::default()
+                    }
+                };
+            self.transaction_classifier = Some((self.transaction_type.clone(), classifier));
+        }
+
+        let features = CategoryFeatures {
+            entity_id: self.transaction_entity_id,
+            description: self.transaction_description.clone(),
+            value: self
+                .transaction_value_tentative
+                .parse::<f64>()
+                .unwrap_or(0.0),
+            date: self.transaction_date,
+        };
+        match &self.transaction_classifier {
+            Some((_, classifier)) => classifier.suggest(&features, 3),
+            None => Vec::new(),
+        }
+    }
+
     fn are_valid_transaction_fields(&mut self) -> bool {
-        ((self.transaction_category.len() > 0)
+        if self.transaction_type == TransactionType::Refund {
+            return self.is_valid_transaction_refund();
+        }
+        (self.is_known_transaction_category()
             | (self.transaction_type.is_fund_change() & self.is_valid_transaction_currency()))
             & self.is_valid_transaction_value()
+            & ((self.transaction_type != TransactionType::Expense)
+                | (self.is_valid_transaction_amortization_months()
+                    & self.are_valid_transaction_shares()))
     }
 
     pub fn handle_show_input_entity_window(&mut self, ctx: &egui::Context) -> () {
@@ -272,7 +503,7 @@ impl AppState {
                             }
                             ui.end_row();
 
-                            ui.label("Account currency: ").on_hover_text("Currency of the account. If multiple, consider creating various accounts with different currencies.");
+                            ui.label("Account currency: ").on_hover_text("Main currency of the account, the one of the initial balance.");
                             ComboBox::from_id_salt("Account currency")
                                 .selected_text(format!("{}", self.account_currency))
                                 .show_ui(ui, |ui| {
@@ -286,6 +517,24 @@ impl AppState {
                                 });
                             ui.end_row();
 
+                            ui.label("Other currencies: ").on_hover_text("Pockets in other currencies held by the account, like in a Wise or Revolut wallet. Their balances start at zero.");
+                            ui.horizontal(|ui| {
+                                for possible_pocket in Currency::iter() {
+                                    if possible_pocket == self.account_currency {
+                                        continue;
+                                    }
+                                    let mut is_pocket: bool = self.account_pockets.contains(&possible_pocket);
+                                    if ui.checkbox(&mut is_pocket, possible_pocket.to_string()).changed() {
+                                        if is_pocket {
+                                            self.account_pockets.push(possible_pocket);
+                                        } else {
+                                            self.account_pockets.retain(|pocket| pocket != &possible_pocket);
+                                        }
+                                    }
+                                }
+                            });
+                            ui.end_row();
+
                             ui.label("Account type: ").on_hover_text("Category of the account.");
                             ComboBox::from_id_salt("Account type")
                                 .selected_text(format!("{}", self.account_type))
@@ -331,6 +580,12 @@ impl AppState {
                                     self.account_currency.clone(),
                                     self.account_type.clone(),
                                     self.account_initial_balance,
+                                    // the main currency might have been ticked before being chosen
+                                    self.account_pockets
+                                        .iter()
+                                        .filter(|pocket| pocket != &&self.account_currency)
+                                        .map(|pocket| pocket.clone())
+                                        .collect(),
                                 );
 
                                 match self.database.insert_account(&account) {
@@ -354,6 +609,139 @@ impl AppState {
             },
         );
     }
+    pub fn handle_show_input_template_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("input_template_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Input template window")
+                .with_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    egui::Grid::new("my_grid")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Template name:")
+                                .on_hover_text("Name of the template. For instance, \"Rent\".");
+                            ui.text_edit_singleline(&mut self.template_name);
+                            if !self.template_name.is_empty() {
+                                ui.colored_label(
+                                    Color32::from_rgb(110, 255, 110),
+                                    "Valid template name!",
+                                );
+                            } else {
+                                ui.colored_label(
+                                    Color32::from_rgb(255, 0, 0),
+                                    "Please enter a template name!",
+                                );
+                            }
+                            ui.end_row();
+
+                            ui.label("Recurrence:").on_hover_text(
+                                "How often the party is due. Manual templates are never due, they can only be loaded by hand.",
+                            );
+                            ComboBox::from_id_salt("Template recurrence")
+                                .selected_text(format!("{}", self.template_recurrence))
+                                .show_ui(ui, |ui| {
+                                    for possible_recurrence in Recurrence::iter() {
+                                        ui.selectable_value(
+                                            &mut self.template_recurrence,
+                                            possible_recurrence.clone(),
+                                            format!("{possible_recurrence}"),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            match self.template_recurrence {
+                                Recurrence::Monthly | Recurrence::Weekly => {
+                                    if self.template_recurrence == Recurrence::Monthly {
+                                        ui.label("Day of the month:").on_hover_text(
+                                            "Day of the month on which the party is due. On shorter months, the last day is taken.",
+                                        );
+                                    } else {
+                                        ui.label("Number of weeks:").on_hover_text(
+                                            "Number of weeks between two occurrences, counting from the start date.",
+                                        );
+                                    }
+                                    ui.text_edit_singleline(
+                                        &mut self.template_recurrence_step_tentative,
+                                    );
+                                    if self.is_valid_template_recurrence_step() {
+                                        ui.colored_label(
+                                            Color32::from_rgb(110, 255, 110),
+                                            "Valid recurrence!",
+                                        );
+                                    } else {
+                                        ui.colored_label(
+                                            Color32::from_rgb(255, 0, 0),
+                                            "Invalid recurrence!",
+                                        );
+                                    }
+                                    ui.end_row();
+                                }
+                                Recurrence::Manual | Recurrence::Yearly => {}
+                            }
+
+                            ui.label("Start date:")
+                                .on_hover_text("Date of the first occurrence of the template.");
+                            ui.add(
+                                DatePickerButton::new(&mut self.template_start_date)
+                                    .id_salt("template_start_date"),
+                            );
+                            ui.end_row();
+                        });
+
+                    ui.separator();
+                    ui.vertical_centered_justified(|ui| {
+                        if self.are_valid_template_fields()
+                            && ui
+                                .button("Save template")
+                                .on_hover_text("Save the template, with the transactions of the current party, into the database.")
+                                .clicked()
+                        {
+                            let template: Template = Template::new(
+                                self.template_name.clone(),
+                                self.template_recurrence.clone(),
+                                self.template_recurrence_step_tentative
+                                    .parse::<u32>()
+                                    .unwrap_or(0),
+                                self.template_start_date,
+                                self.party.transactions.clone(),
+                            );
+
+                            match self.database.insert_template(&template) {
+                                Ok(template_id) => {
+                                    self.template_id = template_id;
+                                    match self.database.save() {
+                                        Ok(_) => {
+                                            self.clear_template_fields();
+                                            self.show_input_template_window = false;
+                                        }
+                                        Err(e) => {
+                                            self.throw_error(e);
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    self.throw_error(e);
+                                }
+                            }
+                        }
+                    });
+                });
+
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_input_template_window = false;
+                }
+            },
+        );
+    }
     pub fn handle_show_input_party_window(&mut self, ctx: &egui::Context) -> () {
         ctx.show_viewport_immediate(
             egui::ViewportId::from_hash_of("input_party_window"),
@@ -367,10 +755,24 @@ impl AppState {
                 );
 
                 egui::CentralPanel::default().show(ctx, |ui| {
+                    if self.template_id >= 0 {
+                        match self.database.template(self.template_id) {
+                            Ok(template) => {
+                                self.template_string = template.name();
+                            }
+                            Err(e) => {
+                                self.template_id = -1;
+                                self.throw_error(e);
+                            }
+                        }
+                    } else {
+                        self.template_string = String::from("No template");
+                    }
+
                     StripBuilder::new(ui)
-                        .size(Size::exact(40.0))
+                        .size(Size::exact(70.0))
                         .size(Size::remainder().at_least(100.0))
-                        .size(Size::exact(40.0))
+                        .size(Size::exact(85.0))
                         .vertical(|mut strip| {
                             strip.cell(|ui| {
                                 ui.vertical_centered_justified(|ui| {
@@ -379,6 +781,79 @@ impl AppState {
                                             self.show_input_party_window & true;
                                     }
                                 });
+                                ui.horizontal(|ui| {
+                                    ComboBox::from_id_salt("Party template")
+                                        .selected_text(self.template_string.to_string())
+                                        .show_ui(ui, |ui| {
+                                            match self.database.iter_template_ids() {
+                                                Ok(iterator) => {
+                                                    for template_id in iterator {
+                                                        ui.selectable_value(
+                                                            &mut self.template_id,
+                                                            template_id,
+                                                            self.database
+                                                                .template(template_id)
+                                                                .unwrap() // safe because we iterate
+                                                                          // over the ids!
+                                                                .name(),
+                                                        );
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    self.throw_polars_error(e);
+                                                }
+                                            }
+                                        });
+                                    if self.template_id >= 0 {
+                                        if ui
+                                            .button("Load template")
+                                            .on_hover_text("Adds the transactions of the template to the party, dated today.")
+                                            .clicked()
+                                        {
+                                            match self.database.template(self.template_id) {
+                                                Ok(template) => {
+                                                    for transaction in template
+                                                        .party(Local::now().date_naive())
+                                                        .transactions
+                                                    {
+                                                        self.party.add_transaction(transaction);
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    self.throw_error(e);
+                                                }
+                                            }
+                                        }
+                                        if ui
+                                            .button("Delete template")
+                                            .on_hover_text("Deletes the template. Parties already entered with it are kept.")
+                                            .clicked()
+                                        {
+                                            match self.database.delete_template(self.template_id) {
+                                                Ok(_) => {
+                                                    self.template_id = -1;
+                                                    match self.database.save() {
+                                                        Ok(_) => {},
+                                                        Err(e) => {
+                                                            self.throw_error(e);
+                                                        }
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    self.throw_polars_error(e);
+                                                }
+                                            }
+                                        }
+                                    }
+                                    if self.party.is_valid()
+                                        && ui
+                                            .button("Save as template")
+                                            .on_hover_text("Saves the party to be entered again, by hand or following a recurrence rule.")
+                                            .clicked()
+                                    {
+                                        self.show_input_template_window = true;
+                                    }
+                                });
                                 ui.separator();
                             });
                             strip.cell(|ui| {
@@ -450,6 +925,42 @@ impl AppState {
                                                 });
                                             });
                                         }
+                                        let mut i_remove_iou: Option<usize> = None;
+                                        for (i, iou) in self.party.ious.iter().enumerate() {
+                                            let name: String = match self.database.entity(iou.entity_id()) {
+                                                Ok(entity) => entity.name(),
+                                                Err(_e) => iou.entity_id().to_string(),
+                                            };
+                                            body.row(30.0, |mut row| {
+                                                row.col(|ui| {
+                                                    ui.label(format!("IOU ({})", name));
+                                                });
+                                                row.col(|ui| {
+                                                    ui.label(format!("{:.2}", iou.value()));
+                                                });
+                                                row.col(|ui| {
+                                                    ui.label(iou.currency().to_string());
+                                                });
+                                                row.col(|ui| {
+                                                    ui.label(iou.date().to_string());
+                                                });
+                                                row.col(|ui| {
+                                                    if ui
+                                                        .button("Remove")
+                                                        .on_hover_text(
+                                                            "Removes the debt from the party",
+                                                        )
+                                                        .clicked()
+                                                    {
+                                                        i_remove_iou = Some(i);
+                                                    }
+                                                });
+                                            });
+                                        }
+                                        if let Some(i) = i_remove_iou {
+                                            self.party.ious.remove(i);
+                                        }
+
                                         if remove {
                                             let removed_transaction: Transaction =
                                                 self.party.remove(i_remove);
@@ -465,6 +976,7 @@ impl AppState {
                                                     subcategory,
                                                     description,
                                                     entity_id,
+                                                    tags,
                                                 } => {
                                                     self.transaction_type = TransactionType::Income;
                                                     self.transaction_value = value;
@@ -476,6 +988,7 @@ impl AppState {
                                                     self.transaction_subcategory = subcategory;
                                                     self.transaction_description = description;
                                                     self.transaction_entity_id = entity_id;
+                                                    self.transaction_tags = tags;
                                                 }
                                                 Transaction::Expense {
                                                     value,
@@ -485,6 +998,40 @@ impl AppState {
                                                     subcategory,
                                                     description,
                                                     entity_id,
+                                                    amortization_months,
+                                                    tags,
+                                                    refund_of,
+                                                    reimbursable,
+                                                } if refund_of >= 0 => {
+                                                    self.transaction_type =
+                                                        TransactionType::Refund;
+                                                    self.transaction_value = -value;
+                                                    self.transaction_value_tentative =
+                                                        (-value).to_string();
+                                                    self.transaction_currency = currency;
+                                                    self.transaction_date = date;
+                                                    self.transaction_category = category;
+                                                    self.transaction_subcategory = subcategory;
+                                                    self.transaction_description = description;
+                                                    self.transaction_entity_id = entity_id;
+                                                    self.transaction_amortization_months_tentative =
+                                                        amortization_months.to_string();
+                                                    self.transaction_tags = tags;
+                                                    self.transaction_refund_of = refund_of;
+                                                    self.transaction_reimbursable = reimbursable;
+                                                }
+                                                Transaction::Expense {
+                                                    value,
+                                                    currency,
+                                                    date,
+                                                    category,
+                                                    subcategory,
+                                                    description,
+                                                    entity_id,
+                                                    amortization_months,
+                                                    tags,
+                                                    reimbursable,
+                                                    ..
                                                 } => {
                                                     self.transaction_type =
                                                         TransactionType::Expense;
@@ -497,12 +1044,17 @@ impl AppState {
                                                     self.transaction_subcategory = subcategory;
                                                     self.transaction_description = description;
                                                     self.transaction_entity_id = entity_id;
+                                                    self.transaction_amortization_months_tentative =
+                                                        amortization_months.to_string();
+                                                    self.transaction_tags = tags;
+                                                    self.transaction_reimbursable = reimbursable;
                                                 }
                                                 Transaction::Credit {
                                                     value,
                                                     currency,
                                                     date,
                                                     account_id,
+                                                    value_date,
                                                 } => {
                                                     self.transaction_type = TransactionType::Credit;
                                                     self.transaction_value = value;
@@ -511,12 +1063,17 @@ impl AppState {
                                                     self.transaction_currency = currency;
                                                     self.transaction_date = date;
                                                     self.transaction_account_id = account_id;
+                                                    self.transaction_has_value_date =
+                                                        value_date.is_some();
+                                                    self.transaction_value_date =
+                                                        value_date.unwrap_or(date);
                                                 }
                                                 Transaction::Debit {
                                                     value,
                                                     currency,
                                                     date,
                                                     account_id,
+                                                    value_date,
                                                 } => {
                                                     self.transaction_type = TransactionType::Debit;
                                                     self.transaction_value = value;
@@ -525,6 +1082,10 @@ impl AppState {
                                                     self.transaction_currency = currency;
                                                     self.transaction_date = date;
                                                     self.transaction_account_id = account_id;
+                                                    self.transaction_has_value_date =
+                                                        value_date.is_some();
+                                                    self.transaction_value_date =
+                                                        value_date.unwrap_or(date);
                                                 }
                                             }
 
@@ -534,13 +1095,105 @@ impl AppState {
                             });
                             strip.cell(|ui| {
                                 ui.separator();
+                                ui.horizontal(|ui| {
+                                    ui.label("Status:").on_hover_text("Planned: known future transactions. Pending: not yet posted by the bank. Cleared: posted by the bank. Void: cancelled, never counted.");
+                                    ComboBox::from_id_salt("Party status")
+                                        .selected_text(format!("{}", self.party.status))
+                                        .show_ui(ui, |ui| {
+                                            for possible_status in TransactionStatus::iter() {
+                                                ui.selectable_value(
+                                                    &mut self.party.status,
+                                                    possible_status.clone(),
+                                                    format!("{possible_status}"),
+                                                );
+                                            }
+                                        });
+
+                                    let project_string: String = if self.party.project_id >= 0 {
+                                        match self.database.project(self.party.project_id) {
+                                            Ok(project) => project.to_string(),
+                                            Err(e) => {
+                                                self.party.project_id = -1;
+                                                self.throw_error(e);
+                                                String::from("No project")
+                                            }
+                                        }
+                                    } else {
+                                        String::from("No project")
+                                    };
+                                    ui.label("Project:").on_hover_text("Trip, event or project the party is part of.");
+                                    ComboBox::from_id_salt("Party project")
+                                        .selected_text(project_string)
+                                        .show_ui(ui, |ui| {
+                                            ui.selectable_value(
+                                                &mut self.party.project_id,
+                                                -1,
+                                                String::from("No project"),
+                                            );
+                                            match self.database.iter_project_ids() {
+                                                Ok(iterator) => {
+                                                    for project_id in iterator {
+                                                        match self.database.project(project_id) {
+                                                            Ok(project) => {
+                                                                ui.selectable_value(
+                                                                    &mut self.party.project_id,
+                                                                    project_id,
+                                                                    project.to_string(),
+                                                                );
+                                                            }
+                                                            Err(e) => {
+                                                                self.throw_error(e);
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    self.throw_polars_error(e);
+                                                }
+                                            }
+                                        });
+                                });
                                 ui.vertical_centered_justified(|ui| {
                                     if self.party.is_valid() {
+                                        match self.database.find_duplicates(&self.party) {
+                                            Ok(duplicates) => {
+                                                if !duplicates.is_empty() {
+                                                    ui.colored_label(
+                                                        Color32::from_rgb(255, 0, 0),
+                                                        format!("Possible duplicate of {} parties!", duplicates.len()),
+                                                    )
+                                                    .on_hover_text(
+                                                        duplicates
+                                                            .iter()
+                                                            .map(|duplicate| duplicate.to_string())
+                                                            .collect::<Vec<String>>()
+                                                            .join("\n"),
+                                                    );
+                                                }
+                                            }
+                                            Err(e) => {
+                                                self.throw_error(e);
+                                            }
+                                        }
                                         if ui.button("Add party").clicked() {
                                             match self.database.insert_party(&mut self.party) {
                                                 Ok(_) => {
-                                                    match self.database.save() { 
+                                                    // a party edited from a due template handles its occurrence
+                                                    let result: Result<(), Box<dyn std::error::Error>> =
+                                                        match self.pending_due_party {
+                                                            Some((template_id, date)) => self
+                                                                .database
+                                                                .handle_due_party(template_id, date),
+                                                            None => Ok(()),
+                                                        };
+                                                    match result.and_then(|_| self.database.save()) { 
                                                         Ok(_) => {
+                                                            if let Some((template_id, date)) = self.pending_due_party {
+                                                                self.due_parties.retain(|due_party| {
+                                                                    (due_party.template_id != template_id)
+                                                                        | (due_party.date != date)
+                                                                });
+                                                            }
                                                             self.clear_fields();
                                                             self.show_input_party_window = false;
                                                         },
@@ -654,11 +1307,11 @@ impl AppState {
                                 ComboBox::from_id_salt("Transaction account")
                                     .selected_text(format!("{}", self.transaction_account_string))
                                     .show_ui(ui, |ui| {
-                                        match self.database.iter_account_ids() {
+                                        match self.database.iter_open_account_ids() {
                                             Ok(iterator) => {
                                         for account_id in iterator {
-                                            if self.database.account(account_id).unwrap().currency()
-                                                == &self.transaction_currency
+                                            if self.database.account(account_id).unwrap()
+                                                .has_currency(&self.transaction_currency)
                                             {
                                                 ui.selectable_value(
                                                     &mut self.transaction_account_id,
@@ -678,7 +1331,16 @@ impl AppState {
                                 }
                                 ui.end_row();
 
-                                ui.label("");
+                                ui.label("Value date:")
+                                    .on_hover_text("Date in which the movement settled in the account, if different from the transaction date. For instance, card payments are often settled some days later.");
+                                ui.horizontal(|ui| {
+                                    ui.checkbox(&mut self.transaction_has_value_date, "");
+                                    ui.add_enabled(
+                                        self.transaction_has_value_date,
+                                        DatePickerButton::new(&mut self.transaction_value_date)
+                                            .id_salt("transaction_value_date"),
+                                    );
+                                });
                                 ui.end_row();
 
                                 ui.label("");
@@ -686,6 +1348,50 @@ impl AppState {
 
                                 ui.label("");
                                 ui.end_row();
+                            } else if self.transaction_type == TransactionType::Refund {
+                                ui.label("Refunded expense:")
+                                    .on_hover_text("Expense that is given back, like a returned purchase or a reimbursed business trip. The refund nets against its category.");
+                                let mut refund_of_string: String = String::from("No expense");
+                                match self.database.refundable_expenses(50) {
+                                    Ok(refundable_expenses) => {
+                                        for (expense_id, label) in refundable_expenses.iter() {
+                                            if *expense_id == self.transaction_refund_of {
+                                                refund_of_string = label.clone();
+                                            }
+                                        }
+                                        ComboBox::from_id_salt("Transaction refund of")
+                                            .selected_text(refund_of_string)
+                                            .show_ui(ui, |ui| {
+                                                for (expense_id, label) in refundable_expenses {
+                                                    ui.selectable_value(
+                                                        &mut self.transaction_refund_of,
+                                                        expense_id,
+                                                        label,
+                                                    );
+                                                }
+                                            });
+                                    }
+                                    Err(e) => {
+                                        self.throw_polars_error(e);
+                                    }
+                                }
+                                if self.is_valid_transaction_refund() {
+                                    ui.colored_label(
+                                        Color32::from_rgb(110, 255, 110),
+                                        "Valid refund!",
+                                    );
+                                } else {
+                                    ui.colored_label(
+                                        Color32::from_rgb(255, 0, 0),
+                                        "Please choose an expense, and give back no more than is left!",
+                                    );
+                                }
+                                ui.end_row();
+
+                                ui.label("Transaction description:")
+                                    .on_hover_text("Text description of the refund.");
+                                ui.text_edit_singleline(&mut self.transaction_description);
+                                ui.end_row();
                             } else {
                                 // it is not fund change
                                 ui.label("Transaction entity:")
@@ -695,7 +1401,7 @@ impl AppState {
                                     .close_behavior(PopupCloseBehavior::CloseOnClick)
                                     .show_ui(ui, |ui| {
                                         ui.text_edit_singleline(&mut self.transaction_filter).request_focus();
-                                        match self.database.iter_entity_ids() {
+                                        match self.database.iter_active_entity_ids() {
                                             Ok(iterator) => {
                                         for entity_id in iterator {
                                             let entity_string =
@@ -718,18 +1424,33 @@ impl AppState {
                                 ui.end_row();
 
                                 ui.label("Transaction category:")
-                                    .on_hover_text("Category of the transaction.");
+                                    .on_hover_text("Category of the transaction. The likeliest ones, learned from the entity, description, value and weekday of the transactions already entered, come first with how likely they are.");
+                                let suggestions: Vec<CategorySuggestion> = self.transaction_category_suggestions();
                                 match self.database.transaction_categories(&self.transaction_type) {
                                     Ok(transaction_categories) => {
+                                let mut search: Vec<String> = suggestions
+                                    .iter()
+                                    .map(|suggestion| suggestion.to_string())
+                                    .collect();
+                                search.extend(transaction_categories.into_iter().filter(|category| {
+                                    !suggestions.iter().any(|suggestion| &suggestion.category == category)
+                                }));
                                 ui.add(
                                     AutoCompleteTextEdit::new(
                                         &mut self.transaction_category,
-                                    
-                                            transaction_categories,
+                                        search,
                                     )
                                     .max_suggestions(10)
-                                    .highlight_matches(true),
-                                );}, Err(e) => {self.throw_polars_error(e);}}
+                                    .highlight_matches(true)
+                                    .popup_on_focus(true),
+                                );
+                                // a picked suggestion leaves only its category
+                                if let Some(suggestion) = suggestions
+                                    .iter()
+                                    .find(|suggestion| suggestion.to_string() == self.transaction_category)
+                                {
+                                    self.transaction_category = suggestion.category.clone();
+                                }}, Err(e) => {self.throw_polars_error(e);}}
                                 if self.transaction_category.len() > 0 {
                                     ui.colored_label(
                                         Color32::from_rgb(110, 255, 110),
@@ -762,10 +1483,208 @@ impl AppState {
                                 );}, Err(e) => {self.throw_polars_error(e);}}
                                 ui.end_row();
 
+                                ui.label("");
+                                if !self.transaction_category.is_empty() {
+                                    if self.is_known_transaction_category() {
+                                        ui.colored_label(
+                                            Color32::from_rgb(110, 255, 110),
+                                            "Known category!",
+                                        );
+                                    } else if ui.button("Create category").on_hover_text("Adds the category and subcategory to the registry, to use them from now on.").clicked() {
+                                        match self.database.register_category(
+                                            &self.transaction_type,
+                                            &self.transaction_category,
+                                            &self.transaction_subcategory,
+                                        ).and_then(|_| self.database.save()) {
+                                            Ok(_) => {}
+                                            Err(e) => {
+                                                self.throw_error(e);
+                                            }
+                                        }
+                                    }
+                                }
+                                ui.end_row();
+
                                 ui.label("Transaction description:")
                                     .on_hover_text("Text description of the transaction.");
                                 ui.text_edit_singleline(&mut self.transaction_description);
                                 ui.end_row();
+
+                                ui.label("Categorization rule:")
+                                    .on_hover_text("Rule that matches the description, entity, value, currency and account of the transaction.");
+                                match self.transaction_rule_proposal() {
+                                    Some(proposal) => {
+                                        ui.label(
+                                            [proposal.category.as_str(), proposal.subcategory.as_str()]
+                                                .iter()
+                                                .filter(|name| !name.is_empty())
+                                                .copied()
+                                                .collect::<Vec<&str>>()
+                                                .join(" / "),
+                                        )
+                                        .on_hover_text(proposal.explanation.clone());
+                                        if ui.button("Apply").on_hover_text("Fills in the entity, category, subcategory and tags that the rule proposes.").clicked() {
+                                            self.apply_transaction_rule_proposal(proposal);
+                                        }
+                                    }
+                                    None => {
+                                        ui.label("No rule matches");
+                                        ui.label("");
+                                    }
+                                }
+                                ui.end_row();
+
+                                ui.label("Transaction tags:")
+                                    .on_hover_text("Labels across categories, like vacation-2025, reimbursable or gift.");
+                                match self.database.tags() {
+                                    Ok(tags) => {
+                                        ui.add(
+                                            AutoCompleteTextEdit::new(
+                                                &mut self.transaction_tag,
+                                                tags,
+                                            )
+                                            .max_suggestions(10)
+                                            .highlight_matches(true),
+                                        );
+                                    }
+                                    Err(e) => {
+                                        self.throw_polars_error(e);
+                                    }
+                                }
+                                if self.is_valid_transaction_tag() {
+                                    if ui.button("Add tag").clicked() {
+                                        self.transaction_tags.push(self.transaction_tag.trim().to_string());
+                                        self.transaction_tag = String::default();
+                                    }
+                                } else {
+                                    ui.label("");
+                                }
+                                ui.end_row();
+
+                                if !self.transaction_tags.is_empty() {
+                                    ui.label("");
+                                    ui.horizontal_wrapped(|ui| {
+                                        let mut i_remove: Option<usize> = None;
+                                        for (i, tag) in self.transaction_tags.iter().enumerate() {
+                                            if ui.button(format!("{} ✖", tag)).on_hover_text("Removes the tag").clicked() {
+                                                i_remove = Some(i);
+                                            }
+                                        }
+                                        if let Some(i) = i_remove {
+                                            self.transaction_tags.remove(i);
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+
+                                if self.transaction_type == TransactionType::Expense {
+                                    ui.label("Amortization months:")
+                                        .on_hover_text("Number of months the expense covers, like a yearly insurance premium. Accrual reports spread its value evenly over them.");
+                                    ui.text_edit_singleline(&mut self.transaction_amortization_months_tentative);
+                                    if self.is_valid_transaction_amortization_months() {
+                                        ui.colored_label(
+                                            Color32::from_rgb(110, 255, 110),
+                                            "Valid number of months!",
+                                        );
+                                    } else {
+                                        ui.colored_label(
+                                            Color32::from_rgb(255, 0, 0),
+                                            "Invalid number of months!",
+                                        );
+                                    }
+                                    ui.end_row();
+
+                                    ui.label("Reimbursable:")
+                                        .on_hover_text("The expense is awaiting payback, like a business trip paid out of pocket. It is listed among the outstanding reimbursements until it is refunded.");
+                                    ui.checkbox(&mut self.transaction_reimbursable, "");
+                                    ui.end_row();
+
+                                    ui.label("Shared with:")
+                                        .on_hover_text("Person who owes us part of the expense, like a friend's half of a dinner we paid. The expense keeps only our own share.");
+                                    let share_entity_string: String = if self.transaction_share_entity_id >= 0 {
+                                        match self.database.entity(self.transaction_share_entity_id) {
+                                            Ok(entity) => entity.to_string(),
+                                            Err(e) => {
+                                                self.transaction_share_entity_id = -1;
+                                                self.throw_error(e);
+                                                String::from("Nobody")
+                                            }
+                                        }
+                                    } else {
+                                        String::from("Nobody")
+                                    };
+                                    ComboBox::from_id_salt("Transaction share entity")
+                                        .selected_text(share_entity_string)
+                                        .show_ui(ui, |ui| {
+                                            match self.database.iter_active_entity_ids() {
+                                                Ok(iterator) => {
+                                                    for entity_id in iterator {
+                                                        match self.database.entity(entity_id) {
+                                                            Ok(entity) => {
+                                                                // only people can owe us money
+                                                                if entity.entity_type() == &EntityType::Human {
+                                                                    ui.selectable_value(
+                                                                        &mut self.transaction_share_entity_id,
+                                                                        entity_id,
+                                                                        entity.to_string(),
+                                                                    );
+                                                                }
+                                                            }
+                                                            Err(e) => {
+                                                                self.throw_error(e);
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    self.throw_polars_error(e);
+                                                }
+                                            }
+                                        });
+                                    ui.end_row();
+
+                                    ui.label("Their share:")
+                                        .on_hover_text("Part of the transaction value that the person owes us.");
+                                    ui.text_edit_singleline(&mut self.transaction_share_tentative);
+                                    if self.is_valid_transaction_share() {
+                                        if ui.button("Add share").clicked() {
+                                            self.transaction_shares.push((
+                                                self.transaction_share_entity_id,
+                                                self.transaction_share_tentative.parse::<f64>().expect("Error parsing share"),
+                                            ));
+                                            self.transaction_share_entity_id = -1;
+                                            self.transaction_share_tentative = String::default();
+                                        }
+                                    } else if !self.are_valid_transaction_shares() {
+                                        ui.colored_label(
+                                            Color32::from_rgb(255, 0, 0),
+                                            "The shares exceed the value!",
+                                        );
+                                    } else {
+                                        ui.label("");
+                                    }
+                                    ui.end_row();
+
+                                    if !self.transaction_shares.is_empty() {
+                                        ui.label("");
+                                        ui.horizontal_wrapped(|ui| {
+                                            let mut i_remove: Option<usize> = None;
+                                            for (i, (entity_id, value)) in self.transaction_shares.iter().enumerate() {
+                                                let name: String = match self.database.entity(*entity_id) {
+                                                    Ok(entity) => entity.name(),
+                                                    Err(_e) => entity_id.to_string(),
+                                                };
+                                                if ui.button(format!("{}: {:.2} ✖", name, value)).on_hover_text("Removes the share").clicked() {
+                                                    i_remove = Some(i);
+                                                }
+                                            }
+                                            if let Some(i) = i_remove {
+                                                self.transaction_shares.remove(i);
+                                            }
+                                        });
+                                        ui.end_row();
+                                    }
+                                }
                             }
                         });
 
@@ -786,32 +1705,75 @@ impl AppState {
                                     subcategory: self.transaction_subcategory.clone(),
                                     description: self.transaction_description.clone(),
                                     entity_id: self.transaction_entity_id,
+                                    tags: self.transaction_tags.clone(),
                                 },
                                 TransactionType::Expense => Transaction::Expense {
-                                    value: self.transaction_value,
+                                    // the others' shares are owed to us, not spent
+                                    value: self.transaction_value
+                                        - self
+                                            .transaction_shares
+                                            .iter()
+                                            .map(|(_, value)| value)
+                                            .sum::<f64>(),
                                     currency: self.transaction_currency.clone(),
                                     date: self.transaction_date,
                                     category: self.transaction_category.clone(),
                                     subcategory: self.transaction_subcategory.clone(),
                                     description: self.transaction_description.clone(),
                                     entity_id: self.transaction_entity_id,
+                                    amortization_months: self
+                                        .transaction_amortization_months_tentative
+                                        .parse::<u32>()
+                                        .expect("Error parsing amortization months"),
+                                    tags: self.transaction_tags.clone(),
+                                    refund_of: -1,
+                                    reimbursable: self.transaction_reimbursable,
+                                },
+                                TransactionType::Refund => match self.database.refund(
+                                    self.transaction_refund_of,
+                                    self.transaction_value,
+                                    self.transaction_date,
+                                    self.transaction_description.clone(),
+                                ) {
+                                    Ok(refund) => refund,
+                                    Err(e) => {
+                                        self.throw_error(e);
+                                        return;
+                                    }
                                 },
                                 TransactionType::Credit => Transaction::Credit {
                                     value: self.transaction_value,
                                     currency: self.transaction_currency.clone(),
                                     date: self.transaction_date,
                                     account_id: self.transaction_account_id,
+                                    value_date: self
+                                        .transaction_has_value_date
+                                        .then_some(self.transaction_value_date),
                                 },
                                 TransactionType::Debit => Transaction::Debit {
                                     value: self.transaction_value,
                                     currency: self.transaction_currency.clone(),
                                     date: self.transaction_date,
                                     account_id: self.transaction_account_id,
+                                    value_date: self
+                                        .transaction_has_value_date
+                                        .then_some(self.transaction_value_date),
                                 },
                             };
 
                             if ui.button("Add transaction").clicked() {
                                 self.party.add_transaction(transaction);
+                                if self.transaction_type == TransactionType::Expense {
+                                    for (entity_id, value) in self.transaction_shares.iter() {
+                                        self.party.add_iou(Iou::new(
+                                            *entity_id,
+                                            *value,
+                                            self.transaction_currency.clone(),
+                                            self.transaction_date,
+                                            self.transaction_description.clone(),
+                                        ));
+                                    }
+                                }
                                 self.clear_transaction_fields();
 
                                 self.show_input_transaction_window = false;
@@ -0,0 +1,330 @@
+use crate::modules::financial::*;
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox};
+use egui_autocomplete::AutoCompleteTextEdit;
+use strum::IntoEnumIterator;
+
+impl AppState {
+    /// Fills the editing fields with the selected entity, once it is chosen
+    fn load_managed_entity(&mut self) {
+        if self.entity_manager_loaded_id == self.entity_manager_id {
+            return;
+        }
+        self.entity_manager_loaded_id = self.entity_manager_id;
+        self.entity_manager_merge_into_id = -1;
+        self.entity_manager_alias = String::default();
+
+        match self.database.entity(self.entity_manager_id) {
+            Ok(entity) => {
+                self.entity_manager_name = entity.name();
+                self.entity_manager_country = entity.country();
+                self.entity_manager_type = entity.entity_type().clone();
+                self.entity_manager_subtype = entity.entity_subtype();
+            }
+            Err(e) => {
+                self.entity_manager_id = -1;
+                self.entity_manager_loaded_id = -1;
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn are_valid_entity_manager_fields(&self) -> bool {
+        (!self.entity_manager_name.is_empty()) & (!self.entity_manager_country.is_empty())
+    }
+
+    fn update_managed_entity(&mut self) {
+        let entity: Entity = Entity::new(
+            self.entity_manager_name.clone(),
+            self.entity_manager_country.clone(),
+            self.entity_manager_type.clone(),
+            self.entity_manager_subtype.clone(),
+        );
+        match self
+            .database
+            .update_entity(self.entity_manager_id, &entity)
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {}
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn merge_managed_entity(&mut self) {
+        match self
+            .database
+            .merge_entities(self.entity_manager_id, self.entity_manager_merge_into_id)
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {
+                // the merged entity is gone, so the one it was merged into is shown
+                self.entity_manager_id = self.entity_manager_merge_into_id;
+                self.entity_manager_loaded_id = -1;
+                // the rules propose the entity it was merged into
+                self.transaction_rules = None;
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn add_managed_entity_alias(&mut self) {
+        match self
+            .database
+            .insert_entity_alias(self.entity_manager_id, &self.entity_manager_alias)
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {
+                self.entity_manager_alias = String::default();
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn remove_managed_entity_alias(&mut self, alias: &str) {
+        match self
+            .database
+            .delete_entity_alias(self.entity_manager_id, alias)
+        {
+            Ok(_) => match self.database.save() {
+                Ok(_) => {}
+                Err(e) => {
+                    self.throw_error(e);
+                }
+            },
+            Err(e) => {
+                self.throw_polars_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_entity_manager_window(&mut self, ctx: &egui::Context) {
+        if self.entity_manager_id >= 0 {
+            self.load_managed_entity();
+        }
+
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("entity_manager_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Entity manager window")
+                .with_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT * 1.5]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let entity_string: String = if self.entity_manager_id >= 0 {
+                        self.database
+                            .entity(self.entity_manager_id)
+                            .map(|entity| entity.to_string())
+                            .unwrap_or_default()
+                    } else {
+                        String::from("Select entity")
+                    };
+
+                    egui::Grid::new("entity_manager_selection")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Entity:").on_hover_text("Entity to edit, merge or give aliases to.");
+                            ComboBox::from_id_salt("Managed entity")
+                                .selected_text(entity_string)
+                                .show_ui(ui, |ui| {
+                                    match self.database.iter_entity_ids() {
+                                        Ok(iterator) => {
+                                            for entity_id in iterator {
+                                                ui.selectable_value(
+                                                    &mut self.entity_manager_id,
+                                                    entity_id,
+                                                    self.database
+                                                        .entity(entity_id)
+                                                        .unwrap() // safe because we iterate over the ids!
+                                                        .to_string(),
+                                                );
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    if self.entity_manager_id < 0 {
+                        return;
+                    }
+
+                    egui::Grid::new("entity_manager_editing")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Name:").on_hover_text("Name of the entity.");
+                            ui.text_edit_singleline(&mut self.entity_manager_name);
+                            if !self.entity_manager_name.is_empty() {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid name!");
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid name!");
+                            }
+                            ui.end_row();
+
+                            ui.label("Country:").on_hover_text("Country of the entity.");
+                            match self.database.entity_countries() {
+                                Ok(entity_countries) => {
+                                    ui.add(
+                                        AutoCompleteTextEdit::new(
+                                            &mut self.entity_manager_country,
+                                            entity_countries,
+                                        )
+                                        .max_suggestions(10)
+                                        .highlight_matches(true),
+                                    );
+                                }
+                                Err(e) => {
+                                    self.throw_polars_error(e);
+                                }
+                            }
+                            if !self.entity_manager_country.is_empty() {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid country!");
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid country!");
+                            }
+                            ui.end_row();
+
+                            ui.label("Type:").on_hover_text("Category of the entity.");
+                            ComboBox::from_id_salt("Managed entity type")
+                                .selected_text(format!("{}", self.entity_manager_type))
+                                .show_ui(ui, |ui| {
+                                    for possible_entity_type in EntityType::iter() {
+                                        ui.selectable_value(
+                                            &mut self.entity_manager_type,
+                                            possible_entity_type.clone(),
+                                            format!("{possible_entity_type}"),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Subtype:").on_hover_text("Sub-category of the entity.");
+                            match self.database.entity_subtypes() {
+                                Ok(entity_subtypes) => {
+                                    ui.add(
+                                        AutoCompleteTextEdit::new(
+                                            &mut self.entity_manager_subtype,
+                                            entity_subtypes,
+                                        )
+                                        .max_suggestions(10)
+                                        .highlight_matches(true),
+                                    );
+                                }
+                                Err(e) => {
+                                    self.throw_polars_error(e);
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label("");
+                            if self.are_valid_entity_manager_fields()
+                                && ui.button("Save changes").on_hover_text("Its transactions are kept, under the new details.").clicked() {
+                                    self.update_managed_entity();
+                                }
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    let merge_into_string: String = if self.entity_manager_merge_into_id >= 0 {
+                        self.database
+                            .entity(self.entity_manager_merge_into_id)
+                            .map(|entity| entity.to_string())
+                            .unwrap_or_default()
+                    } else {
+                        String::from("Select entity")
+                    };
+
+                    egui::Grid::new("entity_manager_merging")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Merge into:").on_hover_text("Entity of which this one is a duplicate.");
+                            ComboBox::from_id_salt("Merge into entity")
+                                .selected_text(merge_into_string)
+                                .show_ui(ui, |ui| {
+                                    match self.database.iter_entity_ids() {
+                                        Ok(iterator) => {
+                                            for entity_id in iterator {
+                                                if entity_id == self.entity_manager_id {
+                                                    continue;
+                                                }
+                                                ui.selectable_value(
+                                                    &mut self.entity_manager_merge_into_id,
+                                                    entity_id,
+                                                    self.database
+                                                        .entity(entity_id)
+                                                        .unwrap() // safe because we iterate over the ids!
+                                                        .to_string(),
+                                                );
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            if self.entity_manager_merge_into_id >= 0
+                                && ui.button("Merge").on_hover_text("Moves all its transactions to the other entity and deletes it, keeping its name as an alias.").clicked() {
+                                    self.merge_managed_entity();
+                                }
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    let aliases: Vec<String> = match self.database.entity_aliases(self.entity_manager_id) {
+                        Ok(aliases) => aliases,
+                        Err(e) => {
+                            self.throw_polars_error(e);
+                            Vec::new()
+                        }
+                    };
+
+                    let mut removed_alias: Option<String> = None;
+                    egui::Grid::new("entity_manager_aliases")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            for alias in aliases.iter() {
+                                ui.label("Alias:");
+                                ui.label(alias);
+                                if ui.button("Remove").clicked() {
+                                    removed_alias = Some(alias.clone());
+                                }
+                                ui.end_row();
+                            }
+
+                            ui.label("New alias:").on_hover_text("Another name under which the entity is known, like the one shown in bank statements. It is matched when importing, ignoring case.");
+                            ui.text_edit_singleline(&mut self.entity_manager_alias);
+                            if !self.entity_manager_alias.trim().is_empty()
+                                && ui.button("Add alias").clicked() {
+                                    self.add_managed_entity_alias();
+                                }
+                            ui.end_row();
+                        });
+                    if let Some(alias) = removed_alias {
+                        self.remove_managed_entity_alias(&alias);
+                    }
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_entity_manager_window = false;
+                }
+            },
+        );
+    }
+}
@@ -1,17 +1,36 @@
 pub mod browsing;
+pub mod budgeting;
+pub mod categorizing;
+pub mod checking;
+pub mod closing;
 pub mod error;
+pub mod forecasting;
+pub mod importing;
 pub mod inputting;
+pub mod managing;
 pub mod plotting;
+pub mod projecting;
+pub mod reconciling;
+pub mod reviewing;
+pub mod settling;
+pub mod staging;
 pub mod summarizing;
 
-use super::database::summaries::TimeUnit;
+use super::database::summaries::{Basis, ReportDate, TimeUnit};
+use crate::modules::categorization::classifier::CategoryClassifier;
+use crate::modules::categorization::rules::CompiledRules;
+use crate::modules::database::envelopes::EnvelopeOverview;
 use crate::modules::database::plotter::BarplotType;
+use crate::modules::database::templates::DueParty;
 use crate::modules::database::*;
 use crate::modules::financial::*;
+use crate::modules::importers::bank_csv::CsvMapping;
+use crate::modules::importers::ImportFormat;
 use chrono::{Local, NaiveDate};
 use derivative::*;
 use eframe::egui;
 use egui_extras::{Size, StripBuilder};
+use std::collections::HashSet;
 
 const WINDOW_HEIGHT: f32 = 400.0;
 const WINDOW_WIDTH: f32 = 600.0;
@@ -24,12 +43,31 @@ pub struct AppState {
     show_input_party_window: bool,
     show_input_transaction_window: bool,
     show_expense_summary_window: bool,
+    show_tag_summary_window: bool,
     show_fund_stand_window: bool,
     show_browse_last_transactions_window: bool,
     show_browse_last_fund_movements_window: bool,
+    show_outstanding_reimbursements_window: bool,
     show_fund_evolution_plot_window: bool,
     show_expense_category_plot_window: bool,
     show_expenses_evolution_window: bool,
+    show_forecast_window: bool,
+    show_input_template_window: bool,
+    show_due_parties_window: bool,
+    show_budget_window: bool,
+    show_envelope_window: bool,
+    show_project_window: bool,
+    show_iou_window: bool,
+    show_reconciliation_window: bool,
+    show_balance_assertion_window: bool,
+    show_integrity_window: bool,
+    show_duplicates_window: bool,
+    show_closing_window: bool,
+    show_entity_manager_window: bool,
+    show_category_manager_window: bool,
+    show_rule_window: bool,
+    show_import_window: bool,
+    show_staging_window: bool,
     show_error_window: bool,
 
     error_message: String,
@@ -47,6 +85,7 @@ pub struct AppState {
     account_type: AccountType,
     account_initial_balance: f64,
     account_initial_balance_tentative: String,
+    account_pockets: Vec<Currency>,
 
     party: Party,
 
@@ -55,6 +94,20 @@ pub struct AppState {
     transaction_currency: Currency,
     #[derivative(Default(value = "Local::now().date_naive()"))]
     transaction_date: NaiveDate,
+    transaction_has_value_date: bool,
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    transaction_value_date: NaiveDate,
+    #[derivative(Default(value = "String::from(\"1\")"))]
+    transaction_amortization_months_tentative: String,
+    transaction_tag: String,
+    transaction_tags: Vec<String>,
+    #[derivative(Default(value = "-1"))]
+    transaction_share_entity_id: i64,
+    transaction_share_tentative: String,
+    transaction_shares: Vec<(i64, f64)>, // others' shares of an expense, by entity_id
+    transaction_reimbursable: bool,
+    #[derivative(Default(value = "-1"))]
+    transaction_refund_of: i64,
     transaction_category: String,
     transaction_subcategory: String,
     transaction_description: String,
@@ -64,6 +117,167 @@ pub struct AppState {
     transaction_account_string: String,
     transaction_type: TransactionType,
     transaction_filter: String,
+    transaction_classifier: Option<(TransactionType, CategoryClassifier)>, // learned, by type
+    transaction_rules: Option<CompiledRules>, // compiled, until the rules change
+
+    template_name: String,
+    template_recurrence: Recurrence,
+    template_recurrence_step_tentative: String,
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    template_start_date: NaiveDate,
+    #[derivative(Default(value = "-1"))]
+    template_id: i64,
+    template_string: String,
+
+    due_parties: Vec<DueParty>,
+    due_parties_checked: bool,
+    pending_due_party: Option<(i64, NaiveDate)>, // template ID and date of the party being edited
+
+    budget_category: String,
+    budget_subcategory: String,
+    budget_period: BudgetPeriod,
+    budget_value_tentative: String,
+    budget_currency: Currency,
+
+    budget_report_csv: String,
+    budget_report_csv_correct: bool,
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    budget_report_date: NaiveDate,
+    budget_report_currency: Currency,
+
+    project_name: String,
+    project_description: String,
+    project_budget_tentative: String,
+    project_currency: Currency,
+
+    project_report_csv: String,
+    project_report_csv_correct: bool,
+    #[derivative(Default(value = "-1"))]
+    project_report_id: i64,
+    project_report_currency: Currency,
+    project_report_include_planned: bool,
+
+    iou_report_csv: String,
+    iou_report_csv_correct: bool,
+    iou_report_currency: Currency,
+    iou_report_include_planned: bool,
+    #[derivative(Default(value = "-1"))]
+    iou_settlement_entity_id: i64,
+    iou_settlement_currency: Currency,
+    #[derivative(Default(value = "-1"))]
+    iou_settlement_account_id: i64,
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    iou_settlement_date: NaiveDate,
+
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    envelope_month: NaiveDate,
+    envelope_currency: Currency,
+    envelope_overview: Option<EnvelopeOverview>,
+    #[derivative(Default(value = "-1"))]
+    assignment_party_id: i64,
+    assignment_category: String,
+    assignment_value_tentative: String,
+
+    #[derivative(Default(value = "-1"))]
+    reconciliation_account_id: i64,
+    reconciliation_currency: Currency,
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    reconciliation_date: NaiveDate,
+    reconciliation_balance_tentative: String,
+
+    #[derivative(Default(value = "-1"))]
+    balance_assertion_account_id: i64,
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    balance_assertion_date: NaiveDate,
+    balance_assertion_value_tentative: String,
+    balance_assertion_currency: Currency,
+    balance_assertions_csv: String,
+    balance_assertions_csv_correct: bool,
+
+    #[derivative(Default(value = "-1"))]
+    entity_manager_id: i64,
+    #[derivative(Default(value = "-1"))]
+    entity_manager_loaded_id: i64,
+    entity_manager_name: String,
+    entity_manager_country: String,
+    entity_manager_type: EntityType,
+    entity_manager_subtype: String,
+    #[derivative(Default(value = "-1"))]
+    entity_manager_merge_into_id: i64,
+    entity_manager_alias: String,
+
+    #[derivative(Default(value = "TransactionType::Expense"))]
+    category_manager_type: TransactionType,
+    #[derivative(Default(value = "-1"))]
+    category_manager_id: i64,
+    category_manager_name: String,
+    #[derivative(Default(value = "-1"))]
+    category_manager_target_id: i64,
+    #[derivative(Default(value = "-1"))]
+    category_manager_parent_id: i64,
+    category_manager_new_path: String,
+
+    #[derivative(Default(value = "-1"))]
+    rule_id: i64,
+    #[derivative(Default(value = "-1"))]
+    rule_loaded_id: i64,
+    rule_name: String,
+    #[derivative(Default(value = "String::from(\"0\")"))]
+    rule_priority_tentative: String,
+    #[derivative(Default(value = "TransactionType::Expense"))]
+    rule_type: TransactionType,
+    rule_pattern: String,
+    rule_min_value_tentative: String,
+    rule_max_value_tentative: String,
+    #[derivative(Default(value = "-1"))]
+    rule_account_id: i64,
+    rule_currency: Option<Currency>,
+    #[derivative(Default(value = "-1"))]
+    rule_entity_id: i64,
+    rule_category: String,
+    rule_subcategory: String,
+    rule_tags_tentative: String,
+    rule_test_rows: Vec<Vec<String>>,
+    rule_tested: bool,
+
+    import_format: ImportFormat,
+    import_mapping: CsvMapping,
+    #[derivative(Default(value = "-1"))]
+    import_mapping_id: i64,
+    #[derivative(Default(value = "-1"))]
+    import_mapping_loaded_id: i64,
+    #[derivative(Default(value = "String::from(\",\")"))]
+    import_delimiter_tentative: String,
+    #[derivative(Default(value = "String::from(\"0\")"))]
+    import_skip_rows_tentative: String,
+    import_file_path: String,
+    import_iban: String,
+    #[derivative(Default(value = "-1"))]
+    import_account_id: i64,
+    import_income_category: String,
+    import_expense_category: String,
+
+    staging_selected_ids: HashSet<i64>,
+    #[derivative(Default(value = "-1"))]
+    staging_entity_id: i64,
+    #[derivative(Default(value = "TransactionType::Expense"))]
+    staging_type: TransactionType,
+    staging_category: String,
+    staging_subcategory: String,
+
+    #[derivative(Default(value = "-1"))]
+    closing_account_id: i64,
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    closing_date: NaiveDate,
+    #[derivative(Default(value = "-1"))]
+    archiving_entity_id: i64,
+
+    outstanding_reimbursements_csv: String,
+    outstanding_reimbursements_csv_correct: bool,
+
+    integrity_report_csv: String,
+
+    duplicates_report_csv: String,
 
     expense_summary_csv: String,
     expense_summary_csv_correct: bool,
@@ -72,20 +286,43 @@ pub struct AppState {
     #[derivative(Default(value = "Local::now().date_naive()"))]
     expense_summary_date_to: NaiveDate,
     expense_summary_currency: Currency,
+    expense_summary_include_planned: bool,
+
+    tag_summary_csv: String,
+    tag_summary_csv_correct: bool,
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    tag_summary_date_from: NaiveDate,
+    #[derivative(Default(value = "Local::now().date_naive()"))]
+    tag_summary_date_to: NaiveDate,
+    tag_summary_currency: Currency,
+    tag_summary_include_planned: bool,
 
     fund_stand_csv: String,
     fund_stand_csv_correct: bool,
     fund_stand_currency: Option<Currency>,
+    fund_stand_include_planned: bool,
 
     expenses_evolution_csv: String,
     expenses_evolution_csv_correct: bool,
     expenses_evolution_currency: Currency,
     expenses_evolution_time_unit: TimeUnit,
+    expenses_evolution_include_planned: bool,
+    expenses_evolution_report_date: ReportDate,
+    expenses_evolution_basis: Basis,
+
+    #[derivative(Default(value = "String::from(\"3\")"))]
+    forecast_months_tentative: String,
+    #[derivative(Default(value = "String::from(\"0\")"))]
+    forecast_threshold_tentative: String,
+    forecast_projection_csv: String,
+    forecast_warnings_csv: String,
+    forecast_csv_correct: bool,
 
     last_transactions_csv: String,
     last_transactions_csv_correct: bool,
     last_transactions_n: usize,
     last_transactions_n_temptative: String,
+    browse_tag: String,
 
     last_fund_movements_csv: String,
     last_fund_movements_csv_correct: bool,
@@ -97,15 +334,28 @@ pub struct AppState {
     browse_account_string: String,
 
     fund_evolution_plot_currency: Currency,
+    fund_evolution_plot_include_planned: bool,
+    fund_evolution_plot_report_date: ReportDate,
 
     expense_category_plot_currency: Currency,
     expense_category_plot_type: BarplotType,
+    expense_category_plot_include_planned: bool,
+    expense_category_plot_report_date: ReportDate,
+    expense_category_plot_basis: Basis,
 }
 
 impl eframe::App for AppState {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) -> () {
         egui_extras::install_image_loaders(ctx);
 
+        if !self.due_parties_checked {
+            // only once, on startup
+            self.due_parties_checked = true;
+            self.load_due_parties();
+            self.show_due_parties_window = !self.due_parties.is_empty();
+            self.show_integrity_window = self.load_integrity_report();
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             StripBuilder::new(ui)
                 .size(Size::exact(20.0))
@@ -118,16 +368,87 @@ impl eframe::App for AppState {
                             };
                             ui.end_row();
 
+                            ui.menu_button("Templates", |ui| {
+                                if ui.button("Due transactions").clicked() {
+                                    self.load_due_parties();
+                                    self.show_due_parties_window = true;
+                                }
+                            });
+                            ui.end_row();
+
                             ui.menu_button("Summaries", |ui| {
                                 if ui.button("Expenses by Category").clicked() {
                                     self.show_expense_summary_window = true;
                                 }
+                                if ui.button("Incomes and Expenses by Tag").clicked() {
+                                    self.show_tag_summary_window = true;
+                                }
                                 if ui.button("Funds by Account").clicked() {
                                     self.show_fund_stand_window = true;
                                 }
                                 if ui.button("Expenses Evolution").clicked() {
                                     self.show_expenses_evolution_window = true;
                                 }
+                                if ui.button("Cash-flow Forecast").clicked() {
+                                    self.show_forecast_window = true;
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.menu_button("Budgeting", |ui| {
+                                if ui.button("Budgets").clicked() {
+                                    self.show_budget_window = true;
+                                }
+                                if ui.button("Envelopes").clicked() {
+                                    self.show_envelope_window = true;
+                                }
+                                if ui.button("Projects").clicked() {
+                                    self.show_project_window = true;
+                                }
+                                if ui.button("IOUs").clicked() {
+                                    self.show_iou_window = true;
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.menu_button("Accounts", |ui| {
+                                if ui.button("Reconcile account").clicked() {
+                                    self.show_reconciliation_window = true;
+                                }
+                                if ui.button("Balance assertions").clicked() {
+                                    self.load_balance_assertions();
+                                    self.show_balance_assertion_window = true;
+                                }
+                                if ui.button("Integrity check").clicked() {
+                                    self.load_integrity_report();
+                                    self.show_integrity_window = true;
+                                }
+                                if ui.button("Find duplicates").clicked() {
+                                    self.load_duplicates_report();
+                                    self.show_duplicates_window = true;
+                                }
+                                if ui.button("Close and archive").clicked() {
+                                    self.show_closing_window = true;
+                                }
+                                if ui.button("Import bank statement").clicked() {
+                                    self.show_import_window = true;
+                                }
+                                if ui.button("Staging inbox").clicked() {
+                                    self.show_staging_window = true;
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.menu_button("Entities and categories", |ui| {
+                                if ui.button("Manage entities").clicked() {
+                                    self.show_entity_manager_window = true;
+                                }
+                                if ui.button("Manage categories").clicked() {
+                                    self.show_category_manager_window = true;
+                                }
+                                if ui.button("Categorization rules").clicked() {
+                                    self.show_rule_window = true;
+                                }
                             });
                             ui.end_row();
 
@@ -148,6 +469,10 @@ impl eframe::App for AppState {
                                 if ui.button("Last fund movements").clicked() {
                                     self.show_browse_last_fund_movements_window = true;
                                 }
+                                if ui.button("Outstanding reimbursements").clicked() {
+                                    self.load_outstanding_reimbursements();
+                                    self.show_outstanding_reimbursements_window = true;
+                                }
                             });
                             ui.end_row();
                         });
@@ -171,10 +496,22 @@ impl eframe::App for AppState {
             self.handle_show_input_transaction_window(ctx)
         }
 
+        if self.show_input_template_window {
+            self.handle_show_input_template_window(ctx);
+        }
+
+        if self.show_due_parties_window {
+            self.handle_show_due_parties_window(ctx);
+        }
+
         if self.show_expense_summary_window {
             self.handle_show_expense_summary_window(ctx)
         }
 
+        if self.show_tag_summary_window {
+            self.handle_show_tag_summary_window(ctx)
+        }
+
         if self.show_fund_stand_window {
             self.handle_show_fund_stand_window(ctx)
         }
@@ -183,6 +520,10 @@ impl eframe::App for AppState {
             self.handle_show_expenses_evolution_window(ctx)
         }
 
+        if self.show_forecast_window {
+            self.handle_show_forecast_window(ctx);
+        }
+
         if self.show_browse_last_transactions_window {
             self.handle_show_browse_last_transactions_window(ctx)
         }
@@ -191,6 +532,66 @@ impl eframe::App for AppState {
             self.handle_show_browse_last_fund_movements_window(ctx);
         }
 
+        if self.show_outstanding_reimbursements_window {
+            self.handle_show_outstanding_reimbursements_window(ctx);
+        }
+
+        if self.show_budget_window {
+            self.handle_show_budget_window(ctx);
+        }
+
+        if self.show_envelope_window {
+            self.handle_show_envelope_window(ctx);
+        }
+
+        if self.show_project_window {
+            self.handle_show_project_window(ctx);
+        }
+
+        if self.show_iou_window {
+            self.handle_show_iou_window(ctx);
+        }
+
+        if self.show_reconciliation_window {
+            self.handle_show_reconciliation_window(ctx);
+        }
+
+        if self.show_balance_assertion_window {
+            self.handle_show_balance_assertion_window(ctx);
+        }
+
+        if self.show_integrity_window {
+            self.handle_show_integrity_window(ctx);
+        }
+
+        if self.show_duplicates_window {
+            self.handle_show_duplicates_window(ctx);
+        }
+
+        if self.show_closing_window {
+            self.handle_show_closing_window(ctx);
+        }
+
+        if self.show_entity_manager_window {
+            self.handle_show_entity_manager_window(ctx);
+        }
+
+        if self.show_category_manager_window {
+            self.handle_show_category_manager_window(ctx);
+        }
+
+        if self.show_rule_window {
+            self.handle_show_rule_window(ctx);
+        }
+
+        if self.show_import_window {
+            self.handle_show_import_window(ctx);
+        }
+
+        if self.show_staging_window {
+            self.handle_show_staging_window(ctx);
+        }
+
         if self.show_fund_evolution_plot_window {
             self.handle_show_fund_evolution_plot(ctx);
         }
@@ -1,4 +1,5 @@
 use crate::modules::database::plotter::BarplotType;
+use crate::modules::database::summaries::{Basis, ReportDate};
 use crate::modules::financial::*;
 use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
 use eframe::egui;
@@ -50,12 +51,33 @@ impl AppState {
                                             });
                                         ui.end_row();
 
+                                        ui.label("Date:").on_hover_text("Date that places the transactions in time: the booking date, or the value date of their fund movements when they settled later.");
+                                        ComboBox::from_id_salt("Fund evolution plot report date")
+                                            .selected_text(format!("{}", self.fund_evolution_plot_report_date))
+                                            .show_ui(ui, |ui| {
+                                                for possible_report_date in ReportDate::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.fund_evolution_plot_report_date,
+                                                        possible_report_date.clone(),
+                                                        format!("{possible_report_date}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Include planned:").on_hover_text(
+                                            "Include the planned fund movements, not only the pending and cleared ones.",
+                                        );
+                                        ui.checkbox(&mut self.fund_evolution_plot_include_planned, "");
+                                        ui.end_row();
+
                                         ui.label("");
                                         if ui.button("Generate!").clicked() {
-                                            match self
-                                                .database
-                                                .funds_evolution(&self.fund_evolution_plot_currency)
-                                            {
+                                            match self.database.funds_evolution(
+                                                &self.fund_evolution_plot_currency,
+                                                self.fund_evolution_plot_include_planned,
+                                                &self.fund_evolution_plot_report_date,
+                                            ) {
                                                 Ok(_) => {}
                                                 Err(e) => {
                                                     self.throw_error(e);
@@ -145,11 +167,48 @@ impl AppState {
                                             });
                                         ui.end_row();
 
+                                        ui.label("Date:").on_hover_text("Date that places the transactions in time: the booking date, or the value date of their fund movements when they settled later.");
+                                        ComboBox::from_id_salt("Expense category plot report date")
+                                            .selected_text(format!("{}", self.expense_category_plot_report_date))
+                                            .show_ui(ui, |ui| {
+                                                for possible_report_date in ReportDate::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.expense_category_plot_report_date,
+                                                        possible_report_date.clone(),
+                                                        format!("{possible_report_date}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Basis:").on_hover_text("Cash: expenses count when they are paid. Accrual: amortized expenses are spread evenly over the months they cover.");
+                                        ComboBox::from_id_salt("Expense category plot basis")
+                                            .selected_text(format!("{}", self.expense_category_plot_basis))
+                                            .show_ui(ui, |ui| {
+                                                for possible_basis in Basis::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.expense_category_plot_basis,
+                                                        possible_basis.clone(),
+                                                        format!("{possible_basis}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Include planned:").on_hover_text(
+                                            "Include the planned expenses, not only the pending and cleared ones.",
+                                        );
+                                        ui.checkbox(&mut self.expense_category_plot_include_planned, "");
+                                        ui.end_row();
+
                                         ui.label("");
                                         if ui.button("Generate!").clicked() {
                                             match self.database.monthly_expenses(
                                                 &self.expense_category_plot_currency,
                                                 &self.expense_category_plot_type,
+                                                self.expense_category_plot_include_planned,
+                                                &self.expense_category_plot_report_date,
+                                                &self.expense_category_plot_basis,
                                             ) {
                                                 Ok(_) => {},
                                                 Err(e) => {self.throw_error(e);}
@@ -0,0 +1,277 @@
+use crate::modules::financial::*;
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox};
+use egui::{Align, Layout};
+use egui_extras::*;
+use strum::IntoEnumIterator;
+
+impl AppState {
+    fn is_valid_project_budget(&self) -> bool {
+        // an empty budget means the project has none
+        if self.project_budget_tentative.is_empty() {
+            return true;
+        }
+        let parsing_result = self.project_budget_tentative.parse::<f64>();
+        match parsing_result {
+            Ok(value) => value >= 0.0,
+            Err(_e) => false,
+        }
+    }
+
+    fn are_valid_project_fields(&self) -> bool {
+        (!self.project_name.is_empty()) & self.is_valid_project_budget()
+    }
+
+    fn clear_project_fields(&mut self) {
+        self.project_name = String::default();
+        self.project_description = String::default();
+        self.project_budget_tentative = String::default();
+        self.project_currency = Currency::default();
+    }
+
+    fn generate_project_report(&mut self) {
+        match self.database.project_report(
+            self.project_report_id,
+            &self.project_report_currency,
+            self.project_report_include_planned,
+        ) {
+            Ok(s) => {
+                self.project_report_csv = s;
+                self.project_report_csv_correct = true;
+            }
+            Err(e) => {
+                self.project_report_csv_correct = false;
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_project_window(&mut self, ctx: &egui::Context) {
+        let project_report_string: String = if self.project_report_id >= 0 {
+            match self.database.project(self.project_report_id) {
+                Ok(project) => project.to_string(),
+                Err(e) => {
+                    self.project_report_id = -1;
+                    self.throw_error(e);
+                    String::from("No project")
+                }
+            }
+        } else {
+            String::from("No project")
+        };
+
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("project_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Projects window")
+                .with_inner_size([WINDOW_WIDTH * 1.2, WINDOW_HEIGHT * 1.5]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let project_report_csv = self.project_report_csv.clone();
+                    let header_line: String = project_report_csv.split("\n").collect::<Vec<&str>>()[0].to_string();
+                    let row_lines: Vec<&str> = project_report_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+                    let column_count: usize = header_line.split(",").count();
+
+                    StripBuilder::new(ui)
+                        .size(Size::exact(260.0))
+                        .size(Size::remainder().at_least(120.0))
+                        .vertical(|mut strip| {
+                            strip.cell(|ui| {
+                                egui::Grid::new("project_input")
+                                    .num_columns(3)
+                                    .spacing([45.0, 4.0])
+                                    .show(ui, |ui| {
+                                        ui.label("Name:").on_hover_text("Name of the trip, event or project, like Stockholm trip 2025.");
+                                        ui.text_edit_singleline(&mut self.project_name);
+                                        if !self.project_name.is_empty() {
+                                            ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid name!");
+                                        } else {
+                                            ui.colored_label(Color32::from_rgb(255, 0, 0), "Please enter a name!");
+                                        }
+                                        ui.end_row();
+
+                                        ui.label("Description:").on_hover_text("Text description of the project.");
+                                        ui.text_edit_singleline(&mut self.project_description);
+                                        ui.end_row();
+
+                                        ui.label("Budget:").on_hover_text("Maximum amount to spend on the project. Leave empty for a project without budget.");
+                                        ui.text_edit_singleline(&mut self.project_budget_tentative);
+                                        if self.is_valid_project_budget() {
+                                            ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid budget!");
+                                        } else {
+                                            ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid budget!");
+                                        }
+                                        ui.end_row();
+
+                                        ui.label("Currency:").on_hover_text("Currency of the budget.");
+                                        ComboBox::from_id_salt("Project currency")
+                                            .selected_text(format!("{}", self.project_currency))
+                                            .show_ui(ui, |ui| {
+                                                for possible_project_currency in Currency::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.project_currency,
+                                                        possible_project_currency.clone(),
+                                                        format!("{possible_project_currency}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("");
+                                        if self.are_valid_project_fields()
+                                            && ui.button("Add project").on_hover_text("Save project into the database. Parties are attached to it in the party window.").clicked() {
+                                                let project: Project = Project::new(
+                                                    self.project_name.clone(),
+                                                    self.project_description.clone(),
+                                                    self.project_budget_tentative.parse::<f64>().unwrap_or(0.0),
+                                                    self.project_currency.clone(),
+                                                );
+                                                match self.database.insert_project(&project) {
+                                                    Ok(project_id) => {
+                                                        match self.database.save() {
+                                                            Ok(_) => {
+                                                                self.clear_project_fields();
+                                                                self.project_report_id = project_id;
+                                                            }
+                                                            Err(e) => {
+                                                                self.throw_error(e);
+                                                            }
+                                                        }
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_polars_error(e);
+                                                    }
+                                                }
+                                            }
+                                        ui.end_row();
+                                    });
+                                ui.separator();
+
+                                egui::Grid::new("project_report")
+                                    .num_columns(3)
+                                    .spacing([45.0, 4.0])
+                                    .show(ui, |ui| {
+                                        ui.label("Project:").on_hover_text("Project to report on.");
+                                        ComboBox::from_id_salt("Project report project")
+                                            .selected_text(project_report_string.clone())
+                                            .show_ui(ui, |ui| {
+                                                match self.database.iter_project_ids() {
+                                                    Ok(iterator) => {
+                                                        for project_id in iterator {
+                                                            match self.database.project(project_id) {
+                                                                Ok(project) => {
+                                                                    ui.selectable_value(
+                                                                        &mut self.project_report_id,
+                                                                        project_id,
+                                                                        project.to_string(),
+                                                                    );
+                                                                }
+                                                                Err(e) => {
+                                                                    self.throw_error(e);
+                                                                }
+                                                            }
+                                                        }
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_polars_error(e);
+                                                    }
+                                                }
+                                            });
+                                        if self.project_report_id >= 0
+                                            && ui.button("Remove project").on_hover_text("Removes the project from the database. Its parties are kept.").clicked() {
+                                                match self.database.delete_project(self.project_report_id) {
+                                                    Ok(_) => {
+                                                        match self.database.save() {
+                                                            Ok(_) => {
+                                                                self.project_report_id = -1;
+                                                                self.project_report_csv_correct = false;
+                                                            }
+                                                            Err(e) => {
+                                                                self.throw_error(e);
+                                                            }
+                                                        }
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_polars_error(e);
+                                                    }
+                                                }
+                                            }
+                                        ui.end_row();
+
+                                        ui.label("Currency:").on_hover_text("Currency in which to express the ammounts. Every transaction is exchanged at its own date.");
+                                        ComboBox::from_id_salt("Project report currency")
+                                            .selected_text(format!("{}", self.project_report_currency))
+                                            .show_ui(ui, |ui| {
+                                                for possible_project_report_currency in Currency::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.project_report_currency,
+                                                        possible_project_report_currency.clone(),
+                                                        format!("{possible_project_report_currency}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Include planned:").on_hover_text("Include the planned expenses and incomes, not only the pending and cleared ones.");
+                                        ui.checkbox(&mut self.project_report_include_planned, "");
+                                        ui.end_row();
+
+                                        ui.label("");
+                                        if self.project_report_id >= 0
+                                            && ui.button("Generate!").clicked() {
+                                                self.generate_project_report();
+                                            }
+                                    });
+                                ui.separator();
+                            });
+                            if self.project_report_csv_correct {
+                            strip.cell(|ui| {
+                                TableBuilder::new(ui)
+                                    .columns(Column::auto().resizable(true), column_count)
+                                    .striped(true)
+                                    .cell_layout(Layout::right_to_left(Align::Center))
+                                    .header(20.0, |mut header| {
+                                        for column_name in header_line.split(",") {
+                                            header.col(|ui| {
+                                                ui.strong(column_name).on_hover_text(column_name);
+                                            });
+                                        }
+                                    })
+                                    .body(|mut body| {
+                                        for row_line in row_lines {
+                                            let is_total_row: bool = row_line.starts_with("Total,");
+                                            // an overrun budget leaves a negative remaining amount
+                                            let is_overrun: bool = row_line.starts_with("Total,Remaining,-");
+                                            body.row(30.0, |mut row_ui| {
+                                                for element in row_line.split(",") {
+                                                    row_ui.col(|ui| {
+                                                        if is_overrun {
+                                                            ui.colored_label(Color32::from_rgb(255, 0, 0), element);
+                                                        } else if is_total_row {
+                                                            ui.strong(element);
+                                                        } else {
+                                                            ui.label(element);
+                                                        }
+                                                    });
+                                                }
+                                            });
+                                        }
+                                    });
+                                ui.separator();
+                            });
+                            }
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_project_window = false;
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,501 @@
+use crate::modules::financial::{BalanceAssertion, Currency, TransactionStatus};
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use chrono::NaiveDate;
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox};
+use egui::{Align, Layout};
+use egui_extras::*;
+use polars::prelude::{col, lit, DataFrame, IntoLazy};
+use strum::IntoEnumIterator;
+
+/// Fund movement as shown in the reconciliation window
+struct ReconciliationRow {
+    fund_movement_id: i64,
+    date: NaiveDate,
+    fund_movement_type: String,
+    value: f64,
+    currency: String,
+    balance: f64,
+    cleared: bool,
+}
+
+impl AppState {
+    fn is_valid_reconciliation_balance(&self) -> bool {
+        self.reconciliation_balance_tentative.parse::<f64>().is_ok()
+    }
+
+    /// Returns the fund movements of the selected account and currency that are not
+    /// reconciled yet, leaving the void ones out.
+    fn unreconciled_rows(&self) -> Result<Vec<ReconciliationRow>, Box<dyn std::error::Error>> {
+        let movements: DataFrame = self
+            .database
+            .account_movements(
+                self.reconciliation_account_id,
+                &self.reconciliation_currency,
+            )?
+            .lazy()
+            .filter(
+                col("reconciliation_id")
+                    .lt(lit(0i64))
+                    .and(col("status").neq(lit(TransactionStatus::Void.to_string()))),
+            )
+            .collect()?;
+
+        let mut rows: Vec<ReconciliationRow> = Vec::new();
+        for i in 0..movements.height() {
+            rows.push(ReconciliationRow {
+                fund_movement_id: movements
+                    .column("fund_movement_id")?
+                    .i64()?
+                    .get(i)
+                    .ok_or("No fund_movement_id!")?,
+                date: movements
+                    .column("date")?
+                    .date()?
+                    .as_date_iter()
+                    .nth(i)
+                    .flatten()
+                    .ok_or("No date!")?,
+                fund_movement_type: movements
+                    .column("fund_movement_type")?
+                    .str()?
+                    .get(i)
+                    .ok_or("No fund_movement_type!")?
+                    .to_string(),
+                value: movements
+                    .column("value")?
+                    .f64()?
+                    .get(i)
+                    .ok_or("No value!")?,
+                currency: movements
+                    .column("currency")?
+                    .str()?
+                    .get(i)
+                    .ok_or("No currency!")?
+                    .to_string(),
+                balance: movements
+                    .column("balance")?
+                    .f64()?
+                    .get(i)
+                    .ok_or("No balance!")?,
+                cleared: movements.column("status")?.str()?.get(i)
+                    == Some(TransactionStatus::Cleared.to_string().as_str()),
+            });
+        }
+
+        Ok(rows)
+    }
+
+    pub fn handle_show_reconciliation_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("reconciliation_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Reconciliation window")
+                .with_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT * 1.5]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let account_string: String = if self.reconciliation_account_id >= 0 {
+                        self.database
+                            .account(self.reconciliation_account_id)
+                            .map(|account| account.to_string())
+                            .unwrap_or_default()
+                    } else {
+                        String::from("Select account")
+                    };
+
+                    let previous_account_id: i64 = self.reconciliation_account_id;
+                    egui::Grid::new("reconciliation_statement")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Account:").on_hover_text("Account to reconcile.");
+                            ComboBox::from_id_salt("Reconciliation account")
+                                .selected_text(account_string)
+                                .show_ui(ui, |ui| {
+                                    match self.database.iter_account_ids() {
+                                        Ok(iterator) => {
+                                            for account_id in iterator {
+                                                ui.selectable_value(
+                                                    &mut self.reconciliation_account_id,
+                                                    account_id,
+                                                    self.database
+                                                        .account(account_id)
+                                                        .unwrap() // safe because we iterate over the ids!
+                                                        .to_string(),
+                                                );
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            ui.end_row();
+
+                            if let Ok(account) = self.database.account(self.reconciliation_account_id) {
+                                // a newly selected account starts with its main currency
+                                if self.reconciliation_account_id != previous_account_id {
+                                    self.reconciliation_currency = account.currency().clone();
+                                }
+                                ui.label("Currency:").on_hover_text("Currency pocket of the account to reconcile, each one has its own statement balance.");
+                                ComboBox::from_id_salt("Reconciliation currency")
+                                    .selected_text(format!("{}", self.reconciliation_currency))
+                                    .show_ui(ui, |ui| {
+                                        for possible_currency in [account.currency()].into_iter().chain(account.pockets()) {
+                                            ui.selectable_value(
+                                                &mut self.reconciliation_currency,
+                                                possible_currency.clone(),
+                                                format!("{possible_currency}"),
+                                            );
+                                        }
+                                    });
+                                ui.end_row();
+                            }
+
+                            ui.label("Statement date:").on_hover_text("Closing date of the bank statement.");
+                            ui.add(DatePickerButton::new(&mut self.reconciliation_date).id_salt("reconciliation_date"));
+                            ui.end_row();
+
+                            ui.label("Statement balance:").on_hover_text("Closing balance of the bank statement.");
+                            ui.text_edit_singleline(&mut self.reconciliation_balance_tentative);
+                            if self.is_valid_reconciliation_balance() {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid balance!");
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid balance!");
+                            }
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    if self.reconciliation_account_id < 0 {
+                        return;
+                    }
+
+                    match self.database.last_reconciliation(self.reconciliation_account_id, &self.reconciliation_currency) {
+                        Ok(Some((date, balance))) => {
+                            ui.label(format!("Last reconciled on {} with a balance of {:.2}", date, balance));
+                        }
+                        Ok(None) => {
+                            ui.label("Never reconciled");
+                        }
+                        Err(e) => {
+                            self.throw_polars_error(e);
+                        }
+                    }
+
+                    let cleared_balance: f64 = match self
+                        .database
+                        .cleared_balance(self.reconciliation_account_id, &self.reconciliation_currency, self.reconciliation_date)
+                    {
+                        Ok(cleared_balance) => cleared_balance,
+                        Err(e) => {
+                            self.throw_error(e);
+                            return;
+                        }
+                    };
+                    ui.label(format!("Cleared balance: {:.2}", cleared_balance));
+
+                    if let Ok(statement_balance) = self.reconciliation_balance_tentative.parse::<f64>() {
+                        let difference: f64 = statement_balance - cleared_balance;
+                        if difference.abs() < 0.005 {
+                            ui.colored_label(Color32::from_rgb(110, 255, 110), "Difference: 0.00");
+                            if ui.button("Reconcile").on_hover_text("Locks the cleared movements up to the statement date.").clicked() {
+                                match self.database.reconcile(
+                                    self.reconciliation_account_id,
+                                    &self.reconciliation_currency,
+                                    self.reconciliation_date,
+                                    statement_balance,
+                                ).and_then(|_| self.database.save()) {
+                                    Ok(_) => {
+                                        self.reconciliation_balance_tentative = String::default();
+                                    }
+                                    Err(e) => {
+                                        self.throw_error(e);
+                                    }
+                                }
+                            }
+                        } else {
+                            ui.colored_label(Color32::from_rgb(255, 0, 0), format!("Difference: {:.2}", difference));
+                        }
+                    }
+                    ui.separator();
+
+                    let rows: Vec<ReconciliationRow> = match self.unreconciled_rows() {
+                        Ok(rows) => rows,
+                        Err(e) => {
+                            self.throw_error(e);
+                            return;
+                        }
+                    };
+
+                    let mut toggled: Option<(i64, bool)> = None;
+                    TableBuilder::new(ui)
+                        .columns(Column::auto().resizable(true), 6)
+                        .striped(true)
+                        .cell_layout(Layout::right_to_left(Align::Center))
+                        .header(20.0, |mut header| {
+                            for column_name in ["Date", "Type", "Value", "Currency", "Balance", "Cleared"] {
+                                header.col(|ui| {
+                                    ui.strong(column_name).on_hover_text(column_name);
+                                });
+                            }
+                        })
+                        .body(|mut body| {
+                            for row in rows.iter() {
+                                body.row(30.0, |mut row_ui| {
+                                    row_ui.col(|ui| {
+                                        // movements after the statement date do not count
+                                        if row.date > self.reconciliation_date {
+                                            ui.weak(row.date.to_string());
+                                        } else {
+                                            ui.label(row.date.to_string());
+                                        }
+                                    });
+                                    row_ui.col(|ui| {
+                                        ui.label(row.fund_movement_type.clone());
+                                    });
+                                    row_ui.col(|ui| {
+                                        ui.label(format!("{:.2}", row.value));
+                                    });
+                                    row_ui.col(|ui| {
+                                        ui.label(row.currency.clone());
+                                    });
+                                    row_ui.col(|ui| {
+                                        ui.label(format!("{:.2}", row.balance));
+                                    });
+                                    row_ui.col(|ui| {
+                                        let mut cleared: bool = row.cleared;
+                                        if ui.checkbox(&mut cleared, "").changed() {
+                                            toggled = Some((row.fund_movement_id, cleared));
+                                        }
+                                    });
+                                });
+                            }
+                        });
+
+                    if let Some((fund_movement_id, cleared)) = toggled {
+                        let status: TransactionStatus = if cleared {
+                            TransactionStatus::Cleared
+                        } else {
+                            TransactionStatus::Pending
+                        };
+                        match self.database.set_fund_movement_status(fund_movement_id, &status) {
+                            Ok(_) => {
+                                if let Err(e) = self.database.save() {
+                                    self.throw_error(e);
+                                }
+                            }
+                            Err(e) => {
+                                self.throw_polars_error(e);
+                            }
+                        }
+                    }
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_reconciliation_window = false;
+                }
+            },
+        );
+    }
+
+    fn is_valid_balance_assertion_value(&self) -> bool {
+        self.balance_assertion_value_tentative
+            .parse::<f64>()
+            .is_ok()
+    }
+
+    /// Refreshes the results of the balance assertions.
+    pub fn load_balance_assertions(&mut self) {
+        match self.database.balance_assertions_report() {
+            Ok(s) => {
+                self.balance_assertions_csv = s;
+                self.balance_assertions_csv_correct = true;
+            }
+            Err(e) => {
+                self.balance_assertions_csv_correct = false;
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_balance_assertion_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("balance_assertion_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Balance assertions window")
+                .with_inner_size([WINDOW_WIDTH * 1.2, WINDOW_HEIGHT * 1.5]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let account_string: String = if self.balance_assertion_account_id >= 0 {
+                        self.database
+                            .account(self.balance_assertion_account_id)
+                            .map(|account| account.to_string())
+                            .unwrap_or_default()
+                    } else {
+                        String::from("Select account")
+                    };
+
+                    let mut changed: bool = false;
+                    egui::Grid::new("balance_assertion_input")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Account:").on_hover_text("Account whose balance is asserted.");
+                            ComboBox::from_id_salt("Balance assertion account")
+                                .selected_text(account_string)
+                                .show_ui(ui, |ui| {
+                                    match self.database.iter_account_ids() {
+                                        Ok(iterator) => {
+                                            for account_id in iterator {
+                                                ui.selectable_value(
+                                                    &mut self.balance_assertion_account_id,
+                                                    account_id,
+                                                    self.database
+                                                        .account(account_id)
+                                                        .unwrap() // safe because we iterate over the ids!
+                                                        .to_string(),
+                                                );
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            if self.balance_assertion_account_id >= 0 {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid account!");
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Please select an account!");
+                            }
+                            ui.end_row();
+
+                            ui.label("Date:").on_hover_text("The balance is the one at the end of this date.");
+                            ui.add(DatePickerButton::new(&mut self.balance_assertion_date).id_salt("balance_assertion_date"));
+                            ui.end_row();
+
+                            ui.label("Balance:").on_hover_text("Balance held by the account.");
+                            ui.text_edit_singleline(&mut self.balance_assertion_value_tentative);
+                            if self.is_valid_balance_assertion_value() {
+                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Valid balance!");
+                            } else {
+                                ui.colored_label(Color32::from_rgb(255, 0, 0), "Invalid balance!");
+                            }
+                            ui.end_row();
+
+                            ui.label("Currency:").on_hover_text("Currency of the balance.");
+                            ComboBox::from_id_salt("Balance assertion currency")
+                                .selected_text(format!("{}", self.balance_assertion_currency))
+                                .show_ui(ui, |ui| {
+                                    for possible_currency in Currency::iter() {
+                                        ui.selectable_value(
+                                            &mut self.balance_assertion_currency,
+                                            possible_currency.clone(),
+                                            format!("{possible_currency}"),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("");
+                            if (self.balance_assertion_account_id >= 0) & self.is_valid_balance_assertion_value()
+                                && ui.button("Add assertion").on_hover_text("Save balance assertion into the database.").clicked() {
+                                    let balance_assertion: BalanceAssertion = BalanceAssertion::new(
+                                        self.balance_assertion_account_id,
+                                        self.balance_assertion_date,
+                                        self.balance_assertion_value_tentative.parse::<f64>().expect("Error parsing balance"),
+                                        self.balance_assertion_currency.clone(),
+                                    );
+                                    match self.database.insert_balance_assertion(&balance_assertion) {
+                                        Ok(_) => {
+                                            self.balance_assertion_value_tentative = String::default();
+                                            changed = true;
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                }
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    if self.balance_assertions_csv_correct {
+                        let balance_assertions_csv: String = self.balance_assertions_csv.clone();
+                        let header_line: String = balance_assertions_csv.split("\n").collect::<Vec<&str>>()[0].to_string();
+                        let row_lines: Vec<&str> = balance_assertions_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+                        let column_count: usize = header_line.split(",").count();
+
+                        let mut remove_balance_assertion_id: Option<i64> = None;
+                        TableBuilder::new(ui)
+                            .columns(Column::auto().resizable(true), column_count)
+                            .striped(true)
+                            .cell_layout(Layout::right_to_left(Align::Center))
+                            .header(20.0, |mut header| {
+                                for column_name in header_line.split(",") {
+                                    header.col(|ui| {
+                                        ui.strong(column_name).on_hover_text(column_name);
+                                    });
+                                }
+                            })
+                            .body(|mut body| {
+                                for row_line in row_lines {
+                                    let fails: bool = row_line.contains(",Fails,");
+                                    body.row(30.0, |mut row_ui| {
+                                        for (i, element) in row_line.split(",").enumerate() {
+                                            row_ui.col(|ui| {
+                                                if i == column_count - 1 {
+                                                    // index of the last column
+                                                    if ui.button("Remove").on_hover_text("Removes the assertion from the database.").clicked() {
+                                                        remove_balance_assertion_id = element.parse().ok();
+                                                    }
+                                                } else if fails {
+                                                    ui.colored_label(Color32::from_rgb(255, 0, 0), element);
+                                                } else {
+                                                    ui.label(element);
+                                                }
+                                            });
+                                        }
+                                    });
+                                }
+                            });
+
+                        if let Some(balance_assertion_id) = remove_balance_assertion_id {
+                            match self.database.delete_balance_assertion(balance_assertion_id) {
+                                Ok(_) => {
+                                    changed = true;
+                                }
+                                Err(e) => {
+                                    self.throw_polars_error(e);
+                                }
+                            }
+                        }
+                    }
+
+                    if changed {
+                        match self.database.save() {
+                            Ok(_) => {
+                                self.load_balance_assertions();
+                            }
+                            Err(e) => {
+                                self.throw_error(e);
+                            }
+                        }
+                    }
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_balance_assertion_window = false;
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use crate::modules::database::templates::DueParty;
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use chrono::Local;
+use eframe::egui;
+use egui::{Align, Layout};
+use egui_extras::*;
+
+enum DuePartyAction {
+    Confirm,
+    Skip,
+    Edit,
+}
+
+impl AppState {
+    /// Refreshes the queue of parties generated from recurring templates.
+    pub fn load_due_parties(&mut self) {
+        match self.database.due_parties(Local::now().date_naive()) {
+            Ok(due_parties) => {
+                self.due_parties = due_parties;
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_due_parties_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("due_parties_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Due transactions window")
+                .with_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let mut i_action: usize = 0;
+                    let mut action: Option<DuePartyAction> = None;
+
+                    TableBuilder::new(ui)
+                        .columns(Column::auto().resizable(true).at_least(50.0), 6)
+                        .striped(true)
+                        .cell_layout(Layout::right_to_left(Align::Center))
+                        .header(20.0, |mut header| {
+                            for column_name in
+                                ["Template", "Date", "Transactions", "Confirm", "Skip", "Edit"]
+                            {
+                                header.col(|ui| {
+                                    ui.strong(column_name).on_hover_text(column_name);
+                                });
+                            }
+                        })
+                        .body(|mut body| {
+                            if self.due_parties.is_empty() {
+                                body.row(30.0, |mut row| {
+                                    row.col(|ui| {
+                                        ui.label("Nothing");
+                                    });
+                                    row.col(|ui| {
+                                        ui.label("is");
+                                    });
+                                    row.col(|ui| {
+                                        ui.label("due");
+                                    });
+                                    for _ in 0..3 {
+                                        row.col(|ui| {
+                                            ui.label("");
+                                        });
+                                    }
+                                });
+                            }
+
+                            for (i, due_party) in self.due_parties.iter().enumerate() {
+                                body.row(30.0, |mut row| {
+                                    row.col(|ui| {
+                                        ui.label(due_party.template_name.clone());
+                                    });
+                                    row.col(|ui| {
+                                        ui.label(due_party.date.to_string());
+                                    });
+                                    row.col(|ui| {
+                                        ui.label(due_party.party.transactions.len().to_string())
+                                            .on_hover_text(
+                                                due_party
+                                                    .party
+                                                    .transactions
+                                                    .iter()
+                                                    .map(|transaction| transaction.to_string())
+                                                    .collect::<Vec<String>>()
+                                                    .join("\n"),
+                                            );
+                                    });
+                                    row.col(|ui| {
+                                        if ui
+                                            .button("Confirm")
+                                            .on_hover_text("Adds the party to the database as it is.")
+                                            .clicked()
+                                        {
+                                            i_action = i;
+                                            action = Some(DuePartyAction::Confirm);
+                                        }
+                                    });
+                                    row.col(|ui| {
+                                        if ui
+                                            .button("Skip")
+                                            .on_hover_text("Discards this occurrence of the template.")
+                                            .clicked()
+                                        {
+                                            i_action = i;
+                                            action = Some(DuePartyAction::Skip);
+                                        }
+                                    });
+                                    row.col(|ui| {
+                                        if ui
+                                            .button("Edit")
+                                            .on_hover_text("Launches the input menu with the party already loaded. The occurrence is handled once the party is added.")
+                                            .clicked()
+                                        {
+                                            i_action = i;
+                                            action = Some(DuePartyAction::Edit);
+                                        }
+                                    });
+                                });
+                            }
+                        });
+
+                    if let Some(action) = action {
+                        match action {
+                            DuePartyAction::Edit => {
+                                // the occurrence is handled once the edited party is added, and
+                                // stays in the queue until then
+                                let due_party: &DueParty = &self.due_parties[i_action];
+                                match self.database.template(due_party.template_id) {
+                                    Ok(template) => {
+                                        self.party = template.party(due_party.date);
+                                        self.pending_due_party =
+                                            Some((due_party.template_id, due_party.date));
+                                        self.show_input_party_window = true;
+                                    }
+                                    Err(e) => {
+                                        self.throw_error(e);
+                                    }
+                                }
+                            }
+                            DuePartyAction::Confirm | DuePartyAction::Skip => {
+                                let mut due_party: DueParty = self.due_parties.remove(i_action);
+
+                                let result: Result<(), Box<dyn std::error::Error>> =
+                                    match action {
+                                        DuePartyAction::Confirm => {
+                                            self.database.insert_party(&mut due_party.party)
+                                        }
+                                        _ => Ok(()),
+                                    };
+
+                                match result.and_then(|_| {
+                                    self.database
+                                        .handle_due_party(due_party.template_id, due_party.date)?;
+                                    self.database.save()
+                                }) {
+                                    Ok(_) => {}
+                                    Err(e) => {
+                                        self.throw_error(e);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_due_parties_window = false;
+                }
+            },
+        );
+    }
+}
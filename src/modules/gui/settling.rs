@@ -0,0 +1,283 @@
+use crate::modules::financial::*;
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox};
+use egui::{Align, Layout};
+use egui_extras::*;
+use strum::IntoEnumIterator;
+
+impl AppState {
+    fn generate_iou_report(&mut self) {
+        match self
+            .database
+            .iou_report(&self.iou_report_currency, self.iou_report_include_planned)
+        {
+            Ok(s) => {
+                self.iou_report_csv = s;
+                self.iou_report_csv_correct = true;
+            }
+            Err(e) => {
+                self.iou_report_csv_correct = false;
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn settle_iou(&mut self) {
+        match self.database.settlement_party(
+            self.iou_settlement_entity_id,
+            &self.iou_settlement_currency,
+            self.iou_settlement_account_id,
+            self.iou_settlement_date,
+        ) {
+            Ok(mut party) => match self.database.insert_party(&mut party) {
+                Ok(_) => match self.database.save() {
+                    Ok(_) => {
+                        self.iou_settlement_entity_id = -1;
+                        self.generate_iou_report();
+                    }
+                    Err(e) => {
+                        self.throw_error(e);
+                    }
+                },
+                Err(e) => {
+                    self.throw_error(e);
+                }
+            },
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_iou_window(&mut self, ctx: &egui::Context) {
+        let settlement_entity_string: String = if self.iou_settlement_entity_id >= 0 {
+            match self.database.entity(self.iou_settlement_entity_id) {
+                Ok(entity) => entity.to_string(),
+                Err(e) => {
+                    self.iou_settlement_entity_id = -1;
+                    self.throw_error(e);
+                    String::from("Nobody")
+                }
+            }
+        } else {
+            String::from("Nobody")
+        };
+        let settlement_account_string: String =
+            match self.database.account(self.iou_settlement_account_id) {
+                Ok(account) => account.to_string(),
+                Err(_e) => String::from("No account"),
+            };
+        let settlement_balance: f64 = if self.iou_settlement_entity_id >= 0 {
+            match self
+                .database
+                .iou_balance(self.iou_settlement_entity_id, &self.iou_settlement_currency)
+            {
+                Ok(balance) => balance,
+                Err(e) => {
+                    self.throw_polars_error(e);
+                    0.0
+                }
+            }
+        } else {
+            0.0
+        };
+
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("iou_window"),
+            egui::ViewportBuilder::default()
+                .with_title("IOUs window")
+                .with_inner_size([WINDOW_WIDTH * 1.2, WINDOW_HEIGHT * 1.5]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let iou_report_csv = self.iou_report_csv.clone();
+                    let header_line: String = iou_report_csv.split("\n").collect::<Vec<&str>>()[0].to_string();
+                    let row_lines: Vec<&str> = iou_report_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+                    let column_count: usize = header_line.split(",").count();
+
+                    StripBuilder::new(ui)
+                        .size(Size::exact(240.0))
+                        .size(Size::remainder().at_least(120.0))
+                        .vertical(|mut strip| {
+                            strip.cell(|ui| {
+                                egui::Grid::new("iou_report")
+                                    .num_columns(3)
+                                    .spacing([45.0, 4.0])
+                                    .show(ui, |ui| {
+                                        ui.label("Currency:").on_hover_text("Currency in which to express the balances, at today's rate.");
+                                        ComboBox::from_id_salt("IOU report currency")
+                                            .selected_text(format!("{}", self.iou_report_currency))
+                                            .show_ui(ui, |ui| {
+                                                for possible_iou_report_currency in Currency::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.iou_report_currency,
+                                                        possible_iou_report_currency.clone(),
+                                                        format!("{possible_iou_report_currency}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Include planned:").on_hover_text("Include the debts of planned parties, not only the pending and cleared ones.");
+                                        ui.checkbox(&mut self.iou_report_include_planned, "");
+                                        ui.end_row();
+
+                                        ui.label("");
+                                        if ui.button("Generate!").clicked() {
+                                            self.generate_iou_report();
+                                        }
+                                    });
+                                ui.separator();
+
+                                egui::Grid::new("iou_settlement")
+                                    .num_columns(3)
+                                    .spacing([45.0, 4.0])
+                                    .show(ui, |ui| {
+                                        ui.label("Person:").on_hover_text("Person with whom to settle the balance.");
+                                        ComboBox::from_id_salt("IOU settlement entity")
+                                            .selected_text(settlement_entity_string.clone())
+                                            .show_ui(ui, |ui| {
+                                                // archived people might still have debts to settle
+                                                match self.database.iter_entity_ids() {
+                                                    Ok(iterator) => {
+                                                        for entity_id in iterator {
+                                                            match self.database.entity(entity_id) {
+                                                                Ok(entity) => {
+                                                                    if entity.entity_type() == &EntityType::Human {
+                                                                        ui.selectable_value(
+                                                                            &mut self.iou_settlement_entity_id,
+                                                                            entity_id,
+                                                                            entity.to_string(),
+                                                                        );
+                                                                    }
+                                                                }
+                                                                Err(e) => {
+                                                                    self.throw_error(e);
+                                                                }
+                                                            }
+                                                        }
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_polars_error(e);
+                                                    }
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Currency:").on_hover_text("Currency of the balance to settle.");
+                                        ComboBox::from_id_salt("IOU settlement currency")
+                                            .selected_text(format!("{}", self.iou_settlement_currency))
+                                            .show_ui(ui, |ui| {
+                                                for possible_iou_settlement_currency in Currency::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.iou_settlement_currency,
+                                                        possible_iou_settlement_currency.clone(),
+                                                        format!("{possible_iou_settlement_currency}"),
+                                                    );
+                                                }
+                                            });
+                                        if settlement_balance > 0.0 {
+                                            ui.label(format!("Owes us {:.2}", settlement_balance));
+                                        } else if settlement_balance < 0.0 {
+                                            ui.label(format!("We owe {:.2}", -settlement_balance));
+                                        } else {
+                                            ui.label("Nothing to settle");
+                                        }
+                                        ui.end_row();
+
+                                        ui.label("Account:").on_hover_text("Account through which the money is handed over.");
+                                        ComboBox::from_id_salt("IOU settlement account")
+                                            .selected_text(settlement_account_string.clone())
+                                            .show_ui(ui, |ui| {
+                                                match self.database.iter_open_account_ids() {
+                                                    Ok(iterator) => {
+                                                        for account_id in iterator {
+                                                            match self.database.account(account_id) {
+                                                                Ok(account) => {
+                                                                    if account.has_currency(&self.iou_settlement_currency) {
+                                                                        ui.selectable_value(
+                                                                            &mut self.iou_settlement_account_id,
+                                                                            account_id,
+                                                                            account.to_string(),
+                                                                        );
+                                                                    }
+                                                                }
+                                                                Err(e) => {
+                                                                    self.throw_error(e);
+                                                                }
+                                                            }
+                                                        }
+                                                    }
+                                                    Err(e) => {
+                                                        self.throw_polars_error(e);
+                                                    }
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Date:").on_hover_text("Date in which the balance is settled.");
+                                        ui.add(DatePickerButton::new(&mut self.iou_settlement_date).id_salt("iou_settlement_date"));
+                                        ui.end_row();
+
+                                        ui.label("");
+                                        let is_valid_account: bool = match self.database.account(self.iou_settlement_account_id) {
+                                            Ok(account) => account.has_currency(&self.iou_settlement_currency),
+                                            Err(_e) => false,
+                                        };
+                                        if (settlement_balance != 0.0) & is_valid_account
+                                            && ui.button("Settle").on_hover_text("Enters the party that clears the balance with the person.").clicked() {
+                                                self.settle_iou();
+                                            }
+                                        ui.end_row();
+                                    });
+                                ui.separator();
+                            });
+                            if self.iou_report_csv_correct {
+                            strip.cell(|ui| {
+                                TableBuilder::new(ui)
+                                    .columns(Column::auto().resizable(true), column_count)
+                                    .striped(true)
+                                    .cell_layout(Layout::right_to_left(Align::Center))
+                                    .header(20.0, |mut header| {
+                                        for column_name in header_line.split(",") {
+                                            header.col(|ui| {
+                                                ui.strong(column_name).on_hover_text(column_name);
+                                            });
+                                        }
+                                    })
+                                    .body(|mut body| {
+                                        for row_line in row_lines {
+                                            let is_total_row: bool = row_line.starts_with("Total,");
+                                            let is_owed_by_us: bool = row_line.contains(",We owe,");
+                                            body.row(30.0, |mut row_ui| {
+                                                for element in row_line.split(",") {
+                                                    row_ui.col(|ui| {
+                                                        if is_total_row {
+                                                            ui.strong(element);
+                                                        } else if is_owed_by_us {
+                                                            ui.colored_label(Color32::from_rgb(255, 0, 0), element);
+                                                        } else {
+                                                            ui.label(element);
+                                                        }
+                                                    });
+                                                }
+                                            });
+                                        }
+                                    });
+                                ui.separator();
+                            });
+                            }
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_iou_window = false;
+                }
+            },
+        );
+    }
+}
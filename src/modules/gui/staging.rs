@@ -0,0 +1,388 @@
+use crate::modules::database::staging::{DraftParty, StagingFlag};
+use crate::modules::financial::TransactionType;
+use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
+use eframe::egui;
+use eframe::egui::{Color32, ComboBox, ScrollArea};
+use egui::{Align, Layout};
+use egui_autocomplete::AutoCompleteTextEdit;
+use egui_extras::*;
+
+/// Draft party as shown in the staging window
+struct StagingRow {
+    staging_id: i64,
+    draft: DraftParty,
+    flags: Vec<StagingFlag>,
+    rule: Option<(String, String)>, // name and explanation of the rule that categorized it
+}
+
+impl AppState {
+    /// Returns the drafts in the staging inbox, with what keeps them from being committed.
+    fn staging_rows(&self) -> Result<Vec<StagingRow>, Box<dyn std::error::Error>> {
+        let mut rows: Vec<StagingRow> = Vec::new();
+        for staging_id in self.database.iter_staged_ids()? {
+            let draft: DraftParty = self.database.staged_draft(staging_id)?;
+            // the rule may have been deleted since
+            let rule: Option<(String, String)> =
+                match self.database.categorization_rule(draft.rule_id) {
+                    Ok(rule) => Some((rule.name.clone(), self.database.explain_rule(&rule)?)),
+                    Err(_) => None,
+                };
+            rows.push(StagingRow {
+                staging_id,
+                draft,
+                flags: self.database.staging_flags(staging_id)?,
+                rule,
+            });
+        }
+
+        Ok(rows)
+    }
+
+    fn selected_staging_ids(&self) -> Vec<i64> {
+        let mut staging_ids: Vec<i64> = self.staging_selected_ids.iter().copied().collect();
+        staging_ids.sort();
+        staging_ids
+    }
+
+    fn assign_staged_entity(&mut self) {
+        match self
+            .database
+            .assign_staged_entity(&self.selected_staging_ids(), self.staging_entity_id)
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {}
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn assign_staged_category(&mut self) {
+        match self
+            .database
+            .assign_staged_category(
+                &self.selected_staging_ids(),
+                &self.staging_type,
+                &self.staging_category,
+                &self.staging_subcategory,
+            )
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {}
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    fn set_staged_accepted(&mut self, staging_ids: &[i64], accepted: bool) {
+        match self.database.set_staged_accepted(staging_ids, accepted) {
+            Ok(_) => {
+                if let Err(e) = self.database.save() {
+                    self.throw_error(e);
+                }
+            }
+            Err(e) => {
+                self.throw_polars_error(e);
+            }
+        }
+    }
+
+    fn discard_staged(&mut self) {
+        match self.database.discard_staged(&self.selected_staging_ids()) {
+            Ok(_) => match self.database.save() {
+                Ok(_) => {
+                    self.staging_selected_ids.clear();
+                }
+                Err(e) => {
+                    self.throw_error(e);
+                }
+            },
+            Err(e) => {
+                self.throw_polars_error(e);
+            }
+        }
+    }
+
+    fn commit_staged(&mut self) {
+        match self
+            .database
+            .commit_staged()
+            .and_then(|_| self.database.save())
+        {
+            Ok(_) => {
+                self.staging_selected_ids.clear();
+            }
+            Err(e) => {
+                self.throw_error(e);
+            }
+        }
+    }
+
+    pub fn handle_show_staging_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("staging_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Staging inbox window")
+                .with_inner_size([WINDOW_WIDTH * 2.0, WINDOW_HEIGHT * 2.0]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let rows: Vec<StagingRow> = match self.staging_rows() {
+                        Ok(rows) => rows,
+                        Err(e) => {
+                            self.throw_error(e);
+                            return;
+                        }
+                    };
+                    // drafts committed or discarded elsewhere cannot stay selected
+                    self.staging_selected_ids
+                        .retain(|staging_id| rows.iter().any(|row| row.staging_id == *staging_id));
+
+                    let entity_string: String = if self.staging_entity_id >= 0 {
+                        self.database
+                            .entity(self.staging_entity_id)
+                            .map(|entity| entity.to_string())
+                            .unwrap_or_default()
+                    } else {
+                        String::from("Select entity")
+                    };
+                    let has_selection: bool = !self.staging_selected_ids.is_empty();
+
+                    egui::Grid::new("staging_assignment")
+                        .num_columns(3)
+                        .spacing([45.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Selection:").on_hover_text("Drafts that the changes below apply to.");
+                            ui.label(format!("{} of {} drafts", self.staging_selected_ids.len(), rows.len()));
+                            ui.horizontal(|ui| {
+                                if ui.button("Select all").clicked() {
+                                    self.staging_selected_ids = rows.iter().map(|row| row.staging_id).collect();
+                                }
+                                if ui.button("Select none").clicked() {
+                                    self.staging_selected_ids.clear();
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label("Entity:").on_hover_text("Entity to assign to the selected drafts.");
+                            ComboBox::from_id_salt("Staging entity")
+                                .selected_text(entity_string)
+                                .show_ui(ui, |ui| {
+                                    match self.database.iter_active_entity_ids() {
+                                        Ok(iterator) => {
+                                            for entity_id in iterator {
+                                                ui.selectable_value(
+                                                    &mut self.staging_entity_id,
+                                                    entity_id,
+                                                    self.database
+                                                        .entity(entity_id)
+                                                        .unwrap() // safe because we iterate over the ids!
+                                                        .to_string(),
+                                                );
+                                            }
+                                        }
+                                        Err(e) => {
+                                            self.throw_polars_error(e);
+                                        }
+                                    }
+                                });
+                            if has_selection & (self.staging_entity_id >= 0)
+                                && ui.button("Assign entity").clicked() {
+                                    self.assign_staged_entity();
+                                }
+                            ui.end_row();
+
+                            ui.label("Type:").on_hover_text("The category is only assigned to the selected drafts of this type.");
+                            ComboBox::from_id_salt("Staging type")
+                                .selected_text(format!("{}", self.staging_type))
+                                .show_ui(ui, |ui| {
+                                    for possible_type in [TransactionType::Income, TransactionType::Expense] {
+                                        ui.selectable_value(
+                                            &mut self.staging_type,
+                                            possible_type.clone(),
+                                            format!("{possible_type}"),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Category:").on_hover_text("Category to assign to the selected drafts.");
+                            match self.database.transaction_categories(&self.staging_type) {
+                                Ok(categories) => {
+                                    ui.add(
+                                        AutoCompleteTextEdit::new(&mut self.staging_category, categories)
+                                            .max_suggestions(10)
+                                            .highlight_matches(true),
+                                    );
+                                }
+                                Err(e) => {
+                                    self.throw_polars_error(e);
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label("Subcategory:").on_hover_text("Optional.");
+                            match self.database.transaction_subcategories(&self.staging_type, self.staging_category.clone()) {
+                                Ok(subcategories) => {
+                                    ui.add(
+                                        AutoCompleteTextEdit::new(&mut self.staging_subcategory, subcategories)
+                                            .max_suggestions(10)
+                                            .highlight_matches(true),
+                                    );
+                                }
+                                Err(e) => {
+                                    self.throw_polars_error(e);
+                                }
+                            }
+                            match self.database.is_known_category(&self.staging_type, &self.staging_category, &self.staging_subcategory) {
+                                Ok(true) => {
+                                    if has_selection & ui.button("Assign category").clicked() {
+                                        self.assign_staged_category();
+                                    }
+                                }
+                                _ => {
+                                    ui.colored_label(Color32::from_rgb(255, 0, 0), "Unknown category!");
+                                }
+                            }
+                            ui.end_row();
+
+                            ui.label("");
+                            if has_selection {
+                                ui.horizontal(|ui| {
+                                    if ui.button("Accept").on_hover_text("Commits the selected drafts with the next commit.").clicked() {
+                                        self.set_staged_accepted(&self.selected_staging_ids(), true);
+                                    }
+                                    if ui.button("Hold back").on_hover_text("Keeps the selected drafts in the inbox at the next commit.").clicked() {
+                                        self.set_staged_accepted(&self.selected_staging_ids(), false);
+                                    }
+                                    if ui.button("Discard").on_hover_text("Removes the selected drafts from the inbox without committing them.").clicked() {
+                                        self.discard_staged();
+                                    }
+                                });
+                            }
+                            ui.end_row();
+                        });
+                    ui.separator();
+
+                    let n_accepted: usize = rows.iter().filter(|row| row.draft.accepted).count();
+                    if n_accepted > 0
+                        && ui.button(format!("Commit {} accepted parties", n_accepted)).on_hover_text("Inserts all the accepted drafts at once. Nothing is inserted if any of them is flagged, other than as a possible duplicate.").clicked() {
+                            self.commit_staged();
+                            return;
+                        }
+
+                    let mut toggled: Option<(i64, bool)> = None;
+                    let mut selected: Option<(i64, bool)> = None;
+                    ScrollArea::horizontal().show(ui, |ui| {
+                        TableBuilder::new(ui)
+                            .columns(Column::auto().resizable(true), 11)
+                            .striped(true)
+                            .cell_layout(Layout::right_to_left(Align::Center))
+                            .header(20.0, |mut header| {
+                                for column_name in ["Select", "Date", "Account", "Value", "Payee", "Description", "Entity", "Category", "Rule", "Flags", "Accepted"] {
+                                    header.col(|ui| {
+                                        ui.strong(column_name).on_hover_text(column_name);
+                                    });
+                                }
+                            })
+                            .body(|mut body| {
+                                for row in rows.iter() {
+                                    let draft: &DraftParty = &row.draft;
+                                    body.row(30.0, |mut row_ui| {
+                                        row_ui.col(|ui| {
+                                            let mut is_selected: bool = self.staging_selected_ids.contains(&row.staging_id);
+                                            if ui.checkbox(&mut is_selected, "").changed() {
+                                                selected = Some((row.staging_id, is_selected));
+                                            }
+                                        });
+                                        row_ui.col(|ui| {
+                                            ui.label(draft.movement.date.to_string());
+                                        });
+                                        row_ui.col(|ui| {
+                                            ui.label(
+                                                self.database
+                                                    .account(draft.account_id)
+                                                    .map(|account| account.name())
+                                                    .unwrap_or_default(),
+                                            );
+                                        });
+                                        row_ui.col(|ui| {
+                                            ui.label(format!("{:.2} {}", draft.movement.value, draft.movement.currency));
+                                        });
+                                        row_ui.col(|ui| {
+                                            ui.label(draft.movement.payee.clone());
+                                        });
+                                        row_ui.col(|ui| {
+                                            ui.label(draft.movement.description.clone());
+                                        });
+                                        row_ui.col(|ui| {
+                                            ui.label(
+                                                self.database
+                                                    .entity(draft.entity_id)
+                                                    .map(|entity| entity.name())
+                                                    .unwrap_or_default(),
+                                            );
+                                        });
+                                        row_ui.col(|ui| {
+                                            ui.label(
+                                                [draft.category.as_str(), draft.subcategory.as_str()]
+                                                    .iter()
+                                                    .filter(|name| !name.is_empty())
+                                                    .copied()
+                                                    .collect::<Vec<&str>>()
+                                                    .join(" / "),
+                                            );
+                                        });
+                                        row_ui.col(|ui| {
+                                            if let Some((rule_name, explanation)) = &row.rule {
+                                                ui.label(rule_name).on_hover_text(explanation);
+                                            }
+                                        });
+                                        row_ui.col(|ui| {
+                                            let flags_string: String = row.flags
+                                                .iter()
+                                                .map(|flag| flag.to_string())
+                                                .collect::<Vec<String>>()
+                                                .join(", ");
+                                            if row.flags.is_empty() {
+                                                ui.colored_label(Color32::from_rgb(110, 255, 110), "Ready");
+                                            } else if row.flags.iter().any(|flag| flag.is_blocking()) {
+                                                ui.colored_label(Color32::from_rgb(255, 0, 0), flags_string);
+                                            } else {
+                                                ui.label(flags_string).on_hover_text("Looks like a party already in the ledger. It can be committed anyway.");
+                                            }
+                                        });
+                                        row_ui.col(|ui| {
+                                            let mut accepted: bool = draft.accepted;
+                                            if ui.checkbox(&mut accepted, "").changed() {
+                                                toggled = Some((row.staging_id, accepted));
+                                            }
+                                        });
+                                    });
+                                }
+                            });
+                    });
+
+                    if let Some((staging_id, is_selected)) = selected {
+                        if is_selected {
+                            self.staging_selected_ids.insert(staging_id);
+                        } else {
+                            self.staging_selected_ids.remove(&staging_id);
+                        }
+                    }
+                    if let Some((staging_id, accepted)) = toggled {
+                        self.set_staged_accepted(&[staging_id], accepted);
+                    }
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_staging_window = false;
+                }
+            },
+        );
+    }
+}
@@ -1,10 +1,10 @@
+use crate::modules::database::summaries::{Basis, ReportDate, TimeUnit};
 use crate::modules::financial::*;
 use crate::modules::gui::{AppState, WINDOW_HEIGHT, WINDOW_WIDTH};
 use eframe::egui;
 use egui::{Align, ComboBox, Layout};
 use egui_extras::*;
 use strum::IntoEnumIterator;
-use crate::modules::database::summaries::TimeUnit;
 
 impl AppState {
     pub fn handle_show_expense_summary_window(&mut self, ctx: &egui::Context) -> () {
@@ -56,12 +56,17 @@ impl AppState {
                                 });
                                         ui.end_row();
 
+                                        ui.label("Include planned:").on_hover_text("Include the planned expenses and incomes, not only the pending and cleared ones.");
+                                        ui.checkbox(&mut self.expense_summary_include_planned, "");
+                                        ui.end_row();
+
                                         ui.label("");
                                         if ui.button("Generate!").clicked() {
                                             match self.database.expenses_summary(
                                                 self.expense_summary_date_from,
                                                 self.expense_summary_date_to,
-                                                &self.expense_summary_currency
+                                                &self.expense_summary_currency,
+                                                self.expense_summary_include_planned,
                                             ) {
                                                 Ok(s) => {self.expense_summary_csv = s; self.expense_summary_csv_correct = true;},
                                                 Err(e) => {self.expense_summary_csv_correct = false; self.throw_error(e);}}
@@ -114,6 +119,113 @@ impl AppState {
             },
         )
     }
+    pub fn handle_show_tag_summary_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("tag_summary_window"),
+            egui::ViewportBuilder::default()
+                .with_title("Tag summary window")
+                .with_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT]),
+            |ctx, class| {
+                assert!(
+                    class == egui::ViewportClass::Immediate,
+                    "This egui backend doesn't support multiple viewports"
+                );
+
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let tag_summary_csv = self.tag_summary_csv.clone();
+                    let header_line: String = tag_summary_csv.split("\n").collect::<Vec<&str>>()[0].to_string();
+                    let row_lines: Vec<&str> = tag_summary_csv.split("\n").collect::<Vec<&str>>()[1..].to_vec();
+                    let column_count: usize = header_line.split(",").count();
+
+                    StripBuilder::new(ui)
+                        .size(Size::exact(40.0))
+                        .size(Size::remainder().at_least(120.0))
+                        .vertical(|mut strip| {
+                            strip.cell(|ui| {
+                                egui::Grid::new("tag_summary")
+                                    .num_columns(3)
+                                    .spacing([45.0, 4.0])
+                                    .show(ui, |ui| {
+                                        ui.label("Start date:").on_hover_text("Include transactions in the summary starting on the specified date, included.");
+                                        ui.add(DatePickerButton::new(&mut self.tag_summary_date_from).id_salt("tag_date_from"));
+                                        ui.end_row();
+
+                                        ui.label("End date:").on_hover_text("Include transactions in the summary until the specified date, included.");
+                                        ui.add(DatePickerButton::new(&mut self.tag_summary_date_to).id_salt("tag_date_to"));
+                                        ui.end_row();
+
+                                        ui.label("Currency:").on_hover_text("Currency in which to express the ammounts.");
+                                        ComboBox::from_id_salt("Tag summary currency")
+                                .selected_text(format!("{}", self.tag_summary_currency))
+                                .show_ui(ui, |ui| {
+                                    for possible_tag_summary_currency in Currency::iter() {
+                                        ui.selectable_value(
+                                            &mut self.tag_summary_currency,
+                                            possible_tag_summary_currency.clone(),
+                                            format!("{possible_tag_summary_currency}"),
+                                        );
+                                    }
+                                });
+                                        ui.end_row();
+
+                                        ui.label("Include planned:").on_hover_text("Include the planned expenses and incomes, not only the pending and cleared ones.");
+                                        ui.checkbox(&mut self.tag_summary_include_planned, "");
+                                        ui.end_row();
+
+                                        ui.label("");
+                                        if ui.button("Generate!").on_hover_text("A transaction with many tags counts in all of them.").clicked() {
+                                            match self.database.tag_summary(
+                                                self.tag_summary_date_from,
+                                                self.tag_summary_date_to,
+                                                &self.tag_summary_currency,
+                                                self.tag_summary_include_planned,
+                                            ) {
+                                                Ok(s) => {
+                                                    self.tag_summary_csv = s; self.tag_summary_csv_correct = true;
+                                                }
+                                                Err(e) => {self.tag_summary_csv_correct = false; self.throw_error(e);}}
+                                        }
+
+                                    });
+                                ui.separator();
+                            });
+                            if self.tag_summary_csv_correct {
+                            strip.cell(|ui| {
+                                TableBuilder::new(ui)
+                                        .columns(Column::auto().resizable(true), column_count)
+                                        .striped(true)
+                                        .cell_layout(Layout::right_to_left(Align::Center))
+                                        .header(20.0, |mut header| {
+                                            for column_name in header_line.split(",") {
+                                                header.col(|ui| {
+                                                    ui.strong(column_name)
+                                                        .on_hover_text(column_name);
+                                                });
+                                            }
+                                        })
+                                        .body(|mut body| {
+                                            for row_line in row_lines {
+                                                body.row(30.0, |mut row_ui| {
+                                                    for element in row_line.split(",") {
+                                                        row_ui.col(|ui| {
+                                                            ui.label(element);
+                                                        });
+                                                    }
+                                                });
+                                            }
+                                        });
+                                ui.separator();
+                            });
+                            }
+                        });
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_tag_summary_window = false;
+                }
+            },
+        )
+    }
+
     pub fn handle_show_fund_stand_window(&mut self, ctx: &egui::Context) -> () {
         ctx.show_viewport_immediate(
             egui::ViewportId::from_hash_of("fund_stand_summary_window"),
@@ -162,10 +274,15 @@ impl AppState {
                                             });
                                         ui.end_row();
 
+                                        ui.label("Include planned:").on_hover_text("Include the planned fund movements, not only the pending and cleared ones.");
+                                        ui.checkbox(&mut self.fund_stand_include_planned, "");
+                                        ui.end_row();
+
                                         ui.label("");
                                         if ui.button("Generate!").clicked() {
                                             match self.database.current_fund_stand(
-                                                self.fund_stand_currency.as_ref()
+                                                self.fund_stand_currency.as_ref(),
+                                                self.fund_stand_include_planned,
                                             ) {
                                                 Ok(s) => {self.fund_stand_csv = s; self.fund_stand_csv_correct = true;},
                                                 Err(e) => {self.fund_stand_csv_correct = false; self.throw_error(e);}
@@ -268,11 +385,46 @@ impl AppState {
                                             });
                                         ui.end_row();
 
+                                        ui.label("Date:").on_hover_text("Date that places the transactions in time: the booking date, or the value date of their fund movements when they settled later.");
+                                        ComboBox::from_id_salt("Expenses evolution report date")
+                                            .selected_text(format!("{}", self.expenses_evolution_report_date))
+                                            .show_ui(ui, |ui| {
+                                                for possible_report_date in ReportDate::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.expenses_evolution_report_date,
+                                                        possible_report_date.clone(),
+                                                        format!("{possible_report_date}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Basis:").on_hover_text("Cash: expenses count when they are paid. Accrual: amortized expenses are spread evenly over the months they cover.");
+                                        ComboBox::from_id_salt("Expenses evolution basis")
+                                            .selected_text(format!("{}", self.expenses_evolution_basis))
+                                            .show_ui(ui, |ui| {
+                                                for possible_basis in Basis::iter() {
+                                                    ui.selectable_value(
+                                                        &mut self.expenses_evolution_basis,
+                                                        possible_basis.clone(),
+                                                        format!("{possible_basis}"),
+                                                    );
+                                                }
+                                            });
+                                        ui.end_row();
+
+                                        ui.label("Include planned:").on_hover_text("Include the planned expenses, not only the pending and cleared ones.");
+                                        ui.checkbox(&mut self.expenses_evolution_include_planned, "");
+                                        ui.end_row();
+
                                         ui.label("");
                                         if ui.button("Generate!").clicked() {
                                             match self.database.evolution_table(
                                                 &self.expenses_evolution_currency,
                                                 &self.expenses_evolution_time_unit,
+                                                self.expenses_evolution_include_planned,
+                                                &self.expenses_evolution_report_date,
+                                                &self.expenses_evolution_basis,
                                             ) {
                                                 Ok(s) => {self.expenses_evolution_csv = s; self.expenses_evolution_csv_correct = true;},
                                                 Err(e) => {self.expenses_evolution_csv_correct = false; self.throw_error(e);}}
@@ -0,0 +1,177 @@
+use crate::modules::financial::Currency;
+use crate::modules::importers::{parse_amount, ImportedMovement};
+use chrono::NaiveDate;
+use polars::prelude::*;
+use std::fmt::Display;
+use std::io::Cursor;
+use std::str::FromStr;
+use strum_macros::{EnumIter, EnumString};
+
+/// How the amounts of a bank export tell the money that comes in from the money that goes out.
+#[derive(Debug, Default, Hash, PartialEq, Eq, EnumIter, Clone, EnumString)]
+pub enum AmountSign {
+    #[default]
+    Signed, // negative amounts leave the account
+    Inverted,     // positive amounts leave the account, like in credit card exports
+    SplitColumns, // money out and money in have their own columns
+}
+
+impl Display for AmountSign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            AmountSign::Signed => "Signed".to_string(),
+            AmountSign::Inverted => "Inverted".to_string(),
+            AmountSign::SplitColumns => "SplitColumns".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Layout of the CSV exports of a bank, saved under the name of the bank to import its
+/// statements again. Columns are given by their header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvMapping {
+    pub name: String,
+    pub delimiter: char,
+    pub skip_rows: usize, // lines before the header, like the details of the account
+    pub date_column: String,
+    pub date_format: String,   // like %d.%m.%Y
+    pub amount_column: String, // money out if the amounts are split in two columns
+    pub credit_column: String, // money in, only if the amounts are split in two columns
+    pub amount_sign: AmountSign,
+    pub decimal_separator: char,
+    pub currency_column: String, // empty if all amounts are in the currency of the account
+    pub payee_column: String,    // empty if there is none
+    pub description_column: String, // empty if there is none
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        CsvMapping {
+            name: String::default(),
+            delimiter: ',',
+            skip_rows: 0,
+            date_column: String::from("Date"),
+            date_format: String::from("%Y-%m-%d"),
+            amount_column: String::from("Amount"),
+            credit_column: String::default(),
+            amount_sign: AmountSign::default(),
+            decimal_separator: '.',
+            currency_column: String::default(),
+            payee_column: String::default(),
+            description_column: String::default(),
+        }
+    }
+}
+
+impl CsvMapping {
+    /// Reads the movements of a bank export. The movements are in the given currency,
+    /// usually the one of the account, unless there is a currency column. Rows without date,
+    /// like the totals some banks add at the end, are skipped.
+    pub(crate) fn parse(
+        &self,
+        content: &[u8],
+        currency: &Currency,
+    ) -> Result<Vec<ImportedMovement>, Box<dyn std::error::Error>> {
+        if !self.delimiter.is_ascii() {
+            return Err("The delimiter has to be a single ASCII character!".into());
+        }
+        let data_frame: DataFrame = CsvReadOptions::default()
+            .with_has_header(true)
+            .with_skip_rows(self.skip_rows)
+            // everything is read as text and parsed below
+            .with_infer_schema_length(Some(0))
+            .with_parse_options(
+                CsvParseOptions::default()
+                    .with_separator(self.delimiter as u8)
+                    .with_encoding(CsvEncoding::LossyUtf8),
+            )
+            .into_reader_with_file_handle(Cursor::new(content))
+            .finish()?;
+
+        let column = |name: &str| -> Result<StringChunked, Box<dyn std::error::Error>> {
+            Ok(data_frame
+                .column(name)
+                .map_err(|_e| format!("No column named {name} in the file!"))?
+                .str()?
+                .clone())
+        };
+        let optional_column =
+            |name: &str| -> Result<Option<StringChunked>, Box<dyn std::error::Error>> {
+                if name.is_empty() {
+                    Ok(None)
+                } else {
+                    Ok(Some(column(name)?))
+                }
+            };
+        let dates: StringChunked = column(&self.date_column)?;
+        let amounts: StringChunked = column(&self.amount_column)?;
+        let credits: Option<StringChunked> = match self.amount_sign {
+            AmountSign::SplitColumns => Some(column(&self.credit_column)?),
+            _ => None,
+        };
+        let currencies: Option<StringChunked> = optional_column(&self.currency_column)?;
+        let payees: Option<StringChunked> = optional_column(&self.payee_column)?;
+        let descriptions: Option<StringChunked> = optional_column(&self.description_column)?;
+
+        let mut movements: Vec<ImportedMovement> = Vec::new();
+        for i in 0..data_frame.height() {
+            let date: &str = dates.get(i).unwrap_or("").trim();
+            if date.is_empty() {
+                continue;
+            }
+            let row: usize = i + 1;
+            let date: NaiveDate = NaiveDate::parse_from_str(date, &self.date_format)
+                .map_err(|_e| format!("Invalid date {date} in row {row}!"))?;
+
+            let value: f64 = match (&self.amount_sign, &credits) {
+                (AmountSign::Signed, _) => {
+                    parse_amount(amounts.get(i).unwrap_or(""), self.decimal_separator)?
+                }
+                (AmountSign::Inverted, _) => {
+                    -parse_amount(amounts.get(i).unwrap_or(""), self.decimal_separator)?
+                }
+                (AmountSign::SplitColumns, Some(credits)) => {
+                    // banks leave the other column empty, and some write money out as negative
+                    let split_amount =
+                        |amount: Option<&str>| -> Result<f64, Box<dyn std::error::Error>> {
+                            match amount.map(|amount| amount.trim()) {
+                                Some(amount) if !amount.is_empty() => {
+                                    Ok(parse_amount(amount, self.decimal_separator)?.abs())
+                                }
+                                _ => Ok(0.0),
+                            }
+                        };
+                    split_amount(credits.get(i))? - split_amount(amounts.get(i))?
+                }
+                (AmountSign::SplitColumns, None) => 0.0,
+            };
+            let currency: Currency = match &currencies {
+                Some(currencies) => {
+                    let currency: &str = currencies.get(i).unwrap_or("").trim();
+                    Currency::from_str(currency)
+                        .map_err(|_e| format!("Invalid currency {currency} in row {row}!"))?
+                }
+                None => currency.clone(),
+            };
+            let text = |column: &Option<StringChunked>| -> String {
+                match column {
+                    Some(column) => column.get(i).unwrap_or("").trim().to_string(),
+                    None => String::default(),
+                }
+            };
+
+            movements.push(ImportedMovement {
+                date,
+                value_date: None,
+                value,
+                currency,
+                payee: text(&payees),
+                description: text(&descriptions),
+                reference: String::default(),
+            });
+        }
+
+        Ok(movements)
+    }
+}
@@ -0,0 +1,244 @@
+use crate::modules::financial::Currency;
+use crate::modules::importers::ImportedMovement;
+use chrono::NaiveDate;
+use roxmltree::{Document, Node};
+use std::str::FromStr;
+
+/// Movements of one account in a camt file, be it a statement, a notification or a report.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CamtStatement {
+    pub iban: String, // without spaces, empty if the bank identifies the account otherwise
+    pub movements: Vec<ImportedMovement>,
+}
+
+/// Returns the IBAN without spaces and in capitals, as it is compared.
+pub(crate) fn normalize_iban(iban: &str) -> String {
+    iban.split_whitespace().collect::<String>().to_uppercase()
+}
+
+/// Checks the length, the country code and the check digits of a normalized IBAN.
+pub(crate) fn is_valid_iban(iban: &str) -> bool {
+    if (iban.len() < 15) | (iban.len() > 34) | !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return false;
+    }
+    if !iban.chars().take(2).all(|c| c.is_ascii_uppercase()) {
+        return false;
+    }
+
+    // the first four characters go to the end, letters count as 10 to 35, and the number
+    // has to leave a remainder of 1 when divided by 97
+    let mut remainder: u32 = 0;
+    for c in iban.chars().skip(4).chain(iban.chars().take(4)) {
+        let digit: u32 = match c.to_digit(36) {
+            Some(digit) => digit,
+            None => return false,
+        };
+        remainder = if digit < 10 {
+            (remainder * 10 + digit) % 97
+        } else {
+            (remainder * 100 + digit) % 97
+        };
+    }
+    remainder == 1
+}
+
+/// Returns the first child element with the name, whatever the version of the namespace.
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.is_element() & (child.tag_name().name() == name))
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.is_element() & (child.tag_name().name() == name))
+}
+
+fn descendant<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+    path.iter().try_fold(node, |node, name| child(node, name))
+}
+
+/// Returns the trimmed text at the end of the path, if there is any.
+fn text(node: Node, path: &[&str]) -> Option<String> {
+    descendant(node, path)
+        .and_then(|node| node.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Returns the date of an element that holds either a date or a date and time.
+fn date(node: Node, name: &str) -> Result<Option<NaiveDate>, Box<dyn std::error::Error>> {
+    let date: String = match text(node, &[name, "Dt"]).or(text(node, &[name, "DtTm"])) {
+        Some(date) => date,
+        None => return Ok(None),
+    };
+    Ok(Some(
+        NaiveDate::parse_from_str(date.get(..10).unwrap_or(&date), "%Y-%m-%d")
+            .map_err(|_e| format!("Invalid date {date}!"))?,
+    ))
+}
+
+/// Returns the amount of an entry or transaction, negative if it is debited. Transactions
+/// without their own indicator take the one of the entry.
+fn amount(
+    node: Node,
+    amount_node: Node,
+    default_indicator: Option<&str>,
+) -> Result<(f64, Currency), Box<dyn std::error::Error>> {
+    let amount: &str = amount_node.text().unwrap_or("").trim();
+    let value: f64 = amount
+        .parse::<f64>()
+        .map_err(|_e| format!("Invalid amount {amount}!"))?;
+    let currency: &str = amount_node.attribute("Ccy").unwrap_or("");
+    let currency: Currency =
+        Currency::from_str(currency).map_err(|_e| format!("Unsupported currency {currency}!"))?;
+
+    let indicator: Option<String> = text(node, &["CdtDbtInd"]);
+    match indicator.as_deref().or(default_indicator) {
+        Some("CRDT") => Ok((value, currency)),
+        Some("DBIT") => Ok((-value, currency)),
+        _ => Err(format!("No credit or debit indicator for the amount {amount}!").into()),
+    }
+}
+
+/// Returns the amount node of a transaction, in any version of the standard.
+fn transaction_amount_node<'a, 'input>(details: Node<'a, 'input>) -> Option<Node<'a, 'input>> {
+    child(details, "Amt").or(descendant(details, &["AmtDtls", "TxAmt", "Amt"]))
+}
+
+/// Returns the name of the other party: the debtor of money that came in, the creditor of
+/// money that went out.
+fn counterparty(details: Node, value: f64) -> String {
+    let party: &str = if value > 0.0 { "Dbtr" } else { "Cdtr" };
+    text(details, &["RltdPties", party, "Nm"])
+        .or(text(details, &["RltdPties", party, "Pty", "Nm"]))
+        .unwrap_or_default()
+}
+
+/// Returns the remittance information of a transaction, with unstructured lines joined.
+fn remittance(details: Node) -> Option<String> {
+    let unstructured: Vec<String> = match child(details, "RmtInf") {
+        Some(information) => children(information, "Ustrd")
+            .filter_map(|line| line.text())
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect(),
+        None => Vec::new(),
+    };
+    if !unstructured.is_empty() {
+        return Some(unstructured.join(" "));
+    }
+    text(details, &["RmtInf", "Strd", "CdtrRefInf", "Ref"]).or(text(details, &["AddtlTxInf"]))
+}
+
+/// Turns an entry into movements: one for the whole entry, or one per transaction if the
+/// bank booked several together and gives the amount of each. Entries that are not booked
+/// yet are skipped, since they can still change.
+fn parse_entry(entry: Node) -> Result<Vec<ImportedMovement>, Box<dyn std::error::Error>> {
+    let status: Option<String> = text(entry, &["Sts", "Cd"]).or(text(entry, &["Sts"]));
+    if status.as_deref().is_some_and(|status| status != "BOOK") {
+        return Ok(Vec::new());
+    }
+
+    let booking_date: Option<NaiveDate> = date(entry, "BookgDt")?;
+    let value_date: Option<NaiveDate> = date(entry, "ValDt")?;
+    let date: NaiveDate = booking_date
+        .or(value_date)
+        .ok_or("Entry without booking date!")?;
+    let value_date: Option<NaiveDate> = value_date.filter(|value_date| *value_date != date);
+
+    let indicator: Option<String> = text(entry, &["CdtDbtInd"]);
+    let entry_reference: String = text(entry, &["AcctSvcrRef"]).unwrap_or_default();
+    let entry_description: String = text(entry, &["AddtlNtryInf"]).unwrap_or_default();
+    let details: Vec<Node> = children(entry, "NtryDtls")
+        .flat_map(|entry_details| children(entry_details, "TxDtls"))
+        .collect();
+
+    let movement = |details: Option<Node>, value: f64, currency: Currency, reference: String| {
+        ImportedMovement {
+            date,
+            value_date,
+            value,
+            currency,
+            payee: details
+                .map(|details| counterparty(details, value))
+                .unwrap_or_default(),
+            description: details
+                .and_then(remittance)
+                .unwrap_or(entry_description.clone()),
+            reference,
+        }
+    };
+
+    let is_batch: bool = (details.len() > 1)
+        & details
+            .iter()
+            .all(|details| transaction_amount_node(*details).is_some());
+    if !is_batch {
+        let (value, currency) = amount(
+            entry,
+            child(entry, "Amt").ok_or("Entry without amount!")?,
+            None,
+        )?;
+        return Ok(vec![movement(
+            details.first().copied(),
+            value,
+            currency,
+            entry_reference,
+        )]);
+    }
+
+    let mut movements: Vec<ImportedMovement> = Vec::new();
+    for (i, transaction) in details.iter().enumerate() {
+        let (value, currency) = amount(
+            *transaction,
+            transaction_amount_node(*transaction).ok_or("Transaction without amount!")?,
+            indicator.as_deref(),
+        )?;
+        // the transactions of a batch need references of their own
+        let reference: String = text(*transaction, &["Refs", "AcctSvcrRef"])
+            .or(text(*transaction, &["Refs", "EndToEndId"])
+                .filter(|reference| reference != "NOTPROVIDED"))
+            .unwrap_or(if entry_reference.is_empty() {
+                String::default()
+            } else {
+                format!("{}/{}", entry_reference, i + 1)
+            });
+        movements.push(movement(Some(*transaction), value, currency, reference));
+    }
+
+    Ok(movements)
+}
+
+/// Reads the statements of a camt.053 file, or the notifications of a camt.054 one. Reports
+/// of camt.052 files share their layout and are read as well.
+pub(crate) fn parse_camt(content: &str) -> Result<Vec<CamtStatement>, Box<dyn std::error::Error>> {
+    let document: Document = Document::parse(content)?;
+    let message: Node = document
+        .root_element()
+        .children()
+        .find(|node| node.is_element())
+        .ok_or("Empty camt file!")?;
+    let report_name: &str = match message.tag_name().name() {
+        "BkToCstmrStmt" => "Stmt",
+        "BkToCstmrDbtCdtNtfctn" => "Ntfctn",
+        "BkToCstmrAcctRpt" => "Rpt",
+        _ => return Err("Not a camt.053 or camt.054 file!".into()),
+    };
+
+    let mut statements: Vec<CamtStatement> = Vec::new();
+    for report in children(message, report_name) {
+        let mut movements: Vec<ImportedMovement> = Vec::new();
+        for entry in children(report, "Ntry") {
+            movements.extend(parse_entry(entry)?);
+        }
+        statements.push(CamtStatement {
+            iban: normalize_iban(&text(report, &["Acct", "Id", "IBAN"]).unwrap_or_default()),
+            movements,
+        });
+    }
+
+    Ok(statements)
+}
@@ -0,0 +1,137 @@
+pub mod bank_csv;
+pub mod camt;
+pub mod mt940;
+pub mod ofx;
+
+use crate::modules::financial::Currency;
+use chrono::NaiveDate;
+use std::fmt::Display;
+use strum_macros::{EnumIter, EnumString};
+
+/// Kinds of files that the banks export and that can be imported.
+#[derive(Debug, Default, Hash, PartialEq, Eq, EnumIter, Clone, EnumString)]
+pub enum ImportFormat {
+    #[default]
+    BankCsv, // with a saved mapping of its columns
+    Camt,  // ISO 20022 camt.053 statements and camt.054 notifications
+    Ofx,   // OFX 1.x and 2.x, and the QFX files of Quicken
+    Mt940, // SWIFT MT940 statements
+}
+
+impl Display for ImportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            ImportFormat::BankCsv => "Bank CSV".to_string(),
+            ImportFormat::Camt => "camt.053 / camt.054".to_string(),
+            ImportFormat::Ofx => "OFX / QFX".to_string(),
+            ImportFormat::Mt940 => "MT940".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Movement of an account as its bank exports it, before it becomes a party.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportedMovement {
+    pub date: NaiveDate,
+    pub value_date: Option<NaiveDate>, // if the bank gives one that differs from the date
+    pub value: f64,                    // positive if money came into the account
+    pub currency: Currency,
+    pub payee: String, // counterparty as the bank names it, empty if unknown
+    pub description: String,
+    pub reference: String, // ID the bank gives to the movement, empty if none
+}
+
+/// Symbols that banks write next to amounts, besides the codes of the currencies.
+const CURRENCY_SYMBOLS: [char; 6] = ['€', '$', '£', '¥', '₣', '₹'];
+
+/// Characters that banks put between the thousands of an amount.
+const THOUSANDS_SEPARATORS: [char; 7] = ['.', ',', '\'', '’', ' ', '\u{a0}', '\u{202f}'];
+
+/// Takes a currency code or symbol off either end of an amount. Returns the rest of the
+/// amount and whether there was one.
+fn strip_currency(amount: &str) -> (&str, bool) {
+    let is_code = |code: &str| code.chars().all(|c| c.is_ascii_uppercase());
+    if let Some(rest) = amount.strip_prefix(CURRENCY_SYMBOLS) {
+        return (rest.trim(), true);
+    }
+    if let Some(rest) = amount.strip_suffix(CURRENCY_SYMBOLS) {
+        return (rest.trim(), true);
+    }
+    if amount.get(..3).is_some_and(is_code) {
+        return (amount[3..].trim(), true);
+    }
+    if amount
+        .len()
+        .checked_sub(3)
+        .and_then(|start| amount.get(start..))
+        .is_some_and(is_code)
+    {
+        return (amount[..amount.len() - 3].trim(), true);
+    }
+    (amount, false)
+}
+
+/// Takes a sign off either end of an amount. Returns the rest of the amount and whether
+/// the sign was a minus.
+fn strip_sign(amount: &str) -> (&str, Option<bool>) {
+    for (sign, is_negative) in [('-', true), ('+', false)] {
+        if let Some(rest) = amount
+            .strip_prefix(sign)
+            .or_else(|| amount.strip_suffix(sign))
+        {
+            return (rest.trim(), Some(is_negative));
+        }
+    }
+    (amount, None)
+}
+
+/// Parses an amount as banks write it, with the given decimal separator. Dots, commas,
+/// spaces or apostrophes other than the decimal separator are taken as thousands
+/// separators, and a currency code or symbol and a sign can be at either end, like in
+/// "1.234,50- EUR". Amounts in parentheses are negative, as in accounting. Anything else
+/// in the amount makes it invalid.
+pub(crate) fn parse_amount(
+    amount: &str,
+    decimal_separator: char,
+) -> Result<f64, Box<dyn std::error::Error>> {
+    let invalid = || format!("Invalid amount: {amount}");
+    let mut rest: &str = amount.trim();
+    let is_parenthesized: bool = (rest.len() > 1) & rest.starts_with('(') & rest.ends_with(')');
+    if is_parenthesized {
+        rest = rest[1..rest.len() - 1].trim();
+    }
+    // the currency can be on either side of the sign, like in "-€12.50" or "€-12.50"
+    let (stripped, has_currency) = strip_currency(rest);
+    let (stripped, sign) = strip_sign(stripped);
+    rest = if has_currency {
+        stripped
+    } else {
+        strip_currency(stripped).0
+    };
+    if is_parenthesized & sign.is_some() {
+        return Err(invalid().into());
+    }
+
+    let mut digits: String = String::new();
+    for c in rest.chars() {
+        if c == decimal_separator {
+            // a second decimal separator is not a number
+            if digits.contains('.') {
+                return Err(invalid().into());
+            }
+            digits.push('.');
+        } else if c.is_ascii_digit() {
+            digits.push(c);
+        } else if !THOUSANDS_SEPARATORS.contains(&c) {
+            return Err(invalid().into());
+        }
+    }
+    let value: f64 = digits.parse::<f64>().map_err(|_e| invalid())?;
+
+    Ok(if is_parenthesized | (sign == Some(true)) {
+        -value
+    } else {
+        value
+    })
+}
@@ -0,0 +1,245 @@
+use crate::modules::financial::Currency;
+use crate::modules::importers::{parse_amount, ImportedMovement};
+use chrono::{Datelike, NaiveDate};
+use regex::Regex;
+use std::str::FromStr;
+
+/// Statement of an MT940 file, with the balances that its movements have to bridge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mt940Statement {
+    pub reference: String, // from :20:
+    pub account: String,   // from :25:, as the bank identifies the account
+    pub currency: Currency,
+    pub opening_balance: Option<f64>, // None until :60F: (or :60M:) is read
+    pub closing_balance: Option<f64>, // None until :62F: (or :62M:) is read
+    pub movements: Vec<ImportedMovement>,
+}
+
+impl Mt940Statement {
+    /// Checks that the opening balance plus the movements gives the closing balance, so that
+    /// no movement was lost or misread. A statement without both balances cannot be checked.
+    pub(crate) fn is_balanced(&self) -> bool {
+        let total: f64 = self
+            .movements
+            .iter()
+            .map(|movement| movement.value)
+            .sum::<f64>();
+        match (self.opening_balance, self.closing_balance) {
+            (Some(opening_balance), Some(closing_balance)) => {
+                (opening_balance + total - closing_balance).abs() < 0.01
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns the date of a YYMMDD field. Years are taken as in this century.
+fn parse_date(date: &str) -> Result<NaiveDate, Box<dyn std::error::Error>> {
+    Ok(NaiveDate::parse_from_str(&format!("20{date}"), "%Y%m%d")
+        .map_err(|_e| format!("Invalid date {date}!"))?)
+}
+
+/// Returns the currency and the signed amount of a balance, like C250131EUR1234,56.
+fn parse_balance(balance: &str) -> Result<(Currency, f64), Box<dyn std::error::Error>> {
+    let (mark, rest) = balance.split_at_checked(1).ok_or("Empty balance!")?;
+    let currency: &str = rest
+        .get(6..9)
+        .ok_or(format!("Invalid balance {balance}!"))?;
+    let currency: Currency =
+        Currency::from_str(currency).map_err(|_e| format!("Unsupported currency {currency}!"))?;
+    let amount: f64 = parse_amount(rest.get(9..).unwrap_or(""), ',')?;
+    match mark {
+        "C" => Ok((currency, amount)),
+        "D" => Ok((currency, -amount)),
+        _ => Err(format!("Invalid balance {balance}!").into()),
+    }
+}
+
+/// Returns the payee and the description of the information of a movement. Structured
+/// information, like the one of German banks, is split in fields that start with a
+/// question mark and their number: 20 to 29 and 60 to 63 are the purpose, and 32 and 33
+/// the name of the other party.
+fn parse_information(lines: &[String]) -> (String, String) {
+    let joined: String = lines.concat();
+    let is_structured: bool = joined.len() > 3
+        && joined.as_bytes()[..3].iter().all(|c| c.is_ascii_digit())
+        && (joined.as_bytes()[3] == b'?');
+    if !is_structured {
+        return (String::default(), lines.join(" ").trim().to_string());
+    }
+
+    let mut payee: Vec<&str> = Vec::new();
+    let mut purpose: Vec<&str> = Vec::new();
+    for field in joined.split('?').skip(1) {
+        let (code, text) = field.split_at_checked(2).unwrap_or(("", field));
+        match code.parse::<u32>() {
+            Ok(20..=29) | Ok(60..=63) => purpose.push(text),
+            Ok(32..=33) => payee.push(text),
+            _ => {}
+        }
+    }
+    (
+        payee.concat().trim().to_string(),
+        purpose.concat().trim().to_string(),
+    )
+}
+
+/// Reads the statements of an MT940 file. Each statement has to have an opening and a
+/// closing balance, and its movements have to bridge them, or the file is refused.
+pub(crate) fn parse_mt940(
+    content: &str,
+) -> Result<Vec<Mt940Statement>, Box<dyn std::error::Error>> {
+    // value date, entry date, debit or credit mark (reversals included), funds code, amount,
+    // type of transaction, reference of the customer and reference of the bank
+    let line_regex: Regex = Regex::new(
+        r"^(\d{6})(\d{4})?(RC|RD|C|D)([A-Z])?(\d+,\d*)([NSF][A-Z0-9]{3})([^/]*)(?://(.*))?$",
+    )?;
+
+    // the fields with their continuation lines, leaving out the headers of the message
+    let mut fields: Vec<(String, Vec<String>)> = Vec::new();
+    for line in content.lines() {
+        let line: &str = line.trim_end();
+        if line.starts_with('{') | line.starts_with('-') | line.is_empty() {
+            continue;
+        }
+        let tag: Option<(&str, &str)> = line
+            .strip_prefix(':')
+            .and_then(|line| line.split_once(':'))
+            .filter(|(tag, _)| (tag.len() <= 3) & tag.chars().all(|c| c.is_ascii_alphanumeric()));
+        match (tag, fields.last_mut()) {
+            (Some((tag, value)), _) => fields.push((tag.to_string(), vec![value.to_string()])),
+            (None, Some((_, lines))) => lines.push(line.to_string()),
+            (None, None) => return Err("Not an MT940 file!".into()),
+        }
+    }
+
+    let mut statements: Vec<Mt940Statement> = Vec::new();
+    let mut statement: Option<Mt940Statement> = None;
+    let mut previous_tag: &str = "";
+    for (tag, lines) in fields.iter() {
+        let value: &str = lines[0].as_str();
+        match (tag.as_str(), &mut statement) {
+            ("20", _) => {
+                if let Some(statement) = statement.take() {
+                    statements.push(statement);
+                }
+                statement = Some(Mt940Statement {
+                    reference: value.to_string(),
+                    account: String::default(),
+                    currency: Currency::default(),
+                    opening_balance: None,
+                    closing_balance: None,
+                    movements: Vec::new(),
+                });
+            }
+            ("25", Some(statement)) => {
+                statement.account = value.to_string();
+            }
+            ("60F", Some(statement)) | ("60M", Some(statement)) => {
+                let (currency, opening_balance) = parse_balance(value)?;
+                statement.currency = currency;
+                statement.opening_balance = Some(opening_balance);
+            }
+            ("62F", Some(statement)) | ("62M", Some(statement)) => {
+                statement.closing_balance = Some(parse_balance(value)?.1);
+            }
+            ("61", Some(statement)) => {
+                let captures = line_regex
+                    .captures(value)
+                    .ok_or(format!("Invalid statement line {value}!"))?;
+                let value_date: NaiveDate = parse_date(&captures[1])?;
+                // the entry date has no year, which can be the one before or after the value
+                // date around new year
+                let date: NaiveDate = match captures.get(2) {
+                    Some(entry_date) => {
+                        let entry_date: NaiveDate = parse_date(&format!(
+                            "{:02}{}",
+                            value_date.year() % 100,
+                            entry_date.as_str()
+                        ))?;
+                        match (value_date.month(), entry_date.month()) {
+                            (1, 12) => entry_date
+                                .with_year(entry_date.year() - 1)
+                                .unwrap_or(entry_date),
+                            (12, 1) => entry_date
+                                .with_year(entry_date.year() + 1)
+                                .unwrap_or(entry_date),
+                            _ => entry_date,
+                        }
+                    }
+                    None => value_date,
+                };
+                let amount: f64 = parse_amount(&captures[5], ',')?;
+                // a reversal of a credit takes money out, and one of a debit puts it back
+                let value: f64 = match &captures[3] {
+                    "C" | "RD" => amount,
+                    _ => -amount,
+                };
+                let customer_reference: &str = captures[7].trim();
+                let bank_reference: &str = captures.get(8).map_or("", |m| m.as_str().trim());
+                let reference: String = if !bank_reference.is_empty() {
+                    bank_reference.to_string()
+                } else if customer_reference != "NONREF" {
+                    customer_reference.to_string()
+                } else {
+                    String::default()
+                };
+
+                statement.movements.push(ImportedMovement {
+                    date,
+                    value_date: Some(value_date).filter(|value_date| *value_date != date),
+                    value,
+                    currency: statement.currency.clone(),
+                    payee: String::default(),
+                    // the supplementary details are replaced if the information follows
+                    description: lines[1..].join(" ").trim().to_string(),
+                    reference,
+                });
+            }
+            // information after the closing balance is about the whole statement
+            ("86", Some(statement)) if previous_tag == "61" => {
+                if let Some(movement) = statement.movements.last_mut() {
+                    let (payee, description) = parse_information(lines);
+                    movement.payee = payee;
+                    if !description.is_empty() {
+                        movement.description = description;
+                    }
+                }
+            }
+            _ => {}
+        }
+        previous_tag = tag.as_str();
+    }
+    if let Some(statement) = statement.take() {
+        statements.push(statement);
+    }
+
+    if statements.is_empty() {
+        return Err("Not an MT940 file!".into());
+    }
+    for statement in statements.iter() {
+        if statement.opening_balance.is_none() {
+            return Err(format!(
+                "The statement {} has no opening balance!",
+                statement.reference
+            )
+            .into());
+        }
+        if statement.closing_balance.is_none() {
+            return Err(format!(
+                "The statement {} has no closing balance!",
+                statement.reference
+            )
+            .into());
+        }
+        if !statement.is_balanced() {
+            return Err(format!(
+                "The movements of the statement {} do not lead from its opening balance to its closing balance!",
+                statement.reference
+            )
+            .into());
+        }
+    }
+
+    Ok(statements)
+}
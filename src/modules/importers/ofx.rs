@@ -0,0 +1,109 @@
+use crate::modules::financial::Currency;
+use crate::modules::importers::{parse_amount, ImportedMovement};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Replaces the character references that OFX files use.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Splits the body of an OFX file into its tags, each one with the text that follows it.
+/// OFX 1.x files are SGML and leave the elements with a value unclosed, while OFX 2.x files
+/// are XML and close them, so closing tags are only kept for the aggregates.
+fn tokenize(content: &str) -> Vec<(String, String)> {
+    let mut tokens: Vec<(String, String)> = Vec::new();
+    // the header of OFX 1.x files is not made of tags
+    let body: &str = match content.find("<OFX>") {
+        Some(start) => &content[start..],
+        None => content,
+    };
+    for piece in body.split('<').skip(1) {
+        let (tag, text) = piece.split_once('>').unwrap_or((piece, ""));
+        // processing instructions and comments, like the header of OFX 2.x files
+        if tag.starts_with('?') | tag.starts_with('!') {
+            continue;
+        }
+        tokens.push((tag.trim().to_uppercase(), unescape(text.trim())));
+    }
+    tokens
+}
+
+/// Returns the date of an OFX date and time, like 20250131120000.000[-5:EST].
+fn parse_date(date: &str) -> Result<NaiveDate, Box<dyn std::error::Error>> {
+    Ok(
+        NaiveDate::parse_from_str(date.get(..8).unwrap_or(date), "%Y%m%d")
+            .map_err(|_e| format!("Invalid date {date}!"))?,
+    )
+}
+
+/// Builds the movement of a STMTTRN aggregate from its elements.
+fn movement(
+    elements: &HashMap<String, String>,
+    currency: &Currency,
+) -> Result<ImportedMovement, Box<dyn std::error::Error>> {
+    let element = |name: &str| -> String { elements.get(name).cloned().unwrap_or_default() };
+    let amount: String = element("TRNAMT");
+    // the decimal separator can be a comma in some countries
+    let decimal_separator: char = if amount.contains(',') & !amount.contains('.') {
+        ','
+    } else {
+        '.'
+    };
+
+    Ok(ImportedMovement {
+        date: parse_date(&element("DTPOSTED"))?,
+        value_date: None,
+        value: parse_amount(&amount, decimal_separator)?,
+        currency: currency.clone(),
+        payee: element("NAME"),
+        description: element("MEMO"),
+        reference: element("FITID"),
+    })
+}
+
+/// Reads the transactions of the statements of an OFX or QFX file, be it OFX 1.x or 2.x.
+/// Their currency is the one of the statement, or the given one if it does not tell.
+pub(crate) fn parse_ofx(
+    content: &str,
+    currency: &Currency,
+) -> Result<Vec<ImportedMovement>, Box<dyn std::error::Error>> {
+    if !content.contains("<OFX>") {
+        return Err("Not an OFX file!".into());
+    }
+
+    let mut movements: Vec<ImportedMovement> = Vec::new();
+    let mut statement_currency: Currency = currency.clone();
+    let mut transaction: Option<HashMap<String, String>> = None;
+    for (tag, text) in tokenize(content) {
+        match (tag.as_str(), &mut transaction) {
+            ("STMTTRN", _) => {
+                transaction = Some(HashMap::new());
+            }
+            ("/STMTTRN", Some(elements)) => {
+                movements.push(movement(elements, &statement_currency)?);
+                transaction = None;
+            }
+            ("CURDEF", None) => {
+                statement_currency = Currency::from_str(&text)
+                    .map_err(|_e| format!("Unsupported currency {text}!"))?;
+            }
+            // the first one is kept, since the name of the payee can appear twice
+            (_, Some(elements)) if !text.is_empty() & !tag.starts_with('/') => {
+                elements.entry(tag).or_insert(text);
+            }
+            _ => {}
+        }
+    }
+    if transaction.is_some() {
+        return Err("The file ends in the middle of a transaction!".into());
+    }
+
+    Ok(movements)
+}
@@ -2,4 +2,6 @@ pub mod financial;
 pub mod tables;
 pub mod database;
 pub mod gui;
-pub mod currency_exchange;
\ No newline at end of file
+pub mod currency_exchange;
+pub mod importers;
+pub mod categorization;
\ No newline at end of file
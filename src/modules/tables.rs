@@ -1,4 +1,12 @@
-use super::financial::{Account, AccountType, Currency, Entity, EntityType, Party, Transaction};
+use super::categorization::rules::CategorizationRule;
+use super::database::staging::DraftParty;
+use super::financial::{
+    category_path, split_category_path, Account, AccountType, Assignment, BalanceAssertion, Budget,
+    BudgetPeriod, Category, Currency, Entity, EntityType, Iou, Party, Project, Recurrence,
+    Template, Transaction, TransactionStatus, TransactionType,
+};
+use super::importers::bank_csv::{AmountSign, CsvMapping};
+use super::importers::ImportedMovement;
 use chrono::{Local, NaiveDate};
 use polars::prelude::*;
 use std::fs::{create_dir, File};
@@ -32,18 +40,32 @@ pub trait Table {
     /// Creates a table instance with zero rows
     fn new() -> Result<Box<Self>, PolarsError>;
 
+    /// Adds to a loaded dataframe the columns that were introduced after it was saved, with
+    /// their default values
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        Ok(data_frame)
+    }
+
     /// Creates a table instance by trying to load a csv in the right location
     fn try_load() -> Result<Box<Self>, PolarsError> {
+        Self::try_load_from(Path::new(&format!("data/{}_table.csv", Self::name())))
+    }
+
+    /// Creates a table instance by trying to load the csv at the path
+    fn try_load_from(path: &Path) -> Result<Box<Self>, PolarsError> {
         let data_frame = CsvReadOptions::default()
             .with_infer_schema_length(None)
             .with_has_header(true)
             .with_parse_options(CsvParseOptions::default().with_try_parse_dates(true))
-            .try_into_reader_with_file_path(Some(
-                format!("data/{}_table.csv", Self::name()).into(),
-            ))?
+            .try_into_reader_with_file_path(Some(path.into()))?
             .finish()?;
 
-        Ok(Self::create(data_frame))
+        // a csv with only the header cannot tell the column types
+        if data_frame.is_empty() {
+            return Self::new();
+        }
+
+        Ok(Self::create(Self::upgrade(data_frame)?))
     }
 
     /// Creates a table instance by trying to load the csv data and,
@@ -62,12 +84,12 @@ pub trait Table {
 
     /// Saves the table data in the right location
     fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if self.data_frame().is_empty() {
-            return Ok(());
-        }
+        self.save_to(Path::new(&format!("data/{}_table.csv", Self::name())))
+    }
 
-        let file_name: String = format!("data/{}_table.csv", Self::name());
-        let path: &Path = Path::new(&file_name);
+    /// Saves the table data in a csv at the path. Empty tables are saved as well, so that
+    /// deleting the last record of a table is not undone when it is loaded again.
+    fn save_to(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
         let parent: &Path = path.parent().ok_or("Path does not have parent!")?;
         if !parent.exists() {
             let _ = create_dir(parent);
@@ -83,6 +105,24 @@ pub trait Table {
         Ok(())
     }
 
+    /// Makes the records that point to one ID in the column point to another one, for
+    /// instance when merging two entities.
+    fn replace_id(&mut self, column: &str, from_id: i64, into_id: i64) -> Result<(), PolarsError> {
+        *self.mut_data_frame() = self
+            .data_frame()
+            .clone()
+            .lazy()
+            .with_column(
+                when(col(column).eq(lit(from_id)))
+                    .then(lit(into_id))
+                    .otherwise(col(column))
+                    .alias(column),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
     /// Gets the ID of the last record of the table + 1. If the table is empty,
     /// returns 0
     fn next_id(&self) -> Result<i64, PolarsError> {
@@ -163,10 +203,25 @@ impl Table for IncomeTable {
                 Vec::<i64>::new(),
             )),
             Column::from(Series::new(PlSmallStr::from("party_id"), Vec::<i64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("status"),
+                Vec::<String>::new(),
+            )),
         ])?;
 
         Ok(IncomeTable::create(data_frame))
     }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        let mut data_frame: LazyFrame = data_frame.lazy();
+        if !data_frame.collect_schema()?.contains("status") {
+            // transactions saved before they had a status were final
+            data_frame =
+                data_frame.with_column(lit(TransactionStatus::Cleared.to_string()).alias("status"));
+        }
+
+        data_frame.collect()
+    }
 }
 
 impl IncomeTable {
@@ -175,6 +230,7 @@ impl IncomeTable {
         &mut self,
         transaction: &Transaction,
         party_id: i64,
+        status: &TransactionStatus,
     ) -> Result<(), Box<dyn std::error::Error>> {
         if let Transaction::Income {
             value,
@@ -184,6 +240,7 @@ impl IncomeTable {
             subcategory,
             description,
             entity_id,
+            ..
         } = transaction
         {
             let id: i64 = self.next_id()?;
@@ -197,7 +254,8 @@ impl IncomeTable {
                     "subcategory" => [subcategory.to_string()],
                     "description" => [description.to_string()],
                     "entity_id" => [*entity_id],
-                    "party_id" => [party_id]
+                    "party_id" => [party_id],
+                    "status" => [status.to_string()]
             )?;
 
             self.data_frame = self.data_frame().vstack(&record)?;
@@ -208,35 +266,15 @@ impl IncomeTable {
         }
     }
 
-    pub(crate) fn categories(&self) -> Result<Vec<String>, PolarsError> {
-        Ok(self
-            .data_frame()
-            .column("category")?
-            .unique()?
-            .str()?
-            .sort(false)
-            .into_no_null_iter()
-            .map(|s| s.to_string())
-            .collect())
-    }
-
-    pub(crate) fn subcategories(&self, category: String) -> Result<Vec<String>, PolarsError> {
-        let mask = self
-            .data_frame()
-            .column("category")?
-            .str()?
-            .equal(category.as_str());
+    /// Files the records under the old category path, or below it, under the new path.
+    pub(crate) fn rewrite_category(
+        &mut self,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), PolarsError> {
+        self.data_frame = rewrite_category_path(&self.data_frame, None, old_path, new_path)?;
 
-        Ok(self
-            .data_frame()
-            .filter(&mask)?
-            .column("subcategory")?
-            .unique()?
-            .str()?
-            .sort(false)
-            .into_no_null_iter()
-            .map(|s| s.to_string())
-            .collect())
+        Ok(())
     }
 
     /// Deletes records corresponding to a party.
@@ -251,6 +289,71 @@ impl IncomeTable {
         Ok(())
     }
 
+    /// Returns the status of the records of a party, if it has any
+    pub(crate) fn party_status(
+        &self,
+        party_id: i64,
+    ) -> Result<Option<TransactionStatus>, Box<dyn std::error::Error>> {
+        let records: DataFrame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").eq(lit(party_id)))
+            .collect()?;
+
+        if records.height() == 0 {
+            return Ok(None);
+        }
+
+        match records.column("status")?.str()?.get(0) {
+            Some(status) => Ok(Some(TransactionStatus::from_str(status)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Changes the status of a single record.
+    pub(crate) fn set_status(
+        &mut self,
+        id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        let id_column: String = format!("{}_id", IncomeTable::name());
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col(id_column.as_str()).eq(lit(id)))
+                    .then(lit(status.to_string()))
+                    .otherwise(col("status"))
+                    .alias("status"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Changes the status of all the records of a party.
+    pub(crate) fn set_party_status(
+        &mut self,
+        party_id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col("party_id").eq(lit(party_id)))
+                    .then(lit(status.to_string()))
+                    .otherwise(col("status"))
+                    .alias("status"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
     /// Returns iterator of income_ids that correspond to the given party_id
     pub(crate) fn iter_party(&self, party_id: i64) -> Result<IntoIter<i64>, PolarsError> {
         Ok(self
@@ -321,6 +424,7 @@ impl IncomeTable {
                 .i64()?
                 .get(0)
                 .ok_or("Could not find entity_id!")?,
+            tags: Vec::new(), // the tags are kept in the tag table
         };
 
         Ok(transaction)
@@ -380,10 +484,48 @@ impl Table for ExpensesTable {
                 Vec::<i64>::new(),
             )),
             Column::from(Series::new(PlSmallStr::from("party_id"), Vec::<i64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("status"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("amortization_months"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("refund_of"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("reimbursable"),
+                Vec::<bool>::new(),
+            )),
         ])?;
 
         Ok(ExpensesTable::create(data_frame))
     }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        let mut data_frame: LazyFrame = data_frame.lazy();
+        if !data_frame.collect_schema()?.contains("status") {
+            // transactions saved before they had a status were final
+            data_frame =
+                data_frame.with_column(lit(TransactionStatus::Cleared.to_string()).alias("status"));
+        }
+        if !data_frame.collect_schema()?.contains("amortization_months") {
+            // expenses saved before they could be spread count in their month only
+            data_frame = data_frame
+                .with_column(lit(1i64).cast(DataType::Int64).alias("amortization_months"));
+        }
+        if !data_frame.collect_schema()?.contains("refund_of") {
+            data_frame = data_frame.with_columns([
+                lit(-1i64).cast(DataType::Int64).alias("refund_of"),
+                lit(false).alias("reimbursable"),
+            ]);
+        }
+
+        data_frame.collect()
+    }
 }
 
 impl ExpensesTable {
@@ -392,6 +534,7 @@ impl ExpensesTable {
         &mut self,
         transaction: &Transaction,
         party_id: i64,
+        status: &TransactionStatus,
     ) -> Result<(), Box<dyn std::error::Error>> {
         if let Transaction::Expense {
             value,
@@ -401,6 +544,10 @@ impl ExpensesTable {
             subcategory,
             description,
             entity_id,
+            amortization_months,
+            refund_of,
+            reimbursable,
+            ..
         } = transaction
         {
             let id: i64 = self.next_id()?;
@@ -414,7 +561,11 @@ impl ExpensesTable {
                 "subcategory" => [subcategory.to_string()],
                 "description" => [description.to_string()],
                 "entity_id" => [*entity_id],
-                "party_id" => [party_id]
+                "party_id" => [party_id],
+                "status" => [status.to_string()],
+                "amortization_months" => [*amortization_months as i64],
+                "refund_of" => [*refund_of],
+                "reimbursable" => [*reimbursable]
             )?;
 
             self.data_frame = self.data_frame.vstack(&record)?;
@@ -425,35 +576,15 @@ impl ExpensesTable {
         }
     }
 
-    pub(crate) fn categories(&self) -> Result<Vec<String>, PolarsError> {
-        Ok(self
-            .data_frame()
-            .column("category")?
-            .unique()?
-            .str()?
-            .sort(false)
-            .into_no_null_iter()
-            .map(|s| s.to_string())
-            .collect())
-    }
-
-    pub(crate) fn subcategories(&self, category: String) -> Result<Vec<String>, PolarsError> {
-        let mask = self
-            .data_frame()
-            .column("category")?
-            .str()?
-            .equal(category.as_str());
+    /// Files the records under the old category path, or below it, under the new path.
+    pub(crate) fn rewrite_category(
+        &mut self,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), PolarsError> {
+        self.data_frame = rewrite_category_path(&self.data_frame, None, old_path, new_path)?;
 
-        Ok(self
-            .data_frame()
-            .filter(&mask)?
-            .column("subcategory")?
-            .unique()?
-            .str()?
-            .sort(false)
-            .into_no_null_iter()
-            .map(|s| s.to_string())
-            .collect())
+        Ok(())
     }
 
     // Deletes records corresponding to a party.
@@ -468,6 +599,71 @@ impl ExpensesTable {
         Ok(())
     }
 
+    /// Returns the status of the records of a party, if it has any
+    pub(crate) fn party_status(
+        &self,
+        party_id: i64,
+    ) -> Result<Option<TransactionStatus>, Box<dyn std::error::Error>> {
+        let records: DataFrame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").eq(lit(party_id)))
+            .collect()?;
+
+        if records.height() == 0 {
+            return Ok(None);
+        }
+
+        match records.column("status")?.str()?.get(0) {
+            Some(status) => Ok(Some(TransactionStatus::from_str(status)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Changes the status of a single record.
+    pub(crate) fn set_status(
+        &mut self,
+        id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        let id_column: String = format!("{}_id", ExpensesTable::name());
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col(id_column.as_str()).eq(lit(id)))
+                    .then(lit(status.to_string()))
+                    .otherwise(col("status"))
+                    .alias("status"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Changes the status of all the records of a party.
+    pub(crate) fn set_party_status(
+        &mut self,
+        party_id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col("party_id").eq(lit(party_id)))
+                    .then(lit(status.to_string()))
+                    .otherwise(col("status"))
+                    .alias("status"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
     /// Returns iterator of expenses_ids that correspond to the given party_id
     pub(crate) fn iter_party(&self, party_id: i64) -> Result<IntoIter<i64>, PolarsError> {
         Ok(self
@@ -483,6 +679,23 @@ impl ExpensesTable {
             .into_iter())
     }
 
+    /// Checks whether any expense of the party is given back by a refund of another party.
+    pub(crate) fn is_refunded_party(&self, party_id: i64) -> Result<bool, PolarsError> {
+        let refunded: Expr = self
+            .iter_party(party_id)?
+            .fold(lit(false), |refunded, expense_id| {
+                refunded.or(col("refund_of").eq(lit(expense_id)))
+            });
+
+        Ok(!self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").neq(lit(party_id)).and(refunded))
+            .collect()?
+            .is_empty())
+    }
+
     /// Returns entity given ID
     pub(crate) fn transaction(&self, id: i64) -> Result<Transaction, Box<dyn std::error::Error>> {
         let mask = self
@@ -534,6 +747,22 @@ impl ExpensesTable {
                 .i64()?
                 .get(0)
                 .ok_or("No entity_id!")?,
+            amortization_months: record
+                .column("amortization_months")?
+                .i64()?
+                .get(0)
+                .ok_or("No amortization_months!")? as u32,
+            tags: Vec::new(), // the tags are kept in the tag table
+            refund_of: record
+                .column("refund_of")?
+                .i64()?
+                .get(0)
+                .ok_or("No refund_of!")?,
+            reimbursable: record
+                .column("reimbursable")?
+                .bool()?
+                .get(0)
+                .ok_or("No reimbursable!")?,
         })
     }
 }
@@ -583,10 +812,57 @@ impl Table for FundsTable {
                 Vec::<i64>::new(),
             )),
             Column::from(Series::new(PlSmallStr::from("party_id"), Vec::<i64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("status"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("reconciliation_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("value_date"),
+                Vec::<NaiveDate>::new(),
+            )),
         ])?;
 
         Ok(FundsTable::create(data_frame))
     }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        let mut data_frame: LazyFrame = data_frame.lazy();
+        let schema = data_frame.collect_schema()?;
+        if !schema.contains("status") {
+            // movements saved before they had a status were final, like their incomes and
+            // expenses
+            data_frame =
+                data_frame.with_column(lit(TransactionStatus::Cleared.to_string()).alias("status"));
+        }
+        if !schema.contains("reconciliation_id") {
+            data_frame =
+                data_frame.with_column(lit(-1i64).cast(DataType::Int64).alias("reconciliation_id"));
+        }
+        if !schema.contains("value_date") {
+            data_frame = data_frame.with_column(lit(NULL).alias("value_date"));
+        }
+
+        // same column order as a new table, so that new records can be stacked
+        data_frame
+            .select([
+                col(format!("{}_id", FundsTable::name()).as_str()),
+                col(format!("{}_type", FundsTable::name()).as_str()),
+                col("value"),
+                col("currency"),
+                col("date"),
+                col("account_id"),
+                col("party_id"),
+                col("status"),
+                col("reconciliation_id"),
+                // a column without any value date is not recognised as dates
+                col("value_date").cast(DataType::Date),
+            ])
+            .collect()
+    }
 }
 
 impl FundsTable {
@@ -595,6 +871,7 @@ impl FundsTable {
         &mut self,
         transaction: &Transaction,
         party_id: i64,
+        status: &TransactionStatus,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let id: i64 = self.next_id()?;
 
@@ -603,6 +880,7 @@ impl FundsTable {
             currency,
             date,
             account_id,
+            value_date,
         } = transaction
         {
             let record = df!(
@@ -612,7 +890,10 @@ impl FundsTable {
                 "currency" => [currency.to_string()],
                 "date" => [*date],
                 "account_id" => [*account_id],
-                "party_id" => [party_id]
+                "party_id" => [party_id],
+                "status" => [status.to_string()],
+                "reconciliation_id" => [-1i64],
+                "value_date" => [*value_date]
             )?;
 
             self.data_frame = self.data_frame.vstack(&record)?;
@@ -623,6 +904,7 @@ impl FundsTable {
             currency,
             date,
             account_id,
+            value_date,
         } = transaction
         {
             let record = df!(
@@ -632,7 +914,10 @@ impl FundsTable {
                 "currency" => [currency.to_string()],
                 "date" => [*date],
                 "account_id" => [*account_id],
-                "party_id" => [party_id]
+                "party_id" => [party_id],
+                "status" => [status.to_string()],
+                "reconciliation_id" => [-1i64],
+                "value_date" => [*value_date]
             )?;
 
             self.data_frame = self.data_frame.vstack(&record)?;
@@ -655,6 +940,134 @@ impl FundsTable {
         Ok(())
     }
 
+    /// Returns the status of the records of a party, if it has any
+    pub(crate) fn party_status(
+        &self,
+        party_id: i64,
+    ) -> Result<Option<TransactionStatus>, Box<dyn std::error::Error>> {
+        let records: DataFrame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").eq(lit(party_id)))
+            .collect()?;
+
+        if records.height() == 0 {
+            return Ok(None);
+        }
+
+        match records.column("status")?.str()?.get(0) {
+            Some(status) => Ok(Some(TransactionStatus::from_str(status)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Changes the status of all the records of a party.
+    pub(crate) fn set_party_status(
+        &mut self,
+        party_id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col("party_id").eq(lit(party_id)))
+                    .then(lit(status.to_string()))
+                    .otherwise(col("status"))
+                    .alias("status"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Checks whether any fund movement of the party has been reconciled
+    pub(crate) fn is_reconciled_party(&self, party_id: i64) -> Result<bool, PolarsError> {
+        Ok(!self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("party_id")
+                    .eq(lit(party_id))
+                    .and(col("reconciliation_id").gt_eq(lit(0i64))),
+            )
+            .collect()?
+            .is_empty())
+    }
+
+    /// Changes the status of the fund movement. Reconciled movements are locked.
+    pub(crate) fn set_status(
+        &mut self,
+        id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        let id_column: String = format!("{}_id", FundsTable::name());
+        let is_reconciled: bool = !self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col(id_column.as_str())
+                    .eq(lit(id))
+                    .and(col("reconciliation_id").gt_eq(lit(0i64))),
+            )
+            .collect()?
+            .is_empty();
+        if is_reconciled {
+            return Err(PolarsError::InvalidOperation(
+                "The fund movement is already reconciled!".into(),
+            ));
+        }
+
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col(id_column.as_str()).eq(lit(id)))
+                    .then(lit(status.to_string()))
+                    .otherwise(col("status"))
+                    .alias("status"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Locks all cleared and not yet reconciled fund movements of the account in the currency
+    /// up to the date, included, under the given reconciliation.
+    pub(crate) fn reconcile(
+        &mut self,
+        account_id: i64,
+        currency: &Currency,
+        date: NaiveDate,
+        reconciliation_id: i64,
+    ) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(
+                    col("account_id")
+                        .eq(lit(account_id))
+                        .and(col("currency").eq(lit(currency.to_string())))
+                        .and(col("status").eq(lit(TransactionStatus::Cleared.to_string())))
+                        .and(col("reconciliation_id").lt(lit(0i64)))
+                        .and(col("date").lt_eq(lit(date))),
+                )
+                .then(lit(reconciliation_id))
+                .otherwise(col("reconciliation_id"))
+                .alias("reconciliation_id"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
     /// Returns iterator of funds_ids that correspond to the given party_id
     pub(crate) fn iter_party(&self, party_id: i64) -> Result<IntoIter<i64>, PolarsError> {
         Ok(self
@@ -706,6 +1119,12 @@ impl FundsTable {
             .i64()?
             .get(0)
             .ok_or("No account_id!")?;
+        let value_date: Option<NaiveDate> = record
+            .column("value_date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .flatten();
 
         if transaction_type == String::from("Credit") {
             Ok(Transaction::Credit {
@@ -713,6 +1132,7 @@ impl FundsTable {
                 currency,
                 date,
                 account_id,
+                value_date,
             })
         } else {
             // then it is debit
@@ -721,6 +1141,7 @@ impl FundsTable {
                 currency,
                 date,
                 account_id,
+                value_date,
             })
         }
     }
@@ -757,10 +1178,25 @@ impl Table for PartyTable {
                 PlSmallStr::from("creation_date"),
                 Vec::<NaiveDate>::new(),
             )),
+            Column::from(Series::new(
+                PlSmallStr::from("project_id"),
+                Vec::<i64>::new(),
+            )),
         ])?;
 
         Ok(PartyTable::create(data_frame))
     }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        let mut data_frame: LazyFrame = data_frame.lazy();
+        if !data_frame.collect_schema()?.contains("project_id") {
+            // parties saved before projects existed are not part of any
+            data_frame =
+                data_frame.with_column(lit(-1i64).cast(DataType::Int64).alias("project_id"));
+        }
+
+        data_frame.collect()
+    }
 }
 
 impl PartyTable {
@@ -770,7 +1206,8 @@ impl PartyTable {
 
         let record = df!(
             format!("{}_id", PartyTable::name()) => [id],
-            "creation_date" => [party.creation_date]
+            "creation_date" => [party.creation_date],
+            "project_id" => [party.project_id]
         )?;
 
         self.data_frame = self.data_frame.vstack(&record)?;
@@ -778,21 +1215,50 @@ impl PartyTable {
         Ok(())
     }
 
-    // Deletes records corresponding to a party.
-    pub(crate) fn delete_party(&mut self, party_id: i64) -> Result<(), PolarsError> {
-        self.data_frame = self
+    /// Returns the project of a party, -1 if it is not part of any
+    pub(crate) fn project_id(&self, party_id: i64) -> Result<i64, PolarsError> {
+        let records: DataFrame = self
             .data_frame
             .clone()
             .lazy()
-            .filter(col("party_id").neq(lit(party_id)))
+            .filter(col("party_id").eq(lit(party_id)))
             .collect()?;
 
-        Ok(())
+        Ok(records.column("project_id")?.i64()?.get(0).unwrap_or(-1))
     }
-}
 
-pub struct EntityTable {
-    pub data_frame: DataFrame,
+    /// Detaches all the parties from a project.
+    pub(crate) fn detach_project(&mut self, project_id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col("project_id").eq(lit(project_id)))
+                    .then(lit(-1i64).cast(DataType::Int64))
+                    .otherwise(col("project_id"))
+                    .alias("project_id"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    // Deletes records corresponding to a party.
+    pub(crate) fn delete_party(&mut self, party_id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").neq(lit(party_id)))
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+pub struct EntityTable {
+    pub data_frame: DataFrame,
 }
 
 impl Table for EntityTable {
@@ -819,10 +1285,20 @@ impl Table for EntityTable {
             "country" => ["Unknown"],
             format!("{}_type", EntityTable::name()) => [EntityType::default().to_string()],
             format!("{}_subtype", EntityTable::name()) => [""],
-            "creation_date" => [Local::now().date_naive()])?;
+            "creation_date" => [Local::now().date_naive()],
+            "archived" => [false])?;
 
         Ok(EntityTable::create(data_frame))
     }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        let mut data_frame: LazyFrame = data_frame.lazy();
+        if !data_frame.collect_schema()?.contains("archived") {
+            data_frame = data_frame.with_column(lit(false).alias("archived"));
+        }
+
+        data_frame.collect()
+    }
 }
 
 impl EntityTable {
@@ -838,6 +1314,22 @@ impl EntityTable {
             .into_iter())
     }
 
+    /// Iterator over the IDs of the entities that are not archived
+    pub(crate) fn iter_active(&self) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("archived").not())
+            .sort(["name"], Default::default())
+            .collect()?
+            .column(format!("{}_id", EntityTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
     /// Adds entity to the table
     pub fn insert_entity(&mut self, entity: &Entity) -> Result<i64, PolarsError> {
         let id: i64 = self.next_id()?;
@@ -848,7 +1340,8 @@ impl EntityTable {
             "country" => [entity.country()],
             format!("{}_type", EntityTable::name()) => [entity.entity_type().to_string()],
             format!("{}_subtype", EntityTable::name()) => [entity.entity_subtype()],
-            "creation_date" => [Local::now().date_naive()]
+            "creation_date" => [Local::now().date_naive()],
+            "archived" => [false]
         )?;
 
         self.data_frame = self.data_frame.vstack(&record)?;
@@ -865,6 +1358,9 @@ impl EntityTable {
             .equal(id);
 
         let record = self.data_frame.filter(&mask)?;
+        if record.height() == 0 {
+            return Err(format!("No entity with ID {id}!").into());
+        }
 
         Ok(Entity::new(
             record
@@ -895,6 +1391,88 @@ impl EntityTable {
         ))
     }
 
+    /// Replaces the name, country, type and subtype of the entity
+    pub(crate) fn update_entity(&mut self, id: i64, entity: &Entity) -> Result<(), PolarsError> {
+        let is_entity: Expr = col(format!("{}_id", EntityTable::name()).as_str()).eq(lit(id));
+        let type_column: String = format!("{}_type", EntityTable::name());
+        let subtype_column: String = format!("{}_subtype", EntityTable::name());
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_columns([
+                when(is_entity.clone())
+                    .then(lit(entity.name()))
+                    .otherwise(col("name"))
+                    .alias("name"),
+                when(is_entity.clone())
+                    .then(lit(entity.country()))
+                    .otherwise(col("country"))
+                    .alias("country"),
+                when(is_entity.clone())
+                    .then(lit(entity.entity_type().to_string()))
+                    .otherwise(col(type_column.as_str()))
+                    .alias(type_column.as_str()),
+                when(is_entity)
+                    .then(lit(entity.entity_subtype()))
+                    .otherwise(col(subtype_column.as_str()))
+                    // an empty subtype is read as null
+                    .cast(DataType::String)
+                    .alias(subtype_column.as_str()),
+            ])
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Deletes the entity
+    pub(crate) fn delete_entity(&mut self, id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", EntityTable::name()).as_str()).neq(lit(id)))
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Checks whether the entity is archived
+    pub(crate) fn is_archived(&self, id: i64) -> Result<bool, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", EntityTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        Ok(self
+            .data_frame
+            .filter(&mask)?
+            .column("archived")?
+            .bool()?
+            .into_iter()
+            .next()
+            .flatten()
+            .ok_or(format!("No entity with ID {id}!"))?)
+    }
+
+    /// Archives the entity, or brings it back if archived is false
+    pub(crate) fn set_archived(&mut self, id: i64, archived: bool) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col(format!("{}_id", EntityTable::name()).as_str()).eq(lit(id)))
+                    .then(lit(archived))
+                    .otherwise(col("archived"))
+                    .alias("archived"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
     /// Returns list of unique countries
     pub(crate) fn countries(&self) -> Result<Vec<String>, PolarsError> {
         Ok(self
@@ -950,10 +1528,38 @@ impl Table for AccountTable {
             "currency" => [Currency::default().to_string()],
             format!("{}_type", AccountTable::name()) => [AccountType::default().to_string()],
             "initial_balance" => [0.0f64],
-            "creation_date" => [Local::now().date_naive()])?;
+            "creation_date" => [Local::now().date_naive()],
+            "pockets" => [""],
+            "closing_date" => [None::<NaiveDate>],
+            "iban" => [""])?;
 
         Ok(AccountTable::create(data_frame))
     }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        let mut data_frame: LazyFrame = data_frame.lazy();
+        if !data_frame.collect_schema()?.contains("pockets") {
+            // accounts saved before they could have pockets held a single currency
+            data_frame = data_frame.with_column(lit("").alias("pockets"));
+        }
+        if !data_frame.collect_schema()?.contains("closing_date") {
+            // accounts saved before they could be closed are open
+            data_frame = data_frame.with_column(lit(NULL).alias("closing_date"));
+        }
+        if !data_frame.collect_schema()?.contains("iban") {
+            data_frame = data_frame.with_column(lit("").alias("iban"));
+        }
+
+        // a column without any pocket or IBAN is not recognised as strings, and one without
+        // any closing date as dates
+        data_frame
+            .with_columns([
+                col("pockets").cast(DataType::String),
+                col("closing_date").cast(DataType::Date),
+                col("iban").cast(DataType::String),
+            ])
+            .collect()
+    }
 }
 
 impl AccountTable {
@@ -969,6 +1575,22 @@ impl AccountTable {
             .into_iter())
     }
 
+    /// Iterator over the IDs of the accounts that are not closed
+    pub(crate) fn iter_open(&self) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("closing_date").is_null())
+            .sort(["name"], Default::default())
+            .collect()?
+            .column(format!("{}_id", AccountTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
     /// Adds account record to the table
     pub fn insert_account(&mut self, account: &Account) -> Result<i64, PolarsError> {
         let id: i64 = self.next_id()?;
@@ -980,7 +1602,16 @@ impl AccountTable {
             "currency" => [account.currency().to_string()],
             format!("{}_type", AccountTable::name()) => [account.account_type().to_string()],
             "initial_balance" => [account.initial_balance()],
-            "creation_date" => [Local::now().date_naive()]
+            "creation_date" => [Local::now().date_naive()],
+            // pockets are joined with semicolons, since commas separate the columns
+            "pockets" => [account
+                .pockets()
+                .iter()
+                .map(|pocket| pocket.to_string())
+                .collect::<Vec<String>>()
+                .join(";")],
+            "closing_date" => [None::<NaiveDate>],
+            "iban" => [""]
         )?;
 
         self.data_frame = self.data_frame.vstack(&record)?;
@@ -988,6 +1619,81 @@ impl AccountTable {
         Ok(id)
     }
 
+    /// Returns the date in which the account was closed, if it is
+    pub(crate) fn closing_date(&self, id: i64) -> Result<Option<NaiveDate>, PolarsError> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", AccountTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        Ok(self
+            .data_frame
+            .filter(&mask)?
+            .column("closing_date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .flatten())
+    }
+
+    /// Sets the closing date of the account, or reopens it if there is none
+    pub(crate) fn set_closing_date(
+        &mut self,
+        id: i64,
+        closing_date: Option<NaiveDate>,
+    ) -> Result<(), PolarsError> {
+        let closing_date: Expr = match closing_date {
+            Some(date) => lit(date).cast(DataType::Date),
+            None => lit(NULL).cast(DataType::Date),
+        };
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col(format!("{}_id", AccountTable::name()).as_str()).eq(lit(id)))
+                    .then(closing_date)
+                    .otherwise(col("closing_date"))
+                    .alias("closing_date"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Sets the IBAN under which the bank identifies the account in its statements
+    pub(crate) fn set_iban(&mut self, id: i64, iban: &str) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col(format!("{}_id", AccountTable::name()).as_str()).eq(lit(id)))
+                    .then(lit(iban))
+                    .otherwise(col("iban"))
+                    .alias("iban"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Returns the ID of the account with the IBAN, if any
+    pub(crate) fn find_iban(&self, iban: &str) -> Result<Option<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("iban").eq(lit(iban)))
+            .collect()?
+            .column(format!("{}_id", AccountTable::name()).as_str())?
+            .i64()?
+            .into_iter()
+            .next()
+            .flatten())
+    }
+
     /// Retrieves account from the table, given ID
     pub(crate) fn account(&self, id: i64) -> Result<Account, Box<dyn std::error::Error>> {
         let mask = self
@@ -997,6 +1703,18 @@ impl AccountTable {
             .equal(id);
 
         let record = self.data_frame.filter(&mask)?;
+        if record.height() == 0 {
+            return Err(format!("No account with ID {id}!").into());
+        }
+        let mut pockets: Vec<Currency> = Vec::new();
+        // empty strings are read as nulls
+        let pockets_string: &str = record.column("pockets")?.str()?.get(0).unwrap_or("");
+        for pocket in pockets_string
+            .split(";")
+            .filter(|pocket| !pocket.is_empty())
+        {
+            pockets.push(Currency::from_str(pocket)?);
+        }
 
         Ok(Account::new(
             record
@@ -1030,6 +1748,7 @@ impl AccountTable {
                 .f64()?
                 .get(0)
                 .ok_or("No initial_balance!")?,
+            pockets,
         ))
     }
 
@@ -1045,3 +1764,2719 @@ impl AccountTable {
             .collect())
     }
 }
+
+pub struct TemplateTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for TemplateTable {
+    fn name() -> String {
+        String::from("template")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(TemplateTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", TemplateTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("name"), Vec::<String>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("recurrence"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("recurrence_step"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("start_date"),
+                Vec::<NaiveDate>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("last_date"),
+                Vec::<NaiveDate>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("handled_dates"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("creation_date"),
+                Vec::<NaiveDate>::new(),
+            )),
+        ])?;
+
+        Ok(TemplateTable::create(data_frame))
+    }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        // the column is empty when every template is handled in order
+        data_frame
+            .lazy()
+            .with_column(col("handled_dates").cast(DataType::String))
+            .collect()
+    }
+}
+
+impl TemplateTable {
+    /// Iterator over IDs
+    pub(crate) fn iter(&self) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .sort(["name"], Default::default())?
+            .column(format!("{}_id", TemplateTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
+    /// Adds template record to the table. The last handled date is set to the day before the
+    /// start date, so that the start date itself is due.
+    pub fn insert_template(&mut self, template: &Template) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+        let last_date: NaiveDate = template
+            .start_date()
+            .pred_opt()
+            .unwrap_or(template.start_date());
+
+        let record = df!(
+            format!("{}_id", TemplateTable::name()) => [id],
+            "name" => [template.name()],
+            "recurrence" => [template.recurrence().to_string()],
+            "recurrence_step" => [template.recurrence_step() as i64],
+            "start_date" => [template.start_date()],
+            "last_date" => [last_date],
+            "handled_dates" => [""],
+            "creation_date" => [Local::now().date_naive()]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Retrieves template from the table, given ID. The transactions have to be added
+    /// afterwards.
+    pub(crate) fn template(&self, id: i64) -> Result<Template, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", TemplateTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        let record = self.data_frame.filter(&mask)?;
+        let start_date: NaiveDate = record
+            .column("start_date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .flatten()
+            .ok_or("No start_date!")?;
+
+        Ok(Template::new(
+            record
+                .column("name")?
+                .str()?
+                .get(0)
+                .ok_or("No name!")?
+                .to_string(),
+            Recurrence::from_str(
+                record
+                    .column("recurrence")?
+                    .str()?
+                    .get(0)
+                    .ok_or("No recurrence!")?,
+            )?,
+            record
+                .column("recurrence_step")?
+                .i64()?
+                .get(0)
+                .ok_or("No recurrence_step!")? as u32,
+            start_date,
+            Vec::new(),
+        ))
+    }
+
+    /// Returns the date of the last occurrence of the template that was already handled.
+    pub(crate) fn last_date(&self, id: i64) -> Result<NaiveDate, Box<dyn std::error::Error>> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", TemplateTable::name()).as_str()).eq(lit(id)))
+            .collect()?
+            .column("last_date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .flatten()
+            .ok_or("No last_date!")?)
+    }
+
+    /// Returns the dates of the occurrences after the last handled date that were handled
+    /// out of order.
+    pub(crate) fn handled_dates(
+        &self,
+        id: i64,
+    ) -> Result<Vec<NaiveDate>, Box<dyn std::error::Error>> {
+        let record = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", TemplateTable::name()).as_str()).eq(lit(id)))
+            .collect()?;
+
+        let mut handled_dates: Vec<NaiveDate> = Vec::new();
+        // empty strings are read as nulls
+        for date in record
+            .column("handled_dates")?
+            .str()?
+            .get(0)
+            .unwrap_or("")
+            .split(';')
+            .filter(|date| !date.is_empty())
+        {
+            handled_dates.push(NaiveDate::from_str(date)?);
+        }
+
+        Ok(handled_dates)
+    }
+
+    /// Sets the last handled date of the template, and the later occurrences that were
+    /// handled too.
+    pub(crate) fn set_handled(
+        &mut self,
+        id: i64,
+        last_date: NaiveDate,
+        handled_dates: &[NaiveDate],
+    ) -> Result<(), PolarsError> {
+        let id_column: String = format!("{}_id", TemplateTable::name());
+        let handled_dates: String = handled_dates
+            .iter()
+            .map(|date| date.to_string())
+            .collect::<Vec<String>>()
+            .join(";");
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_columns([
+                when(col(id_column.as_str()).eq(lit(id)))
+                    .then(lit(last_date).cast(DataType::Date))
+                    .otherwise(col("last_date"))
+                    .alias("last_date"),
+                when(col(id_column.as_str()).eq(lit(id)))
+                    .then(lit(handled_dates))
+                    .otherwise(col("handled_dates"))
+                    .alias("handled_dates"),
+            ])
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Deletes the template record.
+    pub(crate) fn delete_template(&mut self, id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", TemplateTable::name()).as_str()).neq(lit(id)))
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+pub struct TemplateTransactionTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for TemplateTransactionTable {
+    fn name() -> String {
+        String::from("template_transaction")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(TemplateTransactionTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", TemplateTransactionTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("template_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("transaction_type"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("value"), Vec::<f64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("currency"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("category"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("subcategory"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("description"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("entity_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("account_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("amortization_months"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("tags"), Vec::<String>::new())),
+        ])?;
+
+        Ok(TemplateTransactionTable::create(data_frame))
+    }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        let mut data_frame: LazyFrame = data_frame.lazy();
+        let schema = data_frame.collect_schema()?;
+        if !schema.contains("amortization_months") {
+            data_frame = data_frame
+                .with_column(lit(1i64).cast(DataType::Int64).alias("amortization_months"));
+        }
+        if !schema.contains("tags") {
+            data_frame = data_frame.with_column(lit("").alias("tags"));
+        }
+
+        // tags are empty in every record of a template without them
+        data_frame
+            .with_column(col("tags").cast(DataType::String))
+            .collect()
+    }
+}
+
+impl TemplateTransactionTable {
+    /// Adds a transaction of a template to the table. Since all kinds of transactions share
+    /// the same table, the fields that do not apply are left empty (or -1 for IDs). The date
+    /// is not stored, since it is set when the template is used.
+    pub fn insert_transaction(
+        &mut self,
+        transaction: &Transaction,
+        template_id: i64,
+    ) -> Result<(), PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let (category, subcategory, description, entity_id, account_id) = match transaction {
+            Transaction::Income {
+                category,
+                subcategory,
+                description,
+                entity_id,
+                ..
+            }
+            | Transaction::Expense {
+                category,
+                subcategory,
+                description,
+                entity_id,
+                ..
+            } => (
+                category.to_string(),
+                subcategory.to_string(),
+                description.to_string(),
+                *entity_id,
+                -1i64,
+            ),
+            Transaction::Credit { account_id, .. } | Transaction::Debit { account_id, .. } => (
+                String::default(),
+                String::default(),
+                String::default(),
+                -1i64,
+                *account_id,
+            ),
+        };
+        let amortization_months: i64 = match transaction {
+            Transaction::Expense {
+                amortization_months,
+                ..
+            } => *amortization_months as i64,
+            _ => 1i64,
+        };
+        let tags: String = match transaction {
+            Transaction::Income { tags, .. } | Transaction::Expense { tags, .. } => tags.join(";"),
+            _ => String::default(),
+        };
+
+        let record = df!(
+            format!("{}_id", TemplateTransactionTable::name()) => [id],
+            "template_id" => [template_id],
+            "transaction_type" => [transaction.transaction_type()],
+            "value" => [transaction.value()],
+            "currency" => [transaction.currency().to_string()],
+            "category" => [category],
+            "subcategory" => [subcategory],
+            "description" => [description],
+            "entity_id" => [entity_id],
+            "account_id" => [account_id],
+            "amortization_months" => [amortization_months],
+            "tags" => [tags]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(())
+    }
+
+    /// Returns the transactions of the template, dated at the given date.
+    pub(crate) fn transactions(
+        &self,
+        template_id: i64,
+        date: NaiveDate,
+    ) -> Result<Vec<Transaction>, Box<dyn std::error::Error>> {
+        let records: DataFrame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("template_id").eq(lit(template_id)))
+            .collect()?;
+
+        let mut transactions: Vec<Transaction> = Vec::new();
+        for i in 0..records.height() {
+            let transaction_type: &str = records
+                .column("transaction_type")?
+                .str()?
+                .get(i)
+                .ok_or("No transaction_type!")?;
+            let value: f64 = records.column("value")?.f64()?.get(i).ok_or("No value!")?;
+            let currency: Currency = Currency::from_str(
+                records
+                    .column("currency")?
+                    .str()?
+                    .get(i)
+                    .ok_or("No currency!")?,
+            )?;
+            // empty strings are read as nulls
+            let category: String = records
+                .column("category")?
+                .str()?
+                .get(i)
+                .unwrap_or("")
+                .to_string();
+            let subcategory: String = records
+                .column("subcategory")?
+                .str()?
+                .get(i)
+                .unwrap_or("")
+                .to_string();
+            let description: String = records
+                .column("description")?
+                .str()?
+                .get(i)
+                .unwrap_or("")
+                .to_string();
+            let entity_id: i64 = records
+                .column("entity_id")?
+                .i64()?
+                .get(i)
+                .ok_or("No entity_id!")?;
+            let account_id: i64 = records
+                .column("account_id")?
+                .i64()?
+                .get(i)
+                .ok_or("No account_id!")?;
+            let amortization_months: i64 = records
+                .column("amortization_months")?
+                .i64()?
+                .get(i)
+                .ok_or("No amortization_months!")?;
+            let tags: Vec<String> = records
+                .column("tags")?
+                .str()?
+                .get(i)
+                .unwrap_or("")
+                .split(';')
+                .filter(|tag| !tag.is_empty())
+                .map(|tag| tag.to_string())
+                .collect();
+
+            let transaction: Transaction = match transaction_type {
+                "Income" => Transaction::Income {
+                    value,
+                    currency,
+                    date,
+                    category,
+                    subcategory,
+                    description,
+                    entity_id,
+                    tags,
+                },
+                "Expense" => Transaction::Expense {
+                    value,
+                    currency,
+                    date,
+                    category,
+                    subcategory,
+                    description,
+                    entity_id,
+                    amortization_months: amortization_months as u32,
+                    tags,
+                    // templates are entered again, so they cannot give back a given expense
+                    refund_of: -1,
+                    reimbursable: false,
+                },
+                "Credit" => Transaction::Credit {
+                    value,
+                    currency,
+                    date,
+                    account_id,
+                    value_date: None, // settlement delays are not known in advance
+                },
+                "Debit" => Transaction::Debit {
+                    value,
+                    currency,
+                    date,
+                    account_id,
+                    value_date: None, // settlement delays are not known in advance
+                },
+                _ => return Err(IncorrectTableError.into()),
+            };
+            transactions.push(transaction);
+        }
+
+        Ok(transactions)
+    }
+
+    /// Deletes the transactions of a template.
+    pub(crate) fn delete_template(&mut self, template_id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("template_id").neq(lit(template_id)))
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Files the records of the transaction type under the old category path, or below it,
+    /// under the new path.
+    pub(crate) fn rewrite_category(
+        &mut self,
+        transaction_type: &TransactionType,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), PolarsError> {
+        self.data_frame =
+            rewrite_category_path(&self.data_frame, Some(transaction_type), old_path, new_path)?;
+
+        Ok(())
+    }
+}
+
+pub struct BudgetTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for BudgetTable {
+    fn name() -> String {
+        String::from("budget")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(BudgetTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", BudgetTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("category"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("subcategory"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("period"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("value"), Vec::<f64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("currency"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("creation_date"),
+                Vec::<NaiveDate>::new(),
+            )),
+        ])?;
+
+        Ok(BudgetTable::create(data_frame))
+    }
+}
+
+impl BudgetTable {
+    /// Iterator over IDs
+    pub(crate) fn iter(&self) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .sort(["category", "subcategory"], Default::default())?
+            .column(format!("{}_id", BudgetTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
+    /// Adds budget record to the table
+    pub fn insert_budget(&mut self, budget: &Budget) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", BudgetTable::name()) => [id],
+            "category" => [budget.category()],
+            "subcategory" => [budget.subcategory()],
+            "period" => [budget.period().to_string()],
+            "value" => [budget.value()],
+            "currency" => [budget.currency().to_string()],
+            "creation_date" => [Local::now().date_naive()]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Retrieves budget from the table, given ID
+    pub(crate) fn budget(&self, id: i64) -> Result<Budget, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", BudgetTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        let record = self.data_frame.filter(&mask)?;
+
+        Ok(Budget::new(
+            record
+                .column("category")?
+                .str()?
+                .get(0)
+                .ok_or("No category!")?
+                .to_string(),
+            record
+                .column("subcategory")?
+                .str()?
+                .get(0)
+                .unwrap_or("") // empty strings are read as nulls
+                .to_string(),
+            BudgetPeriod::from_str(record.column("period")?.str()?.get(0).ok_or("No period!")?)?,
+            record.column("value")?.f64()?.get(0).ok_or("No value!")?,
+            Currency::from_str(
+                record
+                    .column("currency")?
+                    .str()?
+                    .get(0)
+                    .ok_or("No currency!")?,
+            )?,
+        ))
+    }
+
+    /// Deletes the budget record.
+    pub(crate) fn delete_budget(&mut self, id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", BudgetTable::name()).as_str()).neq(lit(id)))
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Files the records under the old category path, or below it, under the new path.
+    pub(crate) fn rewrite_category(
+        &mut self,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), PolarsError> {
+        self.data_frame = rewrite_category_path(&self.data_frame, None, old_path, new_path)?;
+
+        Ok(())
+    }
+}
+
+pub struct AssignmentTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for AssignmentTable {
+    fn name() -> String {
+        String::from("assignment")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(AssignmentTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", AssignmentTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("party_id"), Vec::<i64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("category"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("value"), Vec::<f64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("currency"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("date"),
+                Vec::<NaiveDate>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("creation_date"),
+                Vec::<NaiveDate>::new(),
+            )),
+        ])?;
+
+        Ok(AssignmentTable::create(data_frame))
+    }
+}
+
+impl AssignmentTable {
+    /// Iterator over the IDs of the assignments of an income party
+    pub(crate) fn iter_party(&self, party_id: i64) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").eq(lit(party_id)))
+            .collect()?
+            .column(format!("{}_id", AssignmentTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
+    /// Adds assignment record to the table
+    pub fn insert_assignment(&mut self, assignment: &Assignment) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", AssignmentTable::name()) => [id],
+            "party_id" => [assignment.party_id()],
+            "category" => [assignment.category()],
+            "value" => [assignment.value()],
+            "currency" => [assignment.currency().to_string()],
+            "date" => [*assignment.date()],
+            "creation_date" => [Local::now().date_naive()]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Retrieves assignment from the table, given ID
+    pub(crate) fn assignment(&self, id: i64) -> Result<Assignment, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", AssignmentTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        let record = self.data_frame.filter(&mask)?;
+
+        let date: NaiveDate = record
+            .column("date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .ok_or("No date!")?
+            .ok_or("No date!")?;
+
+        Ok(Assignment::new(
+            record
+                .column("party_id")?
+                .i64()?
+                .get(0)
+                .ok_or("No party_id!")?,
+            record
+                .column("category")?
+                .str()?
+                .get(0)
+                .ok_or("No category!")?
+                .to_string(),
+            record.column("value")?.f64()?.get(0).ok_or("No value!")?,
+            Currency::from_str(
+                record
+                    .column("currency")?
+                    .str()?
+                    .get(0)
+                    .ok_or("No currency!")?,
+            )?,
+            date,
+        ))
+    }
+
+    /// Deletes the assignment record.
+    pub(crate) fn delete_assignment(&mut self, id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", AssignmentTable::name()).as_str()).neq(lit(id)))
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Deletes all the assignments of an income party.
+    pub(crate) fn delete_party(&mut self, party_id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").neq(lit(party_id)))
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Moves the assignments of the old category to the top level of the new path, since
+    /// envelopes only go by category.
+    pub(crate) fn rewrite_category(
+        &mut self,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), PolarsError> {
+        self.data_frame = rewrite_category_path(&self.data_frame, None, old_path, new_path)?;
+
+        Ok(())
+    }
+}
+
+pub struct ReconciliationTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for ReconciliationTable {
+    fn name() -> String {
+        String::from("reconciliation")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(ReconciliationTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", ReconciliationTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("account_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("currency"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("statement_date"),
+                Vec::<NaiveDate>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("statement_balance"),
+                Vec::<f64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("creation_date"),
+                Vec::<NaiveDate>::new(),
+            )),
+        ])?;
+
+        Ok(ReconciliationTable::create(data_frame))
+    }
+}
+
+impl ReconciliationTable {
+    /// Adds reconciliation record to the table
+    pub fn insert_reconciliation(
+        &mut self,
+        account_id: i64,
+        currency: &Currency,
+        statement_date: NaiveDate,
+        statement_balance: f64,
+    ) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", ReconciliationTable::name()) => [id],
+            "account_id" => [account_id],
+            "currency" => [currency.to_string()],
+            "statement_date" => [statement_date],
+            "statement_balance" => [statement_balance],
+            "creation_date" => [Local::now().date_naive()]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Returns the statement date and balance of the last reconciliation of the account in
+    /// the currency, if there is any
+    pub(crate) fn last_reconciliation(
+        &self,
+        account_id: i64,
+        currency: &Currency,
+    ) -> Result<Option<(NaiveDate, f64)>, PolarsError> {
+        let record: DataFrame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("account_id")
+                    .eq(lit(account_id))
+                    .and(col("currency").eq(lit(currency.to_string()))),
+            )
+            .sort(
+                ["statement_date"],
+                SortMultipleOptions::default().with_order_descending(true),
+            )
+            .collect()?;
+        if record.height() == 0 {
+            return Ok(None);
+        }
+
+        let statement_date: Option<NaiveDate> = record
+            .column("statement_date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .flatten();
+        let statement_balance: Option<f64> = record.column("statement_balance")?.f64()?.get(0);
+
+        Ok(statement_date.zip(statement_balance))
+    }
+}
+
+pub struct BalanceAssertionTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for BalanceAssertionTable {
+    fn name() -> String {
+        String::from("balance_assertion")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(BalanceAssertionTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", BalanceAssertionTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("account_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("date"),
+                Vec::<NaiveDate>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("value"), Vec::<f64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("currency"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("creation_date"),
+                Vec::<NaiveDate>::new(),
+            )),
+        ])?;
+
+        Ok(BalanceAssertionTable::create(data_frame))
+    }
+}
+
+impl BalanceAssertionTable {
+    /// Iterator over IDs, sorted by date
+    pub(crate) fn iter(&self) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .sort(["date", "account_id"], Default::default())?
+            .column(format!("{}_id", BalanceAssertionTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
+    /// Adds balance assertion record to the table
+    pub fn insert_balance_assertion(
+        &mut self,
+        balance_assertion: &BalanceAssertion,
+    ) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", BalanceAssertionTable::name()) => [id],
+            "account_id" => [balance_assertion.account_id()],
+            "date" => [*balance_assertion.date()],
+            "value" => [balance_assertion.value()],
+            "currency" => [balance_assertion.currency().to_string()],
+            "creation_date" => [Local::now().date_naive()]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Retrieves balance assertion from the table, given ID
+    pub(crate) fn balance_assertion(
+        &self,
+        id: i64,
+    ) -> Result<BalanceAssertion, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", BalanceAssertionTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        let record = self.data_frame.filter(&mask)?;
+        let date: NaiveDate = record
+            .column("date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .flatten()
+            .ok_or("No date!")?;
+
+        Ok(BalanceAssertion::new(
+            record
+                .column("account_id")?
+                .i64()?
+                .get(0)
+                .ok_or("No account_id!")?,
+            date,
+            record.column("value")?.f64()?.get(0).ok_or("No value!")?,
+            Currency::from_str(
+                record
+                    .column("currency")?
+                    .str()?
+                    .get(0)
+                    .ok_or("No currency!")?,
+            )?,
+        ))
+    }
+
+    /// Deletes the balance assertion record.
+    pub(crate) fn delete_balance_assertion(&mut self, id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", BalanceAssertionTable::name()).as_str()).neq(lit(id)))
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+/// Links incomes and expenses with their tags. A transaction can have many tags, and a tag
+/// can be on many transactions, so every pair is a record.
+pub struct TagTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for TagTable {
+    fn name() -> String {
+        String::from("tag")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(TagTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", TagTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("transaction_type"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("transaction_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("tag"), Vec::<String>::new())),
+        ])?;
+
+        Ok(TagTable::create(data_frame))
+    }
+}
+
+impl TagTable {
+    /// Adds the tags of an income or expense record, given its type and ID
+    pub fn insert_tags(
+        &mut self,
+        transaction_type: &str,
+        transaction_id: i64,
+        tags: &[String],
+    ) -> Result<(), PolarsError> {
+        for tag in tags.iter() {
+            let id: i64 = self.next_id()?;
+
+            let record = df!(
+                format!("{}_id", TagTable::name()) => [id],
+                "transaction_type" => [transaction_type],
+                "transaction_id" => [transaction_id],
+                "tag" => [tag.as_str()]
+            )?;
+
+            self.data_frame = self.data_frame.vstack(&record)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the tags of an income or expense record, given its type and ID
+    pub(crate) fn transaction_tags(
+        &self,
+        transaction_type: &str,
+        transaction_id: i64,
+    ) -> Result<Vec<String>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("transaction_type")
+                    .eq(lit(transaction_type))
+                    .and(col("transaction_id").eq(lit(transaction_id))),
+            )
+            .collect()?
+            .column("tag")?
+            .str()?
+            .into_no_null_iter()
+            .map(|s| s.to_string())
+            .collect())
+    }
+
+    /// Returns all the tags in use, sorted
+    pub(crate) fn tags(&self) -> Result<Vec<String>, PolarsError> {
+        Ok(self
+            .data_frame()
+            .column("tag")?
+            .unique()?
+            .str()?
+            .sort(false)
+            .into_no_null_iter()
+            .map(|s| s.to_string())
+            .collect())
+    }
+
+    /// Deletes the tags of an income or expense record, given its type and ID
+    pub(crate) fn delete_transaction(
+        &mut self,
+        transaction_type: &str,
+        transaction_id: i64,
+    ) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("transaction_type")
+                    .neq(lit(transaction_type))
+                    .or(col("transaction_id").neq(lit(transaction_id))),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+pub struct ProjectTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for ProjectTable {
+    fn name() -> String {
+        String::from("project")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(ProjectTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", ProjectTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("name"), Vec::<String>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("description"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("budget"), Vec::<f64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("currency"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("creation_date"),
+                Vec::<NaiveDate>::new(),
+            )),
+        ])?;
+
+        Ok(ProjectTable::create(data_frame))
+    }
+}
+
+impl ProjectTable {
+    /// Iterator over IDs, sorted by name
+    pub(crate) fn iter(&self) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .sort(["name"], Default::default())?
+            .column(format!("{}_id", ProjectTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
+    /// Adds project record to the table
+    pub fn insert_project(&mut self, project: &Project) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", ProjectTable::name()) => [id],
+            "name" => [project.name()],
+            "description" => [project.description()],
+            "budget" => [project.budget()],
+            "currency" => [project.currency().to_string()],
+            "creation_date" => [Local::now().date_naive()]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Retrieves project from the table, given ID
+    pub(crate) fn project(&self, id: i64) -> Result<Project, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", ProjectTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        let record = self.data_frame.filter(&mask)?;
+
+        Ok(Project::new(
+            record
+                .column("name")?
+                .str()?
+                .get(0)
+                .ok_or("No name!")?
+                .to_string(),
+            // empty strings are read as nulls
+            record
+                .column("description")?
+                .str()?
+                .get(0)
+                .unwrap_or("")
+                .to_string(),
+            record.column("budget")?.f64()?.get(0).ok_or("No budget!")?,
+            Currency::from_str(
+                record
+                    .column("currency")?
+                    .str()?
+                    .get(0)
+                    .ok_or("No currency!")?,
+            )?,
+        ))
+    }
+
+    /// Deletes the project record.
+    pub(crate) fn delete_project(&mut self, id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", ProjectTable::name()).as_str()).neq(lit(id)))
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+/// Debts with people, each recorded along with the party that opened or settled it.
+pub struct IouTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for IouTable {
+    fn name() -> String {
+        String::from("iou")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(IouTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", IouTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("entity_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("value"), Vec::<f64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("currency"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("date"),
+                Vec::<NaiveDate>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("description"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("party_id"), Vec::<i64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("status"),
+                Vec::<String>::new(),
+            )),
+        ])?;
+
+        Ok(IouTable::create(data_frame))
+    }
+}
+
+impl IouTable {
+    /// Adds debt record to the table
+    pub fn insert_iou(
+        &mut self,
+        iou: &Iou,
+        party_id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", IouTable::name()) => [id],
+            "entity_id" => [iou.entity_id()],
+            "value" => [iou.value()],
+            "currency" => [iou.currency().to_string()],
+            "date" => [iou.date()],
+            "description" => [iou.description()],
+            "party_id" => [party_id],
+            "status" => [status.to_string()]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(())
+    }
+
+    /// Returns the debts recorded along with a party
+    pub(crate) fn party_ious(&self, party_id: i64) -> Result<Vec<Iou>, Box<dyn std::error::Error>> {
+        let records: DataFrame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").eq(lit(party_id)))
+            .collect()?;
+
+        let mut ious: Vec<Iou> = Vec::new();
+        for i in 0..records.height() {
+            ious.push(Iou::new(
+                records
+                    .column("entity_id")?
+                    .i64()?
+                    .get(i)
+                    .ok_or("No entity_id!")?,
+                records.column("value")?.f64()?.get(i).ok_or("No value!")?,
+                Currency::from_str(
+                    records
+                        .column("currency")?
+                        .str()?
+                        .get(i)
+                        .ok_or("No currency!")?,
+                )?,
+                records
+                    .column("date")?
+                    .date()?
+                    .as_date_iter()
+                    .nth(i)
+                    .flatten()
+                    .ok_or("No date!")?,
+                // empty strings are read as nulls
+                records
+                    .column("description")?
+                    .str()?
+                    .get(i)
+                    .unwrap_or("")
+                    .to_string(),
+            ));
+        }
+
+        Ok(ious)
+    }
+
+    /// Changes the status of all the records of a party.
+    pub(crate) fn set_party_status(
+        &mut self,
+        party_id: i64,
+        status: &TransactionStatus,
+    ) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col("party_id").eq(lit(party_id)))
+                    .then(lit(status.to_string()))
+                    .otherwise(col("status"))
+                    .alias("status"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    // Deletes records corresponding to a party.
+    pub(crate) fn delete_party(&mut self, party_id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").neq(lit(party_id)))
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+pub struct EntityAliasTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for EntityAliasTable {
+    fn name() -> String {
+        String::from("entity_alias")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(EntityAliasTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", EntityAliasTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("entity_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("alias"), Vec::<String>::new())),
+        ])?;
+
+        Ok(EntityAliasTable::create(data_frame))
+    }
+}
+
+impl EntityAliasTable {
+    /// Adds another name under which the entity is known
+    pub fn insert_alias(&mut self, entity_id: i64, alias: &str) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", EntityAliasTable::name()) => [id],
+            "entity_id" => [entity_id],
+            "alias" => [alias]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Returns the aliases of the entity, sorted
+    pub(crate) fn entity_aliases(&self, entity_id: i64) -> Result<Vec<String>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("entity_id").eq(lit(entity_id)))
+            .sort(["alias"], Default::default())
+            .collect()?
+            .column("alias")?
+            .str()?
+            .into_no_null_iter()
+            .map(|s| s.to_string())
+            .collect())
+    }
+
+    /// Returns every alias with the ID of its entity
+    pub(crate) fn aliases(&self) -> Result<Vec<(String, i64)>, PolarsError> {
+        Ok(self
+            .data_frame
+            .column("alias")?
+            .str()?
+            .into_no_null_iter()
+            .zip(
+                self.data_frame
+                    .column("entity_id")?
+                    .i64()?
+                    .into_no_null_iter(),
+            )
+            .map(|(alias, entity_id)| (alias.to_string(), entity_id))
+            .collect())
+    }
+
+    /// Deletes an alias of the entity
+    pub(crate) fn delete_alias(&mut self, entity_id: i64, alias: &str) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("entity_id")
+                    .neq(lit(entity_id))
+                    .or(col("alias").neq(lit(alias))),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+/// Files the records under the old category path, or below it, under the new path, keeping
+/// the levels below it. Only the records of the transaction type are changed if there is a
+/// transaction_type column, and tables without subcategory column only keep the top level.
+fn rewrite_category_path(
+    data_frame: &DataFrame,
+    transaction_type: Option<&TransactionType>,
+    old_path: &[String],
+    new_path: &[String],
+) -> Result<DataFrame, PolarsError> {
+    let has_subcategory: bool = data_frame.schema().contains("subcategory");
+    let categories: &StringChunked = data_frame.column("category")?.str()?;
+    let types: Option<&StringChunked> = match transaction_type {
+        Some(_) => Some(data_frame.column("transaction_type")?.str()?),
+        None => None,
+    };
+
+    let mut new_categories: Vec<String> = Vec::new();
+    let mut new_subcategories: Vec<String> = Vec::new();
+    for i in 0..data_frame.height() {
+        // empty strings are read as nulls
+        let category: &str = categories.get(i).unwrap_or("");
+        let subcategory: &str = if has_subcategory {
+            data_frame
+                .column("subcategory")?
+                .str()?
+                .get(i)
+                .unwrap_or("")
+        } else {
+            ""
+        };
+        let is_type: bool = match (transaction_type, types) {
+            (Some(transaction_type), Some(types)) => {
+                types.get(i) == Some(transaction_type.to_string().as_str())
+            }
+            _ => true,
+        };
+
+        let path: Vec<String> = category_path(category, subcategory);
+        if is_type & path.starts_with(old_path) {
+            let mut moved_path: Vec<String> = new_path.to_vec();
+            moved_path.extend_from_slice(&path[old_path.len()..]);
+            let (new_category, new_subcategory) = split_category_path(&moved_path);
+            new_categories.push(new_category);
+            new_subcategories.push(new_subcategory);
+        } else {
+            new_categories.push(category.to_string());
+            new_subcategories.push(subcategory.to_string());
+        }
+    }
+
+    let mut data_frame: DataFrame = data_frame.clone();
+    data_frame.with_column(Series::new("category".into(), new_categories))?;
+    if has_subcategory {
+        data_frame.with_column(Series::new("subcategory".into(), new_subcategories))?;
+    }
+
+    Ok(data_frame)
+}
+
+pub struct CategoryTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for CategoryTable {
+    fn name() -> String {
+        String::from("category")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(CategoryTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", CategoryTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("transaction_type"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("name"), Vec::<String>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("parent_id"),
+                Vec::<i64>::new(),
+            )),
+        ])?;
+
+        Ok(CategoryTable::create(data_frame))
+    }
+}
+
+impl CategoryTable {
+    /// Adds category record to the table
+    pub fn insert_category(&mut self, category: &Category) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", CategoryTable::name()) => [id],
+            "transaction_type" => [category.transaction_type().to_string()],
+            "name" => [category.name()],
+            "parent_id" => [category.parent_id()]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Retrieves category from the table, given ID
+    pub(crate) fn category(&self, id: i64) -> Result<Category, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", CategoryTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        let record = self.data_frame.filter(&mask)?;
+        if record.height() == 0 {
+            return Err(format!("No category with ID {id}!").into());
+        }
+
+        Ok(Category::new(
+            TransactionType::from_str(
+                record
+                    .column("transaction_type")?
+                    .str()?
+                    .get(0)
+                    .ok_or("No transaction_type!")?,
+            )?,
+            record
+                .column("name")?
+                .str()?
+                .get(0)
+                .ok_or("No name!")?
+                .to_string(),
+            record
+                .column("parent_id")?
+                .i64()?
+                .get(0)
+                .ok_or("No parent_id!")?,
+        ))
+    }
+
+    /// Returns the IDs of the categories of the type right under the parent, sorted by name
+    pub(crate) fn children(
+        &self,
+        transaction_type: &TransactionType,
+        parent_id: i64,
+    ) -> Result<Vec<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("transaction_type")
+                    .eq(lit(transaction_type.to_string()))
+                    .and(col("parent_id").eq(lit(parent_id))),
+            )
+            .sort(["name"], Default::default())
+            .collect()?
+            .column(format!("{}_id", CategoryTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect())
+    }
+
+    /// Returns the ID of the category of the type with the name under the parent, if any
+    pub(crate) fn child(
+        &self,
+        transaction_type: &TransactionType,
+        parent_id: i64,
+        name: &str,
+    ) -> Result<Option<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("transaction_type")
+                    .eq(lit(transaction_type.to_string()))
+                    .and(col("parent_id").eq(lit(parent_id)))
+                    .and(col("name").eq(lit(name))),
+            )
+            .collect()?
+            .column(format!("{}_id", CategoryTable::name()).as_str())?
+            .i64()?
+            .into_iter()
+            .next()
+            .flatten())
+    }
+
+    pub(crate) fn rename(&mut self, id: i64, name: &str) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col(format!("{}_id", CategoryTable::name()).as_str()).eq(lit(id)))
+                    .then(lit(name))
+                    .otherwise(col("name"))
+                    .alias("name"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Moves the category, and everything under it, under another parent
+    pub(crate) fn set_parent(&mut self, id: i64, parent_id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col(format!("{}_id", CategoryTable::name()).as_str()).eq(lit(id)))
+                    .then(lit(parent_id))
+                    .otherwise(col("parent_id"))
+                    .alias("parent_id"),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    pub(crate) fn delete_category(&mut self, id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", CategoryTable::name()).as_str()).neq(lit(id)))
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+pub struct CsvMappingTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for CsvMappingTable {
+    fn name() -> String {
+        String::from("csv_mapping")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(CsvMappingTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", CsvMappingTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("name"), Vec::<String>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("delimiter"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("skip_rows"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("date_column"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("date_format"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("amount_column"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("credit_column"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("amount_sign"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("decimal_separator"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("currency_column"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("payee_column"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("description_column"),
+                Vec::<String>::new(),
+            )),
+        ])?;
+
+        Ok(CsvMappingTable::create(data_frame))
+    }
+}
+
+impl CsvMappingTable {
+    /// Adds CSV mapping record to the table
+    pub fn insert_mapping(&mut self, mapping: &CsvMapping) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", CsvMappingTable::name()) => [id],
+            "name" => [mapping.name.clone()],
+            "delimiter" => [mapping.delimiter.to_string()],
+            "skip_rows" => [mapping.skip_rows as i64],
+            "date_column" => [mapping.date_column.clone()],
+            "date_format" => [mapping.date_format.clone()],
+            "amount_column" => [mapping.amount_column.clone()],
+            "credit_column" => [mapping.credit_column.clone()],
+            "amount_sign" => [mapping.amount_sign.to_string()],
+            "decimal_separator" => [mapping.decimal_separator.to_string()],
+            "currency_column" => [mapping.currency_column.clone()],
+            "payee_column" => [mapping.payee_column.clone()],
+            "description_column" => [mapping.description_column.clone()]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Retrieves CSV mapping from the table, given ID
+    pub(crate) fn mapping(&self, id: i64) -> Result<CsvMapping, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", CsvMappingTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        let record = self.data_frame.filter(&mask)?;
+        if record.height() == 0 {
+            return Err(format!("No CSV mapping with ID {id}!").into());
+        }
+        // empty strings are read as nulls
+        let text = |column: &str| -> Result<String, PolarsError> {
+            Ok(record
+                .column(column)?
+                .str()?
+                .get(0)
+                .unwrap_or("")
+                .to_string())
+        };
+        let character = |column: &str| -> Result<char, Box<dyn std::error::Error>> {
+            Ok(text(column)?
+                .chars()
+                .next()
+                .ok_or(format!("No {column}!"))?)
+        };
+
+        Ok(CsvMapping {
+            name: text("name")?,
+            delimiter: character("delimiter")?,
+            skip_rows: record
+                .column("skip_rows")?
+                .i64()?
+                .get(0)
+                .ok_or("No skip_rows!")? as usize,
+            date_column: text("date_column")?,
+            date_format: text("date_format")?,
+            amount_column: text("amount_column")?,
+            credit_column: text("credit_column")?,
+            amount_sign: AmountSign::from_str(&text("amount_sign")?)?,
+            decimal_separator: character("decimal_separator")?,
+            currency_column: text("currency_column")?,
+            payee_column: text("payee_column")?,
+            description_column: text("description_column")?,
+        })
+    }
+
+    /// Returns the ID of the mapping saved under the name, if any
+    pub(crate) fn find_mapping(&self, name: &str) -> Result<Option<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("name").eq(lit(name)))
+            .collect()?
+            .column(format!("{}_id", CsvMappingTable::name()).as_str())?
+            .i64()?
+            .into_iter()
+            .next()
+            .flatten())
+    }
+
+    /// Returns the IDs of the mappings, sorted by name
+    pub(crate) fn iter(&self) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .sort(["name"], Default::default())
+            .collect()?
+            .column(format!("{}_id", CsvMappingTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
+    pub(crate) fn delete_mapping(&mut self, id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", CsvMappingTable::name()).as_str()).neq(lit(id)))
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+pub struct ImportReferenceTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for ImportReferenceTable {
+    fn name() -> String {
+        String::from("import_reference")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(ImportReferenceTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", ImportReferenceTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("account_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("reference"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("party_id"), Vec::<i64>::new())),
+        ])?;
+
+        Ok(ImportReferenceTable::create(data_frame))
+    }
+}
+
+impl ImportReferenceTable {
+    /// Records that the movement with the reference the bank gave it was imported as the party
+    pub fn insert_reference(
+        &mut self,
+        account_id: i64,
+        reference: &str,
+        party_id: i64,
+    ) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", ImportReferenceTable::name()) => [id],
+            "account_id" => [account_id],
+            "reference" => [reference],
+            "party_id" => [party_id]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Checks whether a movement of the account with the reference was already imported
+    pub(crate) fn is_imported(
+        &self,
+        account_id: i64,
+        reference: &str,
+    ) -> Result<bool, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("account_id")
+                    .eq(lit(account_id))
+                    .and(col("reference").eq(lit(reference))),
+            )
+            .collect()?
+            .height()
+            > 0)
+    }
+
+    /// Deletes records corresponding to a party, so that its movements can be imported again.
+    pub(crate) fn delete_party(&mut self, party_id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("party_id").neq(lit(party_id)))
+            .collect()?;
+
+        Ok(())
+    }
+}
+
+pub struct StagingTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for StagingTable {
+    fn name() -> String {
+        String::from("staging")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(StagingTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", StagingTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("account_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("date"),
+                Vec::<NaiveDate>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("value_date"),
+                Vec::<Option<NaiveDate>>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("value"), Vec::<f64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("currency"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("payee"), Vec::<String>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("description"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("reference"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("entity_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("category"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("subcategory"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("accepted"),
+                Vec::<bool>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("tags"), Vec::<String>::new())),
+            Column::from(Series::new(PlSmallStr::from("rule_id"), Vec::<i64>::new())),
+        ])?;
+
+        Ok(StagingTable::create(data_frame))
+    }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        let mut data_frame: LazyFrame = data_frame.lazy();
+        if !data_frame.collect_schema()?.contains("rule_id") {
+            // drafts staged before there were categorization rules
+            data_frame =
+                data_frame.with_columns([lit("").alias("tags"), lit(-1i64).alias("rule_id")]);
+        }
+
+        // columns that are empty in every draft are not recognised as strings or dates
+        data_frame
+            .with_columns([
+                col("value_date").cast(DataType::Date),
+                col("payee").cast(DataType::String),
+                col("description").cast(DataType::String),
+                col("reference").cast(DataType::String),
+                col("category").cast(DataType::String),
+                col("subcategory").cast(DataType::String),
+                col("tags").cast(DataType::String),
+            ])
+            .collect()
+    }
+}
+
+impl StagingTable {
+    /// Adds draft party record to the table
+    pub fn insert_draft(&mut self, draft: &DraftParty) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", StagingTable::name()) => [id],
+            "account_id" => [draft.account_id],
+            "date" => [draft.movement.date],
+            "value_date" => [draft.movement.value_date],
+            "value" => [draft.movement.value],
+            "currency" => [draft.movement.currency.to_string()],
+            "payee" => [draft.movement.payee.clone()],
+            "description" => [draft.movement.description.clone()],
+            "reference" => [draft.movement.reference.clone()],
+            "entity_id" => [draft.entity_id],
+            "category" => [draft.category.clone()],
+            "subcategory" => [draft.subcategory.clone()],
+            "accepted" => [draft.accepted],
+            // tags cannot contain a semicolon
+            "tags" => [draft.tags.join(";")],
+            "rule_id" => [draft.rule_id]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Retrieves draft party from the table, given ID
+    pub(crate) fn draft(&self, id: i64) -> Result<DraftParty, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", StagingTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        let record = self.data_frame.filter(&mask)?;
+        if record.height() == 0 {
+            return Err(format!("No draft party with ID {id}!").into());
+        }
+        // empty strings are read as nulls
+        let text = |column: &str| -> Result<String, PolarsError> {
+            Ok(record
+                .column(column)?
+                .str()?
+                .get(0)
+                .unwrap_or("")
+                .to_string())
+        };
+        let tags: String = text("tags")?;
+
+        let date: NaiveDate = record
+            .column("date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .flatten()
+            .ok_or("No date!")?;
+        let value_date: Option<NaiveDate> = record
+            .column("value_date")?
+            .date()?
+            .as_date_iter()
+            .next()
+            .flatten();
+
+        Ok(DraftParty {
+            account_id: record
+                .column("account_id")?
+                .i64()?
+                .get(0)
+                .ok_or("No account_id!")?,
+            movement: ImportedMovement {
+                date,
+                value_date,
+                value: record.column("value")?.f64()?.get(0).ok_or("No value!")?,
+                currency: Currency::from_str(&text("currency")?)?,
+                payee: text("payee")?,
+                description: text("description")?,
+                reference: text("reference")?,
+            },
+            entity_id: record
+                .column("entity_id")?
+                .i64()?
+                .get(0)
+                .ok_or("No entity_id!")?,
+            category: text("category")?,
+            subcategory: text("subcategory")?,
+            tags: tags
+                .split(';')
+                .filter(|tag| !tag.is_empty())
+                .map(|tag| tag.to_string())
+                .collect(),
+            rule_id: record
+                .column("rule_id")?
+                .i64()?
+                .get(0)
+                .ok_or("No rule_id!")?,
+            accepted: record
+                .column("accepted")?
+                .bool()?
+                .get(0)
+                .ok_or("No accepted!")?,
+        })
+    }
+
+    /// Iterator over IDs, sorted by date
+    pub(crate) fn iter(&self) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .sort(
+                ["date", format!("{}_id", StagingTable::name()).as_str()],
+                Default::default(),
+            )?
+            .column(format!("{}_id", StagingTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
+    /// Returns the number of drafts of the account with the reference
+    pub(crate) fn count_reference(
+        &self,
+        account_id: i64,
+        reference: &str,
+    ) -> Result<usize, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(
+                col("account_id")
+                    .eq(lit(account_id))
+                    .and(col("reference").eq(lit(reference))),
+            )
+            .collect()?
+            .height())
+    }
+
+    /// Returns the mask of the drafts with the IDs
+    fn mask(ids: &[i64]) -> Expr {
+        ids.iter().fold(lit(false), |mask, id| {
+            mask.or(col(format!("{}_id", StagingTable::name()).as_str()).eq(lit(*id)))
+        })
+    }
+
+    /// Replaces a column of the drafts with the IDs by the same value
+    fn set_column(&mut self, ids: &[i64], column: &str, value: Expr) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(StagingTable::mask(ids))
+                    .then(value)
+                    .otherwise(col(column))
+                    .alias(column),
+            )
+            .collect()?;
+
+        Ok(())
+    }
+
+    pub(crate) fn set_entity(&mut self, ids: &[i64], entity_id: i64) -> Result<(), PolarsError> {
+        self.set_column(ids, "entity_id", lit(entity_id))
+    }
+
+    pub(crate) fn set_category(
+        &mut self,
+        ids: &[i64],
+        category: &str,
+        subcategory: &str,
+    ) -> Result<(), PolarsError> {
+        self.set_column(ids, "category", lit(category))?;
+        self.set_column(ids, "subcategory", lit(subcategory))?;
+        // the category is no longer the one a rule proposed
+        self.set_column(ids, "rule_id", lit(-1i64))
+    }
+
+    pub(crate) fn set_accepted(&mut self, ids: &[i64], accepted: bool) -> Result<(), PolarsError> {
+        self.set_column(ids, "accepted", lit(accepted))
+    }
+
+    pub(crate) fn delete_drafts(&mut self, ids: &[i64]) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(StagingTable::mask(ids).not())
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Files the drafts of the transaction type under the old category path, or below it,
+    /// under the new path. Drafts that bring money in become incomes, the rest expenses.
+    pub(crate) fn rewrite_category(
+        &mut self,
+        transaction_type: &TransactionType,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), PolarsError> {
+        let data_frame: DataFrame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .with_column(
+                when(col("value").gt(lit(0.0)))
+                    .then(lit(TransactionType::Income.to_string()))
+                    .otherwise(lit(TransactionType::Expense.to_string()))
+                    .alias("transaction_type"),
+            )
+            .collect()?;
+        self.data_frame =
+            rewrite_category_path(&data_frame, Some(transaction_type), old_path, new_path)?
+                .drop("transaction_type")?;
+
+        Ok(())
+    }
+}
+
+pub struct CategorizationRuleTable {
+    pub data_frame: DataFrame,
+}
+
+impl Table for CategorizationRuleTable {
+    fn name() -> String {
+        String::from("categorization_rule")
+    }
+
+    fn data_frame(&self) -> &DataFrame {
+        &self.data_frame
+    }
+
+    fn mut_data_frame(&mut self) -> &mut DataFrame {
+        &mut self.data_frame
+    }
+
+    fn create(data_frame: DataFrame) -> Box<Self> {
+        Box::new(CategorizationRuleTable { data_frame })
+    }
+
+    fn new() -> Result<Box<Self>, PolarsError> {
+        let data_frame = DataFrame::new(vec![
+            Column::from(Series::new(
+                PlSmallStr::from(format!("{}_id", CategorizationRuleTable::name())),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("name"), Vec::<String>::new())),
+            Column::from(Series::new(PlSmallStr::from("priority"), Vec::<i64>::new())),
+            Column::from(Series::new(
+                PlSmallStr::from("transaction_type"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("description_pattern"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("min_value"),
+                Vec::<Option<f64>>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("max_value"),
+                Vec::<Option<f64>>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("account_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("currency"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("entity_id"),
+                Vec::<i64>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("category"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(
+                PlSmallStr::from("subcategory"),
+                Vec::<String>::new(),
+            )),
+            Column::from(Series::new(PlSmallStr::from("tags"), Vec::<String>::new())),
+        ])?;
+
+        Ok(CategorizationRuleTable::create(data_frame))
+    }
+
+    fn upgrade(data_frame: DataFrame) -> Result<DataFrame, PolarsError> {
+        // columns that are empty in every rule are not recognised as strings or numbers
+        data_frame
+            .lazy()
+            .with_columns([
+                col("description_pattern").cast(DataType::String),
+                col("min_value").cast(DataType::Float64),
+                col("max_value").cast(DataType::Float64),
+                col("currency").cast(DataType::String),
+                col("category").cast(DataType::String),
+                col("subcategory").cast(DataType::String),
+                col("tags").cast(DataType::String),
+            ])
+            .collect()
+    }
+}
+
+impl CategorizationRuleTable {
+    /// Adds categorization rule record to the table
+    pub fn insert_rule(&mut self, rule: &CategorizationRule) -> Result<i64, PolarsError> {
+        let id: i64 = self.next_id()?;
+
+        let record = df!(
+            format!("{}_id", CategorizationRuleTable::name()) => [id],
+            "name" => [rule.name.clone()],
+            "priority" => [rule.priority],
+            "transaction_type" => [rule.transaction_type.to_string()],
+            "description_pattern" => [rule.description_pattern.clone()],
+            "min_value" => [rule.min_value],
+            "max_value" => [rule.max_value],
+            "account_id" => [rule.account_id],
+            "currency" => [rule
+                .currency
+                .as_ref()
+                .map(|currency| currency.to_string())
+                .unwrap_or_default()],
+            "entity_id" => [rule.entity_id],
+            "category" => [rule.category.clone()],
+            "subcategory" => [rule.subcategory.clone()],
+            // tags cannot contain a semicolon
+            "tags" => [rule.tags.join(";")]
+        )?;
+
+        self.data_frame = self.data_frame.vstack(&record)?;
+
+        Ok(id)
+    }
+
+    /// Retrieves categorization rule from the table, given ID
+    pub(crate) fn rule(&self, id: i64) -> Result<CategorizationRule, Box<dyn std::error::Error>> {
+        let mask = self
+            .data_frame
+            .column(format!("{}_id", CategorizationRuleTable::name()).as_str())?
+            .i64()?
+            .equal(id);
+
+        let record = self.data_frame.filter(&mask)?;
+        if record.height() == 0 {
+            return Err(format!("No categorization rule with ID {id}!").into());
+        }
+        // empty strings are read as nulls
+        let text = |column: &str| -> Result<String, PolarsError> {
+            Ok(record
+                .column(column)?
+                .str()?
+                .get(0)
+                .unwrap_or("")
+                .to_string())
+        };
+        let currency: String = text("currency")?;
+        let tags: String = text("tags")?;
+
+        Ok(CategorizationRule {
+            name: text("name")?,
+            priority: record
+                .column("priority")?
+                .i64()?
+                .get(0)
+                .ok_or("No priority!")?,
+            transaction_type: TransactionType::from_str(&text("transaction_type")?)?,
+            description_pattern: text("description_pattern")?,
+            min_value: record.column("min_value")?.f64()?.get(0),
+            max_value: record.column("max_value")?.f64()?.get(0),
+            account_id: record
+                .column("account_id")?
+                .i64()?
+                .get(0)
+                .ok_or("No account_id!")?,
+            currency: if currency.is_empty() {
+                None
+            } else {
+                Some(Currency::from_str(&currency)?)
+            },
+            entity_id: record
+                .column("entity_id")?
+                .i64()?
+                .get(0)
+                .ok_or("No entity_id!")?,
+            category: text("category")?,
+            subcategory: text("subcategory")?,
+            tags: tags
+                .split(';')
+                .filter(|tag| !tag.is_empty())
+                .map(|tag| tag.to_string())
+                .collect(),
+        })
+    }
+
+    /// Returns the ID of the rule saved under the name, if any
+    pub(crate) fn find_rule(&self, name: &str) -> Result<Option<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col("name").eq(lit(name)))
+            .collect()?
+            .column(format!("{}_id", CategorizationRuleTable::name()).as_str())?
+            .i64()?
+            .into_iter()
+            .next()
+            .flatten())
+    }
+
+    /// Returns the IDs of the rules in the order they are tried: by priority, and by age
+    /// between rules of the same priority
+    pub(crate) fn iter(&self) -> Result<IntoIter<i64>, PolarsError> {
+        Ok(self
+            .data_frame
+            .sort(
+                [
+                    "priority",
+                    format!("{}_id", CategorizationRuleTable::name()).as_str(),
+                ],
+                Default::default(),
+            )?
+            .column(format!("{}_id", CategorizationRuleTable::name()).as_str())?
+            .i64()?
+            .into_no_null_iter()
+            .collect::<Vec<i64>>()
+            .into_iter())
+    }
+
+    pub(crate) fn delete_rule(&mut self, id: i64) -> Result<(), PolarsError> {
+        self.data_frame = self
+            .data_frame
+            .clone()
+            .lazy()
+            .filter(col(format!("{}_id", CategorizationRuleTable::name()).as_str()).neq(lit(id)))
+            .collect()?;
+
+        Ok(())
+    }
+
+    /// Makes the rules of the transaction type that propose the old category path, or one
+    /// below it, propose the new path.
+    pub(crate) fn rewrite_category(
+        &mut self,
+        transaction_type: &TransactionType,
+        old_path: &[String],
+        new_path: &[String],
+    ) -> Result<(), PolarsError> {
+        self.data_frame =
+            rewrite_category_path(&self.data_frame, Some(transaction_type), old_path, new_path)?;
+
+        Ok(())
+    }
+}
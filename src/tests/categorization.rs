@@ -0,0 +1,567 @@
+#[cfg(test)]
+mod tests {
+    use crate::modules::categorization::classifier::{
+        CategoryClassifier, CategoryFeatures, CategorySuggestion,
+    };
+    use crate::modules::categorization::rules::{CategorizationRule, RuleProposal};
+    use crate::modules::categorization::CategorizationSubject;
+    use crate::modules::database::DataBase;
+    use crate::modules::financial::*;
+    use crate::modules::importers::ImportedMovement;
+    use chrono::NaiveDate;
+    use polars::prelude::*;
+
+    fn subject(text: &str, value: f64, account_id: i64) -> CategorizationSubject {
+        CategorizationSubject {
+            transaction_type: TransactionType::Expense,
+            text: String::from(text),
+            value,
+            currency: Currency::EUR,
+            account_id,
+        }
+    }
+
+    #[test]
+    fn correct_rule_matching() {
+        let rule = CategorizationRule {
+            name: String::from("Supermarkets"),
+            description_pattern: String::from("aldi|lidl"),
+            min_value: Some(1.0),
+            max_value: Some(200.0),
+            currency: Some(Currency::EUR),
+            ..Default::default()
+        };
+        let regex = rule.regex().unwrap();
+
+        assert!(rule.matches(&regex, &subject("ALDI SUED 1234", 23.45, 0)));
+        assert!(!rule.matches(&regex, &subject("Rewe", 23.45, 0)));
+        assert!(!rule.matches(&regex, &subject("Lidl", 250.0, 0)));
+
+        let mut income = subject("Lidl", 23.45, 0);
+        income.transaction_type = TransactionType::Income;
+        assert!(!rule.matches(&regex, &income));
+        let mut francs = subject("Lidl", 23.45, 0);
+        francs.currency = Currency::CHF;
+        assert!(!rule.matches(&regex, &francs));
+
+        let on_account = CategorizationRule {
+            account_id: 1,
+            ..Default::default()
+        };
+        let regex = on_account.regex().unwrap();
+        assert!(on_account.matches(&regex, &subject("Anything", 5.0, 1)));
+        assert!(!on_account.matches(&regex, &subject("Anything", 5.0, 2)));
+    }
+
+    #[test]
+    fn correct_rule_validation() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        data_base
+            .register_category(&TransactionType::Expense, "Food", "Groceries")
+            .unwrap();
+        let valid = || CategorizationRule {
+            name: String::from("Supermarkets"),
+            description_pattern: String::from("aldi"),
+            category: String::from("Food"),
+            subcategory: String::from("Groceries"),
+            ..Default::default()
+        };
+
+        for rule in [
+            CategorizationRule {
+                name: String::from(" "),
+                ..valid()
+            },
+            CategorizationRule {
+                transaction_type: TransactionType::Debit,
+                ..valid()
+            },
+            CategorizationRule {
+                description_pattern: String::from("aldi("),
+                ..valid()
+            },
+            CategorizationRule {
+                min_value: Some(10.0),
+                max_value: Some(5.0),
+                ..valid()
+            },
+            CategorizationRule {
+                category: String::from("Fod"),
+                ..valid()
+            },
+            CategorizationRule {
+                entity_id: 42,
+                ..valid()
+            },
+            CategorizationRule {
+                account_id: 42,
+                ..valid()
+            },
+        ] {
+            assert!(data_base.insert_categorization_rule(&rule).is_err());
+        }
+
+        // saving under the same name replaces the rule
+        data_base.insert_categorization_rule(&valid()).unwrap();
+        let rule_id: i64 = data_base
+            .insert_categorization_rule(&CategorizationRule {
+                tags: vec![String::from("weekly"), String::from("food")],
+                ..valid()
+            })
+            .unwrap();
+        assert_eq!(
+            data_base
+                .iter_categorization_rule_ids()
+                .unwrap()
+                .collect::<Vec<i64>>(),
+            vec![rule_id]
+        );
+        assert_eq!(
+            data_base.categorization_rule(rule_id).unwrap(),
+            CategorizationRule {
+                tags: vec![String::from("weekly"), String::from("food")],
+                ..valid()
+            }
+        );
+    }
+
+    #[test]
+    fn correct_rule_proposal() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Girokonto"),
+                String::from("Germany"),
+                Currency::EUR,
+                AccountType::Deposit,
+                0.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let aldi_id: i64 = data_base
+            .insert_entity(&Entity::new(
+                String::from("Aldi"),
+                String::from("Germany"),
+                EntityType::Firm,
+                String::from("Supermarket"),
+            ))
+            .unwrap();
+        data_base
+            .register_category(&TransactionType::Expense, "Food", "Groceries")
+            .unwrap();
+        data_base
+            .register_category(&TransactionType::Expense, "Food", "Snacks")
+            .unwrap();
+        data_base
+            .register_category(&TransactionType::Expense, "Uncategorized", "")
+            .unwrap();
+        data_base
+            .register_category(&TransactionType::Income, "Uncategorized", "")
+            .unwrap();
+
+        let groceries_id: i64 = data_base
+            .insert_categorization_rule(&CategorizationRule {
+                name: String::from("Groceries"),
+                priority: 10,
+                description_pattern: String::from("aldi"),
+                account_id,
+                entity_id: aldi_id,
+                category: String::from("Food"),
+                subcategory: String::from("Groceries"),
+                tags: vec![String::from("weekly")],
+                ..Default::default()
+            })
+            .unwrap();
+        // lower priority, so it is tried first
+        let snacks_id: i64 = data_base
+            .insert_categorization_rule(&CategorizationRule {
+                name: String::from("Snacks"),
+                priority: 5,
+                description_pattern: String::from("aldi"),
+                max_value: Some(5.0),
+                category: String::from("Food"),
+                subcategory: String::from("Snacks"),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(
+            data_base
+                .propose_categorization(&subject("ALDI SUED", 3.2, account_id))
+                .unwrap()
+                .map(|proposal| proposal.rule_id),
+            Some(snacks_id)
+        );
+        let proposal: RuleProposal = data_base
+            .propose_categorization(&subject("ALDI SUED", 42.1, account_id))
+            .unwrap()
+            .unwrap();
+        assert_eq!(proposal.rule_id, groceries_id);
+        assert_eq!(proposal.entity_id, aldi_id);
+        assert_eq!(proposal.tags, vec![String::from("weekly")]);
+        assert_eq!(
+            proposal.explanation,
+            "Rule \"Groceries\" (priority 10): expense with a description matching \"aldi\", on Girokonto"
+        );
+        assert!(data_base
+            .propose_categorization(&subject("Rewe", 42.1, account_id))
+            .unwrap()
+            .is_none());
+
+        // imported movements get what the rule proposes
+        let movement = ImportedMovement {
+            date: NaiveDate::from_ymd_opt(2025, 3, 4).unwrap(),
+            value_date: None,
+            value: -42.1,
+            currency: Currency::EUR,
+            payee: String::from("ALDI SUED 1234"),
+            description: String::from("Kartenzahlung"),
+            reference: String::default(),
+        };
+        let staging_ids: Vec<i64> = data_base
+            .stage_movements(account_id, &[movement], "Uncategorized", "Uncategorized")
+            .unwrap();
+        let draft = data_base.staged_draft(staging_ids[0]).unwrap();
+        assert_eq!(draft.entity_id, aldi_id);
+        assert_eq!(draft.category, "Food");
+        assert_eq!(draft.subcategory, "Groceries");
+        assert_eq!(draft.rule_id, groceries_id);
+        match &draft.party().transactions[0] {
+            Transaction::Expense { tags, .. } => assert_eq!(tags, &vec![String::from("weekly")]),
+            _ => panic!("The draft should be an expense!"),
+        }
+
+        // a category assigned by hand is no longer the one of the rule
+        data_base
+            .assign_staged_category(&staging_ids, &TransactionType::Expense, "Uncategorized", "")
+            .unwrap();
+        assert_eq!(data_base.staged_draft(staging_ids[0]).unwrap().rule_id, -1);
+
+        // the rules follow merged entities and renamed categories
+        let aldi_sued_id: i64 = data_base
+            .insert_entity(&Entity::new(
+                String::from("Aldi Süd"),
+                String::from("Germany"),
+                EntityType::Firm,
+                String::from("Supermarket"),
+            ))
+            .unwrap();
+        data_base.merge_entities(aldi_id, aldi_sued_id).unwrap();
+        let snacks_category_id: i64 = data_base
+            .find_category(
+                &TransactionType::Expense,
+                &[String::from("Food"), String::from("Snacks")],
+            )
+            .unwrap()
+            .unwrap();
+        data_base
+            .rename_category(snacks_category_id, "Sweets")
+            .unwrap();
+        assert_eq!(
+            data_base
+                .categorization_rule(groceries_id)
+                .unwrap()
+                .entity_id,
+            aldi_sued_id
+        );
+        assert_eq!(
+            data_base
+                .categorization_rule(snacks_id)
+                .unwrap()
+                .subcategory,
+            "Sweets"
+        );
+    }
+
+    #[test]
+    fn correct_rule_test_against_history() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Girokonto"),
+                String::from("Germany"),
+                Currency::EUR,
+                AccountType::Deposit,
+                0.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let aldi_id: i64 = data_base
+            .insert_entity(&Entity::new(
+                String::from("Aldi"),
+                String::from("Germany"),
+                EntityType::Firm,
+                String::from("Supermarket"),
+            ))
+            .unwrap();
+        data_base
+            .register_category(&TransactionType::Expense, "Food", "Groceries")
+            .unwrap();
+        let mut purchase = |day: u32, value: f64, category: &str, description: &str| {
+            let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 5, day).unwrap();
+            data_base
+                .insert_party(&mut Party::new(vec![
+                    Transaction::Expense {
+                        value,
+                        currency: Currency::EUR,
+                        date,
+                        category: String::from(category),
+                        subcategory: String::from("Groceries"),
+                        description: String::from(description),
+                        entity_id: aldi_id,
+                        amortization_months: 1,
+                        tags: Vec::new(),
+                        refund_of: -1,
+                        reimbursable: false,
+                    },
+                    Transaction::Debit {
+                        value,
+                        currency: Currency::EUR,
+                        date,
+                        account_id,
+                        value_date: None,
+                    },
+                ]))
+                .unwrap();
+        };
+        purchase(1, 20.0, "Food", "Weekly shopping");
+        purchase(8, 25.0, "Household", "Detergent");
+        purchase(9, 300.0, "Food", "Party");
+
+        let rule = CategorizationRule {
+            name: String::from("Groceries"),
+            // matches the name of the entity, not only the description
+            description_pattern: String::from("^weekly|aldi"),
+            max_value: Some(100.0),
+            account_id,
+            category: String::from("Food"),
+            subcategory: String::from("Groceries"),
+            ..Default::default()
+        };
+        let tested: DataFrame = data_base.test_categorization_rule(&rule).unwrap();
+
+        assert_eq!(tested.height(), 2);
+        assert_eq!(
+            tested
+                .column("agrees")
+                .unwrap()
+                .bool()
+                .unwrap()
+                .into_no_null_iter()
+                .collect::<Vec<bool>>(),
+            vec![true, false]
+        );
+        assert_eq!(
+            tested.column("entity").unwrap().str().unwrap().get(0),
+            Some("Aldi")
+        );
+    }
+
+    #[test]
+    fn correct_category_suggestions() {
+        let features = |entity_id: i64, description: &str, value: f64, day: u32| CategoryFeatures {
+            entity_id,
+            description: String::from(description),
+            value,
+            // the 5th of May of 2025 is a Monday
+            date: NaiveDate::from_ymd_opt(2025, 5, day).unwrap(),
+        };
+        let mut classifier: CategoryClassifier = CategoryClassifier::default();
+        assert!(classifier
+            .suggest(&features(1, "Aldi", 20.0, 5), 3)
+            .is_empty());
+
+        for day in [5, 12, 19] {
+            classifier.learn(
+                &features(1, "Weekly shopping", 20.0 + day as f64, day),
+                "Food",
+            );
+        }
+        classifier.learn(&features(2, "Fuel", 60.0, 9), "Transport");
+        classifier.learn(&features(2, "Car wash", 12.0, 10), "Transport");
+        classifier.learn(&features(3, "Cinema tickets", 24.0, 10), "Leisure");
+
+        let suggestions: Vec<CategorySuggestion> =
+            classifier.suggest(&features(1, "shopping", 25.0, 26), 3);
+        assert_eq!(suggestions[0].category, "Food");
+        assert!(suggestions[0].confidence > 0.9);
+        assert!(
+            suggestions
+                .iter()
+                .map(|suggestion| suggestion.confidence)
+                .sum::<f64>()
+                <= 1.0 + 1e-9
+        );
+
+        // the description tells apart what the entity alone cannot
+        assert_eq!(
+            classifier.suggest(&features(0, "Fuel", 55.0, 16), 1)[0].category,
+            "Transport"
+        );
+        assert_eq!(
+            classifier.suggest(&features(0, "Fuel", 55.0, 16), 1).len(),
+            1
+        );
+        assert_eq!(
+            CategorySuggestion {
+                category: String::from("Food"),
+                confidence: 0.834,
+            }
+            .to_string(),
+            "Food (83%)"
+        );
+    }
+
+    #[test]
+    fn correct_category_classifier_learning() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Girokonto"),
+                String::from("Germany"),
+                Currency::EUR,
+                AccountType::Deposit,
+                0.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let mut purchase = |day: u32, category: &str, description: &str| {
+            let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 5, day).unwrap();
+            data_base
+                .insert_party(&mut Party::new(vec![
+                    Transaction::Expense {
+                        value: 20.0,
+                        currency: Currency::EUR,
+                        date,
+                        category: String::from(category),
+                        subcategory: String::default(),
+                        description: String::from(description),
+                        entity_id: 0,
+                        amortization_months: 1,
+                        tags: Vec::new(),
+                        refund_of: -1,
+                        reimbursable: false,
+                    },
+                    Transaction::Debit {
+                        value: 20.0,
+                        currency: Currency::EUR,
+                        date,
+                        account_id,
+                        value_date: None,
+                    },
+                ]))
+                .unwrap();
+        };
+        purchase(1, "Food", "Bakery");
+        purchase(2, "Leisure", "Bakery");
+        // uncategorized expenses teach nothing
+        purchase(4, "", "Bakery");
+        data_base
+            .set_party_status(1, &TransactionStatus::Void)
+            .unwrap();
+
+        let suggestions: Vec<CategorySuggestion> = data_base
+            .category_classifier(&TransactionType::Expense)
+            .unwrap()
+            .suggest(
+                &CategoryFeatures {
+                    entity_id: 0,
+                    description: String::from("Bakery"),
+                    value: 20.0,
+                    date: NaiveDate::from_ymd_opt(2025, 5, 3).unwrap(),
+                },
+                3,
+            );
+        // void transactions do not count
+        assert_eq!(
+            suggestions,
+            vec![CategorySuggestion {
+                category: String::from("Food"),
+                confidence: 1.0,
+            }]
+        );
+        assert!(data_base
+            .category_classifier(&TransactionType::Income)
+            .unwrap()
+            .suggest(
+                &CategoryFeatures {
+                    entity_id: 0,
+                    description: String::from("Bakery"),
+                    value: 20.0,
+                    date: NaiveDate::from_ymd_opt(2025, 5, 3).unwrap(),
+                },
+                3,
+            )
+            .is_empty());
+        assert!(data_base
+            .category_classifier(&TransactionType::Debit)
+            .is_err());
+    }
+
+    #[test]
+    fn correct_category_classifier_dates() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Girokonto"),
+                String::from("Germany"),
+                Currency::EUR,
+                AccountType::Deposit,
+                0.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let mut purchase = |day: u32, category: &str| {
+            let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 5, day).unwrap();
+            data_base
+                .insert_party(&mut Party::new(vec![
+                    Transaction::Expense {
+                        value: 20.0,
+                        currency: Currency::EUR,
+                        date,
+                        category: String::from(category),
+                        subcategory: String::default(),
+                        description: String::from("Bakery"),
+                        entity_id: 0,
+                        amortization_months: 1,
+                        tags: Vec::new(),
+                        refund_of: -1,
+                        reimbursable: false,
+                    },
+                    Transaction::Debit {
+                        value: 20.0,
+                        currency: Currency::EUR,
+                        date,
+                        account_id,
+                        value_date: None,
+                    },
+                ]))
+                .unwrap();
+        };
+        // the uncategorized monday comes first and is skipped
+        purchase(5, "");
+        purchase(6, "Food");
+        purchase(7, "Leisure");
+
+        let classifier: CategoryClassifier = data_base
+            .category_classifier(&TransactionType::Expense)
+            .unwrap();
+        let likeliest = |day: u32| -> String {
+            classifier
+                .suggest(
+                    &CategoryFeatures {
+                        entity_id: 0,
+                        description: String::from("Bakery"),
+                        value: 20.0,
+                        date: NaiveDate::from_ymd_opt(2025, 5, day).unwrap(),
+                    },
+                    1,
+                )
+                .remove(0)
+                .category
+        };
+        // each category is learned with the weekday of its own transaction
+        assert_eq!(likeliest(13), "Food");
+        assert_eq!(likeliest(14), "Leisure");
+    }
+}
@@ -3,37 +3,10 @@
 mod tests {
     use crate::modules::currency_exchange::{CurrencyExchange, Extremum};
     use crate::modules::financial::Currency;
+    use crate::tests::test_helpers::init_testing_currency_exchange;
     use chrono::NaiveDate;
     use polars::prelude::*;
-    use std::collections::HashMap;
 
-    fn init_testing_currency_exchange() -> CurrencyExchange {
-        let data_frame_chfeur: DataFrame = df!(
-            "date" => [
-                NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
-                NaiveDate::from_ymd_opt(2020, 1, 2).unwrap(),
-                NaiveDate::from_ymd_opt(2020, 1, 3).unwrap(),
-            ],
-            "value" => [0.5, 1.0, 1.5]
-        )
-        .unwrap();
-
-        let data_frame_sekeur: DataFrame = df!(
-            "date" => [
-                NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
-                NaiveDate::from_ymd_opt(2020, 1, 2).unwrap(),
-                NaiveDate::from_ymd_opt(2020, 1, 3).unwrap(),
-            ],
-            "value" => [1.5, 1.0, 0.5]
-        )
-        .unwrap();
-
-        let mut hash_map = HashMap::new();
-        hash_map.insert(String::from("CHFEUR"), data_frame_chfeur);
-        hash_map.insert(String::from("SEKEUR"), data_frame_sekeur);
-
-        CurrencyExchange::new(hash_map).unwrap()
-    }
     #[test]
     fn correct_max_date() {
         let extremum: Extremum = Extremum::MAX;
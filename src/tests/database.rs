@@ -1,9 +1,11 @@
 #[cfg(test)]
 mod tests {
+    use crate::modules::database::summaries::{Basis, ReportDate};
     use crate::modules::database::DataBase;
     use crate::modules::financial::*;
-    use crate::tests::test_helpers::init_party;
-    use polars::df;
+    use crate::tests::test_helpers::{init_party, init_testing_currency_exchange};
+    use chrono::NaiveDate;
+    use polars::prelude::*;
 
     #[test]
     fn correct_insert_party() {
@@ -39,6 +41,7 @@ mod tests {
             Currency::CHF,
             AccountType::Deposit,
             1080.0f64,
+            Vec::new(),
         );
 
         let _ = data_base.insert_entity(&entity);
@@ -54,4 +57,1630 @@ mod tests {
 
         assert!(actual_result.equals(&expected_result));
     }
+
+    #[test]
+    fn correct_due_parties() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let template: Template = Template::new(
+            String::from("Salary"),
+            Recurrence::Yearly,
+            0,
+            NaiveDate::from_ymd_opt(2023, 12, 1).unwrap(),
+            init_party().transactions,
+        );
+        let template_id: i64 = data_base.insert_template(&template).unwrap();
+        let until: NaiveDate = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+
+        let due_parties = data_base.due_parties(until).unwrap();
+        assert_eq!(due_parties.len(), 2);
+        assert!(due_parties[1].party.is_valid());
+        assert_eq!(
+            due_parties[1].party.transactions[0].date(),
+            &NaiveDate::from_ymd_opt(2024, 12, 1).unwrap()
+        );
+
+        // handling a later occurrence first leaves the earlier one due
+        data_base
+            .handle_due_party(template_id, due_parties[1].date)
+            .unwrap();
+        let pending = data_base.due_parties(until).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].date, due_parties[0].date);
+
+        data_base
+            .handle_due_party(template_id, due_parties[0].date)
+            .unwrap();
+        assert_eq!(data_base.due_parties(until).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn correct_budget_vs_actual() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let currency_exchange = init_testing_currency_exchange();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2020, 1, 3).unwrap();
+
+        let mut party: Party = Party::new(vec![
+            Transaction::Expense {
+                value: 30.0,
+                currency: Currency::EUR,
+                date: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                category: "Food".to_string(),
+                subcategory: "Groceries".to_string(),
+                description: "".to_string(),
+                entity_id: 0,
+                amortization_months: 1,
+                refund_of: -1,
+                reimbursable: false,
+                tags: Vec::new(),
+            },
+            Transaction::Expense {
+                value: 20.0,
+                currency: Currency::CHF,
+                date: NaiveDate::from_ymd_opt(2020, 1, 2).unwrap(),
+                category: "Food".to_string(),
+                subcategory: "Restaurants".to_string(),
+                description: "".to_string(),
+                entity_id: 0,
+                amortization_months: 1,
+                refund_of: -1,
+                reimbursable: false,
+                tags: Vec::new(),
+            },
+            Transaction::Credit {
+                value: 50.0,
+                currency: Currency::EUR,
+                date: NaiveDate::from_ymd_opt(2020, 1, 2).unwrap(),
+                account_id: 0,
+                value_date: None,
+            },
+        ]);
+        data_base.insert_party(&mut party).unwrap();
+
+        data_base
+            .insert_budget(&Budget::new(
+                "Food".to_string(),
+                "".to_string(),
+                BudgetPeriod::Monthly,
+                100.0,
+                Currency::EUR,
+            ))
+            .unwrap();
+        data_base
+            .insert_budget(&Budget::new(
+                "Food".to_string(),
+                "Groceries".to_string(),
+                BudgetPeriod::Yearly,
+                40.0,
+                Currency::EUR,
+            ))
+            .unwrap();
+
+        let report = data_base
+            .budget_vs_actual(&currency_exchange, date, &Currency::EUR)
+            .unwrap();
+
+        // the 20 CHF are exchanged at 1.5 on the 2020-01-03
+        let expected_result = df!(
+            "category" => ["Food", "Food"],
+            "subcategory" => ["", "Groceries"],
+            "period" => ["Monthly", "Yearly"],
+            "budget" => [100.0, 40.0],
+            "spent" => [60.0, 30.0],
+            "remaining" => [40.0, 10.0],
+            "projected" => [620.0, 3660.0],
+            "status" => ["Projected overrun", "Projected overrun"],
+            "budget_id" => [0i64, 1i64],
+        )
+        .unwrap();
+
+        assert!(report.equals(&expected_result));
+
+        // deeper in the hierarchy, lunches still count against the restaurants
+        let mut lunch: Party = Party::new(vec![
+            Transaction::Expense {
+                value: 12.0,
+                currency: Currency::EUR,
+                date: NaiveDate::from_ymd_opt(2020, 1, 3).unwrap(),
+                category: "Food".to_string(),
+                subcategory: "Restaurants:Lunch".to_string(),
+                description: "".to_string(),
+                entity_id: 0,
+                amortization_months: 1,
+                refund_of: -1,
+                reimbursable: false,
+                tags: Vec::new(),
+            },
+            Transaction::Credit {
+                value: 12.0,
+                currency: Currency::EUR,
+                date: NaiveDate::from_ymd_opt(2020, 1, 3).unwrap(),
+                account_id: 0,
+                value_date: None,
+            },
+        ]);
+        data_base.insert_party(&mut lunch).unwrap();
+        for subcategory in ["Restaurants", "Restaurant"] {
+            data_base
+                .insert_budget(&Budget::new(
+                    "Food".to_string(),
+                    subcategory.to_string(),
+                    BudgetPeriod::Monthly,
+                    50.0,
+                    Currency::EUR,
+                ))
+                .unwrap();
+        }
+
+        let report = data_base
+            .budget_vs_actual(&currency_exchange, date, &Currency::EUR)
+            .unwrap();
+        let spent = report.column("spent").unwrap().f64().unwrap();
+        // sorted by subcategory, and "Restaurant" is not a level above "Restaurants"
+        assert_eq!(spent.get(0), Some(72.0));
+        assert_eq!(spent.get(2), Some(0.0));
+        assert_eq!(spent.get(3), Some(42.0));
+    }
+
+    #[test]
+    fn correct_envelopes() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let currency_exchange = init_testing_currency_exchange();
+        let expense = |value: f64, currency: Currency, date: NaiveDate| -> Party {
+            Party::new(vec![
+                Transaction::Expense {
+                    value,
+                    currency: currency.clone(),
+                    date,
+                    category: "Food".to_string(),
+                    subcategory: "Groceries".to_string(),
+                    description: "".to_string(),
+                    entity_id: 0,
+                    amortization_months: 1,
+                    refund_of: -1,
+                    reimbursable: false,
+                    tags: Vec::new(),
+                },
+                Transaction::Debit {
+                    value,
+                    currency,
+                    date,
+                    account_id: 0,
+                    value_date: None,
+                },
+            ])
+        };
+        let january: NaiveDate = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let february: NaiveDate = NaiveDate::from_ymd_opt(2020, 2, 1).unwrap();
+
+        let mut salary: Party = Party::new(vec![
+            Transaction::Income {
+                value: 100.0,
+                currency: Currency::EUR,
+                date: january,
+                category: "Salary".to_string(),
+                subcategory: "".to_string(),
+                description: "".to_string(),
+                entity_id: 0,
+                tags: Vec::new(),
+            },
+            Transaction::Credit {
+                value: 100.0,
+                currency: Currency::EUR,
+                date: january,
+                account_id: 0,
+                value_date: None,
+            },
+        ]);
+        data_base.insert_party(&mut salary).unwrap();
+        // the 20 CHF are exchanged at 1.0 on the 2020-01-02
+        data_base
+            .insert_party(&mut expense(
+                20.0,
+                Currency::CHF,
+                january.succ_opt().unwrap(),
+            ))
+            .unwrap();
+        data_base
+            .insert_party(&mut expense(
+                50.0,
+                Currency::EUR,
+                february + chrono::Days::new(9),
+            ))
+            .unwrap();
+
+        for (category, value, date) in [
+            ("Food", 60.0, january),
+            ("Rent", 30.0, january),
+            ("Food", 10.0, february),
+        ] {
+            data_base
+                .insert_assignment(&Assignment::new(
+                    0,
+                    category.to_string(),
+                    value,
+                    Currency::EUR,
+                    date,
+                ))
+                .unwrap();
+        }
+
+        let envelopes = data_base
+            .envelopes(&currency_exchange, february, &Currency::EUR)
+            .unwrap();
+
+        let expected_result = df!(
+            "category" => ["Food", "Rent"],
+            "rolled_over" => [40.0, 30.0],
+            "assigned" => [10.0, 0.0],
+            "spent" => [50.0, 0.0],
+            "available" => [0.0, 30.0],
+        )
+        .unwrap();
+
+        assert!(envelopes.equals(&expected_result));
+        assert_eq!(
+            data_base
+                .to_be_assigned(&currency_exchange, january, &Currency::EUR)
+                .unwrap(),
+            10.0
+        );
+        assert_eq!(
+            data_base
+                .to_be_assigned(&currency_exchange, february, &Currency::EUR)
+                .unwrap(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn correct_reconciliation() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Current account"),
+                String::from("Switzerland"),
+                Currency::CHF,
+                AccountType::Deposit,
+                100.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let statement_date: NaiveDate = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+
+        let mut party: Party = Party::new(vec![
+            Transaction::Credit {
+                value: 50.0,
+                currency: Currency::CHF,
+                date: NaiveDate::from_ymd_opt(2024, 12, 1).unwrap(),
+                account_id,
+                value_date: None,
+            },
+            Transaction::Debit {
+                value: 20.0,
+                currency: Currency::CHF,
+                date: NaiveDate::from_ymd_opt(2024, 12, 2).unwrap(),
+                account_id,
+                value_date: None,
+            },
+        ]);
+        data_base.insert_party(&mut party).unwrap();
+
+        let movements = data_base
+            .account_movements(account_id, &Currency::CHF)
+            .unwrap();
+        let balances: Vec<Option<f64>> =
+            movements.column("balance").unwrap().f64().unwrap().to_vec();
+        assert_eq!(balances, vec![Some(150.0), Some(130.0)]);
+
+        data_base
+            .set_fund_movement_status(0, &TransactionStatus::Cleared)
+            .unwrap();
+        assert_eq!(
+            data_base
+                .cleared_balance(account_id, &Currency::CHF, statement_date)
+                .unwrap(),
+            150.0
+        );
+        assert!(data_base
+            .reconcile(account_id, &Currency::CHF, statement_date, 130.0)
+            .is_err());
+
+        data_base
+            .set_fund_movement_status(1, &TransactionStatus::Cleared)
+            .unwrap();
+        assert_eq!(
+            data_base
+                .reconcile(account_id, &Currency::CHF, statement_date, 130.0)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            data_base
+                .last_reconciliation(account_id, &Currency::CHF)
+                .unwrap(),
+            Some((statement_date, 130.0))
+        );
+
+        // reconciled movements are locked
+        assert!(data_base
+            .set_fund_movement_status(0, &TransactionStatus::Pending)
+            .is_err());
+        assert!(data_base
+            .set_party_status(0, &TransactionStatus::Void)
+            .is_err());
+        assert!(data_base.delete_party(0).is_err());
+    }
+
+    #[test]
+    fn correct_account_pockets() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Current account"),
+                String::from("Switzerland"),
+                Currency::CHF,
+                AccountType::Deposit,
+                100.0,
+                vec![Currency::EUR],
+            ))
+            .unwrap();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+
+        let account: Account = data_base.account(account_id).unwrap();
+        assert_eq!(account.pockets(), &[Currency::EUR]);
+        assert!(account.has_currency(&Currency::CHF));
+        assert!(account.has_currency(&Currency::EUR));
+        assert!(!account.has_currency(&Currency::SEK));
+
+        // next to the unknown account
+        let pockets: DataFrame = data_base.account_pockets().unwrap();
+        assert_eq!(pockets.height(), 3);
+        assert_eq!(
+            pockets
+                .column("initial_balance")
+                .unwrap()
+                .f64()
+                .unwrap()
+                .to_vec(),
+            vec![Some(0.0), Some(100.0), Some(0.0)]
+        );
+
+        let mut party: Party = Party::new(vec![
+            Transaction::Income {
+                value: 30.0,
+                currency: Currency::EUR,
+                date,
+                category: "Salary".to_string(),
+                subcategory: "".to_string(),
+                description: "".to_string(),
+                entity_id: 0,
+                tags: Vec::new(),
+            },
+            Transaction::Credit {
+                value: 30.0,
+                currency: Currency::EUR,
+                date,
+                account_id,
+                value_date: None,
+            },
+        ]);
+        party.status = TransactionStatus::Cleared;
+        data_base.insert_party(&mut party).unwrap();
+
+        // each pocket is reconciled on its own, and only the main one has an initial balance
+        assert_eq!(
+            data_base
+                .account_movements(account_id, &Currency::CHF)
+                .unwrap()
+                .height(),
+            0
+        );
+        assert_eq!(
+            data_base
+                .cleared_balance(account_id, &Currency::CHF, date)
+                .unwrap(),
+            100.0
+        );
+        assert_eq!(
+            data_base
+                .cleared_balance(account_id, &Currency::EUR, date)
+                .unwrap(),
+            30.0
+        );
+        assert!(data_base
+            .cleared_balance(account_id, &Currency::SEK, date)
+            .is_err());
+        data_base
+            .reconcile(account_id, &Currency::EUR, date, 30.0)
+            .unwrap();
+        assert_eq!(
+            data_base
+                .last_reconciliation(account_id, &Currency::EUR)
+                .unwrap(),
+            Some((date, 30.0))
+        );
+        assert_eq!(
+            data_base
+                .last_reconciliation(account_id, &Currency::CHF)
+                .unwrap(),
+            None
+        );
+        assert!(data_base.delete_party(0).is_err());
+    }
+
+    #[test]
+    fn correct_pocket_running_balances() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Current account"),
+                String::from("Switzerland"),
+                Currency::CHF,
+                AccountType::Deposit,
+                100.0,
+                vec![Currency::EUR],
+            ))
+            .unwrap();
+        let mut movement = |day: u32, value: f64, currency: Currency| {
+            let date: NaiveDate = NaiveDate::from_ymd_opt(2024, 12, day).unwrap();
+            let transactions: Vec<Transaction> = if value > 0.0 {
+                vec![
+                    Transaction::Income {
+                        value,
+                        currency: currency.clone(),
+                        date,
+                        category: "Salary".to_string(),
+                        subcategory: "".to_string(),
+                        description: "".to_string(),
+                        entity_id: 0,
+                        tags: Vec::new(),
+                    },
+                    Transaction::Credit {
+                        value,
+                        currency,
+                        date,
+                        account_id,
+                        value_date: None,
+                    },
+                ]
+            } else {
+                vec![
+                    Transaction::Expense {
+                        value: -value,
+                        currency: currency.clone(),
+                        date,
+                        category: "Food".to_string(),
+                        subcategory: "".to_string(),
+                        description: "".to_string(),
+                        entity_id: 0,
+                        amortization_months: 1,
+                        tags: Vec::new(),
+                        refund_of: -1,
+                        reimbursable: false,
+                    },
+                    Transaction::Debit {
+                        value: -value,
+                        currency,
+                        date,
+                        account_id,
+                        value_date: None,
+                    },
+                ]
+            };
+            let mut party: Party = Party::new(transactions);
+            party.status = TransactionStatus::Cleared;
+            data_base.insert_party(&mut party).unwrap();
+        };
+        movement(1, 30.0, Currency::EUR);
+        movement(2, -20.0, Currency::CHF);
+        movement(3, 10.0, Currency::EUR);
+
+        let csv: String = data_base.last_fund_movements(10, account_id).unwrap();
+        let mut lines = csv.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let currency_index: usize = header.iter().position(|name| *name == "Currency").unwrap();
+        let balance_index: usize = header.iter().position(|name| *name == "Balance").unwrap();
+        let balances: Vec<(String, f64)> = lines
+            .map(|line| {
+                let fields: Vec<&str> = line.split(',').collect();
+                (
+                    fields[currency_index].to_string(),
+                    fields[balance_index].parse::<f64>().unwrap(),
+                )
+            })
+            .collect();
+        // each pocket runs on its own, and only the main one starts from the initial balance
+        assert_eq!(
+            balances,
+            vec![
+                (String::from("EUR"), 40.0),
+                (String::from("CHF"), 80.0),
+                (String::from("EUR"), 30.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn correct_account_closing() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Old account"),
+                String::from("Switzerland"),
+                Currency::CHF,
+                AccountType::Deposit,
+                100.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let entity_id: i64 = data_base
+            .insert_entity(&Entity::new(
+                String::from("Defunct shop"),
+                String::from("Switzerland"),
+                EntityType::Firm,
+                String::from("Shop"),
+            ))
+            .unwrap();
+        let closing_date: NaiveDate = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+
+        // the account is not empty
+        assert!(data_base.close_account(account_id, closing_date).is_err());
+
+        let mut party: Party = Party::new(vec![
+            Transaction::Expense {
+                value: 100.0,
+                currency: Currency::CHF,
+                date: NaiveDate::from_ymd_opt(2024, 12, 1).unwrap(),
+                category: "Food".to_string(),
+                subcategory: "".to_string(),
+                description: "".to_string(),
+                entity_id,
+                amortization_months: 1,
+                refund_of: -1,
+                reimbursable: false,
+                tags: Vec::new(),
+            },
+            Transaction::Debit {
+                value: 100.0,
+                currency: Currency::CHF,
+                date: NaiveDate::from_ymd_opt(2024, 12, 1).unwrap(),
+                account_id,
+                value_date: None,
+            },
+        ]);
+        data_base.insert_party(&mut party).unwrap();
+
+        // there is a movement after the closing date
+        assert!(data_base
+            .close_account(account_id, NaiveDate::from_ymd_opt(2024, 11, 30).unwrap())
+            .is_err());
+
+        data_base.close_account(account_id, closing_date).unwrap();
+
+        assert_eq!(
+            data_base.account_closing_date(account_id).unwrap(),
+            Some(closing_date)
+        );
+        assert!(!data_base
+            .iter_open_account_ids()
+            .unwrap()
+            .any(|id| id == account_id));
+        assert!(data_base
+            .iter_account_ids()
+            .unwrap()
+            .any(|id| id == account_id));
+        assert!(data_base.close_account(account_id, closing_date).is_err());
+
+        data_base.reopen_account(account_id).unwrap();
+        assert!(data_base
+            .iter_open_account_ids()
+            .unwrap()
+            .any(|id| id == account_id));
+
+        data_base.set_entity_archived(entity_id, true).unwrap();
+        assert!(data_base.is_entity_archived(entity_id).unwrap());
+        assert!(!data_base
+            .iter_active_entity_ids()
+            .unwrap()
+            .any(|id| id == entity_id));
+        // the history is kept
+        assert_eq!(data_base.party(0).unwrap().transactions.len(), 2);
+    }
+
+    #[test]
+    fn correct_entity_merge() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let mut insert_shop = |name: &str| -> i64 {
+            data_base
+                .insert_entity(&Entity::new(
+                    String::from(name),
+                    String::from("Germany"),
+                    EntityType::Firm,
+                    String::from("Supermarket"),
+                ))
+                .unwrap()
+        };
+        let aldi_id: i64 = insert_shop("Aldi");
+        let duplicate_id: i64 = insert_shop("ALDI Süd");
+
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+        for entity_id in [aldi_id, duplicate_id] {
+            let mut party: Party = Party::new(vec![
+                Transaction::Expense {
+                    value: 20.0,
+                    currency: Currency::EUR,
+                    date,
+                    category: "Food".to_string(),
+                    subcategory: "Groceries".to_string(),
+                    description: "".to_string(),
+                    entity_id,
+                    amortization_months: 1,
+                    refund_of: -1,
+                    reimbursable: false,
+                    tags: Vec::new(),
+                },
+                Transaction::Debit {
+                    value: 20.0,
+                    currency: Currency::EUR,
+                    date,
+                    account_id: 0,
+                    value_date: None,
+                },
+            ]);
+            data_base.insert_party(&mut party).unwrap();
+        }
+
+        assert!(data_base.merge_entities(aldi_id, aldi_id).is_err());
+        data_base.merge_entities(duplicate_id, aldi_id).unwrap();
+
+        assert!(data_base.entity(duplicate_id).is_err());
+        for party_id in [0, 1] {
+            match &data_base.party(party_id).unwrap().transactions[0] {
+                Transaction::Expense { entity_id, .. } => assert_eq!(*entity_id, aldi_id),
+                _ => panic!("Not an expense!"),
+            }
+        }
+
+        // the name of the duplicate is kept as an alias
+        assert_eq!(
+            data_base.entity_aliases(aldi_id).unwrap(),
+            vec![String::from("ALDI Süd")]
+        );
+        assert_eq!(data_base.match_entity(" aldi  SÜD").unwrap(), Some(aldi_id));
+        assert_eq!(data_base.match_entity("Lidl").unwrap(), None);
+        assert!(data_base.insert_entity_alias(aldi_id, "aldi süd").is_err());
+
+        data_base
+            .insert_entity_alias(aldi_id, "ALDI SAGT DANKE")
+            .unwrap();
+        data_base
+            .update_entity(
+                aldi_id,
+                &Entity::new(
+                    String::from("Aldi Nord"),
+                    String::from("Germany"),
+                    EntityType::Firm,
+                    String::from("Discounter"),
+                ),
+            )
+            .unwrap();
+        assert_eq!(data_base.entity(aldi_id).unwrap().name(), "Aldi Nord");
+        assert_eq!(
+            data_base.match_entity("Aldi sagt danke").unwrap(),
+            Some(aldi_id)
+        );
+    }
+
+    #[test]
+    fn correct_category_registry() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 2, 1).unwrap();
+        for (category, subcategory) in [("Groceries", ""), ("Food", "Restaurant")] {
+            let mut party: Party = Party::new(vec![
+                Transaction::Expense {
+                    value: 30.0,
+                    currency: Currency::EUR,
+                    date,
+                    category: category.to_string(),
+                    subcategory: subcategory.to_string(),
+                    description: "".to_string(),
+                    entity_id: 0,
+                    amortization_months: 1,
+                    refund_of: -1,
+                    reimbursable: false,
+                    tags: Vec::new(),
+                },
+                Transaction::Debit {
+                    value: 30.0,
+                    currency: Currency::EUR,
+                    date,
+                    account_id: 0,
+                    value_date: None,
+                },
+            ]);
+            data_base.insert_party(&mut party).unwrap();
+        }
+        let expense_category = |data_base: &DataBase, party_id: i64| -> (String, String) {
+            match &data_base.party(party_id).unwrap().transactions[0] {
+                Transaction::Expense {
+                    category,
+                    subcategory,
+                    ..
+                } => (category.clone(), subcategory.clone()),
+                _ => panic!("Not an expense!"),
+            }
+        };
+
+        // the categories in use are registered
+        data_base.seed_categories().unwrap();
+        assert!(data_base
+            .is_known_category(&TransactionType::Expense, "Food", "Restaurant")
+            .unwrap());
+        assert!(!data_base
+            .is_known_category(&TransactionType::Expense, "Food", "Drinks")
+            .unwrap());
+        assert!(!data_base
+            .is_known_category(&TransactionType::Income, "Food", "")
+            .unwrap());
+        let path =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|s| s.to_string()).collect() };
+        let find = |data_base: &DataBase, names: &[&str]| -> i64 {
+            data_base
+                .find_category(&TransactionType::Expense, &path(names))
+                .unwrap()
+                .unwrap()
+        };
+
+        let food_id: i64 = find(&data_base, &["Food"]);
+        let groceries_id: i64 = find(&data_base, &["Groceries"]);
+        data_base.move_category(groceries_id, food_id).unwrap();
+        assert_eq!(
+            expense_category(&data_base, 0),
+            (String::from("Food"), String::from("Groceries"))
+        );
+        assert!(data_base.move_category(food_id, groceries_id).is_err());
+
+        let restaurant_id: i64 = find(&data_base, &["Food", "Restaurant"]);
+        assert!(data_base
+            .rename_category(restaurant_id, "Groceries")
+            .is_err());
+        data_base
+            .rename_category(restaurant_id, "Eating out")
+            .unwrap();
+        assert_eq!(
+            expense_category(&data_base, 1),
+            (String::from("Food"), String::from("Eating out"))
+        );
+
+        // deeper levels go in the subcategory, joined by colons
+        data_base
+            .register_category(&TransactionType::Expense, "Food", "Eating out:Lunch")
+            .unwrap();
+        data_base
+            .merge_categories(restaurant_id, groceries_id)
+            .unwrap();
+        assert_eq!(
+            expense_category(&data_base, 1),
+            (String::from("Food"), String::from("Groceries"))
+        );
+        assert!(data_base.category(restaurant_id).is_err());
+        assert_eq!(
+            data_base
+                .transaction_subcategories(&TransactionType::Expense, String::from("Food"))
+                .unwrap(),
+            vec![String::from("Groceries"), String::from("Groceries:Lunch")]
+        );
+        assert_eq!(
+            data_base
+                .transaction_categories(&TransactionType::Expense)
+                .unwrap(),
+            vec![String::from("Food")]
+        );
+    }
+
+    #[test]
+    fn correct_transaction_status() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Current account"),
+                String::from("Switzerland"),
+                Currency::CHF,
+                AccountType::Deposit,
+                100.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+
+        for (value, status) in [
+            (50.0, TransactionStatus::Cleared),
+            (20.0, TransactionStatus::Planned),
+            (30.0, TransactionStatus::Void),
+        ] {
+            let mut party: Party = Party::new(vec![Transaction::Credit {
+                value,
+                currency: Currency::CHF,
+                date,
+                account_id,
+                value_date: None,
+            }]);
+            party.status = status;
+            data_base.insert_party(&mut party).unwrap();
+        }
+
+        assert_eq!(
+            data_base.party(1).unwrap().status,
+            TransactionStatus::Planned
+        );
+
+        // void movements never count, planned ones only in the running balance
+        let movements = data_base
+            .account_movements(account_id, &Currency::CHF)
+            .unwrap();
+        let balances: Vec<Option<f64>> =
+            movements.column("balance").unwrap().f64().unwrap().to_vec();
+        assert_eq!(balances, vec![Some(150.0), Some(170.0), Some(170.0)]);
+        assert_eq!(
+            data_base
+                .account_balance(account_id, &Currency::CHF, date)
+                .unwrap(),
+            150.0
+        );
+
+        data_base
+            .set_party_status(1, &TransactionStatus::Cleared)
+            .unwrap();
+        assert_eq!(
+            data_base
+                .account_balance(account_id, &Currency::CHF, date)
+                .unwrap(),
+            170.0
+        );
+
+        // a single transaction can change its status apart from its party
+        data_base
+            .set_transaction_status(&TransactionType::Credit, 2, &TransactionStatus::Cleared)
+            .unwrap();
+        assert_eq!(
+            data_base
+                .account_balance(account_id, &Currency::CHF, date)
+                .unwrap(),
+            200.0
+        );
+    }
+
+    #[test]
+    fn correct_report_date() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 1, 30).unwrap();
+        let value_date: NaiveDate = NaiveDate::from_ymd_opt(2025, 2, 2).unwrap();
+        let mut party: Party = Party::new(vec![
+            Transaction::Expense {
+                value: 40.0,
+                currency: Currency::CHF,
+                date,
+                category: "Food".to_string(),
+                subcategory: "".to_string(),
+                description: "".to_string(),
+                entity_id: 0,
+                amortization_months: 1,
+                refund_of: -1,
+                reimbursable: false,
+                tags: Vec::new(),
+            },
+            Transaction::Debit {
+                value: 40.0,
+                currency: Currency::CHF,
+                date,
+                account_id: 0,
+                value_date: Some(value_date),
+            },
+        ]);
+        data_base.insert_party(&mut party).unwrap();
+
+        for (report_date, expected_date) in
+            [(ReportDate::Booking, date), (ReportDate::Value, value_date)]
+        {
+            let expenses = data_base
+                .with_report_date(
+                    df!("party_id" => [0i64], "date" => [date]).unwrap(),
+                    &report_date,
+                )
+                .unwrap();
+            let funds = data_base
+                .with_report_date(
+                    df!("date" => [date], "value_date" => [Some(value_date)]).unwrap(),
+                    &report_date,
+                )
+                .unwrap();
+            for data_frame in [expenses, funds] {
+                let dates: Vec<Option<NaiveDate>> = data_frame
+                    .column("date")
+                    .unwrap()
+                    .date()
+                    .unwrap()
+                    .as_date_iter()
+                    .collect();
+                assert_eq!(dates, vec![Some(expected_date)]);
+            }
+        }
+    }
+
+    #[test]
+    fn correct_transaction_tags() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 7, 14).unwrap();
+        let mut party: Party = Party::new(vec![
+            Transaction::Expense {
+                value: 30.0,
+                currency: Currency::CHF,
+                date,
+                category: "Leisure".to_string(),
+                subcategory: "".to_string(),
+                description: "".to_string(),
+                entity_id: 0,
+                amortization_months: 1,
+                refund_of: -1,
+                reimbursable: false,
+                tags: vec!["vacation-2025".to_string(), "gift".to_string()],
+            },
+            Transaction::Expense {
+                value: 12.0,
+                currency: Currency::CHF,
+                date,
+                category: "Food".to_string(),
+                subcategory: "".to_string(),
+                description: "".to_string(),
+                entity_id: 0,
+                amortization_months: 1,
+                refund_of: -1,
+                reimbursable: false,
+                tags: vec!["vacation-2025".to_string()],
+            },
+        ]);
+        data_base.insert_party(&mut party).unwrap();
+
+        let tags: Vec<Vec<String>> = data_base
+            .party(0)
+            .unwrap()
+            .iter()
+            .map(|transaction| match transaction {
+                Transaction::Expense { tags, .. } => tags.clone(),
+                _ => Vec::new(),
+            })
+            .collect();
+        let gifts: String = data_base.last_transactions(10, "gift").unwrap();
+        let all: String = data_base.last_transactions(10, "").unwrap();
+
+        assert_eq!(data_base.tags().unwrap(), vec!["gift", "vacation-2025"]);
+        assert!(tags.contains(&vec!["vacation-2025".to_string(), "gift".to_string()]));
+        assert_eq!(gifts.lines().count(), 2);
+        assert_eq!(all.lines().count(), 3);
+        assert!(gifts.contains("vacation-2025; gift"));
+
+        // templates keep the tags of their transactions
+        let template_id: i64 = data_base
+            .insert_template(&Template::new(
+                String::from("Holidays"),
+                Recurrence::Yearly,
+                0,
+                date,
+                data_base.party(0).unwrap().transactions,
+            ))
+            .unwrap();
+        match &data_base.template(template_id).unwrap().transactions[0] {
+            Transaction::Expense { tags, .. } => {
+                assert_eq!(tags, &vec!["vacation-2025".to_string(), "gift".to_string()])
+            }
+            _ => panic!("Not an expense!"),
+        }
+
+        data_base.delete_party(0).unwrap();
+
+        assert!(data_base.tags().unwrap().is_empty());
+    }
+
+    #[test]
+    fn correct_project_by_category() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let currency_exchange = init_testing_currency_exchange();
+        let project_id: i64 = data_base
+            .insert_project(&Project::new(
+                String::from("Stockholm trip"),
+                String::from(""),
+                500.0,
+                Currency::EUR,
+            ))
+            .unwrap();
+        let expense =
+            |value: f64, currency: Currency, day: u32, category: &str| Transaction::Expense {
+                value,
+                currency,
+                date: NaiveDate::from_ymd_opt(2020, 1, day).unwrap(),
+                category: category.to_string(),
+                subcategory: "".to_string(),
+                description: "".to_string(),
+                entity_id: 0,
+                amortization_months: 1,
+                refund_of: -1,
+                reimbursable: false,
+                tags: Vec::new(),
+            };
+
+        let mut hotel: Party = Party::new(vec![expense(100.0, Currency::CHF, 1, "Lodging")]);
+        hotel.project_id = project_id;
+        let mut dinner: Party = Party::new(vec![expense(40.0, Currency::SEK, 3, "Food")]);
+        dinner.project_id = project_id;
+        let mut groceries: Party = Party::new(vec![expense(70.0, Currency::EUR, 2, "Food")]);
+        for party in [&mut hotel, &mut dinner, &mut groceries] {
+            data_base.insert_party(party).unwrap();
+        }
+
+        let by_category = data_base
+            .project_by_category(&currency_exchange, project_id, &Currency::EUR, false)
+            .unwrap();
+        let expected = df!(
+            "type" => ["Expense", "Expense"],
+            "category" => ["Food", "Lodging"],
+            "value" => [20.0, 50.0],
+        )
+        .unwrap();
+
+        assert_eq!(by_category, expected);
+        assert_eq!(data_base.party(1).unwrap().project_id, project_id);
+
+        data_base.delete_project(project_id).unwrap();
+
+        assert_eq!(data_base.party(1).unwrap().project_id, -1);
+    }
+
+    #[test]
+    fn correct_iou_settlement() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let friend_id: i64 = data_base
+            .insert_entity(&Entity::new(
+                String::from("Anna"),
+                String::from("Sweden"),
+                EntityType::Human,
+                String::from("Friend"),
+            ))
+            .unwrap();
+
+        // a 100 EUR dinner paid from the bank, half of it is the friend's
+        let mut dinner: Party = Party::new(vec![
+            Transaction::Expense {
+                value: 50.0,
+                currency: Currency::EUR,
+                date,
+                category: "Food".to_string(),
+                subcategory: "Restaurant".to_string(),
+                description: "Dinner".to_string(),
+                entity_id: 0,
+                amortization_months: 1,
+                refund_of: -1,
+                reimbursable: false,
+                tags: Vec::new(),
+            },
+            Transaction::Debit {
+                value: 100.0,
+                currency: Currency::EUR,
+                date,
+                account_id: 0,
+                value_date: None,
+            },
+        ]);
+        dinner.add_iou(Iou::new(
+            friend_id,
+            50.0,
+            Currency::EUR,
+            date,
+            String::from("Dinner"),
+        ));
+
+        assert!(dinner.is_valid());
+
+        data_base.insert_party(&mut dinner).unwrap();
+
+        assert_eq!(data_base.party(0).unwrap().ious.len(), 1);
+        assert_eq!(
+            data_base.iou_balance(friend_id, &Currency::EUR).unwrap(),
+            50.0
+        );
+        assert_eq!(
+            data_base.iou_balance(friend_id, &Currency::SEK).unwrap(),
+            0.0
+        );
+
+        let mut settlement: Party = data_base
+            .settlement_party(friend_id, &Currency::EUR, 0, date)
+            .unwrap();
+
+        assert!(settlement.is_valid());
+        assert!(matches!(
+            settlement.transactions[0],
+            Transaction::Credit { value: 50.0, .. }
+        ));
+
+        data_base.insert_party(&mut settlement).unwrap();
+
+        assert_eq!(data_base.iou_balances(false).unwrap().height(), 0);
+        assert!(data_base
+            .settlement_party(friend_id, &Currency::EUR, 0, date)
+            .is_err());
+    }
+
+    #[test]
+    fn correct_refunds() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+
+        // a business trip paid out of pocket, awaiting payback from the employer
+        let mut trip: Party = Party::new(vec![
+            Transaction::Expense {
+                value: 100.0,
+                currency: Currency::EUR,
+                date,
+                category: "Transport".to_string(),
+                subcategory: "Train".to_string(),
+                description: "Business trip".to_string(),
+                entity_id: 0,
+                amortization_months: 1,
+                tags: Vec::new(),
+                refund_of: -1,
+                reimbursable: true,
+            },
+            Transaction::Debit {
+                value: 100.0,
+                currency: Currency::EUR,
+                date,
+                account_id: 0,
+                value_date: None,
+            },
+        ]);
+        data_base.insert_party(&mut trip).unwrap();
+
+        assert_eq!(data_base.outstanding_reimbursements().unwrap().height(), 1);
+
+        let refund: Transaction = data_base
+            .refund(0, 30.0, date, String::from("Partial payback"))
+            .unwrap();
+
+        assert!(matches!(
+            &refund,
+            Transaction::Expense { value, refund_of: 0, category, .. }
+                if *value == -30.0 && category == "Transport"
+        ));
+
+        let mut payback: Party = Party::new(vec![
+            refund,
+            Transaction::Credit {
+                value: 30.0,
+                currency: Currency::EUR,
+                date,
+                account_id: 0,
+                value_date: None,
+            },
+        ]);
+
+        assert!(payback.is_valid());
+
+        data_base.insert_party(&mut payback).unwrap();
+        let outstanding = data_base.outstanding_reimbursements().unwrap();
+
+        assert_eq!(data_base.refundable_value(0).unwrap(), 70.0);
+        assert_eq!(
+            outstanding
+                .column("refunded")
+                .unwrap()
+                .f64()
+                .unwrap()
+                .get(0),
+            Some(30.0)
+        );
+        assert_eq!(data_base.refundable_expenses(10).unwrap().len(), 1);
+
+        // no more than what is left can be given back, even in several refunds of one party
+        assert!(data_base
+            .refund(0, 80.0, date, String::from("Too much"))
+            .is_err());
+        let half: Transaction = data_base
+            .refund(0, 40.0, date, String::from("Half"))
+            .unwrap();
+        let mut too_much: Party = Party::new(vec![
+            half.clone(),
+            half,
+            Transaction::Credit {
+                value: 80.0,
+                currency: Currency::EUR,
+                date,
+                account_id: 0,
+                value_date: None,
+            },
+        ]);
+        assert!(data_base.insert_party(&mut too_much).is_err());
+        assert_eq!(data_base.refundable_value(0).unwrap(), 70.0);
+        // refunds cannot be refunded
+        assert!(data_base
+            .refund(1, 10.0, date, String::from("Refund of a refund"))
+            .is_err());
+
+        let mut rest: Party = Party::new(vec![data_base
+            .refund(0, 70.0, date, String::from("Payback"))
+            .unwrap()]);
+        rest.add_transaction(Transaction::Credit {
+            value: 70.0,
+            currency: Currency::EUR,
+            date,
+            account_id: 0,
+            value_date: None,
+        });
+        data_base.insert_party(&mut rest).unwrap();
+
+        assert_eq!(data_base.outstanding_reimbursements().unwrap().height(), 0);
+        assert!(data_base.refundable_expenses(10).unwrap().is_empty());
+
+        // the expense cannot be deleted while refunds give it back, but the refunds can
+        assert!(data_base.delete_party(0).is_err());
+        data_base.delete_party(1).unwrap();
+        data_base.delete_party(2).unwrap();
+        data_base.delete_party(0).unwrap();
+    }
+
+    #[test]
+    fn correct_basis() {
+        let data_frame = df!(
+            "date" => [
+                NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 2, 10).unwrap(),
+            ],
+            "category" => ["Insurance", "Food"],
+            "value" => [1200.0, 40.0],
+            "amortization_months" => [12i64, 1i64],
+        )
+        .unwrap();
+
+        let cash = DataBase::with_basis(data_frame.clone(), &Basis::Cash).unwrap();
+        let accrual = DataBase::with_basis(data_frame, &Basis::Accrual).unwrap();
+        let insurance = accrual
+            .lazy()
+            .filter(col("category").eq(lit("Insurance")))
+            .sort(["date"], Default::default())
+            .collect()
+            .unwrap();
+        let dates: Vec<Option<NaiveDate>> = insurance
+            .column("date")
+            .unwrap()
+            .date()
+            .unwrap()
+            .as_date_iter()
+            .collect();
+
+        assert_eq!(cash.height(), 2);
+        assert_eq!(insurance.height(), 12);
+        assert_eq!(
+            insurance.column("value").unwrap().f64().unwrap().sum(),
+            Some(1200.0)
+        );
+        assert_eq!(
+            insurance.column("value").unwrap().f64().unwrap().get(0),
+            Some(100.0)
+        );
+        // months shorter than the date keep their last day
+        assert_eq!(dates[1], NaiveDate::from_ymd_opt(2025, 2, 28));
+        assert_eq!(dates[11], NaiveDate::from_ymd_opt(2025, 12, 31));
+    }
+
+    #[test]
+    fn correct_cash_flow_forecast() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Current account"),
+                String::from("Switzerland"),
+                Currency::CHF,
+                AccountType::Deposit,
+                100.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let date_from: NaiveDate = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+        let rent = |value: f64, date: NaiveDate| -> Vec<Transaction> {
+            vec![
+                Transaction::Expense {
+                    value,
+                    currency: Currency::CHF,
+                    date,
+                    category: "Housing".to_string(),
+                    subcategory: "".to_string(),
+                    description: "".to_string(),
+                    entity_id: 0,
+                    amortization_months: 1,
+                    refund_of: -1,
+                    reimbursable: false,
+                    tags: Vec::new(),
+                },
+                Transaction::Debit {
+                    value,
+                    currency: Currency::CHF,
+                    date,
+                    account_id,
+                    value_date: None,
+                },
+            ]
+        };
+
+        let mut party: Party = Party::new(rent(30.0, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()));
+        data_base.insert_party(&mut party).unwrap();
+        let mut party: Party =
+            Party::new(rent(50.0, NaiveDate::from_ymd_opt(2025, 1, 20).unwrap()));
+        party.status = TransactionStatus::Planned;
+        data_base.insert_party(&mut party).unwrap();
+        let template: Template = Template::new(
+            String::from("Rent"),
+            Recurrence::Monthly,
+            25,
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            rent(40.0, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()),
+        );
+        data_base.insert_template(&template).unwrap();
+
+        let forecast = data_base.cash_flow_forecast(date_from, 1).unwrap();
+        assert_eq!(forecast.height(), 32);
+        let balances: Vec<Option<f64>> =
+            forecast.column("balance").unwrap().f64().unwrap().to_vec();
+        assert_eq!(balances[0], Some(70.0)); // 2025-01-10
+        assert_eq!(balances[10], Some(20.0)); // 2025-01-20
+        assert_eq!(balances[15], Some(-20.0)); // 2025-01-25
+        assert_eq!(balances[31], Some(-20.0)); // 2025-02-10
+
+        let warnings = DataBase::forecast_warnings(&forecast, 30.0).unwrap();
+        assert_eq!(
+            warnings,
+            df!(
+                "account_name" => ["Current account"],
+                "currency" => ["CHF"],
+                "first_date" => [NaiveDate::from_ymd_opt(2025, 1, 20).unwrap()],
+                "lowest_balance" => [-20.0],
+                "lowest_date" => [NaiveDate::from_ymd_opt(2025, 1, 25).unwrap()],
+                "warning" => ["Below zero"],
+            )
+            .unwrap()
+        );
+
+        let (projection, _) = data_base
+            .cash_flow_forecast_report(date_from, 1, 30.0)
+            .unwrap();
+        assert!(projection.starts_with("Date,Current account CHF\n2025-01-10,70.00"));
+    }
+
+    #[test]
+    fn correct_balance_assertions() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Current account"),
+                String::from("Switzerland"),
+                Currency::CHF,
+                AccountType::Deposit,
+                100.0,
+                Vec::new(),
+            ))
+            .unwrap();
+
+        let mut party: Party = Party::new(vec![
+            Transaction::Credit {
+                value: 50.0,
+                currency: Currency::CHF,
+                date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+                account_id,
+                value_date: None,
+            },
+            Transaction::Debit {
+                value: 20.0,
+                currency: Currency::CHF,
+                date: NaiveDate::from_ymd_opt(2025, 4, 1).unwrap(),
+                account_id,
+                value_date: None,
+            },
+        ]);
+        data_base.insert_party(&mut party).unwrap();
+
+        for (date, value) in [((2025, 3, 31), 150.0), ((2025, 4, 30), 150.0)] {
+            data_base
+                .insert_balance_assertion(&BalanceAssertion::new(
+                    account_id,
+                    NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(),
+                    value,
+                    Currency::CHF,
+                ))
+                .unwrap();
+        }
+
+        let statuses: Vec<Option<String>> = data_base
+            .balance_assertions()
+            .unwrap()
+            .column("status")
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_iter()
+            .map(|status| status.map(|status| status.to_string()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![Some(String::from("Holds")), Some(String::from("Fails"))]
+        );
+
+        let issues = data_base.integrity_check().unwrap();
+        assert_eq!(issues.height(), 1);
+        assert_eq!(
+            issues.column("issue").unwrap().str().unwrap().get(0),
+            Some("Current account held 130.00 CHF on 2025-04-30 instead of 150.00")
+        );
+    }
+
+    #[test]
+    fn correct_duplicate_detection() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Girokonto"),
+                String::from("Germany"),
+                Currency::EUR,
+                AccountType::Deposit,
+                0.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let aldi_id: i64 = data_base
+            .insert_entity(&Entity::new(
+                String::from("Aldi"),
+                String::from("Germany"),
+                EntityType::Firm,
+                String::from("Supermarket"),
+            ))
+            .unwrap();
+        let purchase = |day: u32, value: f64, description: &str| -> Party {
+            let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 5, day).unwrap();
+            Party::new(vec![
+                Transaction::Expense {
+                    value,
+                    currency: Currency::EUR,
+                    date,
+                    category: String::from("Groceries"),
+                    subcategory: String::default(),
+                    description: String::from(description),
+                    entity_id: aldi_id,
+                    amortization_months: 1,
+                    tags: Vec::new(),
+                    refund_of: -1,
+                    reimbursable: false,
+                },
+                Transaction::Debit {
+                    value,
+                    currency: Currency::EUR,
+                    date,
+                    account_id,
+                    value_date: None,
+                },
+            ])
+        };
+
+        data_base
+            .insert_party(&mut purchase(2, 42.1, "Weekly shopping"))
+            .unwrap();
+        data_base
+            .insert_party(&mut purchase(20, 42.1, "Weekly shopping"))
+            .unwrap();
+
+        // the receipt entered again, with the date of the bank booking
+        let duplicates = data_base
+            .find_duplicates(&purchase(4, 42.1, "ALDI SUED weekly shopping"))
+            .unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].party_id, 0);
+        assert_eq!(
+            duplicates[0].to_string(),
+            "Expense of 42.10 EUR on 2025-05-02 (Weekly shopping) in party 0"
+        );
+
+        // other amounts, dates far apart or unrelated descriptions are not duplicates
+        for party in [
+            purchase(3, 42.5, "Weekly shopping"),
+            purchase(10, 42.1, "Weekly shopping"),
+        ] {
+            assert!(data_base.find_duplicates(&party).unwrap().is_empty());
+        }
+        assert!(data_base
+            .find_duplicates(&purchase(3, 42.1, "Birthday cake"))
+            .unwrap()
+            .is_empty());
+
+        // void parties do not count
+        data_base
+            .insert_party(&mut purchase(5, 42.1, "Weekly shopping"))
+            .unwrap();
+        let duplicates: DataFrame = data_base.duplicates().unwrap();
+        assert_eq!(duplicates.height(), 1);
+        assert_eq!(
+            duplicates
+                .column("duplicate_party_id")
+                .unwrap()
+                .i64()
+                .unwrap()
+                .get(0),
+            Some(2)
+        );
+        data_base
+            .set_party_status(2, &TransactionStatus::Void)
+            .unwrap();
+        assert_eq!(data_base.duplicates().unwrap().height(), 0);
+
+        // the same amount from the same account, but paid to another shop
+        let lidl_id: i64 = data_base
+            .insert_entity(&Entity::new(
+                String::from("Lidl"),
+                String::from("Germany"),
+                EntityType::Firm,
+                String::from("Supermarket"),
+            ))
+            .unwrap();
+        let mut elsewhere: Party = purchase(3, 42.1, "Weekly shopping");
+        if let Transaction::Expense { entity_id, .. } = &mut elsewhere.transactions[0] {
+            *entity_id = lidl_id;
+        }
+        assert!(data_base.find_duplicates(&elsewhere).unwrap().is_empty());
+        data_base.insert_party(&mut elsewhere).unwrap();
+        assert_eq!(data_base.duplicates().unwrap().height(), 0);
+    }
+
+    #[test]
+    fn correct_refund_not_duplicate() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 5, 2).unwrap();
+        data_base
+            .insert_party(&mut Party::new(vec![
+                Transaction::Expense {
+                    value: 42.1,
+                    currency: Currency::EUR,
+                    date,
+                    category: String::from("Clothing"),
+                    subcategory: String::default(),
+                    description: String::from("Jacket"),
+                    entity_id: 0,
+                    amortization_months: 1,
+                    tags: Vec::new(),
+                    refund_of: -1,
+                    reimbursable: false,
+                },
+                Transaction::Debit {
+                    value: 42.1,
+                    currency: Currency::EUR,
+                    date,
+                    account_id: 0,
+                    value_date: None,
+                },
+            ]))
+            .unwrap();
+
+        // the jacket given back in full the next day
+        let refund_date: NaiveDate = NaiveDate::from_ymd_opt(2025, 5, 3).unwrap();
+        let mut refund: Party = Party::new(vec![
+            data_base
+                .refund(0, 42.1, refund_date, String::from("Jacket"))
+                .unwrap(),
+            Transaction::Credit {
+                value: 42.1,
+                currency: Currency::EUR,
+                date: refund_date,
+                account_id: 0,
+                value_date: None,
+            },
+        ]);
+        assert!(data_base.find_duplicates(&refund).unwrap().is_empty());
+        data_base.insert_party(&mut refund).unwrap();
+        assert_eq!(data_base.duplicates().unwrap().height(), 0);
+    }
 }
@@ -21,6 +21,10 @@ mod tests {
             subcategory: "Electricity".to_string(),
             description: "Monthly electricity bill".to_string(),
             entity_id: 1,
+            amortization_months: 1,
+            refund_of: -1,
+            reimbursable: false,
+            tags: Vec::new(),
         };
 
         let t2 = Transaction::Debit {
@@ -28,6 +32,7 @@ mod tests {
             currency: Currency::EUR,
             date: NaiveDate::from_ymd_opt(2024, 12, 2).unwrap(),
             account_id: 42,
+            value_date: None,
         };
 
         // Example data
@@ -38,10 +43,113 @@ mod tests {
         assert!(!party.is_valid());
     }
 
+    #[test]
+    fn correct_value_date_with_date() {
+        let transaction = Transaction::Credit {
+            value: 20.0,
+            currency: Currency::EUR,
+            date: NaiveDate::from_ymd_opt(2024, 12, 30).unwrap(),
+            account_id: 0,
+            value_date: NaiveDate::from_ymd_opt(2025, 1, 2),
+        };
+
+        let moved: Transaction =
+            transaction.with_date(NaiveDate::from_ymd_opt(2025, 1, 30).unwrap());
+
+        assert_eq!(moved.value_date(), NaiveDate::from_ymd_opt(2025, 2, 2));
+    }
+
     #[test]
     fn multicurrency_party() {
         let party: Party = init_party();
 
         assert!(party.is_valid());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn correct_monthly_due_dates() {
+        let template: Template = Template::new(
+            String::from("Rent"),
+            Recurrence::Monthly,
+            31,
+            NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            init_party().transactions,
+        );
+
+        let due_dates = template.due_dates(
+            NaiveDate::from_ymd_opt(2024, 1, 14).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 4, 29).unwrap(),
+        );
+
+        assert_eq!(
+            due_dates,
+            vec![
+                NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 3, 31).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn correct_weekly_due_dates() {
+        let template: Template = Template::new(
+            String::from("Cleaning"),
+            Recurrence::Weekly,
+            2,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            init_party().transactions,
+        );
+
+        let due_dates = template.due_dates(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        );
+
+        assert_eq!(
+            due_dates,
+            vec![
+                NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 1, 29).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn manual_template_never_due() {
+        let template: Template = Template::new(
+            String::from("Groceries"),
+            Recurrence::Manual,
+            0,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            init_party().transactions,
+        );
+
+        let due_dates = template.due_dates(
+            NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+        );
+
+        assert!(due_dates.is_empty());
+    }
+
+    #[test]
+    fn correct_budget_period_bounds() {
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2024, 2, 14).unwrap();
+
+        assert_eq!(
+            BudgetPeriod::Monthly.bounds(date),
+            (
+                NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(),
+            )
+        );
+        assert_eq!(
+            BudgetPeriod::Yearly.bounds(date),
+            (
+                NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+            )
+        );
+    }
+}
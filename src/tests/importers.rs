@@ -0,0 +1,800 @@
+#[cfg(test)]
+mod tests {
+    use crate::modules::database::staging::{DraftParty, StagingFlag};
+    use crate::modules::database::DataBase;
+    use crate::modules::financial::*;
+    use crate::modules::importers::bank_csv::{AmountSign, CsvMapping};
+    use crate::modules::importers::camt::{is_valid_iban, parse_camt, CamtStatement};
+    use crate::modules::importers::mt940::{parse_mt940, Mt940Statement};
+    use crate::modules::importers::ofx::parse_ofx;
+    use crate::modules::importers::{parse_amount, ImportedMovement};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn correct_parse_amount() {
+        assert_eq!(parse_amount("1'234.50", '.').unwrap(), 1234.5);
+        assert_eq!(parse_amount("-1.234,50", ',').unwrap(), -1234.5);
+        assert_eq!(parse_amount("12,00-", ',').unwrap(), -12.0);
+        assert_eq!(parse_amount("CHF 7.05", '.').unwrap(), 7.05);
+        assert_eq!(parse_amount("-€ 3.20", '.').unwrap(), -3.2);
+        assert_eq!(parse_amount("+15,00 EUR", ',').unwrap(), 15.0);
+        // negative in accounting notation
+        assert_eq!(parse_amount("(12.50)", '.').unwrap(), -12.5);
+        assert!(parse_amount("", '.').is_err());
+        assert!(parse_amount("1e3", '.').is_err());
+        assert!(parse_amount("12.50 pending", '.').is_err());
+        assert!(parse_amount("1.2.3", '.').is_err());
+        assert!(parse_amount("--5", '.').is_err());
+        assert!(parse_amount("(-5)", '.').is_err());
+    }
+
+    #[test]
+    fn correct_bank_csv_parse() {
+        let content: &str = "Kontoauszug Girokonto\n\
+            Buchungstag;Empfänger;Verwendungszweck;Soll;Haben;Währung\n\
+            02.01.2025;Aldi Süd;Einkauf;-23,45;;EUR\n\
+            15.01.2025;ACME GmbH;Gehalt Januar;;2.500,00;EUR\n\
+            20.01.2025;Bank;Gebührenhinweis;;;EUR\n\
+            ;;Summe;23,45;2.500,00;\n";
+        let mapping: CsvMapping = CsvMapping {
+            name: String::from("Sparkasse"),
+            delimiter: ';',
+            skip_rows: 1,
+            date_column: String::from("Buchungstag"),
+            date_format: String::from("%d.%m.%Y"),
+            amount_column: String::from("Soll"),
+            credit_column: String::from("Haben"),
+            amount_sign: AmountSign::SplitColumns,
+            decimal_separator: ',',
+            currency_column: String::from("Währung"),
+            payee_column: String::from("Empfänger"),
+            description_column: String::from("Verwendungszweck"),
+        };
+
+        let movements: Vec<ImportedMovement> =
+            mapping.parse(content.as_bytes(), &Currency::CHF).unwrap();
+
+        assert_eq!(movements.len(), 3);
+        assert_eq!(
+            movements[0],
+            ImportedMovement {
+                date: NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+                value_date: None,
+                value: -23.45,
+                currency: Currency::EUR,
+                payee: String::from("Aldi Süd"),
+                description: String::from("Einkauf"),
+                reference: String::default(),
+            }
+        );
+        assert_eq!(movements[1].value, 2500.0);
+        assert_eq!(movements[2].value, 0.0);
+
+        let credit_card: &str = "Date,Merchant,Amount\n\
+            2025-01-03,SBB CFF FFS,45.00\n\
+            2025-01-31,Payment - thank you,-45.00\n";
+        let inverted: CsvMapping = CsvMapping {
+            name: String::from("Credit card"),
+            payee_column: String::from("Merchant"),
+            amount_sign: AmountSign::Inverted,
+            ..CsvMapping::default()
+        };
+        let movements: Vec<ImportedMovement> = inverted
+            .parse(credit_card.as_bytes(), &Currency::CHF)
+            .unwrap();
+        assert_eq!(movements[0].value, -45.0);
+        assert_eq!(movements[0].currency, Currency::CHF);
+        assert_eq!(movements[1].value, 45.0);
+
+        let missing_column: CsvMapping = CsvMapping {
+            date_column: String::from("Datum"),
+            ..mapping
+        };
+        assert!(missing_column
+            .parse(content.as_bytes(), &Currency::CHF)
+            .is_err());
+    }
+
+    #[test]
+    fn correct_csv_mapping_saving() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let mapping: CsvMapping = CsvMapping {
+            name: String::from("PostFinance"),
+            delimiter: ';',
+            ..CsvMapping::default()
+        };
+
+        let first_id: i64 = data_base.insert_csv_mapping(&mapping).unwrap();
+        assert_eq!(data_base.csv_mapping(first_id).unwrap(), mapping);
+
+        let corrected: CsvMapping = CsvMapping {
+            skip_rows: 4,
+            ..mapping
+        };
+        let second_id: i64 = data_base.insert_csv_mapping(&corrected).unwrap();
+        assert_eq!(
+            data_base
+                .iter_csv_mapping_ids()
+                .unwrap()
+                .collect::<Vec<i64>>(),
+            vec![second_id]
+        );
+        assert_eq!(data_base.csv_mapping(second_id).unwrap(), corrected);
+
+        assert!(data_base
+            .insert_csv_mapping(&CsvMapping::default())
+            .is_err());
+    }
+
+    #[test]
+    fn correct_stage_movements() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Girokonto"),
+                String::from("Germany"),
+                Currency::EUR,
+                AccountType::Deposit,
+                0.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let aldi_id: i64 = data_base
+            .insert_entity(&Entity::new(
+                String::from("Aldi"),
+                String::from("Germany"),
+                EntityType::Firm,
+                String::from("Supermarket"),
+            ))
+            .unwrap();
+        data_base.insert_entity_alias(aldi_id, "ALDI SUED").unwrap();
+        data_base
+            .register_category(&TransactionType::Income, "Uncategorized", "")
+            .unwrap();
+        data_base
+            .register_category(&TransactionType::Expense, "Uncategorized", "")
+            .unwrap();
+
+        let date: NaiveDate = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        let movement = |value: f64, currency: Currency, payee: &str| ImportedMovement {
+            date,
+            value_date: None,
+            value,
+            currency,
+            payee: String::from(payee),
+            description: String::default(),
+            reference: String::default(),
+        };
+        let movements: Vec<ImportedMovement> = vec![
+            movement(-23.45, Currency::EUR, "Aldi Sued"),
+            movement(0.0, Currency::EUR, "Bank"),
+            movement(100.0, Currency::EUR, "Grandma"),
+        ];
+
+        let staging_ids: Vec<i64> = data_base
+            .stage_movements(account_id, &movements, "Uncategorized", "Uncategorized")
+            .unwrap();
+        let parties: Vec<Party> = staging_ids
+            .iter()
+            .map(|staging_id| data_base.staged_draft(*staging_id).unwrap().party())
+            .collect();
+
+        assert_eq!(parties.len(), 2);
+        assert!(parties.iter().all(|party| party.is_valid()));
+        match &parties[0].transactions[..] {
+            [Transaction::Expense {
+                value,
+                entity_id,
+                description,
+                ..
+            }, Transaction::Debit {
+                account_id: debited_id,
+                ..
+            }] => {
+                assert_eq!(*value, 23.45);
+                assert_eq!(*entity_id, aldi_id);
+                assert_eq!(description, "Aldi Sued");
+                assert_eq!(*debited_id, account_id);
+            }
+            _ => panic!("The outgoing movement is not an expense!"),
+        }
+        match &parties[1].transactions[..] {
+            [Transaction::Income { entity_id, .. }, Transaction::Credit { .. }] => {
+                assert_eq!(*entity_id, 0);
+            }
+            _ => panic!("The incoming movement is not an income!"),
+        }
+
+        // unknown categories are staged, but flagged until a known one is assigned
+        let staging_ids: Vec<i64> = data_base
+            .stage_movements(account_id, &movements, "Uncategorized", "Unknown")
+            .unwrap();
+        assert_eq!(
+            data_base.staging_flags(staging_ids[0]).unwrap(),
+            vec![StagingFlag::UnknownCategory]
+        );
+        assert!(data_base.staging_flags(staging_ids[1]).unwrap().is_empty());
+        assert!(data_base
+            .stage_movements(
+                account_id,
+                &[movement(5.0, Currency::SEK, "Grandma")],
+                "Uncategorized",
+                "Uncategorized"
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn correct_iban_validation() {
+        assert!(is_valid_iban("CH9300762011623852957"));
+        assert!(is_valid_iban("SE4550000000058398257466"));
+        assert!(!is_valid_iban("CH9300762011623852958"));
+        assert!(!is_valid_iban("CH93 0076 2011 6238 5295 7"));
+        assert!(!is_valid_iban("93CH00762011623852957"));
+    }
+
+    #[test]
+    fn correct_camt053_parse() {
+        let statements: Vec<CamtStatement> = parse_camt(include_str!("data/camt053.xml")).unwrap();
+
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].iban, "CH9300762011623852957");
+        let movements: &[ImportedMovement] = &statements[0].movements;
+        // the pending entry is left out, and the batch is split in its two payments
+        assert_eq!(movements.len(), 4);
+        assert_eq!(
+            movements[0],
+            ImportedMovement {
+                date: NaiveDate::from_ymd_opt(2025, 1, 6).unwrap(),
+                value_date: None,
+                value: -87.35,
+                currency: Currency::CHF,
+                payee: String::from("MIGROS ZUERICH"),
+                description: String::from("Einkauf 04.01.2025 Karte 1234"),
+                reference: String::from("20250106-CH-0001"),
+            }
+        );
+        assert_eq!(movements[1].value, 6200.0);
+        assert_eq!(movements[1].payee, "ACME AG");
+        assert_eq!(movements[1].description, "LOHN-2025-01");
+        assert_eq!(
+            movements[1].value_date,
+            Some(NaiveDate::from_ymd_opt(2025, 1, 25).unwrap())
+        );
+        assert_eq!(
+            movements[2..]
+                .iter()
+                .map(|movement| (movement.value, movement.reference.as_str()))
+                .collect::<Vec<(f64, &str)>>(),
+            vec![(-200.0, "RENT-2025-02"), (-50.0, "20250128-CH-0003/2")]
+        );
+        // without remittance information, the entry tells what it was
+        assert_eq!(movements[3].description, "Sammelauftrag");
+    }
+
+    #[test]
+    fn correct_camt054_parse() {
+        let statements: Vec<CamtStatement> = parse_camt(include_str!("data/camt054.xml")).unwrap();
+
+        assert_eq!(statements[0].iban, "SE4550000000058398257466");
+        assert_eq!(
+            statements[0].movements,
+            vec![ImportedMovement {
+                date: NaiveDate::from_ymd_opt(2025, 3, 10).unwrap(),
+                value_date: None,
+                value: 1500.0,
+                currency: Currency::SEK,
+                payee: String::from("Anna Svensson"),
+                description: String::from("Swish hyra"),
+                reference: String::from("SE-99017"),
+            }]
+        );
+
+        assert!(parse_camt("<Document><pain.001/></Document>").is_err());
+    }
+
+    #[test]
+    fn correct_camt_staging() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Privatkonto"),
+                String::from("Switzerland"),
+                Currency::CHF,
+                AccountType::Deposit,
+                1000.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        for transaction_type in [TransactionType::Income, TransactionType::Expense] {
+            data_base
+                .register_category(&transaction_type, "Uncategorized", "")
+                .unwrap();
+        }
+        let statements: Vec<CamtStatement> = parse_camt(include_str!("data/camt053.xml")).unwrap();
+
+        assert!(data_base
+            .stage_camt_statements(&statements, "Uncategorized", "Uncategorized")
+            .is_err());
+
+        // nor is anything staged on a closed account
+        let closed_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Altes Konto"),
+                String::from("Switzerland"),
+                Currency::CHF,
+                AccountType::Deposit,
+                0.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        data_base
+            .set_account_iban(closed_id, "CH9300762011623852957")
+            .unwrap();
+        data_base
+            .close_account(closed_id, NaiveDate::from_ymd_opt(2024, 12, 31).unwrap())
+            .unwrap();
+        assert!(data_base
+            .stage_camt_statements(&statements, "Uncategorized", "Uncategorized")
+            .is_err());
+        assert_eq!(data_base.iter_staged_ids().unwrap().count(), 0);
+        data_base
+            .set_account_iban(closed_id, "DE89370400440532013000")
+            .unwrap();
+
+        data_base
+            .set_account_iban(account_id, "ch93 0076 2011 6238 5295 7")
+            .unwrap();
+        assert!(data_base
+            .set_account_iban(0, "CH9300762011623852957")
+            .is_err());
+        assert!(data_base
+            .set_account_iban(0, "CH9300762011623852958")
+            .is_err());
+
+        // a statement that cannot be staged keeps the ones before it out of the inbox too
+        let mut foreign: CamtStatement = statements[0].clone();
+        for movement in foreign.movements.iter_mut() {
+            movement.currency = Currency::EUR;
+            movement.reference.push_str("-EUR");
+        }
+        assert!(data_base
+            .stage_camt_statements(
+                &[statements[0].clone(), foreign],
+                "Uncategorized",
+                "Uncategorized"
+            )
+            .is_err());
+        assert_eq!(data_base.iter_staged_ids().unwrap().count(), 0);
+
+        let drafts: Vec<DraftParty> = data_base
+            .stage_camt_statements(&statements, "Uncategorized", "Uncategorized")
+            .unwrap()
+            .iter()
+            .map(|staging_id| data_base.staged_draft(*staging_id).unwrap())
+            .collect();
+
+        assert_eq!(drafts.len(), 4);
+        assert!(drafts.iter().all(|draft| draft.party().is_valid()));
+        match &drafts[1].party().transactions[1] {
+            Transaction::Credit {
+                account_id: credited_id,
+                value_date,
+                ..
+            } => {
+                assert_eq!(*credited_id, account_id);
+                assert_eq!(*value_date, NaiveDate::from_ymd_opt(2025, 1, 25));
+            }
+            _ => panic!("The salary is not credited!"),
+        }
+    }
+
+    #[test]
+    fn correct_ofx_parse() {
+        let movements: Vec<ImportedMovement> =
+            parse_ofx(include_str!("data/statement.ofx"), &Currency::CHF).unwrap();
+
+        assert_eq!(
+            movements,
+            vec![
+                ImportedMovement {
+                    date: NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
+                    value_date: None,
+                    value: -42.9,
+                    currency: Currency::EUR,
+                    payee: String::from("BAHN & BUS"),
+                    description: String::from("Ticket Berlin - Hamburg"),
+                    reference: String::from("2025010500001"),
+                },
+                ImportedMovement {
+                    date: NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+                    value_date: None,
+                    value: 42.9,
+                    currency: Currency::EUR,
+                    payee: String::from("PAYMENT THANK YOU"),
+                    description: String::default(),
+                    reference: String::from("2025013100002"),
+                },
+            ]
+        );
+
+        let movements: Vec<ImportedMovement> =
+            parse_ofx(include_str!("data/statement_v2.ofx"), &Currency::CHF).unwrap();
+        assert_eq!(movements.len(), 1);
+        assert_eq!(movements[0].value, -129.0);
+        assert_eq!(movements[0].currency, Currency::SEK);
+        assert_eq!(movements[0].payee, "ICA Maxi");
+        assert_eq!(movements[0].description, "Kortköp");
+        assert_eq!(movements[0].reference, "SE-20250214-1");
+
+        assert!(parse_ofx("Date,Amount", &Currency::CHF).is_err());
+    }
+
+    #[test]
+    fn correct_reimport_skipping() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Credit card"),
+                String::from("Germany"),
+                Currency::EUR,
+                AccountType::Deposit,
+                0.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        for transaction_type in [TransactionType::Income, TransactionType::Expense] {
+            data_base
+                .register_category(&transaction_type, "Uncategorized", "")
+                .unwrap();
+        }
+        let movements: Vec<ImportedMovement> =
+            parse_ofx(include_str!("data/statement.ofx"), &Currency::EUR).unwrap();
+        let stage = |data_base: &mut DataBase| -> Vec<i64> {
+            data_base
+                .stage_movements(account_id, &movements, "Uncategorized", "Uncategorized")
+                .unwrap()
+        };
+
+        let staging_ids: Vec<i64> = stage(&mut data_base);
+        assert_eq!(staging_ids.len(), 2);
+        data_base
+            .set_staged_accepted(&staging_ids[1..], false)
+            .unwrap();
+        assert_eq!(data_base.commit_staged().unwrap(), 1);
+        data_base.discard_staged(&staging_ids[1..]).unwrap();
+
+        // only the one that was not imported yet is staged again
+        let staging_ids: Vec<i64> = stage(&mut data_base);
+        assert_eq!(staging_ids.len(), 1);
+        assert_eq!(
+            data_base
+                .staged_draft(staging_ids[0])
+                .unwrap()
+                .movement
+                .reference,
+            "2025013100002"
+        );
+        data_base.discard_staged(&staging_ids).unwrap();
+
+        // deleting the party lets it be imported again
+        data_base.delete_party(0).unwrap();
+        assert_eq!(stage(&mut data_base).len(), 2);
+    }
+
+    #[test]
+    fn correct_staging_commit() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let account_id: i64 = data_base
+            .insert_account(&Account::new(
+                String::from("Girokonto"),
+                String::from("Germany"),
+                Currency::EUR,
+                AccountType::Deposit,
+                0.0,
+                Vec::new(),
+            ))
+            .unwrap();
+        let aldi_id: i64 = data_base
+            .insert_entity(&Entity::new(
+                String::from("Aldi"),
+                String::from("Germany"),
+                EntityType::Firm,
+                String::from("Supermarket"),
+            ))
+            .unwrap();
+        data_base
+            .register_category(&TransactionType::Income, "Salary", "")
+            .unwrap();
+        data_base
+            .register_category(&TransactionType::Expense, "Food", "Groceries")
+            .unwrap();
+
+        let movement = |day: u32, value: f64, reference: &str| ImportedMovement {
+            date: NaiveDate::from_ymd_opt(2025, 3, day).unwrap(),
+            value_date: None,
+            value,
+            currency: Currency::EUR,
+            payee: String::from("Card payment"),
+            description: String::default(),
+            reference: String::from(reference),
+        };
+        let movements: Vec<ImportedMovement> = vec![
+            movement(3, -12.5, "A"),
+            movement(4, -30.0, "B"),
+            movement(5, 2000.0, "C"),
+        ];
+        let n_parties = |data_base: &DataBase| -> i64 {
+            data_base
+                .size()
+                .unwrap()
+                .column("records")
+                .unwrap()
+                .i64()
+                .unwrap()
+                .get(3)
+                .unwrap()
+        };
+
+        let staging_ids: Vec<i64> = data_base
+            .stage_movements(account_id, &movements, "Salary", "")
+            .unwrap();
+        assert_eq!(staging_ids.len(), 3);
+
+        // a flagged draft stops the whole commit
+        assert!(data_base.commit_staged().is_err());
+        assert_eq!(n_parties(&data_base), 0);
+        assert_eq!(data_base.iter_staged_ids().unwrap().len(), 3);
+
+        // categories only go to the drafts of their type
+        assert!(data_base
+            .assign_staged_category(&staging_ids, &TransactionType::Expense, "Food", "Sweets")
+            .is_err());
+        assert_eq!(
+            data_base
+                .assign_staged_category(
+                    &staging_ids,
+                    &TransactionType::Expense,
+                    "Food",
+                    "Groceries"
+                )
+                .unwrap(),
+            2
+        );
+        data_base
+            .assign_staged_entity(&staging_ids[..2], aldi_id)
+            .unwrap();
+        let draft: DraftParty = data_base.staged_draft(staging_ids[1]).unwrap();
+        assert_eq!(draft.category, "Food");
+        assert_eq!(draft.subcategory, "Groceries");
+        assert_eq!(draft.entity_id, aldi_id);
+        assert_eq!(
+            data_base.staged_draft(staging_ids[2]).unwrap().category,
+            "Salary"
+        );
+
+        // staged movements are not staged again
+        assert!(data_base
+            .stage_movements(account_id, &movements[..1], "Salary", "")
+            .unwrap()
+            .is_empty());
+        assert!(data_base.staging_flags(staging_ids[0]).unwrap().is_empty());
+
+        // a movement staged twice is a duplicate until one of them is discarded
+        let again_ids: Vec<i64> = vec![data_base
+            .stage_draft(&data_base.staged_draft(staging_ids[0]).unwrap())
+            .unwrap()];
+        assert_eq!(
+            data_base.staging_flags(staging_ids[0]).unwrap(),
+            vec![StagingFlag::Duplicate]
+        );
+        assert!(data_base.commit_staged().is_err());
+        data_base.discard_staged(&again_ids).unwrap();
+        assert!(data_base.staging_flags(staging_ids[0]).unwrap().is_empty());
+
+        // drafts held back stay in the inbox
+        data_base
+            .set_staged_accepted(&staging_ids[2..], false)
+            .unwrap();
+        assert_eq!(data_base.commit_staged().unwrap(), 2);
+        assert_eq!(n_parties(&data_base), 2);
+        assert_eq!(
+            data_base.iter_staged_ids().unwrap().collect::<Vec<i64>>(),
+            vec![staging_ids[2]]
+        );
+
+        // committed movements are not staged again
+        assert!(data_base
+            .stage_movements(account_id, &movements[..2], "Salary", "")
+            .unwrap()
+            .is_empty());
+
+        // a movement that looks like a committed one is only a warning
+        let staging_ids: Vec<i64> = data_base
+            .stage_movements(account_id, &[movement(4, -12.5, "D")], "Salary", "Food")
+            .unwrap();
+        data_base
+            .assign_staged_entity(&staging_ids, aldi_id)
+            .unwrap();
+        assert_eq!(
+            data_base.staging_flags(staging_ids[0]).unwrap(),
+            vec![StagingFlag::PossibleDuplicate]
+        );
+        assert_eq!(data_base.commit_staged().unwrap(), 1);
+    }
+
+    #[test]
+    fn correct_staging_merges_and_renames() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let mut insert_shop = |name: &str| -> i64 {
+            data_base
+                .insert_entity(&Entity::new(
+                    String::from(name),
+                    String::from("Germany"),
+                    EntityType::Firm,
+                    String::from("Supermarket"),
+                ))
+                .unwrap()
+        };
+        let aldi_id: i64 = insert_shop("Aldi");
+        let duplicate_id: i64 = insert_shop("ALDI Süd");
+        data_base
+            .register_category(&TransactionType::Income, "Food", "")
+            .unwrap();
+        data_base
+            .register_category(&TransactionType::Expense, "Food", "")
+            .unwrap();
+
+        let movement = |value: f64, reference: &str| ImportedMovement {
+            date: NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+            value_date: None,
+            value,
+            currency: Currency::EUR,
+            payee: String::from("Aldi"),
+            description: String::default(),
+            reference: String::from(reference),
+        };
+        let staging_ids: Vec<i64> = data_base
+            .stage_movements(
+                0,
+                &[movement(-12.5, "A"), movement(3.0, "B")],
+                "Food",
+                "Food",
+            )
+            .unwrap();
+        data_base
+            .assign_staged_entity(&staging_ids, duplicate_id)
+            .unwrap();
+
+        data_base.merge_entities(duplicate_id, aldi_id).unwrap();
+        assert_eq!(
+            data_base.staged_draft(staging_ids[0]).unwrap().entity_id,
+            aldi_id
+        );
+
+        // only the drafts of the type of the category are renamed
+        let food_id: i64 = data_base
+            .find_category(&TransactionType::Expense, &[String::from("Food")])
+            .unwrap()
+            .unwrap();
+        data_base.rename_category(food_id, "Groceries").unwrap();
+        assert_eq!(
+            data_base.staged_draft(staging_ids[0]).unwrap().category,
+            "Groceries"
+        );
+        assert_eq!(
+            data_base.staged_draft(staging_ids[1]).unwrap().category,
+            "Food"
+        );
+    }
+
+    #[test]
+    fn correct_mt940_parse() {
+        let content: &str = include_str!("data/statement.mt940");
+        let statements: Vec<Mt940Statement> = parse_mt940(content).unwrap();
+
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].account, "10020030/1234567890");
+        assert_eq!(statements[0].opening_balance, Some(1000.0));
+        assert_eq!(statements[0].closing_balance, Some(3480.0));
+        let movements: &[ImportedMovement] = &statements[0].movements;
+        assert_eq!(
+            movements[0],
+            ImportedMovement {
+                date: NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+                value_date: None,
+                value: -12.5,
+                currency: Currency::EUR,
+                payee: String::from("REWE Markt GmbH"),
+                description: String::from("REWE SAGT DANKEFiliale 4711"),
+                reference: String::from("B4L31-0001"),
+            }
+        );
+        assert_eq!(movements[1].value, 2500.0);
+        assert_eq!(movements[1].description, "Salary January ACME AG");
+        // booked on new year's eve, with value on the 2nd of January
+        assert_eq!(
+            movements[2].date,
+            NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()
+        );
+        assert_eq!(movements[2].value_date, NaiveDate::from_ymd_opt(2025, 1, 2));
+        assert_eq!(movements[2].description, "Lastschrift");
+        // the reversal of the card payment puts the money back
+        assert_eq!(movements[3].value, 12.5);
+        assert_eq!(movements[3].reference, "");
+        assert_eq!(movements[3].description, "Storno Kartenzahlung");
+
+        // a movement that went missing does not bridge the balances
+        let missing_line: String =
+            content.replace(":61:2501021231D20,00NDDTNONREF//B4L31-0003\n", "");
+        assert!(parse_mt940(&missing_line).is_err());
+        // nor do balances that are not there, even without movements to bridge
+        let missing_closing_balance: String = content.replace(":62F:C250103EUR3480,00\n", "");
+        assert!(parse_mt940(&missing_closing_balance).is_err());
+        assert!(parse_mt940(":20:STMT250104\n:25:10020030/1234567890\n").is_err());
+        assert!(parse_mt940("Date;Amount").is_err());
+    }
+
+    #[test]
+    fn correct_mt940_staging() {
+        let mut data_base: DataBase = DataBase::new().unwrap();
+        let mut account = |name: &str| -> i64 {
+            data_base
+                .insert_account(&Account::new(
+                    String::from(name),
+                    String::from("Germany"),
+                    Currency::EUR,
+                    AccountType::Deposit,
+                    1000.0,
+                    Vec::new(),
+                ))
+                .unwrap()
+        };
+        let account_id: i64 = account("Girokonto");
+        let other_id: i64 = account("Tagesgeld");
+        for transaction_type in [TransactionType::Income, TransactionType::Expense] {
+            data_base
+                .register_category(&transaction_type, "Uncategorized", "")
+                .unwrap();
+        }
+        data_base
+            .set_account_iban(other_id, "CH9300762011623852957")
+            .unwrap();
+        let content: &str = include_str!("data/statement.mt940");
+
+        // the statements of several accounts are not staged onto one
+        let second: String = content.replace("10020030/1234567890", "10020030/9876543210");
+        let statements: Vec<Mt940Statement> = parse_mt940(content)
+            .unwrap()
+            .into_iter()
+            .chain(parse_mt940(&second).unwrap())
+            .collect();
+        assert!(data_base
+            .stage_mt940_statements(account_id, &statements, "Uncategorized", "Uncategorized")
+            .is_err());
+        // nor the ones of an account linked to another IBAN
+        let linked: String = content.replace("10020030/1234567890", "CH9300762011623852957");
+        assert!(data_base
+            .stage_mt940_statements(
+                account_id,
+                &parse_mt940(&linked).unwrap(),
+                "Uncategorized",
+                "Uncategorized"
+            )
+            .is_err());
+        assert_eq!(data_base.iter_staged_ids().unwrap().count(), 0);
+
+        assert_eq!(
+            data_base
+                .stage_mt940_statements(
+                    account_id,
+                    &parse_mt940(content).unwrap(),
+                    "Uncategorized",
+                    "Uncategorized"
+                )
+                .unwrap()
+                .len(),
+            4
+        );
+    }
+}
@@ -1,5 +1,7 @@
+mod categorization;
 mod currency_exchange;
 mod database;
 mod financial;
+mod importers;
 mod tables;
 pub(crate) mod test_helpers;
@@ -4,6 +4,7 @@ mod tests {
     use crate::modules::tables::*;
     use chrono::prelude::*;
     use polars::prelude::*;
+    use std::path::PathBuf;
 
     fn init_funds_table() -> FundsTable {
         let data_frame: DataFrame = df!(
@@ -20,7 +21,10 @@ mod tests {
         )
         .unwrap();
 
-        FundsTable { data_frame }
+        // as loaded from a csv saved before the reconciliation columns existed
+        FundsTable {
+            data_frame: FundsTable::upgrade(data_frame).unwrap(),
+        }
     }
 
     #[test]
@@ -39,9 +43,10 @@ mod tests {
             currency: Currency::EUR,
             date: NaiveDate::from_ymd_opt(2024, 12, 2).unwrap(),
             account_id: 0i64,
+            value_date: None,
         };
 
-        let _ = funds_table.insert_transaction(&transaction, 0);
+        let _ = funds_table.insert_transaction(&transaction, 0, &TransactionStatus::Cleared);
 
         let binding = funds_table
             .data_frame()
@@ -63,9 +68,10 @@ mod tests {
             currency: Currency::EUR,
             date: NaiveDate::from_ymd_opt(2024, 12, 2).unwrap(),
             account_id: 0i64,
+            value_date: None,
         };
 
-        let _ = funds_table.insert_transaction(&transaction, 0);
+        let _ = funds_table.insert_transaction(&transaction, 0, &TransactionStatus::Cleared);
 
         let binding = funds_table
             .data_frame()
@@ -79,6 +85,50 @@ mod tests {
         assert_eq!(actual_last_id, &expected_last_id)
     }
 
+    #[test]
+    fn correct_funds_table_upgrade() {
+        let funds_table: FundsTable = init_funds_table();
+
+        let status = funds_table
+            .data_frame()
+            .column("status")
+            .unwrap()
+            .str()
+            .unwrap();
+        let reconciliation_id = funds_table
+            .data_frame()
+            .column("reconciliation_id")
+            .unwrap()
+            .i64()
+            .unwrap();
+
+        assert_eq!(status.get(1), Some("Cleared"));
+        assert_eq!(reconciliation_id.get(1), Some(-1i64));
+    }
+
+    #[test]
+    fn correct_funds_table_value_date() {
+        let mut funds_table: FundsTable = init_funds_table();
+        let transaction = Transaction::Debit {
+            value: 300.0,
+            currency: Currency::EUR,
+            date: NaiveDate::from_ymd_opt(2024, 12, 30).unwrap(),
+            account_id: 0i64,
+            value_date: NaiveDate::from_ymd_opt(2025, 1, 2),
+        };
+
+        funds_table
+            .insert_transaction(&transaction, 0, &TransactionStatus::Cleared)
+            .unwrap();
+
+        // old records have no value date
+        assert_eq!(funds_table.transaction(1).unwrap().value_date(), None);
+        assert_eq!(
+            funds_table.transaction(2).unwrap().value_date(),
+            NaiveDate::from_ymd_opt(2025, 1, 2)
+        );
+    }
+
     #[test]
     fn correct_entity_table_init() {
         let entity_table: EntityTable = *EntityTable::new().unwrap();
@@ -121,6 +171,7 @@ mod tests {
             Currency::CHF,
             AccountType::Deposit,
             1080.0f64,
+            Vec::new(),
         );
 
         let _ = account_table.insert_account(&account);
@@ -137,6 +188,54 @@ mod tests {
         assert_eq!(actual_last_id, &expected_last_id)
     }
 
+    #[test]
+    fn correct_account_table_upgrade() {
+        // as loaded from a csv saved before accounts had pockets or could be closed
+        let data_frame: DataFrame = df!(
+            "account_id" => [0i64],
+            "name" => ["Unknown"],
+            "country" => ["Unknown"],
+            "currency" => [Currency::EUR.to_string()],
+            "account_type" => [AccountType::Deposit.to_string()],
+            "initial_balance" => [0.0f64],
+            "creation_date" => [NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()],
+        )
+        .unwrap();
+        let mut account_table: AccountTable = AccountTable {
+            data_frame: AccountTable::upgrade(data_frame).unwrap(),
+        };
+
+        assert_eq!(account_table.closing_date(0).unwrap(), None);
+
+        let account_id: i64 = account_table
+            .insert_account(&Account::new(
+                String::from("Wallet"),
+                String::from("Sweden"),
+                Currency::SEK,
+                AccountType::Deposit,
+                0.0,
+                vec![Currency::EUR],
+            ))
+            .unwrap();
+        let closing_date: NaiveDate = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        account_table
+            .set_closing_date(account_id, Some(closing_date))
+            .unwrap();
+
+        assert_eq!(
+            account_table.closing_date(account_id).unwrap(),
+            Some(closing_date)
+        );
+        assert_eq!(
+            account_table.iter_open().unwrap().collect::<Vec<i64>>(),
+            vec![0]
+        );
+        assert_eq!(
+            account_table.account(account_id).unwrap().pockets(),
+            &[Currency::EUR]
+        );
+    }
+
     #[test]
     fn correct_income_table_delete() {
         let mut income_table = *IncomeTable::init().unwrap();
@@ -154,6 +253,53 @@ mod tests {
         assert_eq!(orig_size - party_0_size, income_table.data_frame.size())
     }
 
+    #[test]
+    fn correct_expenses_table_amortization() {
+        let mut expenses_table = *ExpensesTable::new().unwrap();
+        let transaction = Transaction::Expense {
+            value: 600.0,
+            currency: Currency::CHF,
+            date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            category: String::from("Insurance"),
+            subcategory: String::from("Health"),
+            description: String::from("Yearly premium"),
+            entity_id: 0,
+            amortization_months: 12,
+            refund_of: -1,
+            reimbursable: false,
+            tags: Vec::new(),
+        };
+        expenses_table
+            .insert_transaction(&transaction, 0, &TransactionStatus::Cleared)
+            .unwrap();
+
+        // as loaded from a csv saved before expenses could be amortized
+        let upgraded = ExpensesTable::upgrade(
+            expenses_table
+                .data_frame
+                .drop("amortization_months")
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            expenses_table.transaction(0).unwrap(),
+            Transaction::Expense {
+                amortization_months: 12,
+                ..
+            }
+        ));
+        assert_eq!(
+            upgraded
+                .column("amortization_months")
+                .unwrap()
+                .i64()
+                .unwrap()
+                .get(0),
+            Some(1i64)
+        );
+    }
+
     #[test]
     fn correct_income_table_transaction() {
         let mut income_table = *IncomeTable::new().unwrap();
@@ -165,8 +311,10 @@ mod tests {
             subcategory: String::from("Test subcategory"),
             description: String::from("Test description"),
             entity_id: 0,
+            tags: Vec::new(),
         };
-        let _ = income_table.insert_transaction(&original_transaction, 0);
+        let _ =
+            income_table.insert_transaction(&original_transaction, 0, &TransactionStatus::Cleared);
         let returned_transaction = income_table.transaction(0).unwrap();
 
         assert!(
@@ -175,4 +323,64 @@ mod tests {
                 & (original_transaction.date() == returned_transaction.date())
         );
     }
+
+    /// Saves the new table and loads it back: with no records, as it is when the last one
+    /// is deleted, or with only the default ones, like the unknown entity.
+    fn new_table_round_trip<T: Table>() {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("{}_{}_table.csv", std::process::id(), T::name()));
+        let mut table: T = *T::new().unwrap();
+        table.save_to(&path).unwrap();
+        let loaded: T = *T::try_load_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.data_frame().schema(), table.data_frame().schema());
+        assert!(loaded.data_frame().equals_missing(table.data_frame()));
+    }
+
+    #[test]
+    fn correct_new_tables_save_and_load() {
+        new_table_round_trip::<IncomeTable>();
+        new_table_round_trip::<ExpensesTable>();
+        new_table_round_trip::<FundsTable>();
+        new_table_round_trip::<PartyTable>();
+        new_table_round_trip::<EntityTable>();
+        new_table_round_trip::<AccountTable>();
+        new_table_round_trip::<TemplateTable>();
+        new_table_round_trip::<TemplateTransactionTable>();
+        new_table_round_trip::<BudgetTable>();
+        new_table_round_trip::<AssignmentTable>();
+        new_table_round_trip::<ReconciliationTable>();
+        new_table_round_trip::<BalanceAssertionTable>();
+        new_table_round_trip::<TagTable>();
+        new_table_round_trip::<ProjectTable>();
+        new_table_round_trip::<IouTable>();
+        new_table_round_trip::<EntityAliasTable>();
+        new_table_round_trip::<CategoryTable>();
+        new_table_round_trip::<CsvMappingTable>();
+        new_table_round_trip::<ImportReferenceTable>();
+        new_table_round_trip::<StagingTable>();
+        new_table_round_trip::<CategorizationRuleTable>();
+    }
+
+    #[test]
+    fn correct_emptied_table_save() {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("{}_emptied_funds_table.csv", std::process::id()));
+        init_funds_table().save_to(&path).unwrap();
+        assert_eq!(
+            FundsTable::try_load_from(&path)
+                .unwrap()
+                .data_frame
+                .height(),
+            2
+        );
+
+        // the records deleted since the last save do not come back
+        FundsTable::new().unwrap().save_to(&path).unwrap();
+        let loaded: Box<FundsTable> = FundsTable::try_load_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(loaded.data_frame.is_empty());
+    }
 }
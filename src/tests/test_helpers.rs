@@ -1,6 +1,40 @@
 use chrono::NaiveDate;
 use crate::modules::financial::{Currency, Party, Transaction};
 
+#[cfg(test)]
+pub(crate) fn init_testing_currency_exchange() -> crate::modules::currency_exchange::CurrencyExchange
+{
+    use crate::modules::currency_exchange::CurrencyExchange;
+    use polars::prelude::*;
+    use std::collections::HashMap;
+
+    let data_frame_chfeur: DataFrame = df!(
+        "date" => [
+            NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2020, 1, 2).unwrap(),
+            NaiveDate::from_ymd_opt(2020, 1, 3).unwrap(),
+        ],
+        "value" => [0.5, 1.0, 1.5]
+    )
+    .unwrap();
+
+    let data_frame_sekeur: DataFrame = df!(
+        "date" => [
+            NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2020, 1, 2).unwrap(),
+            NaiveDate::from_ymd_opt(2020, 1, 3).unwrap(),
+        ],
+        "value" => [1.5, 1.0, 0.5]
+    )
+    .unwrap();
+
+    let mut hash_map = HashMap::new();
+    hash_map.insert(String::from("CHFEUR"), data_frame_chfeur);
+    hash_map.insert(String::from("SEKEUR"), data_frame_sekeur);
+
+    CurrencyExchange::new(hash_map).unwrap()
+}
+
 pub(crate) fn init_party() -> Party {
     let t1 = Transaction::Income {
         value: 120.0,
@@ -10,6 +44,7 @@ pub(crate) fn init_party() -> Party {
         subcategory: "Regular salary".to_string(),
         description: "Finally got the bread".to_string(),
         entity_id: 0,
+        tags: Vec::new(),
     };
 
     let t2 = Transaction::Expense {
@@ -20,6 +55,10 @@ pub(crate) fn init_party() -> Party {
         subcategory: "Alcohol".to_string(),
         description: "Bought some beers to celebrate".to_string(),
         entity_id: 1,
+        amortization_months: 1,
+        refund_of: -1,
+        reimbursable: false,
+        tags: Vec::new(),
     };
 
     let t3 = Transaction::Credit {
@@ -27,6 +66,7 @@ pub(crate) fn init_party() -> Party {
         currency: Currency::EUR,
         date: NaiveDate::from_ymd_opt(2024, 12, 2).unwrap(),
         account_id: 0,
+        value_date: None,
     };
 
     let t4 = Transaction::Debit {
@@ -34,6 +74,7 @@ pub(crate) fn init_party() -> Party {
         currency: Currency::SEK,
         date: NaiveDate::from_ymd_opt(2024, 12, 2).unwrap(),
         account_id: 1,
+        value_date: None,
     };
 
     // Example data